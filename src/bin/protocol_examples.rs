@@ -7,6 +7,32 @@ fn main() {
   println!("");
   println!("A list of examples of protocol commands and their responses.");
   println!("");
+  println!("Any command may carry a numeric `id` beside its tag, e.g. \
+    `{{ \"id\": 7, \"GetModeInfo\": {{}} }}`, which is echoed beside the \
+    tag of its response.  Commands may be sent without waiting for earlier \
+    responses.  They are performed in the order received, but responses \
+    are sent as each is ready, so may arrive out of order.  `Hello` and \
+    the session commands are answered at once, as are `GetModeInfo`, \
+    `ListRulesets`, `ListWorlds`, `GetJournalInfo`, `CurrentRules` and \
+    `CurrentGenerationPhase`, from the state left by the last command \
+    performed.  Every other command waits for the commands received \
+    before it, e.g. a long generation step.");
+  println!("");
+  println!("A message that is not a valid command is answered with `Failed`, \
+    carrying its `id` if one could be read, and the connection stays open.  \
+    A command that fails unexpectedly in the game is answered with \
+    `Failed`, and the game returns to the main menu.");
+  println!("");
+  println!("Clients should begin with `Hello`, to check the protocol version \
+    and discover what the server supports.  If the server stopped in the \
+    middle of a mode, `GetJournalInfo` says what `ReplayJournal` can \
+    rebuild.");
+  println!("");
+  println!("The `protocol_schema` binary emits a JSON Schema of every \
+    command, response and event, which these examples are checked \
+    against, and the `protocol_typescript` binary emits TypeScript \
+    declarations of them.");
+  println!("");
   for proto_cat in protocol_categories {
     println!("# {}", span_color(ORANGE, &proto_cat.name));
     println!("{}", span_bold(&span_color(BROWNISH, &proto_cat.description)));
@@ -37,6 +63,20 @@ fn main() {
       }
     } 
   }
+
+  println!("# {}", span_color(ORANGE, "Events"));
+  println!("{}", span_bold(&span_color(BROWNISH,
+    "Pushed to every connected client as they happen, tagged `Event` \
+     and without an `id`.  Clients need not poll for them.")));
+  for event_doc in renfrew_river::get_event_docs() {
+    println!("## {}", span_color(BRIGHT_GREEN, &event_doc.name));
+    println!("");
+    println!("{}", event_doc.description);
+    println!("");
+    println!("```json");
+    println!("{}", event_doc.example);
+    println!("```");
+  }
 }
 
 const PALE_YELLOW: &'static str = "#dddd88";
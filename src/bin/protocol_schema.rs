@@ -0,0 +1,120 @@
+use std::{ fs, path::PathBuf, process::ExitCode };
+use clap::Parser;
+use serde_json::{ Map, Value };
+use renfrew_river::{
+  check_protocol_examples,
+  get_protocol_schemas,
+  schema_violations,
+};
+
+#[derive(Parser, Debug)]
+#[clap(name="protocol_schema")]
+#[command(
+  version="0.1.0",
+  author="Kannan Vijayan",
+  about="Emit the JSON Schema of the Renfrew River protocol"
+)]
+struct CommandLineArgs {
+  /**
+   * Directory to write one `<Name>.schema.json` per document to.  Without
+   * it, all documents are printed as one object, keyed by name.
+   */
+  #[clap(short, long)]
+  output_dir: Option<String>,
+
+  /** Check the protocol examples against the schemas. */
+  #[clap(long)]
+  check_examples: bool,
+
+  /**
+   * A file of JSON messages, one per line, to check against the schema
+   * named by `--schema`.
+   */
+  #[clap(long, requires="schema")]
+  validate: Option<String>,
+
+  /** The schema to validate against, e.g. `CommandFrame`. */
+  #[clap(long)]
+  schema: Option<String>,
+}
+
+fn report_errors(errors: Vec<String>) -> ExitCode {
+  for error in errors {
+    eprintln!("{}", error);
+  }
+  ExitCode::FAILURE
+}
+
+fn write_schemas(output_dir: Option<String>) -> Result<(), Vec<String>> {
+  let schemas = get_protocol_schemas();
+  let Some(output_dir) = output_dir else {
+    let all = schemas.into_iter()
+      .map(|document| (document.name, document.schema))
+      .collect::<Map<String, Value>>();
+    println!("{}", serde_json::to_string_pretty(&all).unwrap());
+    return Ok(());
+  };
+
+  let output_dir = PathBuf::from(output_dir);
+  fs::create_dir_all(&output_dir).map_err(|err| {
+    vec![format!("Failed to create {}: {}", output_dir.display(), err)]
+  })?;
+  for document in schemas {
+    let path = output_dir.join(format!("{}.schema.json", document.name));
+    let text = serde_json::to_string_pretty(&document.schema).unwrap();
+    fs::write(&path, text + "\n").map_err(|err| {
+      vec![format!("Failed to write {}: {}", path.display(), err)]
+    })?;
+    println!("Wrote {}", path.display());
+  }
+  Ok(())
+}
+
+fn validate_messages(path: &str, schema_name: &str) -> Result<(), Vec<String>> {
+  let schema = get_protocol_schemas().into_iter()
+    .find(|document| document.name == schema_name)
+    .ok_or_else(|| vec![format!("No such schema: {}", schema_name)])?
+    .schema;
+  let text = fs::read_to_string(path)
+    .map_err(|err| vec![format!("Failed to read {}: {}", path, err)])?;
+
+  let mut errors = Vec::new();
+  for (index, line) in text.lines().enumerate() {
+    if line.trim().is_empty() {
+      continue;
+    }
+    match serde_json::from_str::<Value>(line) {
+      Ok(value) => errors.extend(
+        schema_violations(&value, &schema).into_iter()
+          .map(|violation| format!("Line {}: {}", index + 1, violation))
+      ),
+      Err(err) => errors.push(format!("Line {}: not JSON: {}", index + 1, err)),
+    }
+  }
+  if errors.is_empty() { Ok(()) } else { Err(errors) }
+}
+
+pub fn main() -> ExitCode {
+  let args = CommandLineArgs::parse();
+
+  if args.check_examples {
+    let problems = check_protocol_examples();
+    if !problems.is_empty() {
+      return report_errors(problems);
+    }
+    eprintln!("All protocol examples match their schemas.");
+    return ExitCode::SUCCESS;
+  }
+
+  if let (Some(path), Some(schema_name)) = (&args.validate, &args.schema) {
+    return match validate_messages(path, schema_name) {
+      Ok(()) => ExitCode::SUCCESS,
+      Err(errors) => report_errors(errors),
+    };
+  }
+
+  match write_schemas(args.output_dir) {
+    Ok(()) => ExitCode::SUCCESS,
+    Err(errors) => report_errors(errors),
+  }
+}
@@ -0,0 +1,58 @@
+use std::{ fs, process::ExitCode };
+use clap::Parser;
+use renfrew_river::get_protocol_typescript;
+
+#[derive(Parser, Debug)]
+#[clap(name="protocol_typescript")]
+#[command(
+  version="0.1.0",
+  author="Kannan Vijayan",
+  about="Emit TypeScript declarations of the Renfrew River protocol"
+)]
+struct CommandLineArgs {
+  /** File to write the declarations to.  Without it, they are printed. */
+  #[clap(short, long)]
+  output: Option<String>,
+
+  /**
+   * Check that the file given by `--output` is up to date, instead of
+   * writing it.
+   */
+  #[clap(long, requires="output")]
+  check: bool,
+}
+
+pub fn main() -> ExitCode {
+  let args = CommandLineArgs::parse();
+  let text = get_protocol_typescript();
+
+  let Some(output) = args.output else {
+    print!("{}", text);
+    return ExitCode::SUCCESS;
+  };
+
+  if args.check {
+    return match fs::read_to_string(&output) {
+      Ok(existing) if existing == text => ExitCode::SUCCESS,
+      Ok(_) => {
+        eprintln!("{} is out of date with the protocol types.", output);
+        ExitCode::FAILURE
+      },
+      Err(err) => {
+        eprintln!("Failed to read {}: {}", output, err);
+        ExitCode::FAILURE
+      },
+    };
+  }
+
+  match fs::write(&output, text) {
+    Ok(()) => {
+      println!("Wrote {}", output);
+      ExitCode::SUCCESS
+    },
+    Err(err) => {
+      eprintln!("Failed to write {}: {}", output, err);
+      ExitCode::FAILURE
+    },
+  }
+}
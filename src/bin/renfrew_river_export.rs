@@ -0,0 +1,88 @@
+use std::{ path::PathBuf, process::ExitCode };
+use env_logger;
+use clap::Parser;
+use renfrew_river::{ export_world_layer, LayerExportOptions };
+
+#[derive(Parser, Debug)]
+#[clap(name="renfrew_river_export")]
+#[command(
+  version="0.1.0",
+  author="Kannan Vijayan",
+  about="Export a map layer of a saved Renfrew River world"
+)]
+struct CommandLineArgs {
+  /** Root path of data store. */
+  #[clap(short, long, name="data-root")]
+  data_root: String,
+
+  /** Name of the saved world. */
+  #[clap(short, long)]
+  world: String,
+
+  /** Component to export, as `word.component`. */
+  #[clap(short, long)]
+  component: String,
+
+  /** Output format: pgm, ppm, r16 or r32. */
+  #[clap(short, long, default_value="pgm")]
+  format: String,
+
+  /** Palette name from the world's ruleset, for ppm output. */
+  #[clap(short, long)]
+  palette: Option<String>,
+
+  /** Area to export, as `col,row,columns,rows`.  Defaults to the whole map. */
+  #[clap(short, long)]
+  area: Option<String>,
+
+  /** Output file path. */
+  #[clap(short, long)]
+  output: String,
+}
+
+fn parse_area(area: &str) -> Option<((u16, u16), (u16, u16))> {
+  let parts = area.split(',')
+    .map(|part| part.trim().parse::<u16>().ok())
+    .collect::<Option<Vec<u16>>>()?;
+  match parts.as_slice() {
+    [col, row, columns, rows] => Some(((*col, *row), (*columns, *rows))),
+    _ => None,
+  }
+}
+
+pub fn main() -> ExitCode {
+  env_logger::init();
+
+  let args = CommandLineArgs::parse();
+
+  let area = match args.area.as_deref().map(parse_area) {
+    Some(None) => {
+      eprintln!("Invalid area, expected col,row,columns,rows");
+      return ExitCode::FAILURE;
+    },
+    Some(area) => area,
+    None => None,
+  };
+
+  let options = LayerExportOptions {
+    data_root: PathBuf::from(args.data_root),
+    world_name: args.world,
+    component: args.component,
+    area,
+    format: args.format,
+    palette: args.palette,
+    output: PathBuf::from(args.output),
+  };
+  match export_world_layer(&options) {
+    Ok(bytes) => {
+      println!("Wrote {} bytes to {}", bytes, options.output.display());
+      ExitCode::SUCCESS
+    },
+    Err(errors) => {
+      for error in errors {
+        eprintln!("{}", error);
+      }
+      ExitCode::FAILURE
+    },
+  }
+}
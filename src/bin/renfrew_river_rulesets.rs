@@ -0,0 +1,100 @@
+use std::{ path::PathBuf, process::ExitCode };
+use env_logger;
+use clap::{ Parser, Subcommand };
+use renfrew_river::{
+  export_ruleset_bundle,
+  import_ruleset_bundle,
+  RulesetExportOptions,
+  RulesetImportOptions,
+};
+
+#[derive(Parser, Debug)]
+#[clap(name="renfrew_river_rulesets")]
+#[command(
+  version="0.1.0",
+  author="Kannan Vijayan",
+  about="Export and import Renfrew River ruleset bundles"
+)]
+struct CommandLineArgs {
+  /** Root path of data store. */
+  #[clap(short, long, name="data-root")]
+  data_root: String,
+
+  #[command(subcommand)]
+  action: Action,
+}
+
+#[derive(Subcommand, Debug)]
+enum Action {
+  /** Write a stored ruleset to a bundle file. */
+  Export {
+    /** Name of the ruleset. */
+    #[clap(short, long)]
+    ruleset: String,
+
+    /** Include the assembled terrain generator programs. */
+    #[clap(long)]
+    include_programs: bool,
+
+    /** Output file path. */
+    #[clap(short, long)]
+    output: String,
+  },
+
+  /** Add the ruleset in a bundle file to the data store. */
+  Import {
+    /** Bundle file path. */
+    #[clap(short, long)]
+    input: String,
+
+    /** What to do on a name collision: fail, rename or overwrite. */
+    #[clap(long, default_value="fail")]
+    on_conflict: String,
+  },
+}
+
+fn report_errors(errors: Vec<String>) -> ExitCode {
+  for error in errors {
+    eprintln!("{}", error);
+  }
+  ExitCode::FAILURE
+}
+
+pub fn main() -> ExitCode {
+  env_logger::init();
+
+  let args = CommandLineArgs::parse();
+  let data_root = PathBuf::from(args.data_root);
+
+  match args.action {
+    Action::Export { ruleset, include_programs, output } => {
+      let options = RulesetExportOptions {
+        data_root,
+        ruleset_name: ruleset,
+        include_programs,
+        output: PathBuf::from(output),
+      };
+      match export_ruleset_bundle(&options) {
+        Ok(()) => {
+          println!("Wrote {}", options.output.display());
+          ExitCode::SUCCESS
+        },
+        Err(errors) => report_errors(errors),
+      }
+    },
+    Action::Import { input, on_conflict } => {
+      let options = RulesetImportOptions {
+        data_root,
+        input: PathBuf::from(input),
+        on_conflict,
+      };
+      match import_ruleset_bundle(&options) {
+        Ok(name) => {
+          println!("Imported ruleset {:?}", name);
+          ExitCode::SUCCESS
+        },
+        Err(errors) => report_errors(errors),
+      }
+    },
+  }
+}
@@ -18,6 +18,10 @@ struct CommandLineArgs {
   /** Root path of data store. */
   #[clap(short, long, name="data-root")]
   data_root: Option<String>,
+
+  /** Most connections to allow at once. */
+  #[clap(short, long, name="max-sessions")]
+  max_sessions: Option<usize>,
 }
 
 #[tokio::main(flavor = "current_thread")]
@@ -37,5 +41,9 @@ pub async fn main() {
     network_server_config.data_root = data_root.clone();
   }
 
+  if let Some(max_sessions) = args.max_sessions {
+    network_server_config.max_sessions = max_sessions;
+  }
+
   ws_serve(network_server_config).await;
 }
@@ -8,6 +8,7 @@ use std::{
   rc::Rc,
   sync::{
     Arc,
+    OnceLock,
     atomic::AtomicBool,
   },
 };
@@ -26,6 +27,26 @@ use super::{
 };
 
 
+const ADAPTER_OPTIONS: wgpu::RequestAdapterOptions<'static> =
+  wgpu::RequestAdapterOptions {
+    power_preference: wgpu::PowerPreference::HighPerformance,
+    compatible_surface: None,
+    force_fallback_adapter: false,
+  };
+
+/**
+ * Information about the GPU adapter devices are created on, or `None` if
+ * there is no suitable adapter.  The adapter is only looked up once.
+ */
+pub(crate) fn cog_adapter_info() -> Option<wgpu::AdapterInfo> {
+  static ADAPTER_INFO: OnceLock<Option<wgpu::AdapterInfo>> = OnceLock::new();
+  ADAPTER_INFO.get_or_init(|| {
+    let instance = wgpu::Instance::default();
+    futures::executor::block_on(instance.request_adapter(&ADAPTER_OPTIONS))
+      .map(|adapter| adapter.get_info())
+  }).clone()
+}
+
 /**
  * Inner wrapper for Device and Queue.
  */
@@ -37,16 +58,12 @@ pub(crate) struct CogDeviceInner {
   poll_thread: PollThread,
 }
 impl CogDeviceInner {
-  fn new() -> Self {
+  fn new() -> Result<Self, String> {
     let instance = wgpu::Instance::default();
 
     let adapter = futures::executor::block_on(
-      instance.request_adapter(&wgpu::RequestAdapterOptions {
-        power_preference: wgpu::PowerPreference::HighPerformance,
-        compatible_surface: None,
-        force_fallback_adapter: false,
-      })
-    ).expect("Failed to find a suitable GPU adapter");
+      instance.request_adapter(&ADAPTER_OPTIONS)
+    ).ok_or_else(|| "Failed to find a suitable GPU adapter".to_string())?;
 
     let (device, queue) = futures::executor::block_on(
         adapter.request_device(
@@ -59,14 +76,14 @@ impl CogDeviceInner {
         /* trace_path */
         None
       )
-    ).expect("Failed to create a GPU device");
+    ).map_err(|err| format!("Failed to create a GPU device: {}", err))?;
 
     let wgpu_device = Arc::new(device);
     let wgpu_queue = Arc::new(queue);
     let poll_thread = PollThread::new(&wgpu_device);
     let shader_store = CogShaderStore::new();
 
-    CogDeviceInner { wgpu_device, wgpu_queue, poll_thread, shader_store }
+    Ok(CogDeviceInner { wgpu_device, wgpu_queue, poll_thread, shader_store })
   }
 }
 
@@ -84,9 +101,13 @@ pub(crate) struct CogDevice {
   inner: Rc<CogDeviceInner>,
 }
 impl CogDevice {
-  pub(crate) fn new() -> Self {
-    let inner = Rc::new(CogDeviceInner::new());
-    Self { inner }
+  /**
+   * Create a device on the GPU adapter, failing if there is no suitable
+   * adapter or the device cannot be created on it.
+   */
+  pub(crate) fn new() -> Result<Self, String> {
+    let inner = Rc::new(CogDeviceInner::new()?);
+    Ok(Self { inner })
   }
 
   pub(crate) fn wgpu_device(&self) -> &wgpu::Device {
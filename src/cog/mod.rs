@@ -28,7 +28,7 @@ pub(crate) use self::{
     CogComputePass2D,
     CogComputePipeline,
   },
-  device::CogDevice,
+  device::{ cog_adapter_info, CogDevice },
   encoder::CogEncoder,
   invoke::CogInvoke,
   shader::{
@@ -0,0 +1,202 @@
+use std::collections::BTreeMap;
+use serde_json::Value;
+use super::json_patch::escape_token;
+
+/**
+ * A stable code for a kind of validation problem.  Clients key localised
+ * messages on these, so existing codes must not be renamed.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum DiagnosticCode {
+  // A value that must be given was empty.
+  Required,
+  TooLong,
+  NotANumber,
+  // A number outside its allowed range; params `min` and/or `max`.
+  OutOfRange,
+  // A name with characters it may not contain: symbol names allow only
+  // letters, digits and underscores; ruleset names also allow spaces and
+  // `-.'()`, but no leading or trailing space.
+  InvalidName,
+  // A name used more than once; param `name`.
+  Duplicate,
+  // A reference to something that does not exist.
+  NotFound,
+  AlreadyExists,
+  // Two format components share bits.
+  Overlap,
+  // More items than are allowed; param `max`.
+  TooMany,
+  // A program line that does not assemble; param `line`.
+  ParseError,
+  // A program test vector that failed; param `test`.
+  TestFailed,
+  // A chain of ruleset bases that leads back to itself.
+  Cycle,
+  // The data store could not be read.
+  StoreError,
+  // A part that is invalid because of problems reported within it.
+  Invalid,
+  // A command that could not be carried out.
+  Failed,
+  // Not yet given a specific code.
+  Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum DiagnosticSeverity {
+  #[default]
+  Error,
+  Warning,
+}
+
+/**
+ * A message in a nested validation.  It carries a code and the values
+ * substituted into its text, but serializes as just the text, so that the
+ * nested validations keep their shape.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ValidationMessage {
+  pub(crate) code: DiagnosticCode,
+  pub(crate) severity: DiagnosticSeverity,
+  pub(crate) text: String,
+  pub(crate) params: BTreeMap<String, Value>,
+
+  // Appended to the path of the field the message is reported under,
+  // e.g. to single out one entry of a list.
+  pub(crate) subpath: String,
+}
+impl ValidationMessage {
+  pub(crate) fn new<S: Into<String>>(code: DiagnosticCode, text: S) -> Self {
+    ValidationMessage {
+      code,
+      severity: DiagnosticSeverity::Error,
+      text: text.into(),
+      params: BTreeMap::new(),
+      subpath: String::new(),
+    }
+  }
+
+  pub(crate) fn param<V: Into<Value>>(mut self, name: &str, value: V) -> Self {
+    self.params.insert(name.to_string(), value.into());
+    self
+  }
+
+  /** Point the message at a member (or index) below its field. */
+  pub(crate) fn at<S: ToString>(mut self, token: S) -> Self {
+    self.subpath.push('/');
+    self.subpath.push_str(&escape_token(&token.to_string()));
+    self
+  }
+
+  /**
+   * Point the message into a member (or index) of its field, with the
+   * member it is already at below that.
+   */
+  pub(crate) fn within<S: ToString>(mut self, token: S) -> Self {
+    self.subpath = format!("/{}{}", escape_token(&token.to_string()), self.subpath);
+    self
+  }
+
+  pub(crate) fn to_diagnostic(&self, path: &str) -> Diagnostic {
+    Diagnostic {
+      code: self.code,
+      path: format!("{}{}", path, self.subpath),
+      severity: self.severity,
+      message: self.text.clone(),
+      params: self.params.clone(),
+    }
+  }
+}
+impl From<String> for ValidationMessage {
+  fn from(text: String) -> Self {
+    ValidationMessage::new(DiagnosticCode::Other, text)
+  }
+}
+impl From<&str> for ValidationMessage {
+  fn from(text: &str) -> Self {
+    ValidationMessage::new(DiagnosticCode::Other, text)
+  }
+}
+impl serde::Serialize for ValidationMessage {
+  fn serialize<S: serde::Serializer>(&self, serializer: S)
+    -> Result<S::Ok, S::Error>
+  {
+    serializer.serialize_str(&self.text)
+  }
+}
+impl<'de> serde::Deserialize<'de> for ValidationMessage {
+  fn deserialize<D: serde::Deserializer<'de>>(deserializer: D)
+    -> Result<Self, D::Error>
+  {
+    String::deserialize(deserializer).map(ValidationMessage::from)
+  }
+}
+impl schemars::JsonSchema for ValidationMessage {
+  fn schema_name() -> String {
+    "ValidationMessage".to_string()
+  }
+  fn json_schema(generator: &mut schemars::gen::SchemaGenerator)
+    -> schemars::schema::Schema
+  {
+    String::json_schema(generator)
+  }
+}
+
+/**
+ * A validation problem, located in the input by a JSON Pointer.
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct Diagnostic {
+  pub(crate) code: DiagnosticCode,
+  pub(crate) path: String,
+  pub(crate) severity: DiagnosticSeverity,
+
+  // In English, with the params already substituted.
+  pub(crate) message: String,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+  pub(crate) params: BTreeMap<String, Value>,
+}
+
+/**
+ * A nested validation that can be flattened into diagnostics.
+ */
+pub(crate) trait Diagnose {
+  /**
+   * Add a diagnostic for every message in the validation.  `path` is the
+   * JSON Pointer of the validated input.
+   */
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>);
+
+  fn diagnostics(&self) -> Vec<Diagnostic> {
+    let mut out = Vec::new();
+    self.diagnose("", &mut out);
+    out
+  }
+}
+
+/**
+ * Add diagnostics for the messages of a field.  An empty `field` is the
+ * validated input itself.
+ */
+pub(crate) fn diagnose_messages(
+  messages: &[ValidationMessage],
+  path: &str,
+  field: &str,
+  out: &mut Vec<Diagnostic>,
+) {
+  let path = if field.is_empty() {
+    path.to_string()
+  } else {
+    format!("{}/{}", path, field)
+  };
+  out.extend(messages.iter().map(|message| message.to_diagnostic(&path)));
+}
@@ -1,8 +1,12 @@
-use super::map::CellComponentSelector;
+use super::{
+  map::CellComponentSelector,
+  ruleset::{ FormatRules, FormatValue },
+};
 
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum GenerationStepKind {
   RandGen,         // NewlyCreated -> PreInitialize
   InitializeCell,  // PreInitialize -> CellInitialized
@@ -13,6 +17,7 @@ pub(crate) enum GenerationStepKind {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum GenerationPhase {
   NewlyCreated,
   PreInitialize,
@@ -34,6 +39,7 @@ impl GenerationPhase {
 
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum GenerationCellDatumId {
   /** Value of named randgen field. */
   RandGen {},
@@ -41,3 +47,33 @@ pub(crate) enum GenerationCellDatumId {
   /** Value of map cell format word component */
   Selector(CellComponentSelector),
 }
+impl GenerationCellDatumId {
+  /**
+   * Interpret a raw value read for this datum, according to the type of
+   * the component it selects.  Randgen values are plain integers.
+   */
+  pub(crate) fn decode(&self, format_rules: &FormatRules, raw: u32)
+    -> FormatValue
+  {
+    match self {
+      GenerationCellDatumId::RandGen {} => FormatValue::Integer(raw as i64),
+      GenerationCellDatumId::Selector(sel) => {
+        format_rules.component(&sel.word, &sel.component)
+          .map_or(FormatValue::Integer(raw as i64), |c| c.decode(raw))
+      },
+    }
+  }
+
+  /**
+   * Decode the per-datum value vectors of a map data read.
+   */
+  pub(crate) fn decode_all(
+    datum_ids: &[GenerationCellDatumId],
+    format_rules: &FormatRules,
+    data: &[Vec<u32>],
+  ) -> Vec<Vec<FormatValue>> {
+    datum_ids.iter().zip(data.iter()).map(|(datum_id, values)| {
+      values.iter().map(|&raw| datum_id.decode(format_rules, raw)).collect()
+    }).collect()
+  }
+}
@@ -0,0 +1,224 @@
+use crate::data::map::WorldDims;
+
+/**
+ * Encodings accepted for uploaded heightmaps.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum HeightmapFormat {
+  // Netpbm greyscale, binary (P5) or plain (P2), 8 or 16 bits per sample.
+  Pgm,
+
+  // Headerless little-endian u16 samples.  Dimensions must be supplied.
+  Raw16,
+
+  // Headerless little-endian u32 samples.  Dimensions must be supplied.
+  Raw32,
+}
+
+/**
+ * Summary of a stored heightmap.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct HeightmapEntry {
+  pub(crate) name: String,
+  pub(crate) dims: WorldDims,
+}
+
+/**
+ * A decoded heightmap: row-major samples at its own resolution, which
+ * need not match the dimensions of the world it is used for.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct Heightmap {
+  pub(crate) dims: WorldDims,
+  pub(crate) values: Vec<u32>,
+}
+impl Heightmap {
+  /** Bytes allowed for a PGM header, comments included. */
+  const MAX_PGM_HEADER_LEN: usize = 4096;
+
+  /**
+   * The longest encoding of a heightmap no larger than `max_dims`: a plain
+   * PGM file, whose samples take up to six bytes each.
+   */
+  pub(crate) fn max_encoded_len(max_dims: WorldDims) -> usize {
+    max_dims.area() as usize * 6 + Self::MAX_PGM_HEADER_LEN
+  }
+
+  /**
+   * Decode a heightmap, rejecting one with more columns or rows than
+   * `max_dims` before any samples are read.
+   */
+  pub(crate) fn decode(
+    format: HeightmapFormat,
+    dims: Option<WorldDims>,
+    max_dims: WorldDims,
+    bytes: &[u8],
+  ) -> Result<Heightmap, String> {
+    let heightmap = match format {
+      HeightmapFormat::Pgm => Self::decode_pgm(bytes, max_dims)?,
+      HeightmapFormat::Raw16 => Self::decode_raw(dims, max_dims, bytes, 2)?,
+      HeightmapFormat::Raw32 => Self::decode_raw(dims, max_dims, bytes, 4)?,
+    };
+    if heightmap.dims.area() == 0 {
+      return Err("Heightmap is empty".to_string());
+    }
+    let [min, max] = heightmap.value_range();
+    if min == max {
+      return Err("Heightmap is flat".to_string());
+    }
+    Ok(heightmap)
+  }
+
+  /** Inclusive range of sample values. */
+  pub(crate) fn value_range(&self) -> [u32; 2] {
+    let min = self.values.iter().copied().min().unwrap_or(0);
+    let max = self.values.iter().copied().max().unwrap_or(0);
+    [min, max]
+  }
+
+  /**
+   * Samples scaled down proportionally, if needed, so that none exceeds
+   * `max_value`.
+   */
+  pub(crate) fn values_within(&self, max_value: u32) -> Vec<u32> {
+    let [_, max] = self.value_range();
+    if max <= max_value {
+      return self.values.clone();
+    }
+    self.values.iter().map(|&value| {
+      ((value as u64 * max_value as u64) / max as u64) as u32
+    }).collect()
+  }
+
+  /** Serialize as headerless little-endian u32 samples. */
+  pub(crate) fn to_raw32(&self) -> Vec<u8> {
+    let mut out = Vec::with_capacity(self.values.len() * 4);
+    for value in &self.values {
+      out.extend_from_slice(&value.to_le_bytes());
+    }
+    out
+  }
+
+  fn check_dims(dims: WorldDims, max_dims: WorldDims)
+    -> Result<(), String>
+  {
+    if dims.columns > max_dims.columns || dims.rows > max_dims.rows {
+      return Err(format!(
+        "Heightmap is {}x{}, larger than the maximum {}x{}",
+        dims.columns, dims.rows, max_dims.columns, max_dims.rows
+      ));
+    }
+    Ok(())
+  }
+
+  fn decode_raw(
+    dims: Option<WorldDims>,
+    max_dims: WorldDims,
+    bytes: &[u8],
+    sample_size: usize,
+  ) -> Result<Heightmap, String> {
+    let dims = dims.ok_or_else(|| {
+      "Dimensions are required for raw heightmaps".to_string()
+    })?;
+    Self::check_dims(dims, max_dims)?;
+    let expected = dims.area() as usize * sample_size;
+    if bytes.len() != expected {
+      return Err(format!(
+        "Raw heightmap has {} bytes, expected {} for {}x{}",
+        bytes.len(), expected, dims.columns, dims.rows
+      ));
+    }
+    let values = bytes.chunks_exact(sample_size).map(|sample| {
+      match sample_size {
+        2 => u16::from_le_bytes([sample[0], sample[1]]) as u32,
+        _ => u32::from_le_bytes([sample[0], sample[1], sample[2], sample[3]]),
+      }
+    }).collect();
+    Ok(Heightmap { dims, values })
+  }
+
+  fn decode_pgm(bytes: &[u8], max_dims: WorldDims)
+    -> Result<Heightmap, String>
+  {
+    let mut pos = 0;
+    let magic = Self::pgm_token(bytes, &mut pos)?;
+    let plain = match magic.as_str() {
+      "P5" => false,
+      "P2" => true,
+      _ => return Err(format!("Not a PGM file (magic {:?})", magic)),
+    };
+    let columns = Self::pgm_number(bytes, &mut pos)?;
+    let rows = Self::pgm_number(bytes, &mut pos)?;
+    let maxval = Self::pgm_number(bytes, &mut pos)?;
+    if columns == 0 || rows == 0
+      || columns > u16::MAX as u32 || rows > u16::MAX as u32
+    {
+      return Err(format!("Unsupported PGM dimensions {}x{}", columns, rows));
+    }
+    if maxval == 0 || maxval > 0xFFFF {
+      return Err(format!("Unsupported PGM maxval {}", maxval));
+    }
+    let dims = WorldDims::new(columns as u16, rows as u16);
+    Self::check_dims(dims, max_dims)?;
+    let count = dims.area() as usize;
+
+    let values = if plain {
+      // Grown as samples are parsed, so a header cannot claim the memory.
+      let mut values = Vec::new();
+      for _ in 0 .. count {
+        values.push(Self::pgm_number(bytes, &mut pos)?);
+      }
+      values
+    } else {
+      // Exactly one whitespace byte separates the header from the raster.
+      pos += 1;
+      let sample_size = if maxval < 256 { 1 } else { 2 };
+      let raster = bytes.get(pos .. pos + count * sample_size)
+        .ok_or_else(|| "PGM raster is truncated".to_string())?;
+      raster.chunks_exact(sample_size).map(|sample| {
+        match sample_size {
+          1 => sample[0] as u32,
+          _ => u16::from_be_bytes([sample[0], sample[1]]) as u32,
+        }
+      }).collect()
+    };
+    if values.iter().any(|&value| value > maxval) {
+      return Err("PGM sample exceeds maxval".to_string());
+    }
+    Ok(Heightmap { dims, values })
+  }
+
+  fn pgm_token(bytes: &[u8], pos: &mut usize) -> Result<String, String> {
+    // Skip whitespace and comments.
+    while *pos < bytes.len() {
+      if bytes[*pos].is_ascii_whitespace() {
+        *pos += 1;
+      } else if bytes[*pos] == b'#' {
+        while *pos < bytes.len() && bytes[*pos] != b'\n' {
+          *pos += 1;
+        }
+      } else {
+        break;
+      }
+    }
+    let start = *pos;
+    while *pos < bytes.len() && !bytes[*pos].is_ascii_whitespace() {
+      *pos += 1;
+    }
+    if start == *pos {
+      return Err("PGM header is truncated".to_string());
+    }
+    Ok(String::from_utf8_lossy(&bytes[start .. *pos]).to_string())
+  }
+
+  fn pgm_number(bytes: &[u8], pos: &mut usize) -> Result<u32, String> {
+    let token = Self::pgm_token(bytes, pos)?;
+    token.parse::<u32>()
+      .map_err(|_| format!("Invalid number in PGM file: {:?}", token))
+  }
+}
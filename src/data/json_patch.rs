@@ -0,0 +1,264 @@
+use serde_json::Value;
+
+/**
+ * An operation of a JSON Patch (RFC 6902).
+ *
+ * Paths are JSON Pointers (RFC 6901): `""` is the whole document, and
+ * `/a/0/b` is member `b` of the first element of member `a`.  The last
+ * segment of an `add` path into an array may be `-`, meaning the end.
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+#[serde(tag = "op")]
+pub(crate) enum JsonPatchOp {
+  #[serde(rename = "add")]
+  Add { path: String, value: Value },
+
+  #[serde(rename = "remove")]
+  Remove { path: String },
+
+  #[serde(rename = "replace")]
+  Replace { path: String, value: Value },
+
+  #[serde(rename = "move")]
+  Move { from: String, path: String },
+
+  #[serde(rename = "copy")]
+  Copy { from: String, path: String },
+
+  #[serde(rename = "test")]
+  Test { path: String, value: Value },
+}
+impl JsonPatchOp {
+  /** The paths the operation changes or reads. */
+  pub(crate) fn paths(&self) -> Vec<&str> {
+    match self {
+      JsonPatchOp::Add { path, .. } |
+      JsonPatchOp::Remove { path } |
+      JsonPatchOp::Replace { path, .. } |
+      JsonPatchOp::Test { path, .. } => vec![path],
+      JsonPatchOp::Move { from, path } |
+      JsonPatchOp::Copy { from, path } => vec![from, path],
+    }
+  }
+
+  /**
+   * Whether any of the operation's paths is `prefix` or lies under it.
+   */
+  pub(crate) fn touches(&self, prefix: &str) -> bool {
+    self.paths().iter().any(|path| {
+      path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+        || prefix.starts_with(&format!("{}/", path))
+        || path.is_empty()
+    })
+  }
+}
+
+/**
+ * Apply `ops` to `document`, in order.  If any operation fails, the
+ * document is left unchanged.
+ */
+pub(crate) fn apply_json_patch(document: &mut Value, ops: &[JsonPatchOp])
+  -> Result<(), String>
+{
+  let mut patched = document.clone();
+  for (i, op) in ops.iter().enumerate() {
+    apply_op(&mut patched, op)
+      .map_err(|err| format!("Patch operation {} failed: {}", i, err))?;
+  }
+  *document = patched;
+  Ok(())
+}
+
+/**
+ * Apply `ops` to the JSON form of `input`, returning the patched input.
+ */
+pub(crate) fn patch_input<T>(input: &T, ops: &[JsonPatchOp]) -> Result<T, String>
+  where T: serde::Serialize + serde::de::DeserializeOwned
+{
+  let mut document = serde_json::to_value(input)
+    .map_err(|err| format!("Failed to serialize input: {}", err))?;
+  apply_json_patch(&mut document, ops)?;
+  serde_json::from_value(document)
+    .map_err(|err| format!("The patched input is not valid: {}", err))
+}
+
+/**
+ * A patch that turns `from` into `to`, changing only what differs.
+ * Arrays of different lengths are replaced whole.
+ */
+pub(crate) fn diff_json(from: &Value, to: &Value) -> Vec<JsonPatchOp> {
+  let mut ops = Vec::new();
+  diff_into(from, to, String::new(), &mut ops);
+  ops
+}
+
+fn diff_into(from: &Value, to: &Value, path: String, ops: &mut Vec<JsonPatchOp>) {
+  match (from, to) {
+    (Value::Object(from_map), Value::Object(to_map)) => {
+      for (key, from_value) in from_map {
+        let child = format!("{}/{}", path, escape_token(key));
+        match to_map.get(key) {
+          Some(to_value) => diff_into(from_value, to_value, child, ops),
+          None => ops.push(JsonPatchOp::Remove { path: child }),
+        }
+      }
+      for (key, to_value) in to_map {
+        if !from_map.contains_key(key) {
+          let child = format!("{}/{}", path, escape_token(key));
+          ops.push(JsonPatchOp::Add { path: child, value: to_value.clone() });
+        }
+      }
+    },
+    (Value::Array(from_vec), Value::Array(to_vec))
+      if from_vec.len() == to_vec.len() =>
+    {
+      for (i, (from_value, to_value)) in from_vec.iter().zip(to_vec).enumerate() {
+        diff_into(from_value, to_value, format!("{}/{}", path, i), ops);
+      }
+    },
+    _ if from == to => {},
+    _ => ops.push(JsonPatchOp::Replace { path, value: to.clone() }),
+  }
+}
+
+fn apply_op(document: &mut Value, op: &JsonPatchOp) -> Result<(), String> {
+  match op {
+    JsonPatchOp::Add { path, value } => add(document, path, value.clone()),
+    JsonPatchOp::Remove { path } => remove(document, path).map(|_| ()),
+    JsonPatchOp::Replace { path, value } => {
+      let target = resolve_mut(document, path)?;
+      *target = value.clone();
+      Ok(())
+    },
+    JsonPatchOp::Move { from, path } => {
+      if path.starts_with(&format!("{}/", from)) {
+        return Err(format!("Cannot move '{}' into itself", from));
+      }
+      let value = remove(document, from)?;
+      add(document, path, value)
+    },
+    JsonPatchOp::Copy { from, path } => {
+      let value = resolve_mut(document, from)?.clone();
+      add(document, path, value)
+    },
+    JsonPatchOp::Test { path, value } => {
+      if resolve_mut(document, path)? == value {
+        Ok(())
+      } else {
+        Err(format!("Value at '{}' does not match", path))
+      }
+    },
+  }
+}
+
+fn add(document: &mut Value, path: &str, value: Value) -> Result<(), String> {
+  let Some((parent_path, token)) = split_last(path)? else {
+    *document = value;
+    return Ok(());
+  };
+  match resolve_mut(document, parent_path)? {
+    Value::Object(map) => {
+      map.insert(token, value);
+      Ok(())
+    },
+    Value::Array(vec) => {
+      let index = if token == "-" {
+        vec.len()
+      } else {
+        parse_index(&token, vec.len() + 1)?
+      };
+      vec.insert(index, value);
+      Ok(())
+    },
+    _ => Err(format!("'{}' is not an object or array", parent_path)),
+  }
+}
+
+fn remove(document: &mut Value, path: &str) -> Result<Value, String> {
+  let Some((parent_path, token)) = split_last(path)? else {
+    return Err("Cannot remove the whole document".to_string());
+  };
+  match resolve_mut(document, parent_path)? {
+    Value::Object(map) => map.remove(&token)
+      .ok_or_else(|| format!("No member at '{}'", path)),
+    Value::Array(vec) => {
+      let index = parse_index(&token, vec.len())?;
+      Ok(vec.remove(index))
+    },
+    _ => Err(format!("'{}' is not an object or array", parent_path)),
+  }
+}
+
+fn resolve_mut<'a>(document: &'a mut Value, path: &str)
+  -> Result<&'a mut Value, String>
+{
+  let mut target = document;
+  for token in parse_pointer(path)? {
+    target = match target {
+      Value::Object(map) => map.get_mut(&token),
+      Value::Array(vec) => {
+        let index = parse_index(&token, vec.len())?;
+        vec.get_mut(index)
+      },
+      _ => None,
+    }.ok_or_else(|| format!("Nothing at '{}'", path))?;
+  }
+  Ok(target)
+}
+
+/**
+ * Split a pointer into its parent's pointer and its last token, or `None`
+ * for the whole document.
+ */
+fn split_last(path: &str) -> Result<Option<(&str, String)>, String> {
+  if path.is_empty() {
+    return Ok(None);
+  }
+  parse_pointer(path)?;
+  let split = path.rfind('/').unwrap();
+  Ok(Some((&path[..split], unescape_token(&path[split + 1..]))))
+}
+
+fn parse_pointer(path: &str) -> Result<Vec<String>, String> {
+  if path.is_empty() {
+    return Ok(Vec::new());
+  }
+  let Some(rest) = path.strip_prefix('/') else {
+    return Err(format!("Path '{}' must start with '/'", path));
+  };
+  Ok(rest.split('/').map(unescape_token).collect())
+}
+
+fn parse_index(token: &str, len: usize) -> Result<usize, String> {
+  // Only plain decimal digits, without leading zeros; `usize::from_str`
+  // would also take a leading `+`.
+  let valid_form = (token == "0" || !token.starts_with('0'))
+    && !token.is_empty()
+    && token.bytes().all(|byte| byte.is_ascii_digit());
+  match token.parse::<usize>() {
+    Ok(index) if valid_form && index < len => Ok(index),
+    _ => Err(format!("Invalid array index '{}'", token)),
+  }
+}
+
+fn unescape_token(token: &str) -> String {
+  token.replace("~1", "/").replace("~0", "~")
+}
+
+/** Escape a segment of a JSON Pointer (RFC 6901). */
+pub(crate) fn escape_token(token: &str) -> String {
+  token.replace('~', "~0").replace('/', "~1")
+}
+
+/**
+ * The JSON form of a validation, or `null` when there is nothing to
+ * report.
+ */
+pub(crate) fn validation_json<T: serde::Serialize>(validation: Option<&T>) -> Value {
+  validation
+    .and_then(|validation| serde_json::to_value(validation).ok())
+    .unwrap_or(Value::Null)
+}
@@ -0,0 +1,124 @@
+use crate::data::{
+  map::WorldDims,
+  ruleset::PaletteRules,
+};
+
+/**
+ * File formats a single map layer can be exported as.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum LayerExportFormat {
+  // Binary greyscale PGM (P5), 16 bits per sample, big-endian.
+  // Values above 0xFFFF are clamped.
+  Pgm16,
+
+  // Binary PPM (P6), 8 bits per channel, coloured with a ruleset palette.
+  PalettePpm,
+
+  // Headerless little-endian u16 samples, clamped to 0xFFFF.
+  Raw16,
+
+  // Headerless little-endian u32 samples.
+  Raw32,
+}
+impl LayerExportFormat {
+  pub(crate) fn extension(&self) -> &'static str {
+    match self {
+      LayerExportFormat::Pgm16 => "pgm",
+      LayerExportFormat::PalettePpm => "ppm",
+      LayerExportFormat::Raw16 => "r16",
+      LayerExportFormat::Raw32 => "r32",
+    }
+  }
+
+  /**
+   * Output filename for the given base name.  Base names are restricted
+   * to a plain identifier so that exports stay inside their directory.
+   */
+  pub(crate) fn filename(&self, stem: &str) -> Result<String, String> {
+    let valid = !stem.is_empty() && stem.len() <= 64 && stem.chars().all(|c| {
+      c.is_ascii_alphanumeric() || c == '_' || c == '-'
+    });
+    if !valid {
+      return Err(format!(
+        "Invalid export filename '{}': use 1-64 letters, digits, '_' or '-'",
+        stem
+      ));
+    }
+    Ok(format!("{}.{}", stem, self.extension()))
+  }
+
+  pub(crate) fn needs_palette(&self) -> bool {
+    matches!(self, LayerExportFormat::PalettePpm)
+  }
+
+  pub(crate) fn from_name(name: &str) -> Option<Self> {
+    match name.to_ascii_lowercase().as_str() {
+      "pgm" | "pgm16" => Some(LayerExportFormat::Pgm16),
+      "ppm" | "paletteppm" => Some(LayerExportFormat::PalettePpm),
+      "r16" | "raw16" => Some(LayerExportFormat::Raw16),
+      "r32" | "raw32" => Some(LayerExportFormat::Raw32),
+      _ => None,
+    }
+  }
+
+  /**
+   * Encode a layer of row-major values with the given dimensions.
+   */
+  pub(crate) fn encode(&self,
+    dims: WorldDims,
+    values: &[u32],
+    palette: Option<&PaletteRules>,
+  ) -> Result<Vec<u8>, String> {
+    if values.len() != dims.area() as usize {
+      return Err(format!(
+        "Layer has {} values, expected {}",
+        values.len(), dims.area()
+      ));
+    }
+
+    let clamp16 = |value: u32| value.min(0xFFFF) as u16;
+    match self {
+      LayerExportFormat::Pgm16 => {
+        let mut out = Self::netpbm_header("P5", dims, 0xFFFF);
+        out.reserve(values.len() * 2);
+        for &value in values {
+          out.extend_from_slice(&clamp16(value).to_be_bytes());
+        }
+        Ok(out)
+      },
+      LayerExportFormat::PalettePpm => {
+        let palette = palette.ok_or_else(|| {
+          "A palette is required for PPM export".to_string()
+        })?;
+        let mut out = Self::netpbm_header("P6", dims, 0xFF);
+        out.reserve(values.len() * 3);
+        for &value in values {
+          out.extend_from_slice(&palette.color_for(value));
+        }
+        Ok(out)
+      },
+      LayerExportFormat::Raw16 => {
+        let mut out = Vec::with_capacity(values.len() * 2);
+        for &value in values {
+          out.extend_from_slice(&clamp16(value).to_le_bytes());
+        }
+        Ok(out)
+      },
+      LayerExportFormat::Raw32 => {
+        let mut out = Vec::with_capacity(values.len() * 4);
+        for &value in values {
+          out.extend_from_slice(&value.to_le_bytes());
+        }
+        Ok(out)
+      },
+    }
+  }
+
+  fn netpbm_header(magic: &str, dims: WorldDims, maxval: u32) -> Vec<u8> {
+    format!("{}\n{} {}\n{}\n", magic, dims.columns, dims.rows, maxval)
+      .into_bytes()
+  }
+}
@@ -9,6 +9,7 @@ use super::WorldDims;
  */
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CellCoord {
   pub(crate) col: u16,
   pub(crate) row: u16,
@@ -54,6 +54,7 @@ impl From<CellDataWords> for CellData {
  */
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CellComponentSelector {
   pub(crate) word: String,
   pub(crate) component: String,
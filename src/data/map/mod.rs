@@ -8,6 +8,7 @@ pub(crate) use self::{
   cell_data::{
     CellData,
     CellComponentSelector,
+    CELL_DATA_NUM_WORDS,
   },
   world_descriptor::{
     WorldDescriptor,
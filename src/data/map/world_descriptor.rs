@@ -1,5 +1,13 @@
 use sha256;
-use crate::data::ruleset::RulesetEntry;
+use crate::data::{
+  ruleset::RulesetEntry,
+  diagnose_messages,
+  Diagnose,
+  Diagnostic,
+  DiagnosticCode,
+  HeightmapEntry,
+  ValidationMessage,
+};
 use super::{
   WorldDims,
   WorldDimsInput,
@@ -8,6 +16,7 @@ use super::{
 
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDescriptor {
   pub(crate) name: String,
   pub(crate) description: String,
@@ -15,6 +24,12 @@ pub(crate) struct WorldDescriptor {
   pub(crate) dims: WorldDims,
   #[serde(rename = "rulesetName")]
   pub(crate) ruleset_name: String,
+
+  // Uploaded heightmap used in place of procedural noise for randgen.
+  // Its edges are kept as drawn, without the noise's border fade.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) heightmap: Option<String>,
 }
 impl WorldDescriptor {
   pub(crate) fn to_input(&self) -> WorldDescriptorInput {
@@ -24,6 +39,7 @@ impl WorldDescriptor {
       seed: self.seed.clone(),
       dims: self.dims.to_input(),
       ruleset_name: self.ruleset_name.clone(),
+      heightmap: self.heightmap.clone().unwrap_or_default(),
     }
   }
 
@@ -34,14 +50,21 @@ impl WorldDescriptor {
   }
 }
 
+#[derive(Clone, Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDescriptorLimits {
+  #[serde(rename = "minDims")]
   pub(crate) min_dims: WorldDims,
+  #[serde(rename = "maxDims")]
   pub(crate) max_dims: WorldDims,
+  #[serde(rename = "maxDescriptionLength")]
   pub(crate) max_description_length: usize,
 }
 
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDescriptorInput {
   pub(crate) name: String,
   pub(crate) description: String,
@@ -49,11 +72,17 @@ pub(crate) struct WorldDescriptorInput {
   pub(crate) dims: WorldDimsInput,
   #[serde(rename = "rulesetName")]
   pub(crate) ruleset_name: String,
+
+  // Name of an uploaded heightmap, or empty for procedural noise.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub(crate) heightmap: String,
 }
 impl WorldDescriptorInput {
   pub(crate) fn to_world_descriptor(&self,
     limits: WorldDescriptorLimits,
     ruleset_entries: &[RulesetEntry],
+    heightmap_entries: &[HeightmapEntry],
   ) -> Result<WorldDescriptor, WorldDescriptorValidation> {
     let errors = Vec::new();
     let mut name_errors = Vec::new();
@@ -61,25 +90,33 @@ impl WorldDescriptorInput {
     let mut seed_errors = Vec::new();
     let mut dims_validation = WorldDimsValidation::new_valid();
     let mut ruleset_name_errors = Vec::new();
+    let mut heightmap_errors = Vec::new();
 
     let name = if self.name.len() > 0 {
       self.name.clone()
     } else {
-      name_errors.push("Name is empty.".to_string());
+      name_errors.push(
+        ValidationMessage::new(DiagnosticCode::Required, "Name is empty.")
+      );
       String::new()
     };
 
     let description = if self.description.len() <= limits.max_description_length {
       self.description.clone()
     } else {
-      description_errors.push("Description is too long.".to_string());
+      description_errors.push(ValidationMessage::new(
+        DiagnosticCode::TooLong,
+        "Description is too long.",
+      ).param("max", limits.max_description_length));
       String::new()
     };
 
     let seed = if self.seed.len() > 0 {
       self.seed.clone()
     } else {
-      seed_errors.push("Seed is empty.".to_string());
+      seed_errors.push(
+        ValidationMessage::new(DiagnosticCode::Required, "Seed is empty.")
+      );
       String::new()
     };
 
@@ -94,20 +131,37 @@ impl WorldDescriptorInput {
     let ruleset_name = if self.ruleset_name.len() > 0 {
       self.ruleset_name.clone()
     } else {
-      ruleset_name_errors.push("Ruleset name is empty.".to_string());
+      ruleset_name_errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "Ruleset name is empty.",
+      ));
       String::new()
     };
 
     if ! ruleset_entries.iter().any(|entry| entry.name == ruleset_name) {
-      ruleset_name_errors.push("Ruleset not found.".to_string());
+      ruleset_name_errors.push(
+        ValidationMessage::new(DiagnosticCode::NotFound, "Ruleset not found.")
+      );
     }
 
+    let heightmap = if self.heightmap.is_empty() {
+      None
+    } else if heightmap_entries.iter().any(|e| e.name == self.heightmap) {
+      Some(self.heightmap.clone())
+    } else {
+      heightmap_errors.push(
+        ValidationMessage::new(DiagnosticCode::NotFound, "Heightmap not found.")
+      );
+      None
+    };
+
     if errors.is_empty()
       && name_errors.is_empty()
       && description_errors.is_empty()
       && seed_errors.is_empty()
       && dims_validation.is_valid()
       && ruleset_name_errors.is_empty()
+      && heightmap_errors.is_empty()
     {
       Ok(WorldDescriptor {
         name,
@@ -115,28 +169,54 @@ impl WorldDescriptorInput {
         seed,
         dims,
         ruleset_name,
+        heightmap,
       })
     } else {
-      Err(WorldDescriptorValidation {
+      let mut validation = WorldDescriptorValidation {
         errors,
         name: name_errors,
         description: description_errors,
         seed: seed_errors,
         dims: dims_validation,
         ruleset_name: ruleset_name_errors,
-      })
+        heightmap: heightmap_errors,
+        diagnostics: Vec::new(),
+      };
+      validation.diagnostics = validation.diagnostics();
+      Err(validation)
     }
   }
 }
 
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDescriptorValidation {
-  pub(crate) errors: Vec<String>,
-  pub(crate) name: Vec<String>,
-  pub(crate) description: Vec<String>,
-  pub(crate) seed: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
+  pub(crate) name: Vec<ValidationMessage>,
+  pub(crate) description: Vec<ValidationMessage>,
+  pub(crate) seed: Vec<ValidationMessage>,
   pub(crate) dims: WorldDimsValidation,
   #[serde(rename = "rulesetName")]
-  pub(crate) ruleset_name: Vec<String>,
+  pub(crate) ruleset_name: Vec<ValidationMessage>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) heightmap: Vec<ValidationMessage>,
+
+  // Every message above, flattened and located by its path in the input.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) diagnostics: Vec<Diagnostic>,
+}
+impl Diagnose for WorldDescriptorValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    diagnose_messages(&self.name, path, "name", out);
+    diagnose_messages(&self.description, path, "description", out);
+    diagnose_messages(&self.seed, path, "seed", out);
+    self.dims.diagnose(&format!("{}/dims", path), out);
+    diagnose_messages(&self.ruleset_name, path, "rulesetName", out);
+    diagnose_messages(&self.heightmap, path, "heightmap", out);
+  }
 }
@@ -1,11 +1,21 @@
 use serde;
-use crate::data::map::CellCoord;
+use crate::data::{
+  diagnostic::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  map::CellCoord,
+};
 
 /**
  * The dimensions of the game world.
  */
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDims {
   pub(crate) columns: u16,
   pub(crate) rows: u16,
@@ -97,6 +107,7 @@ impl WorldDims {
 
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDimsInput {
   pub(crate) columns: String,
   pub(crate) rows: String,
@@ -113,22 +124,31 @@ impl WorldDimsInput {
       match self.columns.parse::<u16>() {
         Ok(value) => {
           if value < min.columns {
-            column_errors.push(format!("Columns must be at least {}.", min.columns));
-            column_errors.push(self.columns.clone());
+            column_errors.push(ValidationMessage::new(
+              DiagnosticCode::OutOfRange,
+              format!("Columns must be at least {}.", min.columns),
+            ).param("min", min.columns).param("value", self.columns.clone()));
           } else if value > max.columns {
-            column_errors.push(format!("Columns must be at most {}.", max.columns));
-            column_errors.push(self.columns.clone());
+            column_errors.push(ValidationMessage::new(
+              DiagnosticCode::OutOfRange,
+              format!("Columns must be at most {}.", max.columns),
+            ).param("max", max.columns).param("value", self.columns.clone()));
           }
           value
         },
         Err(_) => {
-          column_errors.push("Columns must be a number.".to_string());
-          column_errors.push(self.columns.clone());
+          column_errors.push(ValidationMessage::new(
+            DiagnosticCode::NotANumber,
+            "Columns must be a number.",
+          ).param("value", self.columns.clone()));
           0
         }
       }
     } else {
-      errors.push("Columns is empty.".to_string());
+      errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "Columns is empty.",
+      ).at("columns"));
       0
     };
 
@@ -136,22 +156,31 @@ impl WorldDimsInput {
       match self.rows.parse::<u16>() {
         Ok(value) => {
           if value < min.rows {
-            row_errors.push(format!("Rows must be at least {}.", min.rows));
-            row_errors.push(self.rows.clone());
+            row_errors.push(ValidationMessage::new(
+              DiagnosticCode::OutOfRange,
+              format!("Rows must be at least {}.", min.rows),
+            ).param("min", min.rows).param("value", self.rows.clone()));
           } else if value > max.rows {
-            row_errors.push(format!("Rows must be at most {}.", max.rows));
-            row_errors.push(self.rows.clone());
+            row_errors.push(ValidationMessage::new(
+              DiagnosticCode::OutOfRange,
+              format!("Rows must be at most {}.", max.rows),
+            ).param("max", max.rows).param("value", self.rows.clone()));
           }
           value
         }
         Err(_) => {
-          row_errors.push("Rows must be a number.".to_string());
-          row_errors.push(self.rows.clone());
+          row_errors.push(ValidationMessage::new(
+            DiagnosticCode::NotANumber,
+            "Rows must be a number.",
+          ).param("value", self.rows.clone()));
           0
         }
       }
     } else {
-      errors.push("Rows is empty.".to_string());
+      errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "Rows is empty.",
+      ).at("rows"));
       0
     };
 
@@ -165,10 +194,11 @@ impl WorldDimsInput {
 
 #[derive(Clone, Debug)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct WorldDimsValidation {
-  pub(crate) errors: Vec<String>,
-  pub(crate) columns: Vec<String>,
-  pub(crate) rows: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
+  pub(crate) columns: Vec<ValidationMessage>,
+  pub(crate) rows: Vec<ValidationMessage>,
 }
 impl WorldDimsValidation {
   pub(crate) fn new_valid() -> Self {
@@ -183,3 +213,10 @@ impl WorldDimsValidation {
     self.errors.is_empty() && self.columns.is_empty() && self.rows.is_empty()
   }
 }
+impl Diagnose for WorldDimsValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    diagnose_messages(&self.columns, path, "columns", out);
+    diagnose_messages(&self.rows, path, "rows", out);
+  }
+}
@@ -1,18 +1,39 @@
+mod diagnostic;
 mod generation;
+mod heightmap;
 mod histogram;
+mod json_patch;
+mod layer_export;
 mod statistics;
 mod vec_map;
 
 pub(crate) mod map;
 pub(crate) mod ruleset;
+pub(crate) mod world_file;
 
 pub(crate) use self::{
+  diagnostic::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    DiagnosticSeverity,
+    ValidationMessage,
+  },
   generation::{
     GenerationStepKind,
     GenerationPhase,
     GenerationCellDatumId,
   },
+  heightmap::{ Heightmap, HeightmapEntry, HeightmapFormat },
   histogram::Histogram,
+  json_patch::{
+    diff_json,
+    patch_input,
+    validation_json,
+    JsonPatchOp,
+  },
+  layer_export::LayerExportFormat,
   statistics::Statistics,
   vec_map::VecMap,
 };
@@ -0,0 +1,256 @@
+use serde_json::Value;
+use crate::{
+  data_store::DataStore,
+  shady_vm::{ ShasmProgram, ShasmSymbols },
+};
+use super::{
+  migration::migrate_ruleset_value,
+  Ruleset,
+  RulesetValidation,
+  RULESET_SCHEMA_VERSION,
+};
+
+/**
+ * A self-describing file for sharing a ruleset outside of a data root.
+ *
+ * The ruleset is carried as plain JSON, exactly as it was stored, so that
+ * bundles written by older versions can still be migrated on import.  The
+ * content hash covers that JSON and catches damage from hand-editing.
+ * Derived rulesets are bundled flattened, since their bases may not exist
+ * where the bundle is imported.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesetBundle {
+  // Always `RULESET_BUNDLE_KIND`.
+  pub(crate) kind: String,
+
+  // The schema version of `ruleset`.
+  #[serde(rename = "schemaVersion")]
+  pub(crate) schema_version: u32,
+
+  // Hex SHA-256 of the JSON form of `ruleset`.
+  #[serde(rename = "contentHash")]
+  pub(crate) content_hash: String,
+
+  pub(crate) ruleset: Value,
+
+  // The terrain generator programs, assembled, for consumers that do
+  // not want to assemble shasm themselves.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) programs: Option<RulesetBundlePrograms>,
+}
+
+/**
+ * Assembled programs, as the pairs of words uploaded to the GPU.
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesetBundlePrograms {
+  #[serde(rename = "initProgram")]
+  pub(crate) init_program: Vec<[u32; 2]>,
+
+  #[serde(rename = "pairwiseProgram")]
+  pub(crate) pairwise_program: Vec<[u32; 2]>,
+
+  #[serde(rename = "mergeProgram")]
+  pub(crate) merge_program: Vec<[u32; 2]>,
+
+  #[serde(rename = "finalProgram")]
+  pub(crate) final_program: Vec<[u32; 2]>,
+}
+impl RulesetBundlePrograms {
+  fn assemble(ruleset: &Ruleset) -> Result<Self, String> {
+    let stage = &ruleset.terrain_gen.stage;
+    let symbols = ruleset.terrain_gen.symbols();
+    let assemble_program = |name: &str, program: &ShasmProgram| {
+      Self::assemble_program(name, program, &symbols)
+    };
+    Ok(RulesetBundlePrograms {
+      init_program: assemble_program("initProgram", &stage.init_program)?,
+      pairwise_program:
+        assemble_program("pairwiseProgram", &stage.pairwise_program)?,
+      merge_program: assemble_program("mergeProgram", &stage.merge_program)?,
+      final_program: assemble_program("finalProgram", &stage.final_program)?,
+    })
+  }
+
+  fn assemble_program(name: &str,
+    program: &ShasmProgram,
+    symbols: &dyn ShasmSymbols,
+  ) -> Result<Vec<[u32; 2]>, String> {
+    let shady_program = program.parse_shady_program(symbols).map_err(|_| {
+      format!("Failed to assemble {}", name)
+    })?;
+    Ok(shady_program.iter_instructions().map(|&instr| instr.into()).collect())
+  }
+}
+
+/**
+ * How an import treats a ruleset whose name is already in the store.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum RulesetImportConflict {
+  // Refuse the import.
+  #[default]
+  Fail,
+
+  // Import under the first free name of the form `Name (2)`, `Name (3)`...
+  Rename,
+
+  // Replace the stored ruleset.
+  Overwrite,
+}
+impl RulesetImportConflict {
+  pub(crate) fn from_name(name: &str) -> Option<Self> {
+    match name.to_ascii_lowercase().as_str() {
+      "fail" => Some(RulesetImportConflict::Fail),
+      "rename" => Some(RulesetImportConflict::Rename),
+      "overwrite" => Some(RulesetImportConflict::Overwrite),
+      _ => None,
+    }
+  }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) enum RulesetImportError {
+  // The bundled ruleset does not pass validation.
+  Invalid(RulesetValidation),
+
+  // The bundle is damaged, or could not be stored.
+  Failed(Vec<String>),
+}
+impl RulesetImportError {
+  fn failed<S: Into<String>>(message: S) -> Self {
+    RulesetImportError::Failed(vec![message.into()])
+  }
+}
+
+pub(crate) const RULESET_BUNDLE_KIND: &str = "RenfrewRiverRuleset";
+
+impl RulesetBundle {
+  pub(crate) fn new(ruleset: &Ruleset, include_programs: bool)
+    -> Result<Self, String>
+  {
+    let ruleset = &ruleset.flattened();
+    let ruleset_value = serde_json::to_value(ruleset)
+      .map_err(|err| format!("Failed to serialize ruleset: {}", err))?;
+    let programs = if include_programs {
+      Some(RulesetBundlePrograms::assemble(ruleset)?)
+    } else {
+      None
+    };
+    Ok(RulesetBundle {
+      kind: RULESET_BUNDLE_KIND.to_string(),
+      schema_version: ruleset.schema_version,
+      content_hash: Self::hash_ruleset_value(&ruleset_value),
+      ruleset: ruleset_value,
+      programs,
+    })
+  }
+
+  pub(crate) fn new_example() -> Self {
+    Self::new(&Ruleset::new_example(), false)
+      .expect("Failed to bundle example ruleset")
+  }
+
+  fn hash_ruleset_value(value: &Value) -> String {
+    sha256::digest(value.to_string())
+  }
+
+  /**
+   * Check the bundle's integrity and return the ruleset it carries,
+   * migrated to the current schema.  The ruleset is not validated.
+   */
+  pub(crate) fn unpack(&self) -> Result<Ruleset, Vec<String>> {
+    if self.kind != RULESET_BUNDLE_KIND {
+      return Err(vec![format!("Not a ruleset bundle (kind {:?})", self.kind)]);
+    }
+    if self.schema_version > RULESET_SCHEMA_VERSION {
+      return Err(vec![format!(
+        "Bundle schema version {} is newer than supported version {}",
+        self.schema_version, RULESET_SCHEMA_VERSION
+      )]);
+    }
+    if Self::hash_ruleset_value(&self.ruleset) != self.content_hash {
+      return Err(vec![
+        "Bundle content hash does not match its ruleset.".to_string(),
+      ]);
+    }
+
+    // The schema version may only be implied by the bundle.
+    let mut ruleset_value = self.ruleset.clone();
+    if let Value::Object(object) = &mut ruleset_value {
+      let version = object.entry("schemaVersion")
+        .or_insert_with(|| Value::from(self.schema_version));
+      if version.as_u64() != Some(self.schema_version as u64) {
+        return Err(vec![format!(
+          "Bundle schema version {} does not match ruleset version {}",
+          self.schema_version, version
+        )]);
+      }
+    }
+    let ruleset = migrate_ruleset_value(ruleset_value)
+      .map_err(|err| vec![err])?
+      .ruleset;
+
+    if let Some(programs) = &self.programs {
+      let assembled = RulesetBundlePrograms::assemble(&ruleset)
+        .map_err(|err| vec![err])?;
+      if &assembled != programs {
+        return Err(vec![
+          "Bundled programs do not match the ruleset's program text."
+            .to_string(),
+        ]);
+      }
+    }
+    Ok(ruleset)
+  }
+
+  /**
+   * Validate the bundled ruleset and add it to the store, returning the
+   * stored ruleset.
+   */
+  pub(crate) fn import(&self,
+    data_store: &DataStore,
+    on_conflict: RulesetImportConflict,
+  ) -> Result<Ruleset, RulesetImportError> {
+    let ruleset = self.unpack().map_err(RulesetImportError::Failed)?;
+    let mut store = data_store.rulesets().map_err(|err| {
+      RulesetImportError::failed(format!("Failed to read ruleset store: {}", err))
+    })?;
+
+    let mut input = ruleset.to_input();
+    let mut update_existing = None;
+    if store.contains(&input.name) {
+      match on_conflict {
+        RulesetImportConflict::Fail => {
+          return Err(RulesetImportError::failed(format!(
+            "A ruleset with this name already exists: {}", input.name
+          )));
+        },
+        RulesetImportConflict::Rename => {
+          input.name = (2 ..)
+            .map(|n| format!("{} ({})", ruleset.name, n))
+            .find(|name| !store.contains(name))
+            .expect("Ran out of ruleset names");
+        },
+        RulesetImportConflict::Overwrite => {
+          update_existing = Some(ruleset.name.as_str());
+        },
+      }
+    }
+
+    let validated = input.to_validated(data_store, update_existing)
+      .map_err(RulesetImportError::Invalid)?;
+    store.write(&validated.name, &validated).map_err(|err| {
+      RulesetImportError::failed(format!("Failed to save ruleset: {}", err))
+    })?;
+    Ok(validated)
+  }
+}
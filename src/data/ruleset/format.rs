@@ -1,3 +1,14 @@
+use crate::{
+  data::{
+    map::CELL_DATA_NUM_WORDS,
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::ShasmSymbols,
+};
 use super::{
   format_word::{
     FormatWordInput,
@@ -8,6 +19,7 @@ use super::{
     FormatComponentSelector,
     FormatComponentRules,
   },
+  format_type::FormatComponentType,
 };
 
 /**
@@ -16,6 +28,7 @@ use super::{
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatRules {
   // The format for each word.
   #[serde(rename = "wordFormats")]
@@ -31,11 +44,13 @@ impl FormatRules {
             name: "Component1".to_string(),
             offset: 0,
             bits: 8,
+            kind: FormatComponentType::Unsigned,
           },
           FormatComponentRules {
             name: "Component2".to_string(),
             offset: 8,
             bits: 8,
+            kind: FormatComponentType::Unsigned,
           },
         ],
       },
@@ -49,6 +64,31 @@ impl FormatRules {
     FormatInput { word_formats }
   }
 
+  /**
+   * SHA-256 of the JSON form of these rules.  Stored alongside cell data
+   * so that it is never decoded with a different format.
+   */
+  pub(crate) fn content_hash(&self) -> [u8; 32] {
+    let json = serde_json::to_string(self)
+      .expect("Failed to serialize format rules");
+    let hex_string = sha256::digest(json);
+    let mut hash = [0_u8; 32];
+    for (i, byte) in hash.iter_mut().enumerate() {
+      *byte = u8::from_str_radix(&hex_string[i * 2 .. i * 2 + 2], 16).unwrap();
+    }
+    hash
+  }
+
+  pub(crate) fn component(&self, word_name: &str, component_name: &str)
+    -> Option<&FormatComponentRules>
+  {
+    self.word_formats.iter()
+      .find(|word| word.name == word_name)
+      .and_then(|word| {
+        word.components.iter().find(|c| c.name == component_name)
+      })
+  }
+
   pub(crate) fn selector_for(&self, word_name: &str, component_name: &str)
     -> Option<FormatComponentSelector>
   {
@@ -60,6 +100,7 @@ impl FormatRules {
               word: word_index as u8,
               offset: component.offset,
               count: component.bits,
+              signed: component.kind.is_signed(),
             });
           }
         }
@@ -69,11 +110,46 @@ impl FormatRules {
   }
 }
 
+/**
+ * Programs may refer to a component's values by name:
+ *
+ *   `#Word.Component.Name` is the value of an enum component's named value.
+ *   `#Word.Component(literal)` is the raw value that stands for `literal`,
+ *   e.g. `#Climate.Temperature(-12.5)` for a fixed-point component.
+ */
+impl ShasmSymbols for FormatRules {
+  fn resolve_symbol(&self, name: &str, argument: Option<&str>)
+    -> Result<i64, String>
+  {
+    let parts = name.split('.').collect::<Vec<_>>();
+    let (word_name, component_name, value_name) = match parts.as_slice() {
+      [word, component] => (*word, *component, None),
+      [word, component, value] => (*word, *component, Some(*value)),
+      _ => return Err(format!(
+        "Symbol '#{}' must be of the form #Word.Component", name
+      )),
+    };
+    let component = self.component(word_name, component_name)
+      .ok_or_else(|| format!("No component '{}.{}'", word_name, component_name))?;
+    let literal = match (value_name, argument) {
+      (Some(value), None) => value,
+      (None, Some(argument)) => argument,
+      _ => return Err(format!(
+        "Symbol '#{}' needs either a value name or a value in parentheses",
+        name
+      )),
+    };
+    component.kind.encode_literal(component.bits, literal)
+      .map_err(|err| format!("#{}: {}", name, err))
+  }
+}
+
 /**
  * The input for a format.
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatInput {
   #[serde(rename = "wordFormats")]
   pub(crate) word_formats: Vec<FormatWordInput>,
@@ -91,14 +167,37 @@ impl FormatInput {
       word_formats: Vec::new(),
     };
 
-    // Validate each word.
+    if self.word_formats.len() > CELL_DATA_NUM_WORDS {
+      validation.errors.push(ValidationMessage::new(
+        DiagnosticCode::TooMany,
+        format!(
+          "At most {} words are allowed, but {} are defined.",
+          CELL_DATA_NUM_WORDS, self.word_formats.len()
+        ),
+      ).param("max", CELL_DATA_NUM_WORDS).at("wordFormats"));
+    }
+
+    // Validate each word.  Word validations line up with the input words,
+    // with empty validations for valid words.
     let mut word_rules = Vec::new();
-    for word in &self.word_formats {
+    for (i, word) in self.word_formats.iter().enumerate() {
       let maybe_validated = word.to_validated();
-      match maybe_validated {
-        Ok(validated) => word_rules.push(validated),
-        Err(word_validation) => validation.word_formats.push(word_validation),
+      let mut word_validation = match maybe_validated {
+        Ok(validated) => {
+          word_rules.push(validated);
+          FormatWordValidation::new()
+        },
+        Err(word_validation) => word_validation,
+      };
+      let earlier = self.word_formats[..i].iter()
+        .position(|other| other.name == word.name);
+      if let (Some(j), false) = (earlier, word.name.is_empty()) {
+        word_validation.errors.push(ValidationMessage::new(
+          DiagnosticCode::Duplicate,
+          format!("The name '{}' is already used by word {}.", word.name, j),
+        ).param("name", word.name.clone()).param("other", j).at("name"));
       }
+      validation.word_formats.push(word_validation);
     }
 
     if validation.is_valid() {
@@ -116,10 +215,13 @@ impl FormatInput {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatValidation {
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub(crate) errors: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
 
+  #[serde(default)]
   #[serde(rename = "wordFormats")]
   #[serde(skip_serializing_if = "Vec::is_empty")]
   pub(crate) word_formats: Vec<FormatWordValidation>,
@@ -130,3 +232,11 @@ impl FormatValidation {
       && self.word_formats.iter().all(|wf| wf.is_valid())
   }
 }
+impl Diagnose for FormatValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    for (i, word) in self.word_formats.iter().enumerate() {
+      word.diagnose(&format!("{}/wordFormats/{}", path, i), out);
+    }
+  }
+}
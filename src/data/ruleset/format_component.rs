@@ -1,9 +1,19 @@
 
+use crate::data::{
+  diagnose_messages,
+  Diagnose,
+  Diagnostic,
+  DiagnosticCode,
+  ValidationMessage,
+};
+use super::format_type::{ FormatComponentType, FormatValue };
+
 /**
  * A single component of a word.
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatComponentRules {
   // The name of the component.
   pub(crate) name: String,
@@ -13,6 +23,12 @@ pub(crate) struct FormatComponentRules {
 
   // The number of bits in the component.
   pub(crate) bits: u8,
+
+  // How the bits are interpreted.
+  #[serde(rename = "type")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "FormatComponentType::is_unsigned")]
+  pub(crate) kind: FormatComponentType,
 }
 impl FormatComponentRules {
   pub(crate) fn to_input(&self) -> FormatComponentInput {
@@ -20,8 +36,22 @@ impl FormatComponentRules {
       name: self.name.clone(),
       offset: self.offset.to_string(),
       bits: self.bits.to_string(),
+      kind: self.kind.clone(),
     }
   }
+
+  pub(crate) fn decode(&self, raw: u32) -> FormatValue {
+    self.kind.decode(self.bits, raw)
+  }
+
+  /** The index of the last bit of the component. */
+  pub(crate) fn last_bit(&self) -> u8 {
+    self.offset + self.bits - 1
+  }
+
+  pub(crate) fn overlaps(&self, other: &FormatComponentRules) -> bool {
+    self.offset <= other.last_bit() && other.offset <= self.last_bit()
+  }
 }
 
 /**
@@ -29,6 +59,7 @@ impl FormatComponentRules {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatComponentInput {
   // The name of the component.
   pub(crate) name: String,
@@ -38,6 +69,11 @@ pub(crate) struct FormatComponentInput {
 
   // The number of bits in the component.
   pub(crate) bits: String,
+
+  #[serde(rename = "type")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "FormatComponentType::is_unsigned")]
+  pub(crate) kind: FormatComponentType,
 }
 impl FormatComponentInput {
   pub(crate) fn to_validated(&self) -> Result<FormatComponentRules, FormatComponentValidation> {
@@ -45,13 +81,15 @@ impl FormatComponentInput {
     let name = self.validate_name(&mut validation);
     let offset = self.validate_offset(&mut validation);
     let bits = self.validate_bits(&mut validation);
+    self.kind.validate(bits, &mut validation.kind);
 
     // Consistency checks.
     if let (Some(offset), Some(bits)) = (offset, bits) {
       if offset + bits > 32 {
-        validation.errors.push(
-          "The offset and bits must not exceed 32.".to_string()
-        );
+        validation.errors.push(ValidationMessage::new(
+          DiagnosticCode::OutOfRange,
+          "The offset and bits must not exceed 32.",
+        ).param("max", 32));
       }
     }
 
@@ -60,6 +98,7 @@ impl FormatComponentInput {
         name: name.unwrap(),
         offset: offset.unwrap(),
         bits: bits.unwrap(),
+        kind: self.kind.clone(),
       })
     } else {
       Err(validation)
@@ -70,7 +109,9 @@ impl FormatComponentInput {
     -> Option<String>
   {
     if self.name.is_empty() {
-      validation.name.push("The name is required.".to_string());
+      validation.name.push(
+        ValidationMessage::new(DiagnosticCode::Required, "The name is required.")
+      );
       None
     } else {
       Some(self.name.clone())
@@ -82,21 +123,29 @@ impl FormatComponentInput {
   {
     // Validate the offset.
     if self.offset.is_empty() {
-      validation.offset.push("The offset is required.".to_string());
+      validation.offset.push(
+        ValidationMessage::new(DiagnosticCode::Required, "The offset is required.")
+      );
       return None;
     }
     let maybe_offset = self.offset.parse::<u8>();
     let offset = match maybe_offset {
       Ok(offset) => {
         if offset > 31 {
-          validation.offset.push("The offset must be less than 32.".to_string());
+          validation.offset.push(ValidationMessage::new(
+            DiagnosticCode::OutOfRange,
+            "The offset must be less than 32.",
+          ).param("max", 31));
           return None
         } else {
           offset
         }
       },
       _ => {
-        validation.offset.push("The offset must be a number.".to_string());
+        validation.offset.push(ValidationMessage::new(
+          DiagnosticCode::NotANumber,
+          "The offset must be a number.",
+        ));
         return None
       },
     };
@@ -108,21 +157,36 @@ impl FormatComponentInput {
   {
     // Validate the bits.
     if self.bits.is_empty() {
-      validation.bits.push("The `bits` value is required.".to_string());
+      validation.bits.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "The `bits` value is required.",
+      ));
       return None;
     }
     let maybe_bits = self.bits.parse::<u8>();
     let bits = match maybe_bits {
       Ok(bits) => {
-        if bits > 32 {
-          validation.bits.push("The bits must be <= 32.".to_string());
+        if bits == 0 {
+          validation.bits.push(ValidationMessage::new(
+            DiagnosticCode::OutOfRange,
+            "The bits must be at least 1.",
+          ).param("min", 1));
+          return None;
+        } else if bits > 32 {
+          validation.bits.push(ValidationMessage::new(
+            DiagnosticCode::OutOfRange,
+            "The bits must be <= 32.",
+          ).param("max", 32));
           return None;
         } else {
           bits
         }
       },
       _ => {
-        validation.bits.push("The bits must be a number.".to_string());
+        validation.bits.push(ValidationMessage::new(
+          DiagnosticCode::NotANumber,
+          "The bits must be a number.",
+        ));
         return None;
       },
     };
@@ -135,18 +199,28 @@ impl FormatComponentInput {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatComponentValidation {
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) errors: Vec<ValidationMessage>,
+
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub(crate) errors: Vec<String>,
+  pub(crate) name: Vec<ValidationMessage>,
 
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub(crate) name: Vec<String>,
+  pub(crate) offset: Vec<ValidationMessage>,
 
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub(crate) offset: Vec<String>,
+  pub(crate) bits: Vec<ValidationMessage>,
 
+  #[serde(rename = "type")]
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub(crate) bits: Vec<String>,
+  pub(crate) kind: Vec<ValidationMessage>,
 }
 impl FormatComponentValidation {
   pub(crate) fn new() -> Self {
@@ -155,6 +229,7 @@ impl FormatComponentValidation {
       name: Vec::new(),
       offset: Vec::new(),
       bits: Vec::new(),
+      kind: Vec::new(),
     }
   }
 
@@ -163,6 +238,16 @@ impl FormatComponentValidation {
       && self.name.is_empty()
       && self.offset.is_empty()
       && self.bits.is_empty()
+      && self.kind.is_empty()
+  }
+}
+impl Diagnose for FormatComponentValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    diagnose_messages(&self.name, path, "name", out);
+    diagnose_messages(&self.offset, path, "offset", out);
+    diagnose_messages(&self.bits, path, "bits", out);
+    diagnose_messages(&self.kind, path, "type", out);
   }
 }
 
@@ -175,6 +260,11 @@ pub(crate) struct FormatComponentSelector {
   pub(crate) word: u8,
   pub(crate) offset: u8,
   pub(crate) count: u8,
+
+  // Whether the component is two's complement, so that statistics and
+  // histograms see negative values as negative.
+  #[serde(default)]
+  pub(crate) signed: bool,
 }
 impl FormatComponentSelector {
   pub(crate) const WORD_SHIFT: u32 = 0;
@@ -189,22 +279,27 @@ impl FormatComponentSelector {
   pub(crate) const COUNT_BITS: u32 = 5;
   pub(crate) const COUNT_MASK: u32 = (1 << Self::COUNT_BITS) - 1;
 
+  // Bits 15-19 hold the out index of a `FormatComponentSelectorReadSpec`.
+  pub(crate) const SIGNED_SHIFT: u32 = 20;
+
   pub(crate) const fn new(word: u8, offset: u8, count: u8) -> Self {
-    Self { word, offset, count }
+    Self { word, offset, count, signed: false }
   }
 
   pub(crate) fn to_u32(&self) -> u32 {
     let word = ((self.word as u32) & Self::WORD_MASK) << Self::WORD_SHIFT;
     let offset = ((self.offset as u32) & Self::OFFSET_MASK) << Self::OFFSET_SHIFT;
     let count = ((self.count as u32) & Self::COUNT_MASK) << Self::COUNT_SHIFT;
-    word | offset | count
+    let signed = (self.signed as u32) << Self::SIGNED_SHIFT;
+    word | offset | count | signed
   }
 
   pub(crate) fn from_u32(value: u32) -> Self {
     let word = ((value >> Self::WORD_SHIFT) & Self::WORD_MASK) as u8;
     let offset = ((value >> Self::OFFSET_SHIFT) & Self::OFFSET_MASK) as u8;
     let count = ((value >> Self::COUNT_SHIFT) & Self::COUNT_MASK) as u8;
-    Self { word, offset, count }
+    let signed = (value >> Self::SIGNED_SHIFT) & 1 != 0;
+    Self { word, offset, count, signed }
   }
 }
 
@@ -0,0 +1,212 @@
+use crate::data::{ DiagnosticCode, ValidationMessage };
+
+/**
+ * How the bits of a format component are interpreted.
+ */
+#[derive(Debug, Clone, PartialEq, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum FormatComponentType {
+  #[default]
+  Unsigned,
+
+  // Two's complement.
+  Signed,
+
+  // An integer, signed or not, counting multiples of `scale`.
+  // e.g. with a scale of 0.5, the raw value -25 stands for -12.5.
+  Fixed {
+    scale: f64,
+
+    #[serde(default)]
+    signed: bool,
+  },
+
+  // Unsigned values, some of which have names.
+  Enum {
+    values: Vec<FormatEnumValue>,
+  },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct FormatEnumValue {
+  pub(crate) name: String,
+  pub(crate) value: u32,
+}
+
+/**
+ * A decoded component value.
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+#[serde(untagged)]
+pub(crate) enum FormatValue {
+  Integer(i64),
+  Number(f64),
+  Name(String),
+}
+
+impl FormatComponentType {
+  pub(crate) fn is_unsigned(&self) -> bool {
+    *self == FormatComponentType::Unsigned
+  }
+
+  /** Whether raw values are two's complement. */
+  pub(crate) fn is_signed(&self) -> bool {
+    match self {
+      FormatComponentType::Signed => true,
+      FormatComponentType::Fixed { signed, .. } => *signed,
+      _ => false,
+    }
+  }
+
+  /**
+   * Check the type against the width of its component.
+   */
+  pub(crate) fn validate(&self, bits: Option<u8>, errors: &mut Vec<ValidationMessage>) {
+    match self {
+      FormatComponentType::Unsigned | FormatComponentType::Signed => {},
+      FormatComponentType::Fixed { scale, .. } => {
+        if !scale.is_finite() || *scale <= 0.0 {
+          errors.push(ValidationMessage::new(
+            DiagnosticCode::OutOfRange,
+            "The scale must be a positive number.",
+          ).at("Fixed").at("scale"));
+        }
+      },
+      FormatComponentType::Enum { values } => {
+        if values.is_empty() {
+          errors.push(ValidationMessage::new(
+            DiagnosticCode::Required,
+            "An enum needs at least one named value.",
+          ).at("Enum").at("values"));
+        }
+        for (i, enum_value) in values.iter().enumerate() {
+          let at_value = |message: ValidationMessage, member: &str| {
+            message.param("name", enum_value.name.clone())
+              .at("Enum").at("values").at(i).at(member)
+          };
+          if !is_symbol_name(&enum_value.name) {
+            errors.push(at_value(ValidationMessage::new(
+              DiagnosticCode::InvalidName,
+              format!(
+                "Enum name '{}' must be letters, digits and underscores, \
+                 not starting with a digit.",
+                enum_value.name
+              ),
+            ), "name"));
+          }
+          if values[..i].iter().any(|other| other.name == enum_value.name) {
+            errors.push(at_value(ValidationMessage::new(
+              DiagnosticCode::Duplicate,
+              format!("Enum name '{}' is used more than once.", enum_value.name),
+            ), "name"));
+          }
+          if let Some(other) =
+            values[..i].iter().find(|other| other.value == enum_value.value)
+          {
+            errors.push(at_value(ValidationMessage::new(
+              DiagnosticCode::Duplicate,
+              format!(
+                "Enum names '{}' and '{}' have the same value {}.",
+                other.name, enum_value.name, enum_value.value
+              ),
+            ).param("value", enum_value.value), "value"));
+          }
+          if let Some(bits) = bits {
+            if (enum_value.value as u64) >= (1_u64 << bits) {
+              errors.push(at_value(ValidationMessage::new(
+                DiagnosticCode::OutOfRange,
+                format!(
+                  "Enum value {} ('{}') does not fit in {} bits.",
+                  enum_value.value, enum_value.name, bits
+                ),
+              ).param("value", enum_value.value).param("bits", bits), "value"));
+            }
+          }
+        }
+      },
+    }
+  }
+
+  /**
+   * Interpret the raw bits of a `bits`-wide component.
+   */
+  pub(crate) fn decode(&self, bits: u8, raw: u32) -> FormatValue {
+    match self {
+      FormatComponentType::Unsigned => FormatValue::Integer(raw as i64),
+      FormatComponentType::Signed =>
+        FormatValue::Integer(sign_extend(bits, raw)),
+      FormatComponentType::Fixed { scale, signed } => {
+        let count = if *signed { sign_extend(bits, raw) } else { raw as i64 };
+        FormatValue::Number(count as f64 * scale)
+      },
+      FormatComponentType::Enum { values } => {
+        match values.iter().find(|enum_value| enum_value.value == raw) {
+          Some(enum_value) => FormatValue::Name(enum_value.name.clone()),
+          None => FormatValue::Integer(raw as i64),
+        }
+      },
+    }
+  }
+
+  /**
+   * The integer a program works with to stand for `value`, which is
+   * either a number or, for enums, a value name.  Signed values are
+   * returned sign-extended rather than truncated to the component width.
+   */
+  pub(crate) fn encode_literal(&self, bits: u8, value: &str)
+    -> Result<i64, String>
+  {
+    // Validation rejects zero-bit components, but stored rules are not
+    // revalidated; such a component can only hold zero.
+    let (min, max) = if bits == 0 {
+      (0, 0)
+    } else if self.is_signed() {
+      (-(1_i64 << (bits - 1)), (1_i64 << (bits - 1)) - 1)
+    } else {
+      (0, (1_i64 << bits) - 1)
+    };
+    let count = match self {
+      FormatComponentType::Fixed { scale, .. } => {
+        let number = value.parse::<f64>()
+          .map_err(|_| format!("'{}' is not a number", value))?;
+        (number / scale).round() as i64
+      },
+      FormatComponentType::Enum { values } => {
+        match values.iter().find(|enum_value| enum_value.name == value) {
+          Some(enum_value) => enum_value.value as i64,
+          None => value.parse::<i64>()
+            .map_err(|_| format!("'{}' is not a value of this enum", value))?,
+        }
+      },
+      _ => value.parse::<i64>()
+        .map_err(|_| format!("'{}' is not an integer", value))?,
+    };
+    if count < min || count > max {
+      return Err(format!("'{}' is out of range for this component", value));
+    }
+    Ok(count)
+  }
+}
+
+fn sign_extend(bits: u8, raw: u32) -> i64 {
+  if bits == 0 {
+    return 0;
+  }
+  let unused = 64 - bits as u32;
+  ((raw as i64) << unused) >> unused
+}
+
+/** Whether a name can be referred to from shasm. */
+pub(crate) fn is_symbol_name(name: &str) -> bool {
+  let mut chars = name.chars();
+  match chars.next() {
+    Some(c) if c.is_ascii_alphabetic() || c == '_' => {},
+    _ => return false,
+  }
+  chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
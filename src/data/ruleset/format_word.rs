@@ -1,3 +1,10 @@
+use crate::data::{
+  diagnose_messages,
+  Diagnose,
+  Diagnostic,
+  DiagnosticCode,
+  ValidationMessage,
+};
 use super::format_component::{
   FormatComponentInput,
   FormatComponentRules,
@@ -9,6 +16,7 @@ use super::format_component::{
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatWordRules {
   // The name of the word.
   pub(crate) name: String,
@@ -31,6 +39,7 @@ impl FormatWordRules {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatWordInput {
   // The name of the word.
   pub(crate) name: String,
@@ -40,35 +49,90 @@ pub(crate) struct FormatWordInput {
 }
 impl FormatWordInput {
   pub(crate) fn to_validated(&self) -> Result<FormatWordRules, FormatWordValidation> {
-    let mut validation = FormatWordValidation {
-      errors: Vec::new(),
-      components: Vec::new(),
-    };
+    let mut validation = FormatWordValidation::new();
 
     // Validate the name.
     if self.name.is_empty() {
-      validation.errors.push("The name is required.".to_string());
+      validation.errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "The name is required.",
+      ).at("name"));
     }
 
-    // Validate each component.
+    // Validate each component.  Component validations line up with the
+    // input components, with empty validations for valid components.
     let mut component_rules = Vec::new();
     for component in &self.components {
       let maybe_validated = component.to_validated();
       match maybe_validated {
-        Ok(validated) => component_rules.push(validated),
-        Err(component_validation) => validation.components.push(component_validation),
+        Ok(validated) => {
+          component_rules.push(Some(validated));
+          validation.components.push(FormatComponentValidation::new());
+        },
+        Err(component_validation) => {
+          component_rules.push(None);
+          validation.components.push(component_validation);
+        },
       }
     }
 
+    self.validate_component_names(&mut validation);
+    Self::validate_component_overlaps(&component_rules, &mut validation);
+
     if validation.is_valid() {
       Ok(FormatWordRules {
         name: self.name.clone(),
-        components: component_rules,
+        components: component_rules.into_iter().flatten().collect(),
       })
     } else {
       Err(validation)
     }
   }
+
+  fn validate_component_names(&self, validation: &mut FormatWordValidation) {
+    for (i, component) in self.components.iter().enumerate() {
+      if component.name.is_empty() {
+        continue;
+      }
+      let earlier = self.components[..i].iter()
+        .position(|other| other.name == component.name);
+      if let Some(j) = earlier {
+        validation.components[i].name.push(ValidationMessage::new(
+          DiagnosticCode::Duplicate,
+          format!(
+            "The name '{}' is already used by component {}.",
+            component.name, j
+          ),
+        ).param("name", component.name.clone()).param("other", j));
+      }
+    }
+  }
+
+  /**
+   * Report each overlap on the bits of the later of the two components,
+   * naming the earlier one.
+   */
+  fn validate_component_overlaps(
+    component_rules: &[Option<FormatComponentRules>],
+    validation: &mut FormatWordValidation,
+  ) {
+    for (i, maybe_component) in component_rules.iter().enumerate() {
+      if let Some(component) = maybe_component {
+        for other in component_rules[..i].iter().flatten() {
+          if component.overlaps(other) {
+            validation.components[i].errors.push(ValidationMessage::new(
+              DiagnosticCode::Overlap,
+              format!(
+                "Bits {}-{} overlap component '{}' (bits {}-{}).",
+                component.offset, component.last_bit(),
+                other.name, other.offset, other.last_bit()
+              ),
+            ).param("other", other.name.clone()).at("bits"));
+          }
+        }
+      }
+    }
+  }
 }
 
 /**
@@ -76,16 +140,34 @@ impl FormatWordInput {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FormatWordValidation {
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
-  pub(crate) errors: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
 
+  #[serde(default)]
   #[serde(skip_serializing_if = "Vec::is_empty")]
   pub(crate) components: Vec<FormatComponentValidation>,
 }
 impl FormatWordValidation {
+  pub(crate) fn new() -> Self {
+    Self {
+      errors: Vec::new(),
+      components: Vec::new(),
+    }
+  }
+
   pub(crate) fn is_valid(&self) -> bool {
     self.errors.is_empty()
       && self.components.iter().all(|c| c.is_valid())
   }
 }
+impl Diagnose for FormatWordValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    for (i, component) in self.components.iter().enumerate() {
+      component.diagnose(&format!("{}/components/{}", path, i), out);
+    }
+  }
+}
@@ -0,0 +1,327 @@
+use crate::shady_vm::ShasmProgram;
+use super::{
+  format_component::{ FormatComponentInput, FormatComponentRules },
+  format_word::FormatWordInput,
+  palette::PaletteRules,
+  terrain_gen::{ TerrainGenInput, TerrainGenRules, TerrainGenValidation },
+  terrain_gen_randgen::{ TerrainGenPerlinInput, TerrainGenPerlinRules },
+  terrain_gen_stage::TerrainGenStageCache,
+  terrain_gen_tests::TerrainGenProgramTest,
+  Ruleset,
+};
+
+/**
+ * The changes a derived ruleset makes to its base.
+ *
+ * A derived ruleset names a `base` ruleset and keeps only these overrides.
+ * Its effective terrain generator and palettes are the base's, with the
+ * overrides applied, and are recomputed whenever the ruleset is read so
+ * that changes to the base carry through.
+ */
+#[derive(Debug, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesetOverrides {
+  // Replaces the base's noise parameters.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) perlin: Option<TerrainGenPerlinRules>,
+
+  // Replace the base's programs, individually.
+  #[serde(rename = "initProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) init_program: Option<ShasmProgram>,
+
+  #[serde(rename = "pairwiseProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) pairwise_program: Option<ShasmProgram>,
+
+  #[serde(rename = "mergeProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) merge_program: Option<ShasmProgram>,
+
+  #[serde(rename = "finalProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) final_program: Option<ShasmProgram>,
+
+  // Replaces the base's program tests.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) tests: Option<Vec<TerrainGenProgramTest>>,
+
+  // Components added to the base's format.
+  #[serde(rename = "addedComponents")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) added_components: Vec<FormatComponentAddition>,
+
+  // Palettes that replace the base's palettes of the same name, or are
+  // added after them.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) palettes: Vec<PaletteRules>,
+}
+impl RulesetOverrides {
+  pub(crate) fn new_example() -> Self {
+    RulesetOverrides {
+      init_program: Some(ShasmProgram::new_example()),
+      added_components: vec![FormatComponentAddition::new_example()],
+      ..Default::default()
+    }
+  }
+
+  pub(crate) fn is_empty(&self) -> bool {
+    self.perlin.is_none()
+      && self.init_program.is_none()
+      && self.pairwise_program.is_none()
+      && self.merge_program.is_none()
+      && self.final_program.is_none()
+      && self.tests.is_none()
+      && self.added_components.is_empty()
+      && self.palettes.is_empty()
+  }
+
+  pub(crate) fn to_input(&self) -> RulesetOverridesInput {
+    let program_text = |program: &Option<ShasmProgram>| {
+      program.as_ref().map(|program| program.program_text.clone())
+    };
+    RulesetOverridesInput {
+      perlin: self.perlin.as_ref().map(|perlin| perlin.to_input()),
+      init_program: program_text(&self.init_program),
+      pairwise_program: program_text(&self.pairwise_program),
+      merge_program: program_text(&self.merge_program),
+      final_program: program_text(&self.final_program),
+      tests: self.tests.clone(),
+      added_components: self.added_components.iter()
+        .map(|addition| addition.to_input())
+        .collect(),
+      palettes: self.palettes.clone(),
+    }
+  }
+}
+
+/**
+ * A component added to a word of the base format.  If the base format has
+ * no word of that name, a new word is added.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct FormatComponentAddition {
+  pub(crate) word: String,
+  pub(crate) component: FormatComponentRules,
+}
+impl FormatComponentAddition {
+  pub(crate) fn new_example() -> Self {
+    FormatComponentAddition {
+      word: "ExampleWord".to_string(),
+      component: FormatComponentRules {
+        name: "Component3".to_string(),
+        offset: 16,
+        bits: 8,
+        kind: Default::default(),
+      },
+    }
+  }
+
+  pub(crate) fn to_input(&self) -> FormatComponentAdditionInput {
+    FormatComponentAdditionInput {
+      word: self.word.clone(),
+      component: self.component.to_input(),
+    }
+  }
+}
+
+/**
+ * The input for the overrides of a derived ruleset.
+ */
+#[derive(Debug, Clone, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesetOverridesInput {
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) perlin: Option<TerrainGenPerlinInput>,
+
+  #[serde(rename = "initProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) init_program: Option<String>,
+
+  #[serde(rename = "pairwiseProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) pairwise_program: Option<String>,
+
+  #[serde(rename = "mergeProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) merge_program: Option<String>,
+
+  #[serde(rename = "finalProgram")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) final_program: Option<String>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) tests: Option<Vec<TerrainGenProgramTest>>,
+
+  #[serde(rename = "addedComponents")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) added_components: Vec<FormatComponentAdditionInput>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) palettes: Vec<PaletteRules>,
+}
+impl RulesetOverridesInput {
+  pub(crate) fn is_empty(&self) -> bool {
+    self.perlin.is_none()
+      && self.init_program.is_none()
+      && self.pairwise_program.is_none()
+      && self.merge_program.is_none()
+      && self.final_program.is_none()
+      && self.tests.is_none()
+      && self.added_components.is_empty()
+      && self.palettes.is_empty()
+  }
+
+  /**
+   * Apply the overrides to the input form of a base terrain generator and
+   * validate the result.  On success, returns the effective terrain
+   * generator along with the validated overrides.
+   */
+  pub(crate) fn to_validated(&self, base: &TerrainGenRules)
+    -> Result<(TerrainGenRules, RulesetOverrides), TerrainGenValidation>
+  {
+    self.to_validated_cached(base, &mut TerrainGenStageCache::default())
+  }
+
+  pub(crate) fn to_validated_cached(&self,
+    base: &TerrainGenRules,
+    cache: &mut TerrainGenStageCache,
+  ) -> Result<(TerrainGenRules, RulesetOverrides), TerrainGenValidation> {
+    let mut merged_input = base.to_input();
+    self.apply(&mut merged_input);
+    let merged = merged_input.to_validated_cached(cache)?;
+
+    // Take each overridden value back out of the validated result.
+    let stage = &merged.stage;
+    let overridden = |input: &Option<String>, program: &ShasmProgram| {
+      input.as_ref().map(|_| program.clone())
+    };
+    let added_components = self.added_components.iter()
+      .filter_map(|addition| {
+        stage.format.component(&addition.word, &addition.component.name)
+          .map(|component| FormatComponentAddition {
+            word: addition.word.clone(),
+            component: component.clone(),
+          })
+      })
+      .collect();
+    let overrides = RulesetOverrides {
+      perlin: self.perlin.as_ref().map(|_| merged.perlin.clone()),
+      init_program: overridden(&self.init_program, &stage.init_program),
+      pairwise_program:
+        overridden(&self.pairwise_program, &stage.pairwise_program),
+      merge_program: overridden(&self.merge_program, &stage.merge_program),
+      final_program: overridden(&self.final_program, &stage.final_program),
+      tests: self.tests.clone(),
+      added_components,
+      palettes: self.palettes.clone(),
+    };
+    Ok((merged, overrides))
+  }
+
+  fn apply(&self, terrain_gen: &mut TerrainGenInput) {
+    if let Some(perlin) = &self.perlin {
+      terrain_gen.perlin = perlin.clone();
+    }
+    let stage = &mut terrain_gen.stage;
+    let programs = [
+      (&self.init_program, &mut stage.init_program),
+      (&self.pairwise_program, &mut stage.pairwise_program),
+      (&self.merge_program, &mut stage.merge_program),
+      (&self.final_program, &mut stage.final_program),
+    ];
+    for (program_override, program) in programs {
+      if let Some(text) = program_override {
+        *program = text.clone();
+      }
+    }
+    if let Some(tests) = &self.tests {
+      stage.tests = tests.clone();
+    }
+    for addition in &self.added_components {
+      let words = &mut stage.format.word_formats;
+      match words.iter_mut().find(|word| word.name == addition.word) {
+        Some(word) => word.components.push(addition.component.clone()),
+        None => words.push(FormatWordInput {
+          name: addition.word.clone(),
+          components: vec![addition.component.clone()],
+        }),
+      }
+    }
+  }
+
+  /**
+   * The base's palettes with this ruleset's palettes applied.
+   */
+  pub(crate) fn merge_palettes(&self, base: &[PaletteRules])
+    -> Vec<PaletteRules>
+  {
+    let mut palettes = base.to_vec();
+    for palette in &self.palettes {
+      match palettes.iter_mut().find(|p| p.name == palette.name) {
+        Some(existing) => *existing = palette.clone(),
+        None => palettes.push(palette.clone()),
+      }
+    }
+    palettes
+  }
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct FormatComponentAdditionInput {
+  pub(crate) word: String,
+  pub(crate) component: FormatComponentInput,
+}
+
+impl Ruleset {
+  /**
+   * Recompute this derived ruleset's effective terrain generator and
+   * palettes from its (already resolved) base.
+   */
+  pub(crate) fn resolve_against(&self, base: &Ruleset) -> Result<Ruleset, String> {
+    let overrides_input = self.overrides.to_input();
+    let (terrain_gen, _) = overrides_input.to_validated(&base.terrain_gen)
+      .map_err(|_| format!(
+        "The overrides of ruleset '{}' are no longer valid for its base '{}'",
+        self.name, base.name
+      ))?;
+    Ok(Ruleset {
+      terrain_gen,
+      palettes: overrides_input.merge_palettes(&base.palettes),
+      ..self.clone()
+    })
+  }
+
+  /**
+   * This ruleset as a standalone ruleset, with no base.
+   */
+  pub(crate) fn flattened(&self) -> Ruleset {
+    Ruleset {
+      base: None,
+      overrides: RulesetOverrides::default(),
+      ..self.clone()
+    }
+  }
+}
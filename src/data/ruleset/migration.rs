@@ -0,0 +1,95 @@
+use serde_json::Value;
+use super::Ruleset;
+
+/**
+ * Current version of the on-disk ruleset schema.
+ *
+ * Bump this whenever a change to `Ruleset` (or anything it contains)
+ * would stop older files from deserializing, and append a migration to
+ * `MIGRATIONS` that rewrites the previous version's JSON into the new
+ * shape.  Purely additive fields with serde defaults do not need a bump.
+ */
+pub(crate) const RULESET_SCHEMA_VERSION: u32 = 1;
+
+/** Name of the version field in ruleset JSON. */
+const SCHEMA_VERSION_KEY: &str = "schemaVersion";
+
+/**
+ * A migration rewrites ruleset JSON from one schema version to the next.
+ * `MIGRATIONS[i]` migrates from version `i` to version `i + 1`.
+ */
+type RulesetMigration = fn(&mut serde_json::Map<String, Value>)
+  -> Result<(), String>;
+
+const MIGRATIONS: &[RulesetMigration] = &[
+  migrate_v0_to_v1,
+];
+
+/**
+ * The result of loading ruleset JSON.
+ */
+pub(crate) struct MigratedRuleset {
+  pub(crate) ruleset: Ruleset,
+
+  // The schema version the JSON was stored with.
+  pub(crate) from_version: u32,
+}
+impl MigratedRuleset {
+  pub(crate) fn was_migrated(&self) -> bool {
+    self.from_version != RULESET_SCHEMA_VERSION
+  }
+}
+
+/**
+ * Parse ruleset JSON of any known schema version, migrating it forward
+ * to the current version.
+ */
+pub(crate) fn parse_ruleset_json(json: &str) -> Result<MigratedRuleset, String> {
+  let value: Value = serde_json::from_str(json)
+    .map_err(|err| format!("Invalid ruleset JSON: {}", err))?;
+  migrate_ruleset_value(value)
+}
+
+pub(crate) fn migrate_ruleset_value(value: Value)
+  -> Result<MigratedRuleset, String>
+{
+  let mut object = match value {
+    Value::Object(object) => object,
+    _ => return Err("Ruleset JSON is not an object".to_string()),
+  };
+
+  // Files written before versioning was introduced carry no version.
+  let from_version = match object.get(SCHEMA_VERSION_KEY) {
+    None => 0,
+    Some(version) => version.as_u64()
+      .and_then(|version| u32::try_from(version).ok())
+      .ok_or_else(|| format!("Invalid {}: {}", SCHEMA_VERSION_KEY, version))?,
+  };
+  if from_version > RULESET_SCHEMA_VERSION {
+    return Err(format!(
+      "Ruleset schema version {} is newer than supported version {}",
+      from_version, RULESET_SCHEMA_VERSION
+    ));
+  }
+
+  for version in from_version .. RULESET_SCHEMA_VERSION {
+    MIGRATIONS[version as usize](&mut object).map_err(|err| {
+      format!("Migrating ruleset from version {}: {}", version, err)
+    })?;
+    object.insert(SCHEMA_VERSION_KEY.to_string(), Value::from(version + 1));
+  }
+
+  let ruleset = serde_json::from_value(Value::Object(object))
+    .map_err(|err| format!("Invalid ruleset: {}", err))?;
+  Ok(MigratedRuleset { ruleset, from_version })
+}
+
+/**
+ * Version 1 introduces the `schemaVersion` field itself; the rest of the
+ * schema is unchanged.
+ */
+fn migrate_v0_to_v1(_object: &mut serde_json::Map<String, Value>)
+  -> Result<(), String>
+{
+  Ok(())
+}
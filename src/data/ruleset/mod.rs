@@ -1,11 +1,23 @@
+mod bundle;
 mod format;
 mod format_word;
 mod format_component;
+mod format_type;
+mod inheritance;
+mod migration;
+mod palette;
 mod terrain_gen;
+mod terrain_gen_params;
 mod terrain_gen_randgen;
 mod terrain_gen_stage;
+mod terrain_gen_tests;
 
 pub(crate) use self::{
+  bundle::{
+    RulesetBundle,
+    RulesetImportConflict,
+    RulesetImportError,
+  },
   format::{
     FormatRules,
     FormatInput,
@@ -23,30 +35,65 @@ pub(crate) use self::{
     FormatComponentSelector,
     FormatComponentSelectorReadSpec,
   },
+  format_type::{
+    FormatComponentType,
+    FormatEnumValue,
+    FormatValue,
+  },
+  inheritance::{
+    FormatComponentAddition,
+    FormatComponentAdditionInput,
+    RulesetOverrides,
+    RulesetOverridesInput,
+  },
+  migration::{ parse_ruleset_json, RULESET_SCHEMA_VERSION },
+  palette::PaletteRules,
   terrain_gen::{
     TerrainGenRules,
     TerrainGenInput,
     TerrainGenValidation,
   },
+  terrain_gen_params::{
+    TerrainGenParamRules,
+    TerrainGenParamInput,
+    TerrainGenParamValidation,
+  },
   terrain_gen_randgen::{
     TerrainGenPerlinRules,
     TerrainGenPerlinInput,
     TerrainGenPerlinValidation,
   },
   terrain_gen_stage::{
+    TerrainGenStageCache,
     TerrainGenStageRules,
     TerrainGenStageInput,
     TerrainGenStageValidation,
   },
+  terrain_gen_tests::{
+    TerrainGenProgramKind,
+    TerrainGenProgramTest,
+    TerrainGenProgramTestValidation,
+  },
 };
 
-use crate::data_store::DataStore;
+use crate::{
+  data::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    JsonPatchOp,
+    ValidationMessage,
+  },
+  data_store::DataStore,
+};
 
 /**
  * The entry in a directory of rulesets.
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct RulesetEntry {
   pub(crate) name: String,
   pub(crate) description: String,
@@ -60,23 +107,51 @@ pub(crate) struct RulesetEntry {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct Ruleset {
+  // The schema version this ruleset was written with.  See `migration`.
+  // Rulesets written before versioning carry none, i.e. version 0.
+  #[serde(rename = "schemaVersion")]
+  #[serde(default)]
+  pub(crate) schema_version: u32,
+
   // The name of the ruleset.
   pub(crate) name: String,
 
   // The description of the ruleset.
   pub(crate) description: String,
 
-  // The terrain generator definition.
+  // The ruleset this one is derived from, if any.  See `inheritance`.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) base: Option<String>,
+
+  // What a derived ruleset changes relative to its base.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "RulesetOverrides::is_empty")]
+  pub(crate) overrides: RulesetOverrides,
+
+  // The terrain generator definition.  For a derived ruleset, this is the
+  // effective definition, recomputed from the base on every read.
   #[serde(rename = "terrainGen")]
   pub(crate) terrain_gen: TerrainGenRules,
+
+  // Colour palettes for rendering map layers.  Effective palettes, for a
+  // derived ruleset.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) palettes: Vec<PaletteRules>,
 }
 impl Ruleset {
   pub(crate) fn new_example() -> Self {
     Ruleset {
+      schema_version: RULESET_SCHEMA_VERSION,
       name: "Example Ruleset".to_string(),
       description: "An example ruleset.".to_string(),
+      base: None,
+      overrides: RulesetOverrides::default(),
       terrain_gen: TerrainGenRules::new_example(),
+      palettes: vec![PaletteRules::new_example()],
     }
   }
 
@@ -87,95 +162,351 @@ impl Ruleset {
     }
   }
 
+  pub(crate) fn new_derived_example() -> Self {
+    let base = Self::new_example();
+    let derived = Ruleset {
+      name: "Example Derived Ruleset".to_string(),
+      description: "The example ruleset, with one more component.".to_string(),
+      base: Some(base.name.clone()),
+      overrides: RulesetOverrides::new_example(),
+      ..base.clone()
+    };
+    derived.resolve_against(&base)
+      .expect("Failed to resolve example derived ruleset")
+  }
+
   pub(crate) fn to_input(&self) -> RulesetInput {
     RulesetInput {
       name: self.name.clone(),
       description: self.description.clone(),
+      base: self.base.clone(),
+      overrides: self.overrides.to_input(),
       terrain_gen: self.terrain_gen.to_input(),
+      palettes: self.palettes.clone(),
     }
   }
+
+  pub(crate) fn palette(&self, name: &str) -> Option<&PaletteRules> {
+    self.palettes.iter().find(|palette| palette.name == name)
+  }
 }
 
 /**
  * The input for a ruleset.
+ *
+ * When `base` is given, `terrainGen` and `palettes` are ignored: the
+ * ruleset takes them from its base, with `overrides` applied.
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct RulesetInput {
   pub(crate) name: String,
   pub(crate) description: String,
 
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) base: Option<String>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "RulesetOverridesInput::is_empty")]
+  pub(crate) overrides: RulesetOverridesInput,
+
   #[serde(rename = "terrainGen")]
   pub(crate) terrain_gen: TerrainGenInput,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) palettes: Vec<PaletteRules>,
 }
 impl RulesetInput {
+  const MAX_NAME_LENGTH: usize = 64;
   const MAX_DESCRIPTION_LENGTH: usize = 100;
 
   pub(crate) fn new() -> Self {
     RulesetInput {
       name: String::new(),
       description: String::new(),
+      base: None,
+      overrides: RulesetOverridesInput::default(),
       terrain_gen: TerrainGenInput::new(),
+      palettes: Vec::new(),
     }
   }
 
   pub(crate) fn to_validated(&self,
     store: &DataStore,
     update_existing: Option<&str>,
+  ) -> Result<Ruleset, RulesetValidation> {
+    let resolved = self.resolve_terrain_gen(
+      store,
+      update_existing,
+      &mut TerrainGenStageCache::default(),
+    );
+    self.to_validated_with(store, update_existing, &resolved)
+  }
+
+  /**
+   * Resolve and validate the terrain generator, against the base for a
+   * derived ruleset.  The stage's programs and tests are only checked
+   * again where they differ from what `cache` holds.
+   */
+  pub(crate) fn resolve_terrain_gen(&self,
+    store: &DataStore,
+    update_existing: Option<&str>,
+    cache: &mut TerrainGenStageCache,
+  ) -> ResolvedTerrainGen {
+    // A derived ruleset's terrain generator and palettes come from its
+    // base, so there is nothing more to validate if the base is unusable.
+    match &self.base {
+      None => ResolvedTerrainGen {
+        terrain_gen: self.terrain_gen.to_validated_cached(cache),
+        overrides: None,
+        base_palettes: None,
+        base_errors: Vec::new(),
+      },
+      Some(base_name) => {
+        match self.read_base(store, base_name, update_existing) {
+          Ok(base) => {
+            let (terrain_gen, overrides) =
+              match self.overrides.to_validated_cached(&base.terrain_gen,
+                cache)
+              {
+                Ok((terrain_gen, overrides)) =>
+                  (Ok(terrain_gen), Some(overrides)),
+                Err(validation) => (Err(validation), None),
+              };
+            ResolvedTerrainGen {
+              terrain_gen,
+              overrides,
+              base_palettes: Some(base.palettes),
+              base_errors: Vec::new(),
+            }
+          },
+          Err(err) => ResolvedTerrainGen {
+            terrain_gen: Err(TerrainGenValidation::new()),
+            overrides: None,
+            base_palettes: None,
+            base_errors: vec![err],
+          },
+        }
+      },
+    }
+  }
+
+  /**
+   * Whether a patch operation could change the result of
+   * `resolve_terrain_gen`.
+   */
+  pub(crate) fn patch_affects_terrain_gen(&self, op: &JsonPatchOp) -> bool {
+    ["/terrainGen", "/base", "/overrides"].iter().any(|path| op.touches(path))
+      // The name matters to a base's cycle check.
+      || (self.base.is_some() && op.touches("/name"))
+  }
+
+  /**
+   * Validate the ruleset, with its terrain generator already resolved.
+   */
+  pub(crate) fn to_validated_with(&self,
+    store: &DataStore,
+    update_existing: Option<&str>,
+    resolved: &ResolvedTerrainGen,
   ) -> Result<Ruleset, RulesetValidation> {
     let errors = Vec::new();
     let mut name_errors = Vec::new();
     let mut description_errors = Vec::new();
+    let mut palette_errors = Vec::new();
+    let base_errors = resolved.base_errors.clone();
 
-    self.validate_name(&mut name_errors);
+    Self::validate_name(&self.name, store, update_existing, &mut name_errors);
     self.validate_description(&mut description_errors);
 
-    // Check for name conflicts unless we're updating an existing ruleset.
-    let check_for_name_conflict =
-      if let Some(existing_name) = update_existing {
-        self.name != existing_name
-      } else {
-        true
-      };
-    if check_for_name_conflict &&
-       store.rulesets().list().iter().any(|entry| entry.name == self.name)
-    {
-      name_errors.push("A ruleset with this name already exists.".to_string());
-    }
-
     log::debug!("Validating terrain generator... name_errors: {:?}, description_errors: {:?}",
       name_errors, description_errors);
 
-    let maybe_terrain_gen = self.terrain_gen.to_validated();
-    if name_errors.is_empty() && description_errors.is_empty() && maybe_terrain_gen.is_ok() {
+    let maybe_terrain_gen = resolved.terrain_gen.clone();
+    let overrides = resolved.overrides.clone();
+    let palettes = match (&self.base, &resolved.base_palettes) {
+      (None, _) => self.palettes.clone(),
+      (Some(_), Some(base_palettes)) =>
+        self.overrides.merge_palettes(base_palettes),
+      (Some(_), None) => Vec::new(),
+    };
+    // A derived ruleset's palettes are merged, so do not line up with
+    // its input.
+    let indexed = self.base.is_none();
+    Self::validate_palettes(&palettes, indexed, &mut palette_errors);
+
+    if name_errors.is_empty()
+      && description_errors.is_empty()
+      && palette_errors.is_empty()
+      && base_errors.is_empty()
+      && maybe_terrain_gen.is_ok()
+    {
       log::debug!("   => OK!");
       Ok(Ruleset {
+        schema_version: RULESET_SCHEMA_VERSION,
         name: self.name.clone(),
         description: self.description.clone(),
+        base: self.base.clone(),
+        overrides: overrides.unwrap_or_default(),
         terrain_gen: maybe_terrain_gen.unwrap(),
+        palettes,
       })
     } else {
       log::debug!("   => ERR!");
-      Err(RulesetValidation {
+      let mut validation = RulesetValidation {
         errors,
         name: name_errors,
         description: description_errors,
-        terrain_gen: maybe_terrain_gen.err(),
-      })
+        base: base_errors,
+        terrain_gen: maybe_terrain_gen.err().filter(|tgv| !tgv.is_valid()),
+        palettes: palette_errors,
+        diagnostics: Vec::new(),
+      };
+      validation.diagnostics = validation.diagnostics();
+      Err(validation)
+    }
+  }
+
+  /**
+   * Read the effective base ruleset, refusing a base whose chain of
+   * bases leads back to this ruleset.
+   */
+  fn read_base(&self,
+    store: &DataStore,
+    base_name: &str,
+    update_existing: Option<&str>,
+  ) -> Result<Ruleset, ValidationMessage> {
+    let store_error = |err: String| {
+      ValidationMessage::new(DiagnosticCode::StoreError, err)
+    };
+    let rulesets = store.rulesets().map_err(|err| {
+      store_error(format!("Failed to read ruleset store: {}", err))
+    })?;
+    if !rulesets.contains(base_name) {
+      return Err(ValidationMessage::new(
+        DiagnosticCode::NotFound,
+        format!("No such base ruleset: {}", base_name),
+      ).param("name", base_name));
+    }
+    let chain = rulesets.base_chain(base_name)
+      .map_err(|err| store_error(err.to_string()))?;
+    let is_self = |name: &String| {
+      *name == self.name || Some(name.as_str()) == update_existing
+    };
+    if chain.iter().any(is_self) {
+      let mut cycle = vec![self.name.clone()];
+      cycle.extend(chain.into_iter().take_while(|name| !is_self(name)));
+      cycle.push(self.name.clone());
+      return Err(ValidationMessage::new(
+        DiagnosticCode::Cycle,
+        format!("The base would form a cycle: {}", cycle.join(" -> ")),
+      ).param("cycle", cycle));
     }
+    rulesets.read(base_name).map_err(|err| store_error(err.to_string()))
   }
 
-  fn validate_name(&self, name_errors: &mut Vec<String>) {
-    if self.name.is_empty() {
-      name_errors.push("The name is required.".to_string());
+  /**
+   * Validate a ruleset name.  It must be at most `MAX_NAME_LENGTH`
+   * characters of letters, digits, spaces and `_-.'()`, with no leading
+   * or trailing space, and not taken by a stored ruleset unless that is
+   * `update_existing`, the ruleset being updated.
+   */
+  pub(crate) fn validate_name(
+    name: &str,
+    store: &DataStore,
+    update_existing: Option<&str>,
+    name_errors: &mut Vec<ValidationMessage>,
+  ) {
+    if name.is_empty() {
+      name_errors.push(
+        ValidationMessage::new(DiagnosticCode::Required, "The name is required.")
+      );
+      return;
+    }
+    if update_existing == Some(name) {
+      return;
+    }
+    if name.chars().count() > Self::MAX_NAME_LENGTH {
+      name_errors.push(ValidationMessage::new(
+        DiagnosticCode::TooLong,
+        "The name is too long.",
+      ).param("max", Self::MAX_NAME_LENGTH));
+      return;
+    }
+    let allowed = |c: char| c.is_alphanumeric() || " _-.'()".contains(c);
+    if !name.chars().all(allowed) || name.trim() != name {
+      name_errors.push(ValidationMessage::new(
+        DiagnosticCode::InvalidName,
+        "The name must be letters, digits, spaces and _-.'(), \
+         not starting or ending with a space.",
+      ));
+      return;
+    }
+    match store.rulesets() {
+      Ok(rulesets) if rulesets.contains(name) => name_errors.push(
+        ValidationMessage::new(
+          DiagnosticCode::AlreadyExists,
+          "A ruleset with this name already exists.",
+        ).param("name", name.to_string())
+      ),
+      Ok(_) => {},
+      Err(err) => name_errors.push(ValidationMessage::new(
+        DiagnosticCode::StoreError,
+        format!("Failed to read ruleset store: {}", err),
+      )),
     }
   }
 
-  fn validate_description(&self, descr_errors: &mut Vec<String>) {
+  fn validate_description(&self, descr_errors: &mut Vec<ValidationMessage>) {
     if self.description.len() > Self::MAX_DESCRIPTION_LENGTH {
-      descr_errors.push("The description is too long.".to_string());
+      descr_errors.push(ValidationMessage::new(
+        DiagnosticCode::TooLong,
+        "The description is too long.",
+      ).param("max", Self::MAX_DESCRIPTION_LENGTH));
     }
   }
+
+  /**
+   * Validate the palettes.  When `indexed`, messages are placed at the
+   * palette's index.
+   */
+  fn validate_palettes(
+    palettes: &[PaletteRules],
+    indexed: bool,
+    palette_errors: &mut Vec<ValidationMessage>,
+  ) {
+    for (i, palette) in palettes.iter().enumerate() {
+      let mut errors = Vec::new();
+      palette.validate(&mut errors);
+      if palettes[..i].iter().any(|other| other.name == palette.name) {
+        errors.push(ValidationMessage::new(
+          DiagnosticCode::Duplicate,
+          format!("Palette name '{}' is used more than once.", palette.name),
+        ).param("name", palette.name.clone()).at("name"));
+      }
+      palette_errors.extend(errors.into_iter().map(|error| {
+        if indexed { error.within(i) } else { error.param("palette", i) }
+      }));
+    }
+  }
+}
+
+/**
+ * The terrain generator of a ruleset input, resolved against its base and
+ * validated.  This is the expensive part of validating a ruleset, so it
+ * can be kept while edits leave the parts it depends on alone.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct ResolvedTerrainGen {
+  terrain_gen: Result<TerrainGenRules, TerrainGenValidation>,
+  overrides: Option<RulesetOverrides>,
+
+  // The base's palettes, for a derived ruleset whose base could be read.
+  base_palettes: Option<Vec<PaletteRules>>,
+
+  base_errors: Vec<ValidationMessage>,
 }
 
 /**
@@ -183,15 +514,31 @@ impl RulesetInput {
  */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct RulesetValidation {
-  pub(crate) errors: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
 
-  pub(crate) name: Vec<String>,
-  pub(crate) description: Vec<String>,
+  pub(crate) name: Vec<ValidationMessage>,
+  pub(crate) description: Vec<ValidationMessage>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) base: Vec<ValidationMessage>,
 
   #[serde(rename = "terrainGen")]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub(crate) terrain_gen: Option<TerrainGenValidation>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) palettes: Vec<ValidationMessage>,
+
+  // Every message above, flattened and located by its path in the input.
+  // For a derived ruleset, `terrainGen` paths are into the effective
+  // terrain generator.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) diagnostics: Vec<Diagnostic>,
 }
 impl RulesetValidation {
   pub(crate) fn new_valid() -> Self {
@@ -199,23 +546,48 @@ impl RulesetValidation {
       errors: Vec::new(),
       name: Vec::new(),
       description: Vec::new(),
+      base: Vec::new(),
       terrain_gen: None,
+      palettes: Vec::new(),
+      diagnostics: Vec::new(),
     }
   }
 
   pub(crate) fn new_example() -> Self {
-    RulesetValidation {
+    let mut validation = RulesetValidation {
       errors: Vec::new(),
-      name: vec!["Name is already used.".to_string()],
+      name: vec![ValidationMessage::new(
+        DiagnosticCode::AlreadyExists,
+        "Name is already used.",
+      ).param("name", "ExampleRuleset")],
       description: Vec::new(),
+      base: Vec::new(),
       terrain_gen: Some(TerrainGenValidation::new()),
-    }
+      palettes: Vec::new(),
+      diagnostics: Vec::new(),
+    };
+    validation.diagnostics = validation.diagnostics();
+    validation
   }
 
   pub(crate) fn is_valid(&self) -> bool {
     self.errors.is_empty()
       && self.name.is_empty()
       && self.description.is_empty()
+      && self.base.is_empty()
+      && self.palettes.is_empty()
       && self.terrain_gen.as_ref().map_or(true, |tgv| tgv.is_valid())
   }
 }
+impl Diagnose for RulesetValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    diagnose_messages(&self.name, path, "name", out);
+    diagnose_messages(&self.description, path, "description", out);
+    diagnose_messages(&self.base, path, "base", out);
+    if let Some(terrain_gen) = &self.terrain_gen {
+      terrain_gen.diagnose(&format!("{}/terrainGen", path), out);
+    }
+    diagnose_messages(&self.palettes, path, "palettes", out);
+  }
+}
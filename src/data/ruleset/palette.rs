@@ -0,0 +1,90 @@
+use crate::data::{ DiagnosticCode, ValidationMessage };
+
+/**
+ * A named colour palette, used to render a map layer in false colour.
+ *
+ * Stops are given in raw component value space, in ascending order.
+ * Values between stops are linearly interpolated, and values outside the
+ * range of stops are clamped to the nearest end.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct PaletteRules {
+  pub(crate) name: String,
+  pub(crate) stops: Vec<PaletteStop>,
+}
+impl PaletteRules {
+  pub(crate) fn new_example() -> Self {
+    PaletteRules {
+      name: "Elevation".to_string(),
+      stops: vec![
+        PaletteStop { value: 0, color: [0x10, 0x30, 0x80] },
+        PaletteStop { value: 0x7FFF, color: [0x40, 0xA0, 0x40] },
+        PaletteStop { value: 0xFFFF, color: [0xF0, 0xF0, 0xF0] },
+      ],
+    }
+  }
+
+  pub(crate) fn color_for(&self, value: u32) -> [u8; 3] {
+    let first = match self.stops.first() {
+      Some(stop) => stop,
+      None => return [0, 0, 0],
+    };
+    if value <= first.value {
+      return first.color;
+    }
+    for pair in self.stops.windows(2) {
+      let (lo, hi) = (&pair[0], &pair[1]);
+      if value <= hi.value {
+        let span = (hi.value - lo.value).max(1) as u64;
+        let t = (value - lo.value) as u64;
+        let mut color = [0_u8; 3];
+        for (c, out) in color.iter_mut().enumerate() {
+          let a = lo.color[c] as u64;
+          let b = hi.color[c] as u64;
+          *out = ((a * (span - t) + b * t) / span) as u8;
+        }
+        return color;
+      }
+    }
+    self.stops.last().unwrap().color
+  }
+
+  /**
+   * Check the palette.  Messages are at members of the palette.
+   */
+  pub(crate) fn validate(&self, errors: &mut Vec<ValidationMessage>) {
+    if self.name.is_empty() {
+      errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "A palette name is required.",
+      ).at("name"));
+    }
+    if self.stops.is_empty() {
+      errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        format!("Palette '{}' has no stops.", self.name),
+      ).param("name", self.name.clone()).at("stops"));
+    }
+    if let Some(i) =
+      self.stops.windows(2).position(|pair| pair[0].value >= pair[1].value)
+    {
+      errors.push(ValidationMessage::new(
+        DiagnosticCode::OutOfRange,
+        format!("Palette '{}' stops must be in ascending order.", self.name),
+      ).param("name", self.name.clone()).at("stops").at(i + 1));
+    }
+  }
+}
+
+/**
+ * A single colour stop in a palette.
+ */
+#[derive(Debug, Clone, Copy)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct PaletteStop {
+  pub(crate) value: u32,
+  pub(crate) color: [u8; 3],
+}
@@ -1,21 +1,39 @@
-use crate::shady_vm::{ ShadyRegister, ShasmProgram };
+use crate::{
+  data::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::{ ShadyRegister, ShasmProgram },
+};
 use super::{
+  terrain_gen_params::{
+    TerrainGenParamRules,
+    TerrainGenParamInput,
+    TerrainGenParamValidation,
+    TerrainGenSymbols,
+  },
   terrain_gen_randgen::{
     TerrainGenPerlinRules,
     TerrainGenPerlinInput,
     TerrainGenPerlinValidation,
   },
   terrain_gen_stage::{
+    TerrainGenStageCache,
     TerrainGenStageRules,
     TerrainGenStageInput,
     TerrainGenStageValidation,
   },
+  terrain_gen_tests::TerrainGenProgramTest,
   FormatRules
 };
 
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenRules {
   // The terrain generation starts with some number of optional perlin passes
   // to initialize one or more registers of the terrain generator VM.
@@ -23,12 +41,26 @@ pub(crate) struct TerrainGenRules {
 
   // The definition of each terrain generator pass.
   pub(crate) stage: TerrainGenStageRules,
+
+  // Tunable parameters, given values per world.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) params: Vec<TerrainGenParamRules>,
 }
 impl TerrainGenRules {
   pub(crate) fn to_input(&self) -> TerrainGenInput {
     TerrainGenInput {
       perlin: self.perlin.to_input(),
       stage: self.stage.to_input(),
+      params: self.params.iter().map(|param| param.to_input()).collect(),
+    }
+  }
+
+  /** The symbols the programs are assembled with. */
+  pub(crate) fn symbols(&self) -> TerrainGenSymbols<'_> {
+    TerrainGenSymbols {
+      format: Some(&self.stage.format),
+      params: &self.params,
     }
   }
 }
@@ -44,53 +76,118 @@ impl TerrainGenRules {
         pairwise_program: ShasmProgram::new_example(),
         merge_program: ShasmProgram::new_example(),
         final_program: ShasmProgram::new_example(),
+        tests: vec![TerrainGenProgramTest::new_example()],
       },
+      params: vec![TerrainGenParamRules::new_example()],
     }
   }
 }
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenInput {
   pub(crate) perlin: TerrainGenPerlinInput,
   pub(crate) stage: TerrainGenStageInput,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) params: Vec<TerrainGenParamInput>,
 }
 impl TerrainGenInput {
   pub(crate) fn new() -> Self {
     TerrainGenInput {
       perlin: TerrainGenPerlinInput::new(),
       stage: TerrainGenStageInput::new(),
+      params: Vec::new(),
     }
   }
 
-  pub(crate) fn to_validated(&self) -> Result<TerrainGenRules, TerrainGenValidation> {
+  /**
+   * Validate, keeping the stage's program and test results in `cache` for
+   * the next validation.
+   */
+  pub(crate) fn to_validated_cached(&self, cache: &mut TerrainGenStageCache)
+    -> Result<TerrainGenRules, TerrainGenValidation>
+  {
+    let mut errors = Vec::new();
+    let (params, param_validations) = self.validate_params(&mut errors);
     let perlin = self.perlin.to_validated();
-    let stage = self.stage.to_validated();
-    if perlin.is_ok() && stage.is_ok() {
+    let stage = self.stage.to_validated(&params, cache);
+    let params_valid = param_validations.iter().all(|pv| pv.is_valid());
+    if errors.is_empty() && params_valid && perlin.is_ok() && stage.is_ok() {
       Ok(TerrainGenRules {
         perlin: perlin.unwrap(),
-        stage: stage.unwrap()
+        stage: stage.unwrap(),
+        params,
       })
     } else {
+      errors.insert(0, ValidationMessage::new(
+        DiagnosticCode::Invalid,
+        "The terrain generator is invalid.",
+      ));
       Err(TerrainGenValidation {
-        errors: vec!["The terrain generator is invalid.".to_string()],
+        errors,
         perlin: perlin.err(),
         stage: stage.err(),
+        params: if params_valid { Vec::new() } else { param_validations },
       })
     }
   }
+
+  /**
+   * Validate the parameters.  Returns the valid ones, and validations that
+   * line up with the inputs.
+   */
+  fn validate_params(&self, errors: &mut Vec<ValidationMessage>)
+    -> (Vec<TerrainGenParamRules>, Vec<TerrainGenParamValidation>)
+  {
+    if self.params.len() > TerrainGenParamRules::MAX_PARAMS {
+      errors.push(ValidationMessage::new(
+        DiagnosticCode::TooMany,
+        format!(
+          "At most {} parameters are allowed, but {} are defined.",
+          TerrainGenParamRules::MAX_PARAMS, self.params.len()
+        ),
+      ).param("max", TerrainGenParamRules::MAX_PARAMS).at("params"));
+    }
+    let mut params = Vec::new();
+    let mut validations = Vec::new();
+    for (i, param) in self.params.iter().enumerate() {
+      let mut validation = match param.to_validated() {
+        Ok(validated) => {
+          params.push(validated);
+          TerrainGenParamValidation::new()
+        },
+        Err(validation) => validation,
+      };
+      if self.params[..i].iter().any(|other| other.name == param.name) {
+        validation.name.push(ValidationMessage::new(
+          DiagnosticCode::Duplicate,
+          format!("The name '{}' is used by another parameter.", param.name),
+        ).param("name", param.name.clone()));
+      }
+      validations.push(validation);
+    }
+    (params, validations)
+  }
 }
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenValidation {
-  pub(crate) errors: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Option::is_none")]
   pub(crate) perlin: Option<TerrainGenPerlinValidation>,
 
   #[serde(skip_serializing_if = "Option::is_none")]
   pub(crate) stage: Option<TerrainGenStageValidation>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) params: Vec<TerrainGenParamValidation>,
 }
 impl TerrainGenValidation {
   pub(crate) fn new() -> Self {
@@ -98,6 +195,7 @@ impl TerrainGenValidation {
       errors: Vec::new(),
       perlin: None,
       stage: None,
+      params: Vec::new(),
     }
   }
 
@@ -105,5 +203,20 @@ impl TerrainGenValidation {
     self.errors.is_empty()
       && self.perlin.as_ref().map_or(true, |pv| pv.is_valid())
       && self.stage.as_ref().map_or(true, |sv| sv.is_valid())
+      && self.params.iter().all(|pv| pv.is_valid())
+  }
+}
+impl Diagnose for TerrainGenValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    if let Some(perlin) = &self.perlin {
+      perlin.diagnose(&format!("{}/perlin", path), out);
+    }
+    if let Some(stage) = &self.stage {
+      stage.diagnose(&format!("{}/stage", path), out);
+    }
+    for (i, param) in self.params.iter().enumerate() {
+      param.diagnose(&format!("{}/params/{}", path, i), out);
+    }
   }
 }
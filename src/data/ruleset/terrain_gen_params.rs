@@ -0,0 +1,250 @@
+use crate::{
+  data::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::{
+    ShadyRegister,
+    ShadyRegisterFile,
+    ShasmSymbols,
+    SHADY_FIRST_INPUT_REG,
+  },
+};
+use super::{ format_type::is_symbol_name, FormatRules };
+
+/**
+ * A named integer that tunes the terrain generator, so that variants of
+ * a ruleset differ only in their parameters.
+ *
+ * Programs read a parameter from its register in a fixed block of VM
+ * input registers, preloaded with the defaults.  Only the CPU test vector
+ * runner preloads them so far; worlds cannot override the values, as no
+ * GPU dispatch of the stage programs exists yet to receive them.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct TerrainGenParamRules {
+  pub(crate) name: String,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub(crate) description: String,
+
+  pub(crate) default: i32,
+
+  // The inclusive range of allowed values.
+  pub(crate) min: i32,
+  pub(crate) max: i32,
+}
+impl TerrainGenParamRules {
+  /** At most this many parameters fit in the register block. */
+  pub(crate) const MAX_PARAMS: usize = 16;
+
+  /**
+   * The first register of the parameter block: the start of the VM's
+   * input block (see `SHADY_FIRST_INPUT_REG`).
+   */
+  pub(crate) const FIRST_REG: u8 = SHADY_FIRST_INPUT_REG;
+
+  pub(crate) fn new_example() -> Self {
+    TerrainGenParamRules {
+      name: "SeaLevel".to_string(),
+      description: "Elevation below which cells are ocean.".to_string(),
+      default: 200,
+      min: 0,
+      max: 1000,
+    }
+  }
+
+  pub(crate) fn to_input(&self) -> TerrainGenParamInput {
+    TerrainGenParamInput {
+      name: self.name.clone(),
+      description: self.description.clone(),
+      default: self.default.to_string(),
+      min: self.min.to_string(),
+      max: self.max.to_string(),
+    }
+  }
+
+  /**
+   * A register file with each parameter's default in its register, and
+   * all other registers zero.
+   */
+  pub(crate) fn preload_registers(params: &[TerrainGenParamRules])
+    -> ShadyRegisterFile
+  {
+    let mut registers = ShadyRegisterFile::new_zeroed();
+    for (i, param) in params.iter().enumerate() {
+      registers.write_reg(Self::register_for_index(i).to_u8(), param.default);
+    }
+    registers
+  }
+
+  fn register_for_index(index: usize) -> ShadyRegister {
+    ShadyRegister::new(Self::FIRST_REG + index as u8)
+  }
+}
+
+/**
+ * The input for a parameter.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct TerrainGenParamInput {
+  pub(crate) name: String,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "String::is_empty")]
+  pub(crate) description: String,
+
+  pub(crate) default: String,
+  pub(crate) min: String,
+  pub(crate) max: String,
+}
+impl TerrainGenParamInput {
+  pub(crate) fn to_validated(&self)
+    -> Result<TerrainGenParamRules, TerrainGenParamValidation>
+  {
+    let mut validation = TerrainGenParamValidation::new();
+    if !is_symbol_name(&self.name) {
+      validation.name.push(ValidationMessage::new(
+        DiagnosticCode::InvalidName,
+        "The name must be letters, digits and underscores, \
+         not starting with a digit.",
+      ));
+    }
+    let parse = |value: &str, errors: &mut Vec<ValidationMessage>| {
+      match value.trim().parse::<i32>() {
+        Ok(value) => Some(value),
+        Err(_) => {
+          errors.push(ValidationMessage::new(
+            DiagnosticCode::NotANumber,
+            "The value must be an integer.",
+          ));
+          None
+        },
+      }
+    };
+    let default = parse(&self.default, &mut validation.default);
+    let min = parse(&self.min, &mut validation.min);
+    let max = parse(&self.max, &mut validation.max);
+
+    if let (Some(min), Some(max)) = (min, max) {
+      if min > max {
+        validation.max.push(ValidationMessage::new(
+          DiagnosticCode::OutOfRange,
+          "The maximum must not be below the minimum.",
+        ).param("min", min));
+      } else if let Some(default) = default {
+        if default < min || default > max {
+          validation.default.push(ValidationMessage::new(
+            DiagnosticCode::OutOfRange,
+            format!("The default must be between {} and {}.", min, max),
+          ).param("min", min).param("max", max));
+        }
+      }
+    }
+
+    if validation.is_valid() {
+      Ok(TerrainGenParamRules {
+        name: self.name.clone(),
+        description: self.description.clone(),
+        default: default.unwrap(),
+        min: min.unwrap(),
+        max: max.unwrap(),
+      })
+    } else {
+      Err(validation)
+    }
+  }
+}
+
+/**
+ * The validation of a parameter.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct TerrainGenParamValidation {
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) errors: Vec<ValidationMessage>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) name: Vec<ValidationMessage>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) default: Vec<ValidationMessage>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) min: Vec<ValidationMessage>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) max: Vec<ValidationMessage>,
+}
+impl TerrainGenParamValidation {
+  pub(crate) fn new() -> Self {
+    TerrainGenParamValidation {
+      errors: Vec::new(),
+      name: Vec::new(),
+      default: Vec::new(),
+      min: Vec::new(),
+      max: Vec::new(),
+    }
+  }
+
+  pub(crate) fn is_valid(&self) -> bool {
+    self.errors.is_empty()
+      && self.name.is_empty()
+      && self.default.is_empty()
+      && self.min.is_empty()
+      && self.max.is_empty()
+  }
+}
+impl Diagnose for TerrainGenParamValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    diagnose_messages(&self.name, path, "name", out);
+    diagnose_messages(&self.default, path, "default", out);
+    diagnose_messages(&self.min, path, "min", out);
+    diagnose_messages(&self.max, path, "max", out);
+  }
+}
+
+/**
+ * The symbols available to terrain generator programs: those of the
+ * format, and `#param.Name`, the register holding a parameter.  Read a
+ * parameter with e.g. `add r0, r#param.SeaLevel, 0`.
+ */
+pub(crate) struct TerrainGenSymbols<'a> {
+  // `None` if the format is not valid.
+  pub(crate) format: Option<&'a FormatRules>,
+  pub(crate) params: &'a [TerrainGenParamRules],
+}
+impl<'a> ShasmSymbols for TerrainGenSymbols<'a> {
+  fn resolve_symbol(&self, name: &str, argument: Option<&str>)
+    -> Result<i64, String>
+  {
+    if let Some(param_name) = name.strip_prefix("param.") {
+      return self.params.iter()
+        .position(|param| param.name == param_name)
+        .map(|i| TerrainGenParamRules::register_for_index(i).to_u8() as i64)
+        .ok_or_else(|| format!("No parameter '{}'", param_name));
+    }
+    match self.format {
+      Some(format) => format.resolve_symbol(name, argument),
+      None => Err(format!(
+        "'#{}' cannot be resolved until the format is valid", name
+      )),
+    }
+  }
+}
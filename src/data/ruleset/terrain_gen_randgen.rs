@@ -1,7 +1,17 @@
-use crate::shady_vm::ShadyRegister;
+use crate::{
+  data::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::ShadyRegister,
+};
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenPerlinRules {
   // The register to store the result in.
   pub(crate) register: ShadyRegister,
@@ -15,6 +25,7 @@ impl TerrainGenPerlinRules {
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenPerlinInput {
   pub(crate) register: String,
 }
@@ -40,15 +51,19 @@ impl TerrainGenPerlinInput {
 
   fn validate_register(&self, validation: &mut TerrainGenPerlinValidation) -> Option<ShadyRegister> {
     if self.register.is_empty() {
-      validation.errors.push("The `register` value is required.".to_string());
+      validation.errors.push(ValidationMessage::new(
+        DiagnosticCode::Required,
+        "The `register` value is required.",
+      ).at("register"));
     }
     let maybe_register = self.register.parse::<u8>();
     match maybe_register {
       Ok(_) => Some(ShadyRegister::new(maybe_register.unwrap())),
       _ => {
-        validation.register.push(
-          "The register must be a valid register.".to_string()
-        );
+        validation.register.push(ValidationMessage::new(
+          DiagnosticCode::NotANumber,
+          "The register must be a valid register.",
+        ));
         None
       }
     }
@@ -57,30 +72,31 @@ impl TerrainGenPerlinInput {
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenPerlinValidation {
   #[serde(skip_serializing_if = "Vec::is_empty")]
   #[serde(default)]
-  pub(crate) errors: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Vec::is_empty")]
   #[serde(default)]
-  pub(crate) seed: Vec<String>,
+  pub(crate) seed: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Vec::is_empty")]
   #[serde(default)]
-  pub(crate) octaves: Vec<String>,
+  pub(crate) octaves: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Vec::is_empty")]
   #[serde(default)]
-  pub(crate) frequency: Vec<String>,
+  pub(crate) frequency: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Vec::is_empty")]
   #[serde(default)]
-  pub(crate) amplitude: Vec<String>,
+  pub(crate) amplitude: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Vec::is_empty")]
   #[serde(default)]
-  pub(crate) register: Vec<String>,
+  pub(crate) register: Vec<ValidationMessage>,
 }
 impl TerrainGenPerlinValidation {
   pub(crate) fn new() -> Self {
@@ -103,3 +119,13 @@ impl TerrainGenPerlinValidation {
       && self.register.is_empty()
   }
 }
+impl Diagnose for TerrainGenPerlinValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    diagnose_messages(&self.seed, path, "seed", out);
+    diagnose_messages(&self.octaves, path, "octaves", out);
+    diagnose_messages(&self.frequency, path, "frequency", out);
+    diagnose_messages(&self.amplitude, path, "amplitude", out);
+    diagnose_messages(&self.register, path, "register", out);
+  }
+}
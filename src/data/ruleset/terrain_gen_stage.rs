@@ -1,5 +1,20 @@
-use crate::shady_vm::{ ShasmProgram, ShasmProgramValidation };
+use crate::{
+  data::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::{ ShasmProgram, ShasmProgramValidation },
+};
 use super::{
+  terrain_gen_params::{ TerrainGenParamRules, TerrainGenSymbols },
+  terrain_gen_tests::{
+    TerrainGenProgramKind,
+    TerrainGenProgramTest,
+    TerrainGenProgramTestValidation,
+  },
   FormatInput,
   FormatRules,
   FormatValidation,
@@ -7,6 +22,7 @@ use super::{
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenStageRules {
   // The format of the terrain data at this stage.
   pub(crate) format: FormatRules,
@@ -28,6 +44,11 @@ pub(crate) struct TerrainGenStageRules {
   // next stage's format.
   #[serde(rename = "finalProgram")]
   pub(crate) final_program: ShasmProgram,
+
+  // Test vectors for the programs, run whenever the stage is validated.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) tests: Vec<TerrainGenProgramTest>,
 }
 impl TerrainGenStageRules {
   pub(crate) fn to_input(&self) -> TerrainGenStageInput {
@@ -37,12 +58,14 @@ impl TerrainGenStageRules {
       pairwise_program: self.pairwise_program.program_text.clone(),
       merge_program: self.merge_program.program_text.to_string(),
       final_program: self.final_program.program_text.to_string(),
+      tests: self.tests.clone(),
     }
   }
 }
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenStageInput {
   pub(crate) format: FormatInput,
 
@@ -57,6 +80,10 @@ pub(crate) struct TerrainGenStageInput {
 
   #[serde(rename = "finalProgram")]
   pub(crate) final_program: String,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) tests: Vec<TerrainGenProgramTest>,
 }
 impl TerrainGenStageInput {
   pub(crate) fn new() -> Self {
@@ -66,15 +93,39 @@ impl TerrainGenStageInput {
       pairwise_program: "".to_string(),
       merge_program: "".to_string(),
       final_program: "".to_string(),
+      tests: Vec::new(),
     }
   }
 
-  pub(crate) fn to_validated(&self) -> Result<TerrainGenStageRules, TerrainGenStageValidation> {
+  /**
+   * Validate the stage, reusing what `cache` holds from an earlier
+   * validation for the programs and tests that have not changed.
+   */
+  pub(crate) fn to_validated(&self,
+    params: &[TerrainGenParamRules],
+    cache: &mut TerrainGenStageCache,
+  ) -> Result<TerrainGenStageRules, TerrainGenStageValidation> {
     let maybe_format = self.format.to_validated();
-    let maybe_init_program = ShasmProgram::to_validated(&self.init_program);
-    let maybe_pairwise_program = ShasmProgram::to_validated(&self.pairwise_program);
-    let maybe_merge_program = ShasmProgram::to_validated(&self.merge_program);
-    let maybe_final_program = ShasmProgram::to_validated(&self.final_program);
+    cache.enter_context(&self.format, params);
+
+    // Programs can only use the format's symbols once it is valid.
+    let symbols = TerrainGenSymbols {
+      format: maybe_format.as_ref().ok(),
+      params,
+    };
+    let mut validate_program = |kind: TerrainGenProgramKind, text: &str| {
+      cache.program(kind, text, || {
+        ShasmProgram::to_validated_with_symbols(text, &symbols)
+      })
+    };
+    let maybe_init_program =
+      validate_program(TerrainGenProgramKind::Init, &self.init_program);
+    let maybe_pairwise_program =
+      validate_program(TerrainGenProgramKind::Pairwise, &self.pairwise_program);
+    let maybe_merge_program =
+      validate_program(TerrainGenProgramKind::Merge, &self.merge_program);
+    let maybe_final_program =
+      validate_program(TerrainGenProgramKind::Final, &self.final_program);
 
     if maybe_format.is_err() ||
        maybe_init_program.is_err() ||
@@ -87,24 +138,156 @@ impl TerrainGenStageInput {
       validation.pairwise_program = maybe_pairwise_program.err();
       validation.merge_program = maybe_merge_program.err();
       validation.final_program = maybe_final_program.err();
-      Err(validation)
+      return Err(validation);
+    }
+
+    let stage = TerrainGenStageRules {
+      format: maybe_format.unwrap(),
+      init_program: maybe_init_program.unwrap(),
+      pairwise_program: maybe_pairwise_program.unwrap(),
+      merge_program: maybe_merge_program.unwrap(),
+      final_program: maybe_final_program.unwrap(),
+      tests: self.tests.clone(),
+    };
+    let mut validation = TerrainGenStageValidation::new();
+    validation.tests = stage.run_tests(params, &mut validation.errors, cache);
+    if validation.is_valid() {
+      Ok(stage)
     } else {
-      Ok(TerrainGenStageRules {
-        format: maybe_format.unwrap(),
-        init_program: maybe_init_program.unwrap(),
-        pairwise_program: maybe_pairwise_program.unwrap(),
-        merge_program: maybe_merge_program.unwrap(),
-        final_program: maybe_final_program.unwrap(),
-      })
+      Err(validation)
+    }
+  }
+}
+
+impl TerrainGenStageRules {
+  pub(crate) fn program(&self, kind: TerrainGenProgramKind) -> &ShasmProgram {
+    match kind {
+      TerrainGenProgramKind::Init => &self.init_program,
+      TerrainGenProgramKind::Pairwise => &self.pairwise_program,
+      TerrainGenProgramKind::Merge => &self.merge_program,
+      TerrainGenProgramKind::Final => &self.final_program,
+    }
+  }
+
+  /**
+   * Run the test vectors with the CPU interpreter, returning the tests
+   * that failed.  A test whose vector and program are as they were when
+   * `cache` last saw it is not run again.
+   */
+  fn run_tests(
+    &self,
+    params: &[TerrainGenParamRules],
+    errors: &mut Vec<ValidationMessage>,
+    cache: &mut TerrainGenStageCache,
+  ) -> Vec<TerrainGenProgramTestValidation> {
+    let symbols = TerrainGenSymbols { format: Some(&self.format), params };
+    let previous = std::mem::take(&mut cache.tests);
+    let mut failures = Vec::new();
+    for (i, test) in self.tests.iter().enumerate() {
+      if self.tests[..i].iter().any(|other| other.name == test.name) {
+        errors.push(ValidationMessage::new(
+          DiagnosticCode::Duplicate,
+          format!("Test name '{}' is used more than once.", test.name),
+        ).param("name", test.name.clone()).at("tests").at(i).at("name"));
+      }
+      let program = self.program(test.program);
+      let cached = previous.iter().find(|cached| {
+        cached.test == *test && cached.program_text == program.program_text
+      });
+      let test_errors = match cached {
+        Some(cached) => cached.errors.clone(),
+        None => match program.parse_shady_program(&symbols) {
+          Ok(program) => test.run(&program, params),
+          Err(_) => vec![ValidationMessage::new(
+            DiagnosticCode::ParseError,
+            "The program does not assemble.",
+          ).at("program")],
+        },
+      };
+      cache.tests.push(CachedTest {
+        test: test.clone(),
+        program_text: program.program_text.clone(),
+        errors: test_errors.clone(),
+      });
+      if !test_errors.is_empty() {
+        failures.push(TerrainGenProgramTestValidation {
+          name: test.name.clone(),
+          index: i,
+          errors: test_errors,
+        });
+      }
+    }
+    failures
+  }
+}
+
+/**
+ * What the last validation of a stage found for each program and test,
+ * so that revalidating after an edit only assembles the programs whose
+ * text changed, and only runs the tests whose vector or program changed.
+ * It is all dropped when the format or parameters, which every program is
+ * assembled against, change.
+ */
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TerrainGenStageCache {
+  // The format input and parameters the entries were validated against.
+  context: serde_json::Value,
+
+  programs: Vec<CachedProgram>,
+  tests: Vec<CachedTest>,
+}
+impl TerrainGenStageCache {
+  fn enter_context(&mut self,
+    format: &FormatInput,
+    params: &[TerrainGenParamRules],
+  ) {
+    let context = serde_json::json!([format, params]);
+    if context != self.context {
+      self.context = context;
+      self.programs.clear();
+      self.tests.clear();
     }
+  }
 
+  fn program<F>(&mut self, kind: TerrainGenProgramKind, text: &str, validate: F)
+    -> Result<ShasmProgram, ShasmProgramValidation>
+    where F: FnOnce() -> Result<ShasmProgram, ShasmProgramValidation>
+  {
+    let cached = self.programs.iter()
+      .find(|cached| cached.kind == kind && cached.text == text);
+    if let Some(cached) = cached {
+      return cached.result.clone();
+    }
+    let result = validate();
+    self.programs.retain(|cached| cached.kind != kind);
+    self.programs.push(CachedProgram {
+      kind,
+      text: text.to_string(),
+      result: result.clone(),
+    });
+    result
   }
 }
 
+#[derive(Debug, Clone)]
+struct CachedProgram {
+  kind: TerrainGenProgramKind,
+  text: String,
+  result: Result<ShasmProgram, ShasmProgramValidation>,
+}
+
+#[derive(Debug, Clone)]
+struct CachedTest {
+  test: TerrainGenProgramTest,
+  program_text: String,
+  errors: Vec<ValidationMessage>,
+}
+
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TerrainGenStageValidation {
-  pub(crate) errors: Vec<String>,
+  pub(crate) errors: Vec<ValidationMessage>,
 
   #[serde(skip_serializing_if = "Option::is_none")]
   pub(crate) format: Option<FormatValidation>,
@@ -124,6 +307,11 @@ pub(crate) struct TerrainGenStageValidation {
   #[serde(rename = "finalProgram")]
   #[serde(skip_serializing_if = "Option::is_none")]
   pub(crate) final_program: Option<ShasmProgramValidation>,
+
+  // The tests that failed.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) tests: Vec<TerrainGenProgramTestValidation>,
 }
 impl TerrainGenStageValidation {
   pub(crate) fn new() -> Self {
@@ -134,6 +322,7 @@ impl TerrainGenStageValidation {
       pairwise_program: None,
       merge_program: None,
       final_program: None,
+      tests: Vec::new(),
     }
   }
 
@@ -144,5 +333,28 @@ impl TerrainGenStageValidation {
       && self.pairwise_program.as_ref().map_or(true, |ppv| ppv.is_valid())
       && self.merge_program.as_ref().map_or(true, |mpv| mpv.is_valid())
       && self.final_program.as_ref().map_or(true, |fpv| fpv.is_valid())
+      && self.tests.iter().all(|tv| tv.is_valid())
+  }
+}
+impl Diagnose for TerrainGenStageValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+    if let Some(format) = &self.format {
+      format.diagnose(&format!("{}/format", path), out);
+    }
+    let programs = [
+      (&self.init_program, "initProgram"),
+      (&self.pairwise_program, "pairwiseProgram"),
+      (&self.merge_program, "mergeProgram"),
+      (&self.final_program, "finalProgram"),
+    ];
+    for (program, field) in programs {
+      if let Some(program) = program {
+        program.diagnose(&format!("{}/{}", path, field), out);
+      }
+    }
+    for test in &self.tests {
+      test.diagnose(&format!("{}/tests/{}", path, test.index), out);
+    }
   }
 }
@@ -0,0 +1,199 @@
+use std::collections::BTreeMap;
+use crate::{
+  data::{
+    diagnose_messages,
+    Diagnose,
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::{
+    ShadyInterpreter,
+    ShadyProgram,
+    ShadyRegisterFile,
+    SHADY_REG_LAST_GP,
+  },
+};
+use super::terrain_gen_params::TerrainGenParamRules;
+
+/**
+ * Which of a stage's programs a test runs.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum TerrainGenProgramKind {
+  Init,
+  Pairwise,
+  Merge,
+  Final,
+}
+
+/**
+ * A test vector for one of a stage's programs: given these registers,
+ * the program should leave those registers with these values.
+ *
+ * Registers are named `r0` to `r239`.  Parameters hold their defaults
+ * unless the inputs set their registers, and all other registers start
+ * at zero.  The neighbouring cell's values of a pairwise test are loaded
+ * into consecutive registers from `r184`.
+ */
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct TerrainGenProgramTest {
+  pub(crate) name: String,
+  pub(crate) program: TerrainGenProgramKind,
+
+  #[serde(default)]
+  pub(crate) inputs: BTreeMap<String, i32>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) neighbour: Vec<i32>,
+
+  pub(crate) expected: BTreeMap<String, i32>,
+}
+impl TerrainGenProgramTest {
+  /** The first register of a pairwise test's neighbour values. */
+  pub(crate) const FIRST_NEIGHBOUR_REG: u8 = 184;
+
+  const MAX_NEIGHBOUR_VALUES: usize = 56;
+
+  pub(crate) fn new_example() -> Self {
+    TerrainGenProgramTest {
+      name: "AddsInputs".to_string(),
+      program: TerrainGenProgramKind::Init,
+      inputs: BTreeMap::from([
+        ("r1".to_string(), 2),
+        ("r2".to_string(), 3),
+      ]),
+      neighbour: Vec::new(),
+      expected: BTreeMap::from([("r0".to_string(), 5)]),
+    }
+  }
+
+  /**
+   * Run the test against its program, returning the reasons it failed.
+   * Messages are at members of the test.
+   */
+  pub(crate) fn run(
+    &self,
+    program: &ShadyProgram,
+    params: &[TerrainGenParamRules],
+  ) -> Vec<ValidationMessage> {
+    let mut errors = Vec::new();
+    if !self.neighbour.is_empty() {
+      if self.program != TerrainGenProgramKind::Pairwise {
+        errors.push(ValidationMessage::new(
+          DiagnosticCode::Invalid,
+          "Only pairwise tests take neighbour values.",
+        ).at("neighbour"));
+      } else if self.neighbour.len() > Self::MAX_NEIGHBOUR_VALUES {
+        errors.push(ValidationMessage::new(
+          DiagnosticCode::TooMany,
+          format!(
+            "At most {} neighbour values are allowed.",
+            Self::MAX_NEIGHBOUR_VALUES
+          ),
+        ).param("max", Self::MAX_NEIGHBOUR_VALUES).at("neighbour"));
+      }
+    }
+    let inputs =
+      Self::parse_registers(&self.inputs, "input", "inputs", &mut errors);
+    let expected =
+      Self::parse_registers(&self.expected, "expected", "expected", &mut errors);
+    if !errors.is_empty() {
+      return errors;
+    }
+
+    let mut registers = TerrainGenParamRules::preload_registers(params);
+    for (reg, value) in inputs {
+      registers.write_reg(reg, value);
+    }
+    for (i, value) in self.neighbour.iter().enumerate() {
+      registers.write_reg(Self::FIRST_NEIGHBOUR_REG + i as u8, *value);
+    }
+
+    if let Err(error) = ShadyInterpreter::new(program).run(&mut registers) {
+      return vec![
+        ValidationMessage::new(DiagnosticCode::TestFailed, error)
+          .param("test", self.name.clone())
+      ];
+    }
+    Self::compare(&registers, &expected).into_iter()
+      .map(|error| error.param("test", self.name.clone()))
+      .collect()
+  }
+
+  fn parse_registers(
+    values: &BTreeMap<String, i32>,
+    role: &str,
+    member: &str,
+    errors: &mut Vec<ValidationMessage>,
+  ) -> Vec<(u8, i32)> {
+    values.iter().filter_map(|(name, value)| {
+      match parse_register_name(name) {
+        Some(reg) => Some((reg, *value)),
+        None => {
+          errors.push(ValidationMessage::new(
+            DiagnosticCode::InvalidName,
+            format!(
+              "Unknown {} register '{}'; expected r0 to r{}.",
+              role, name, SHADY_REG_LAST_GP
+            ),
+          ).param("name", name.clone()).at(member).at(name));
+          None
+        },
+      }
+    }).collect()
+  }
+
+  fn compare(registers: &ShadyRegisterFile, expected: &[(u8, i32)])
+    -> Vec<ValidationMessage>
+  {
+    expected.iter().filter_map(|(reg, value)| {
+      let actual = registers.read_reg(*reg);
+      (actual != *value).then(|| {
+        ValidationMessage::new(
+          DiagnosticCode::TestFailed,
+          format!("r{}: expected {}, got {}.", reg, value, actual),
+        ).param("expected", *value)
+          .param("actual", actual)
+          .at("expected")
+          .at(format!("r{}", reg))
+      })
+    }).collect()
+  }
+}
+
+fn parse_register_name(name: &str) -> Option<u8> {
+  let reg = name.trim().strip_prefix('r')?.parse::<u8>().ok()?;
+  (reg <= SHADY_REG_LAST_GP).then_some(reg)
+}
+
+/**
+ * The result of a test that did not pass.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct TerrainGenProgramTestValidation {
+  pub(crate) name: String,
+
+  // The position of the test in the stage's tests.
+  #[serde(default)]
+  pub(crate) index: usize,
+
+  pub(crate) errors: Vec<ValidationMessage>,
+}
+impl TerrainGenProgramTestValidation {
+  pub(crate) fn is_valid(&self) -> bool {
+    self.errors.is_empty()
+  }
+}
+impl Diagnose for TerrainGenProgramTestValidation {
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    diagnose_messages(&self.errors, path, "", out);
+  }
+}
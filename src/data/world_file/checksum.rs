@@ -0,0 +1,16 @@
+/**
+ * CRC-32 (IEEE 802.3, reflected) checksum, used to validate each
+ * compressed chunk of a world file.
+ */
+pub(crate) fn crc32(data: &[u8]) -> u32 {
+  const POLY: u32 = 0xEDB8_8320;
+  let mut crc = 0xFFFF_FFFF_u32;
+  for &byte in data {
+    crc ^= byte as u32;
+    for _ in 0 .. 8 {
+      let mask = (crc & 1).wrapping_neg();
+      crc = (crc >> 1) ^ (POLY & mask);
+    }
+  }
+  !crc
+}
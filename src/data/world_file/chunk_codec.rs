@@ -0,0 +1,106 @@
+use std::io;
+use crate::data::map::{ CellData, CELL_DATA_NUM_WORDS };
+
+/**
+ * Compression of a single chunk of cell data.
+ *
+ * Each word of the cell format is encoded as a separate plane, in
+ * row-major cell order.  Values within a plane are delta-encoded against
+ * the previous cell, and the deltas are then run-length encoded as
+ * `(run_length, zigzag(delta))` varint pairs.  Terrain layers tend to be
+ * smooth, so deltas are small and frequently repeat.
+ */
+pub(crate) fn encode_chunk(cells: &[CellData]) -> Vec<u8> {
+  let mut out = Vec::new();
+  for word in 0 .. CELL_DATA_NUM_WORDS {
+    let mut prev = 0_u32;
+    let mut run_delta = 0_u32;
+    let mut run_length = 0_u32;
+    for cell in cells {
+      let value = cell.words[word];
+      let delta = zigzag_encode(value.wrapping_sub(prev) as i32);
+      prev = value;
+      if run_length > 0 && delta == run_delta {
+        run_length += 1;
+        continue;
+      }
+      if run_length > 0 {
+        write_varint(&mut out, run_length);
+        write_varint(&mut out, run_delta);
+      }
+      run_delta = delta;
+      run_length = 1;
+    }
+    if run_length > 0 {
+      write_varint(&mut out, run_length);
+      write_varint(&mut out, run_delta);
+    }
+  }
+  out
+}
+
+/**
+ * Decode a chunk produced by `encode_chunk`, expecting exactly `num_cells`
+ * cells.
+ */
+pub(crate) fn decode_chunk(data: &[u8], num_cells: usize)
+  -> io::Result<Vec<CellData>>
+{
+  let mut cells = vec![CellData::new([0; CELL_DATA_NUM_WORDS]); num_cells];
+  let mut pos = 0_usize;
+  for word in 0 .. CELL_DATA_NUM_WORDS {
+    let mut prev = 0_u32;
+    let mut cell_i = 0_usize;
+    while cell_i < num_cells {
+      let run_length = read_varint(data, &mut pos)? as usize;
+      let delta = zigzag_decode(read_varint(data, &mut pos)?);
+      if run_length == 0 || cell_i + run_length > num_cells {
+        return Err(invalid_data("Chunk run overflows cell count"));
+      }
+      for _ in 0 .. run_length {
+        prev = prev.wrapping_add(delta as u32);
+        cells[cell_i].words[word] = prev;
+        cell_i += 1;
+      }
+    }
+  }
+  if pos != data.len() {
+    return Err(invalid_data("Trailing bytes after chunk data"));
+  }
+  Ok(cells)
+}
+
+fn zigzag_encode(value: i32) -> u32 {
+  ((value << 1) ^ (value >> 31)) as u32
+}
+
+fn zigzag_decode(value: u32) -> i32 {
+  ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+fn write_varint(out: &mut Vec<u8>, value: u32) {
+  let mut value = value;
+  while value >= 0x80 {
+    out.push((value as u8) | 0x80);
+    value >>= 7;
+  }
+  out.push(value as u8);
+}
+
+fn read_varint(data: &[u8], pos: &mut usize) -> io::Result<u32> {
+  let mut value = 0_u32;
+  for shift in (0 .. 35).step_by(7) {
+    let byte = *data.get(*pos)
+      .ok_or_else(|| invalid_data("Truncated varint in chunk data"))?;
+    *pos += 1;
+    value |= ((byte & 0x7F) as u32) << shift;
+    if byte & 0x80 == 0 {
+      return Ok(value);
+    }
+  }
+  Err(invalid_data("Varint too long in chunk data"))
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,164 @@
+use std::io::{ self, Read, Write };
+use crate::data::map::{ CellCoord, WorldDims };
+
+/**
+ * The fixed-size header at the start of every world file.
+ */
+#[derive(Debug, Clone)]
+pub(crate) struct WorldFileHeader {
+  // The dimensions of the world.
+  pub(crate) dims: WorldDims,
+
+  // The number of 32-bit words stored per cell.
+  pub(crate) words_per_cell: u16,
+
+  // The dimensions of each chunk (edge chunks may be smaller).
+  pub(crate) chunk_dims: WorldDims,
+
+  // The numeric seed the world was generated with.
+  pub(crate) seed: u32,
+
+  // SHA-256 of the format rules the cell data is laid out in.
+  pub(crate) format_hash: [u8; 32],
+}
+impl WorldFileHeader {
+  pub(crate) const MAGIC: [u8; 4] = *b"RRWF";
+  pub(crate) const VERSION: u16 = 1;
+  pub(crate) const ENCODED_SIZE: usize = 4 + 2 + 2 + 4 + 4 + 4 + 32 + 4;
+
+  pub(crate) fn chunk_grid_dims(&self) -> WorldDims {
+    self.dims.div_ceil_dims(self.chunk_dims)
+  }
+
+  pub(crate) fn num_chunks(&self) -> usize {
+    self.chunk_grid_dims().area() as usize
+  }
+
+  /**
+   * The top-left cell and extent of the chunk at the given index.
+   */
+  pub(crate) fn chunk_bounds(&self, chunk_index: usize)
+    -> (CellCoord, WorldDims)
+  {
+    let grid_coord = self.chunk_grid_dims().index_coord(chunk_index);
+    let top_left = CellCoord::new(
+      grid_coord.col * self.chunk_dims.columns,
+      grid_coord.row * self.chunk_dims.rows,
+    );
+    let extent = WorldDims::new(
+      self.chunk_dims.columns.min(self.dims.columns - top_left.col),
+      self.chunk_dims.rows.min(self.dims.rows - top_left.row),
+    );
+    (top_left, extent)
+  }
+
+  pub(crate) fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    out.write_all(&Self::MAGIC)?;
+    out.write_all(&Self::VERSION.to_le_bytes())?;
+    out.write_all(&self.words_per_cell.to_le_bytes())?;
+    out.write_all(&self.dims.columns.to_le_bytes())?;
+    out.write_all(&self.dims.rows.to_le_bytes())?;
+    out.write_all(&self.chunk_dims.columns.to_le_bytes())?;
+    out.write_all(&self.chunk_dims.rows.to_le_bytes())?;
+    out.write_all(&self.seed.to_le_bytes())?;
+    out.write_all(&self.format_hash)?;
+    out.write_all(&(self.num_chunks() as u32).to_le_bytes())?;
+    Ok(())
+  }
+
+  pub(crate) fn read_from<R: Read>(input: &mut R) -> io::Result<Self> {
+    let mut bytes = [0_u8; Self::ENCODED_SIZE];
+    input.read_exact(&mut bytes)?;
+    let mut reader = ByteReader::new(&bytes);
+
+    if reader.take::<4>() != Self::MAGIC {
+      return Err(invalid_data("Not a world file (bad magic)"));
+    }
+    let version = reader.u16();
+    if version != Self::VERSION {
+      return Err(invalid_data(
+        &format!("Unsupported world file version: {}", version)
+      ));
+    }
+    let words_per_cell = reader.u16();
+    let dims = WorldDims::new(reader.u16(), reader.u16());
+    let chunk_dims = WorldDims::new(reader.u16(), reader.u16());
+    let seed = reader.u32();
+    let format_hash = reader.take::<32>();
+    let num_chunks = reader.u32() as usize;
+
+    if dims.area() == 0 || chunk_dims.area() == 0 {
+      return Err(invalid_data("World file has empty dimensions"));
+    }
+    let header =
+      WorldFileHeader { dims, words_per_cell, chunk_dims, seed, format_hash };
+    if header.num_chunks() != num_chunks {
+      return Err(invalid_data("World file chunk count mismatch"));
+    }
+    Ok(header)
+  }
+}
+
+/**
+ * The location and checksum of a single compressed chunk.
+ */
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct ChunkTableEntry {
+  // Byte offset of the chunk from the start of the file.
+  pub(crate) offset: u64,
+
+  // Compressed length of the chunk in bytes.
+  pub(crate) length: u32,
+
+  // CRC-32 of the compressed chunk bytes.
+  pub(crate) checksum: u32,
+}
+impl ChunkTableEntry {
+  pub(crate) const ENCODED_SIZE: usize = 8 + 4 + 4;
+
+  pub(crate) fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+    out.write_all(&self.offset.to_le_bytes())?;
+    out.write_all(&self.length.to_le_bytes())?;
+    out.write_all(&self.checksum.to_le_bytes())?;
+    Ok(())
+  }
+
+  pub(crate) fn read_from<R: Read>(input: &mut R) -> io::Result<Self> {
+    let mut bytes = [0_u8; Self::ENCODED_SIZE];
+    input.read_exact(&mut bytes)?;
+    let mut reader = ByteReader::new(&bytes);
+    Ok(ChunkTableEntry {
+      offset: reader.u64(),
+      length: reader.u32(),
+      checksum: reader.u32(),
+    })
+  }
+}
+
+/**
+ * Little-endian cursor over a fixed-size byte array.
+ */
+struct ByteReader<'a> {
+  bytes: &'a [u8],
+  pos: usize,
+}
+impl<'a> ByteReader<'a> {
+  fn new(bytes: &'a [u8]) -> Self {
+    ByteReader { bytes, pos: 0 }
+  }
+
+  fn take<const N: usize>(&mut self) -> [u8; N] {
+    let mut result = [0_u8; N];
+    result.copy_from_slice(&self.bytes[self.pos .. self.pos + N]);
+    self.pos += N;
+    result
+  }
+
+  fn u16(&mut self) -> u16 { u16::from_le_bytes(self.take()) }
+  fn u32(&mut self) -> u32 { u32::from_le_bytes(self.take()) }
+  fn u64(&mut self) -> u64 { u64::from_le_bytes(self.take()) }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
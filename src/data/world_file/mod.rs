@@ -0,0 +1,200 @@
+mod checksum;
+mod chunk_codec;
+mod header;
+
+pub(crate) use self::header::{ WorldFileHeader, ChunkTableEntry };
+
+use std::io::{ self, Read, Seek, SeekFrom, Write };
+use crate::data::{
+  map::{ CellCoord, CellData, WorldDims, CELL_DATA_NUM_WORDS },
+  VecMap,
+};
+use self::{
+  checksum::crc32,
+  chunk_codec::{ decode_chunk, encode_chunk },
+};
+
+/**
+ * Versioned binary world file.
+ *
+ * Layout (all integers little-endian):
+ *   - `WorldFileHeader`
+ *   - one `ChunkTableEntry` per chunk, chunks in row-major grid order
+ *   - the compressed chunk payloads
+ *
+ * Every chunk is compressed and checksummed independently, so that
+ * `read_region` need only decode the chunks that overlap a region.  The
+ * server does not load partial regions yet: viewing a world puts the
+ * whole map on the GPU, so it reads every chunk.
+ */
+pub(crate) struct WorldFile;
+impl WorldFile {
+  /** Default chunk size used when writing world files. */
+  pub(crate) const DEFAULT_CHUNK_DIMS: WorldDims = WorldDims::new(64, 64);
+
+  pub(crate) fn write<W: Write>(
+    out: &mut W,
+    seed: u32,
+    format_hash: [u8; 32],
+    cells: &VecMap<CellData>,
+  ) -> io::Result<()> {
+    let header = WorldFileHeader {
+      dims: cells.dims(),
+      words_per_cell: CELL_DATA_NUM_WORDS as u16,
+      chunk_dims: Self::DEFAULT_CHUNK_DIMS,
+      seed,
+      format_hash,
+    };
+
+    // Compress every chunk up front so that the table can be written
+    // before the payloads.
+    let mut payloads = Vec::with_capacity(header.num_chunks());
+    for chunk_index in 0 .. header.num_chunks() {
+      let (top_left, extent) = header.chunk_bounds(chunk_index);
+      let mut chunk_cells = Vec::with_capacity(extent.area() as usize);
+      for row in cells.area_slice_iter(top_left, extent).unwrap() {
+        chunk_cells.extend_from_slice(row);
+      }
+      payloads.push(encode_chunk(&chunk_cells));
+    }
+
+    header.write_to(out)?;
+    let mut offset = (
+      WorldFileHeader::ENCODED_SIZE +
+      header.num_chunks() * ChunkTableEntry::ENCODED_SIZE
+    ) as u64;
+    for payload in &payloads {
+      let entry = ChunkTableEntry {
+        offset,
+        length: payload.len() as u32,
+        checksum: crc32(payload),
+      };
+      entry.write_to(out)?;
+      offset += payload.len() as u64;
+    }
+    for payload in &payloads {
+      out.write_all(payload)?;
+    }
+    Ok(())
+  }
+}
+
+/**
+ * Random-access reader over a world file.
+ */
+pub(crate) struct WorldFileReader<R: Read + Seek> {
+  input: R,
+  header: WorldFileHeader,
+  chunk_table: Vec<ChunkTableEntry>,
+}
+impl<R: Read + Seek> WorldFileReader<R> {
+  pub(crate) fn open(input: R) -> io::Result<Self> {
+    let mut input = input;
+    input.seek(SeekFrom::Start(0))?;
+    let header = WorldFileHeader::read_from(&mut input)?;
+    if header.words_per_cell as usize != CELL_DATA_NUM_WORDS {
+      return Err(invalid_data(&format!(
+        "World file has {} words per cell, expected {}",
+        header.words_per_cell, CELL_DATA_NUM_WORDS
+      )));
+    }
+    let mut chunk_table = Vec::with_capacity(header.num_chunks());
+    for _ in 0 .. header.num_chunks() {
+      chunk_table.push(ChunkTableEntry::read_from(&mut input)?);
+    }
+    Ok(WorldFileReader { input, header, chunk_table })
+  }
+
+  pub(crate) fn header(&self) -> &WorldFileHeader {
+    &self.header
+  }
+
+  /**
+   * Read and decode the whole map.
+   */
+  pub(crate) fn read_all(&mut self) -> io::Result<VecMap<CellData>> {
+    self.read_region(CellCoord::zero(), self.header.dims)
+  }
+
+  /**
+   * Read and decode a rectangular region of the map, touching only the
+   * chunks that overlap it.
+   */
+  pub(crate) fn read_region(&mut self, top_left: CellCoord, dims: WorldDims)
+    -> io::Result<VecMap<CellData>>
+  {
+    let world_dims = self.header.dims;
+    if dims.area() == 0
+      || !world_dims.contains_coord(top_left)
+      || !world_dims.contains_coord(dims.bottom_right_inclusive(top_left))
+    {
+      return Err(invalid_data("Region is out of bounds of the world"));
+    }
+
+    let chunk_dims = self.header.chunk_dims;
+    let grid_dims = self.header.chunk_grid_dims();
+    let bottom_right = dims.bottom_right_inclusive(top_left);
+    let first = CellCoord::new(
+      top_left.col / chunk_dims.columns,
+      top_left.row / chunk_dims.rows,
+    );
+    let last = CellCoord::new(
+      bottom_right.col / chunk_dims.columns,
+      bottom_right.row / chunk_dims.rows,
+    );
+
+    let mut region =
+      VecMap::new(dims, CellData::new([0; CELL_DATA_NUM_WORDS]));
+    for grid_row in first.row ..= last.row {
+      for grid_col in first.col ..= last.col {
+        let grid_coord = CellCoord::new(grid_col, grid_row);
+        let chunk_index = grid_dims.coord_index(grid_coord) as usize;
+        let (chunk_top_left, extent) = self.header.chunk_bounds(chunk_index);
+        let chunk_cells = self.read_chunk(chunk_index)?;
+        for (i, cell) in chunk_cells.into_iter().enumerate() {
+          let local = extent.index_coord(i);
+          let coord = CellCoord::new(
+            chunk_top_left.col + local.col,
+            chunk_top_left.row + local.row,
+          );
+          if coord.col < top_left.col || coord.row < top_left.row
+            || coord.col > bottom_right.col || coord.row > bottom_right.row
+          {
+            continue;
+          }
+          let out_coord = CellCoord::new(
+            coord.col - top_left.col,
+            coord.row - top_left.row,
+          );
+          region.set(out_coord, cell);
+        }
+      }
+    }
+    Ok(region)
+  }
+
+  /**
+   * Read, verify and decode a single chunk.
+   */
+  pub(crate) fn read_chunk(&mut self, chunk_index: usize)
+    -> io::Result<Vec<CellData>>
+  {
+    let entry = *self.chunk_table.get(chunk_index)
+      .ok_or_else(|| invalid_data("Chunk index out of range"))?;
+    let (_, extent) = self.header.chunk_bounds(chunk_index);
+
+    let mut payload = vec![0_u8; entry.length as usize];
+    self.input.seek(SeekFrom::Start(entry.offset))?;
+    self.input.read_exact(&mut payload)?;
+    if crc32(&payload) != entry.checksum {
+      return Err(invalid_data(
+        &format!("Checksum mismatch in world file chunk {}", chunk_index)
+      ));
+    }
+    decode_chunk(&payload, extent.area() as usize)
+  }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+  io::Error::new(io::ErrorKind::InvalidData, message)
+}
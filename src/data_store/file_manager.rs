@@ -22,6 +22,9 @@ pub(crate) struct FileManagerSubtree {
   subtree_dir: PathBuf,
 }
 impl FileManagerSubtree {
+  /** Suffix of the temporary files used by `write_atomic`. */
+  pub(crate) const TEMP_SUFFIX: &'static str = ".tmp";
+
   fn new(subtree_dir: PathBuf) -> Self {
     FileManagerSubtree { subtree_dir }
   }
@@ -62,6 +65,48 @@ impl FileManagerSubtree {
     Ok(())
   }
 
+  pub(crate) fn open(&self, name: &str) -> io::Result<fs::File> {
+    let mut path = self.subtree_dir.clone();
+    path.push(name);
+    fs::File::open(path)
+  }
+
+  pub(crate) fn write_bytes(&self, name: &str, contents: &[u8])
+    -> io::Result<()>
+  {
+    let mut path = self.subtree_dir.clone();
+    path.push(name);
+    let mut file = fs::File::create(path)?;
+    file.write_all(contents)?;
+    Ok(())
+  }
+
+  /**
+   * Replace a file's contents so that readers see either the old or the
+   * new contents, never a partial write: the data is written and synced
+   * to a temporary file which is then renamed over the target.
+   */
+  pub(crate) fn write_atomic(&self, name: &str, contents: &[u8])
+    -> io::Result<()>
+  {
+    let mut path = self.subtree_dir.clone();
+    path.push(name);
+    let mut tmp_path = self.subtree_dir.clone();
+    tmp_path.push(format!("{}{}", name, Self::TEMP_SUFFIX));
+    {
+      let mut file = fs::File::create(&tmp_path)?;
+      file.write_all(contents)?;
+      file.sync_all()?;
+    }
+    fs::rename(&tmp_path, &path)
+  }
+
+  pub(crate) fn exists(&self, name: &str) -> bool {
+    let mut path = self.subtree_dir.clone();
+    path.push(name);
+    path.exists()
+  }
+
   pub(crate) fn delete(&self, name: &str) -> io::Result<()> {
     let mut path = self.subtree_dir.clone();
     path.push(name);
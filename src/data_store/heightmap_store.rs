@@ -0,0 +1,119 @@
+use std::io::{ self, Read };
+use super::FileManagerSubtree;
+use crate::data::{
+  map::WorldDims,
+  Heightmap,
+  HeightmapEntry,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct HeightmapStoreEntry {
+  pub(crate) name: String,
+  pub(crate) filename: String,
+  pub(crate) dims: WorldDims,
+}
+impl HeightmapStoreEntry {
+  pub(crate) fn into_heightmap_entry(self) -> HeightmapEntry {
+    HeightmapEntry {
+      name: self.name,
+      dims: self.dims,
+    }
+  }
+}
+
+/**
+ * Store of uploaded heightmaps.  Each heightmap is kept decoded, as raw
+ * little-endian u32 samples, and `heightmaps.json` records its dims.
+ */
+pub(crate) struct HeightmapStore {
+  subtree: FileManagerSubtree,
+  entries: Vec<HeightmapStoreEntry>,
+}
+impl HeightmapStore {
+  const INDEX_FILENAME: &'static str = "heightmaps.json";
+
+  pub(crate) fn new(subtree: FileManagerSubtree, is_new: bool)
+    -> io::Result<Self>
+  {
+    let entries = if is_new {
+      subtree.write_atomic(Self::INDEX_FILENAME, b"[]")?;
+      Vec::new()
+    } else {
+      let index_json = subtree.read(Self::INDEX_FILENAME)?;
+      serde_json::from_str(&index_json)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+    };
+    Ok(Self { subtree, entries })
+  }
+
+  pub(crate) fn list(&self) -> Vec<HeightmapStoreEntry> {
+    self.entries.clone()
+  }
+
+  pub(crate) fn find_entry(&self, name: &str) -> Option<&HeightmapStoreEntry> {
+    self.entries.iter().find(|entry| entry.name == name)
+  }
+
+  pub(crate) fn write(&mut self, name: &str, heightmap: &Heightmap)
+    -> io::Result<()>
+  {
+    let filename = match self.find_entry(name) {
+      Some(entry) => entry.filename.clone(),
+      None => self.new_filename(name),
+    };
+    self.subtree.write_atomic(&filename, &heightmap.to_raw32())?;
+
+    let entry = HeightmapStoreEntry {
+      name: name.to_string(),
+      filename,
+      dims: heightmap.dims,
+    };
+    self.entries.retain(|e| e.name != entry.name);
+    self.entries.push(entry);
+    self.write_index()
+  }
+
+  pub(crate) fn read(&self, name: &str) -> io::Result<Heightmap> {
+    let entry = self.find_entry(name).ok_or_else(|| {
+      io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("No such heightmap: {}", name)
+      )
+    })?;
+    let mut bytes = Vec::new();
+    let mut file = self.subtree.open(&entry.filename)?;
+    file.read_to_end(&mut bytes)?;
+    let values: Vec<u32> = bytes.chunks_exact(4)
+      .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+      .collect();
+    if values.len() != entry.dims.area() as usize {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Heightmap file size does not match index: {}", name),
+      ));
+    }
+    Ok(Heightmap { dims: entry.dims, values })
+  }
+
+  fn new_filename(&self, name: &str) -> String {
+    let stem: String = name.chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+      .take(32)
+      .collect();
+    let mut counter = self.entries.len();
+    loop {
+      let filename = format!("hgt{}_{}.r32", counter, stem);
+      if !self.entries.iter().any(|e| e.filename == filename) {
+        return filename;
+      }
+      counter += 1;
+    }
+  }
+
+  fn write_index(&self) -> io::Result<()> {
+    let index_str = serde_json::to_string(&self.entries)
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    self.subtree.write_atomic(Self::INDEX_FILENAME, index_str.as_bytes())
+  }
+}
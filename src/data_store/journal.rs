@@ -0,0 +1,103 @@
+use std::{
+  io,
+  time::{ SystemTime, UNIX_EPOCH },
+};
+use super::FileManagerSubtree;
+use crate::utility::append_to_file;
+
+/**
+ * A command as recorded in the journal, with when it was performed.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct JournalEntry<C> {
+  // Milliseconds since the Unix epoch.
+  pub(crate) timestamp: u64,
+  pub(crate) command: C,
+}
+
+/**
+ * Journal of the commands that built up the current mode, so that it can
+ * be rebuilt after the server stops unexpectedly.  Each entry is a line
+ * of JSON appended to `current.jsonl`.  When the server starts, the
+ * journal left by the last run is set aside as `previous.jsonl`, where it
+ * stays until a new run's journal replaces it.
+ */
+pub(crate) struct CommandJournal {
+  subtree: FileManagerSubtree,
+}
+impl CommandJournal {
+  const CURRENT_FILENAME: &'static str = "current.jsonl";
+  const PREVIOUS_FILENAME: &'static str = "previous.jsonl";
+
+  pub(crate) fn new(subtree: FileManagerSubtree) -> Self {
+    Self { subtree }
+  }
+
+  pub(crate) fn append<C: serde::Serialize>(&self, command: &C)
+    -> io::Result<()>
+  {
+    let entry = JournalEntry { timestamp: now_millis(), command };
+    let mut line = serde_json::to_string(&entry)
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    line.push('\n');
+    append_to_file(&self.subtree.path().join(Self::CURRENT_FILENAME),
+      line.as_bytes())
+  }
+
+  /**
+   * Start the journal over, with no entries.  It is left empty rather
+   * than removed, so that setting it aside also clears the previous one.
+   */
+  pub(crate) fn clear(&self) -> io::Result<()> {
+    self.subtree.write(Self::CURRENT_FILENAME, "")
+  }
+
+  /**
+   * Keep the current journal as the previous one, and start a new one.
+   * If there is no current journal, e.g. when the server restarts before
+   * any command is journaled, the previous one is kept.
+   */
+  pub(crate) fn set_aside(&self) -> io::Result<()> {
+    if self.subtree.exists(Self::CURRENT_FILENAME) {
+      std::fs::rename(
+        self.subtree.path().join(Self::CURRENT_FILENAME),
+        self.subtree.path().join(Self::PREVIOUS_FILENAME),
+      )?;
+    }
+    Ok(())
+  }
+
+  /**
+   * Read the previous journal's entries.  A line that cannot be read,
+   * e.g. one cut short by a crash, ends the entries.
+   */
+  pub(crate) fn read_previous<C: serde::de::DeserializeOwned>(&self)
+    -> io::Result<Vec<JournalEntry<C>>>
+  {
+    if !self.subtree.exists(Self::PREVIOUS_FILENAME) {
+      return Ok(Vec::new());
+    }
+    let text = self.subtree.read(Self::PREVIOUS_FILENAME)?;
+    let mut entries = Vec::new();
+    for (index, line) in text.lines().enumerate() {
+      match serde_json::from_str(line) {
+        Ok(entry) => entries.push(entry),
+        Err(err) => {
+          log::warn!(
+            "CommandJournal::read_previous: stopping at line {}: {}",
+            index + 1, err
+          );
+          break;
+        }
+      }
+    }
+    Ok(entries)
+  }
+}
+
+fn now_millis() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_millis() as u64)
+    .unwrap_or(0)
+}
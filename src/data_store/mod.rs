@@ -1,38 +1,156 @@
 mod file_manager;
+mod heightmap_store;
+mod journal;
 mod ruleset_store;
+mod world_store;
 
 pub(crate) use self::{
   file_manager::{ FileManager, FileManagerSubtree },
-  ruleset_store::{ RulesetStore, RulesetStoreEntry },
+  heightmap_store::{ HeightmapStore, HeightmapStoreEntry },
+  journal::{ CommandJournal, JournalEntry },
+  ruleset_store::{ RulesetMigrationResult, RulesetStore, RulesetStoreEntry },
+  world_store::{ WorldStore, WorldStoreEntry },
 };
 
-use std::{ path::Path, fs };
+use std::{ path::{ Path, PathBuf }, fs, io::{ self, Write } };
 
 pub(crate) struct DataStore {
   file_manager: FileManager,
+
+  // The lock file held while this store is open, if any.
+  lock_path: Option<PathBuf>,
 }
 impl DataStore {
-  pub(crate) fn new(root_path: &Path) -> Self {
+  const LOCK_FILENAME: &'static str = ".lock";
+
+  /**
+   * Open the data root for exclusive use, creating it if needed.  Fails
+   * if another live process holds the data root's lock file.  A lock left
+   * by a process that has exited is taken over, but only where `/proc`
+   * shows which processes are alive (i.e. Linux); elsewhere it must be
+   * removed by hand.
+   */
+  pub(crate) fn new(root_path: &Path) -> io::Result<Self> {
     log::info!("DataStore::new: root_path={:?}", root_path);
     // If root path doesn't exist, create it.
-    Self::ensure_dir(root_path);
+    Self::ensure_dir(root_path)?;
+
+    let lock_path = root_path.join(Self::LOCK_FILENAME);
+    Self::acquire_lock(&lock_path)?;
+    let file_manager = FileManager::new(root_path);
+    Ok(Self { file_manager, lock_path: Some(lock_path) })
+  }
 
+  /**
+   * Open the data root without taking the lock, for tools that only read
+   * from it while a server may be running.
+   */
+  pub(crate) fn new_read_only(root_path: &Path) -> Self {
+    log::info!("DataStore::new_read_only: root_path={:?}", root_path);
     let file_manager = FileManager::new(root_path);
-    Self { file_manager }
+    Self { file_manager, lock_path: None }
   }
 
-  pub(crate) fn rulesets(&self) -> RulesetStore {
+  pub(crate) fn rulesets(&self) -> io::Result<RulesetStore> {
     let subtree = self.file_manager.root().subdir("rulesets");
-    let is_new = Self::ensure_dir(subtree.path());
+    let is_new = Self::ensure_dir(subtree.path())?;
     RulesetStore::new(subtree, is_new)
   }
 
-  fn ensure_dir(path: &Path) -> bool {
-    if !path.exists() {
-      fs::create_dir_all(path).expect("Failed to create directory");
-      true
+  pub(crate) fn worlds(&self) -> io::Result<WorldStore> {
+    let subtree = self.file_manager.root().subdir("worlds");
+    let is_new = Self::ensure_dir(subtree.path())?;
+    WorldStore::new(subtree, is_new)
+  }
+
+  pub(crate) fn heightmaps(&self) -> io::Result<HeightmapStore> {
+    let subtree = self.file_manager.root().subdir("heightmaps");
+    let is_new = Self::ensure_dir(subtree.path())?;
+    HeightmapStore::new(subtree, is_new)
+  }
+
+  /**
+   * Directory that exported map layers are written to.
+   */
+  pub(crate) fn exports(&self) -> io::Result<FileManagerSubtree> {
+    let subtree = self.file_manager.root().subdir("exports");
+    Self::ensure_dir(subtree.path())?;
+    Ok(subtree)
+  }
+
+  /**
+   * Journal of the commands that built the current mode.
+   */
+  pub(crate) fn journal(&self) -> io::Result<CommandJournal> {
+    let subtree = self.file_manager.root().subdir("journal");
+    Self::ensure_dir(subtree.path())?;
+    Ok(CommandJournal::new(subtree))
+  }
+
+  fn acquire_lock(lock_path: &Path) -> io::Result<()> {
+    for _ in 0 .. 2 {
+      match fs::OpenOptions::new().write(true).create_new(true).open(lock_path) {
+        Ok(mut file) => {
+          write!(file, "{}", std::process::id())?;
+          return Ok(());
+        },
+        Err(err) if err.kind() == io::ErrorKind::AlreadyExists => {
+          if !Self::lock_is_stale(lock_path) {
+            break;
+          }
+          log::warn!("DataStore: removing stale lock {:?}", lock_path);
+          fs::remove_file(lock_path)?;
+        },
+        Err(err) => return Err(err),
+      }
+    }
+    Err(io::Error::new(
+      io::ErrorKind::WouldBlock,
+      format!(
+        "Data root is locked by another process.  If no server is running, \
+         remove {:?}.",
+        lock_path
+      ),
+    ))
+  }
+
+  /**
+   * A lock is stale when the process that wrote it no longer exists.
+   * Liveness can only be checked where `/proc` is available; elsewhere
+   * locks are never considered stale.
+   */
+  fn lock_is_stale(lock_path: &Path) -> bool {
+    let pid = match fs::read_to_string(lock_path) {
+      Ok(contents) => contents.trim().parse::<u32>().ok(),
+      Err(_) => return false,
+    };
+    let proc_dir = Path::new("/proc");
+    if !proc_dir.exists() {
+      return false;
+    }
+    match pid {
+      Some(pid) => !proc_dir.join(pid.to_string()).exists(),
+      None => true,
+    }
+  }
+
+  /** Create a directory if it does not exist, returning whether it did. */
+  fn ensure_dir(path: &Path) -> io::Result<bool> {
+    if path.is_dir() {
+      Ok(false)
     } else {
-      false
+      // Fails if something other than a directory is in the way.
+      fs::create_dir_all(path)?;
+      Ok(true)
+    }
+  }
+}
+impl Drop for DataStore {
+  fn drop(&mut self) {
+    if let Some(lock_path) = &self.lock_path {
+      if let Err(err) = fs::remove_file(lock_path) {
+        log::warn!("DataStore: failed to remove lock {:?}: {}", lock_path, err);
+      }
     }
   }
 }
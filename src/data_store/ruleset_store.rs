@@ -1,5 +1,6 @@
+use std::io;
 use super::FileManagerSubtree;
-use crate::data::ruleset::{ Ruleset, RulesetEntry };
+use crate::data::ruleset::{ parse_ruleset_json, Ruleset, RulesetEntry };
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -17,85 +18,324 @@ impl RulesetStoreEntry {
   }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum RulesetMigrationResult {
+  UpToDate,
+  Migrated { from_version: u32 },
+  Failed(String),
+}
+
+/**
+ * Store of rulesets.  Each ruleset is a JSON file with an opaque
+ * filename, and `rulesets.json` indexes them by name.
+ *
+ * All files are replaced atomically.  The index is only a cache of what
+ * the ruleset files contain: if it is missing or unreadable it is rebuilt
+ * by scanning the directory.
+ */
 pub(crate) struct RulesetStore {
   subtree: FileManagerSubtree,
   entries: Vec<RulesetStoreEntry>,
 }
 impl RulesetStore {
   const INDEX_FILENAME: &'static str = "rulesets.json";
+  const FILENAME_PREFIX: &'static str = "rls";
+  const FILENAME_SUFFIX: &'static str = ".json";
 
-  pub(crate) fn new(subtree: FileManagerSubtree, is_new: bool) -> Self {
-    let ruleset_json = {
-      if is_new {
-        subtree.write(Self::INDEX_FILENAME, "[]")
-          .expect("Failed to write ruleset index file");
-        "[]".to_string()
-      } else {
-        subtree.read(Self::INDEX_FILENAME)
-          .expect("Failed to read ruleset index file")
-      }
-    };
-    let entries: Vec<RulesetStoreEntry> =
-      serde_json::from_str(&ruleset_json).expect("Failed to parse index.json");
+  pub(crate) fn new(subtree: FileManagerSubtree, is_new: bool)
+    -> io::Result<Self>
+  {
+    let mut store = Self { subtree, entries: Vec::new() };
+    if is_new {
+      store.write_index()?;
+      return Ok(store);
+    }
 
-    Self { subtree, entries }
+    let maybe_entries = store.subtree.read(Self::INDEX_FILENAME)
+      .and_then(|index_json| {
+        serde_json::from_str::<Vec<RulesetStoreEntry>>(&index_json)
+          .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+      });
+    match maybe_entries {
+      Ok(entries) => store.entries = entries,
+      Err(err) => {
+        log::warn!("RulesetStore: rebuilding unreadable index: {}", err);
+        store.rebuild_index()?;
+      }
+    }
+    Ok(store)
   }
 
   pub(crate) fn list(&self) -> Vec<RulesetStoreEntry> {
-    // Read and parse the index file.
     self.entries.clone()
   }
 
+  pub(crate) fn contains(&self, name: &str) -> bool {
+    self.find_entry(name).is_some()
+  }
+
   fn find_entry(&self, name: &str) -> Option<&RulesetStoreEntry> {
     self.entries.iter().find(|entry| entry.name == name)
   }
 
-  pub(crate) fn read(&self, name: &str) -> Ruleset {
-    let entry = self.find_entry(name)
-      .expect(format!("Failed to find ruleset entry: {}", name).as_str());
-    let ruleset_str = self.subtree.read(&entry.filename)
-      .expect(format!("Failed to read ruleset file: {}", &entry.filename).as_str());
-    serde_json::from_str(&ruleset_str)
-      .expect(format!("Failed to parse ruleset JSON: {}", name).as_str())
+  fn require_entry(&self, name: &str) -> io::Result<&RulesetStoreEntry> {
+    self.find_entry(name).ok_or_else(|| io::Error::new(
+      io::ErrorKind::NotFound,
+      format!("No such ruleset: {}", name),
+    ))
   }
 
-  pub(crate) fn write(&mut self, name: &str, ruleset: &Ruleset) {
-    let existing_entry = self.find_entry(name);
-    let filename = if let Some(entry) = existing_entry {
-      entry.filename.clone()
-    } else {
-      format!("rls{}_{}.json", self.entries.len(), name)
-    };
+  /**
+   * Read a ruleset.  A derived ruleset is resolved against its chain of
+   * bases, so the result always holds the effective rules.
+   */
+  pub(crate) fn read(&self, name: &str) -> io::Result<Ruleset> {
+    let mut chain = Vec::new();
+    self.read_resolved(name, &mut chain)
+  }
+
+  fn read_resolved(&self, name: &str, chain: &mut Vec<String>)
+    -> io::Result<Ruleset>
+  {
+    if chain.iter().any(|link| link == name) {
+      chain.push(name.to_string());
+      return Err(Self::cycle_error(chain));
+    }
+    chain.push(name.to_string());
+    let ruleset = self.read_stored(name)?;
+    match &ruleset.base {
+      None => Ok(ruleset),
+      Some(base_name) => {
+        let base = self.read_resolved(base_name, chain)?;
+        ruleset.resolve_against(&base)
+          .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+      },
+    }
+  }
+
+  /**
+   * The names of a ruleset and each of its bases in turn.
+   */
+  pub(crate) fn base_chain(&self, name: &str) -> io::Result<Vec<String>> {
+    let mut chain = vec![name.to_string()];
+    let mut ruleset = self.read_stored(name)?;
+    while let Some(base_name) = ruleset.base {
+      let is_cycle = chain.contains(&base_name);
+      chain.push(base_name.clone());
+      if is_cycle {
+        return Err(Self::cycle_error(&chain));
+      }
+      ruleset = self.read_stored(&base_name)?;
+    }
+    Ok(chain)
+  }
+
+  /**
+   * The names of the rulesets whose base is `name`.
+   */
+  pub(crate) fn derived_from(&self, name: &str) -> Vec<String> {
+    self.entries.iter()
+      .filter(|entry| {
+        self.read_stored(&entry.name)
+          .is_ok_and(|ruleset| ruleset.base.as_deref() == Some(name))
+      })
+      .map(|entry| entry.name.clone())
+      .collect()
+  }
+
+  fn cycle_error(chain: &[String]) -> io::Error {
+    io::Error::new(
+      io::ErrorKind::InvalidData,
+      format!("Ruleset inheritance cycle: {}", chain.join(" -> ")),
+    )
+  }
 
+  /**
+   * Read a ruleset as stored, without resolving its base.
+   */
+  fn read_stored(&self, name: &str) -> io::Result<Ruleset> {
+    let entry = self.require_entry(name)?;
+    let ruleset_str = self.subtree.read(&entry.filename)?;
+    parse_ruleset_json(&ruleset_str)
+      .map(|migrated| migrated.ruleset)
+      .map_err(|err| io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("{}: {}", name, err),
+      ))
+  }
+
+  /**
+   * Write a ruleset, replacing the stored ruleset called `name` if there
+   * is one.  The ruleset's own name may differ from `name`, in which case
+   * the entry is renamed, along with the base of any derived rulesets.
+   */
+  pub(crate) fn write(&mut self, name: &str, ruleset: &Ruleset)
+    -> io::Result<()>
+  {
+    let (filename, derived) = match self.find_entry(name) {
+      Some(entry) if name != ruleset.name =>
+        (entry.filename.clone(), self.derived_from(name)),
+      Some(entry) => (entry.filename.clone(), Vec::new()),
+      None => (self.new_filename(&ruleset.name), Vec::new()),
+    };
     let ruleset_str = serde_json::to_string(ruleset)
-      .expect("Failed to serialize ruleset to JSON");
-    self.subtree.write(&filename, &ruleset_str)
-      .expect(format!("Failed to write ruleset file: {}", &filename).as_str());
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    self.subtree.write_atomic(&filename, ruleset_str.as_bytes())?;
+
+    self.entries.retain(|entry| entry.name != name);
+    self.entries.push(RulesetStoreEntry {
+      name: ruleset.name.clone(),
+      description: ruleset.description.clone(),
+      filename,
+    });
+    self.write_index()?;
+
+    for derived_name in derived {
+      let mut derived_ruleset = self.read_stored(&derived_name)?;
+      derived_ruleset.base = Some(ruleset.name.clone());
+      self.write(&derived_name, &derived_ruleset)?;
+    }
+    Ok(())
+  }
+
+  pub(crate) fn delete(&mut self, name: &str) -> io::Result<()> {
+    let filename = self.require_entry(name)?.filename.clone();
+    self.subtree.delete(&filename)?;
+    self.entries.retain(|entry| entry.name != name);
+    self.write_index()
+  }
+
+  /**
+   * Rename a stored ruleset.  The ruleset keeps its file.
+   */
+  pub(crate) fn rename(&mut self, name: &str, new_name: &str)
+    -> io::Result<()>
+  {
+    let mut ruleset = self.read_stored(name)?;
+    ruleset.name = new_name.to_string();
+    self.write(name, &ruleset)
+  }
+
+  /**
+   * Store a copy of a ruleset under a new name.
+   */
+  pub(crate) fn duplicate(&mut self, name: &str, new_name: &str)
+    -> io::Result<()>
+  {
+    let mut ruleset = self.read_stored(name)?;
+    ruleset.name = new_name.to_string();
+    self.write(new_name, &ruleset)
+  }
+
+  /**
+   * Bring every stored ruleset up to the current schema version,
+   * rewriting the files of those that were migrated.
+   */
+  pub(crate) fn migrate_all(&self) -> Vec<(String, RulesetMigrationResult)> {
+    self.entries.iter().map(|entry| {
+      (entry.name.clone(), self.migrate_entry(entry))
+    }).collect()
+  }
+
+  fn migrate_entry(&self, entry: &RulesetStoreEntry) -> RulesetMigrationResult {
+    let ruleset_str = match self.subtree.read(&entry.filename) {
+      Ok(ruleset_str) => ruleset_str,
+      Err(err) => return RulesetMigrationResult::Failed(err.to_string()),
+    };
+    let migrated = match parse_ruleset_json(&ruleset_str) {
+      Ok(migrated) => migrated,
+      Err(err) => return RulesetMigrationResult::Failed(err),
+    };
+    if !migrated.was_migrated() {
+      return RulesetMigrationResult::UpToDate;
+    }
+    let write_result = serde_json::to_string(&migrated.ruleset)
+      .map_err(|err| err.to_string())
+      .and_then(|json| {
+        self.subtree.write_atomic(&entry.filename, json.as_bytes())
+          .map_err(|err| err.to_string())
+      });
+    match write_result {
+      Ok(()) => RulesetMigrationResult::Migrated {
+        from_version: migrated.from_version,
+      },
+      Err(err) => RulesetMigrationResult::Failed(err),
+    }
+  }
+
+  /**
+   * Recreate the index from the ruleset files on disk.  Files that cannot
+   * be parsed are skipped (and left in place), as are later files that
+   * repeat an earlier ruleset's name.
+   */
+  fn rebuild_index(&mut self) -> io::Result<()> {
+    let mut filenames = self.subtree.list()?.into_iter()
+      .filter(|filename| Self::is_ruleset_filename(filename))
+      .collect::<Vec<_>>();
+    filenames.sort();
 
-    if existing_entry.is_none() {
+    self.entries.clear();
+    for filename in filenames {
+      let parsed = self.subtree.read(&filename)
+        .map_err(|err| err.to_string())
+        .and_then(|json| parse_ruleset_json(&json));
+      let ruleset = match parsed {
+        Ok(migrated) => migrated.ruleset,
+        Err(err) => {
+          log::warn!("RulesetStore: skipping {}: {}", filename, err);
+          continue;
+        }
+      };
+      if self.contains(&ruleset.name) {
+        log::warn!(
+          "RulesetStore: skipping {}: duplicate ruleset name {:?}",
+          filename, ruleset.name
+        );
+        continue;
+      }
       self.entries.push(RulesetStoreEntry {
-        name: ruleset.name.clone(),
-        description: ruleset.description.clone(),
+        name: ruleset.name,
+        description: ruleset.description,
         filename,
       });
     }
+    log::info!("RulesetStore: rebuilt index with {} rulesets",
+      self.entries.len());
+    self.write_index()
+  }
 
-    let index_str = serde_json::to_string(&self.entries)
-      .expect("Failed to serialize ruleset index to JSON");
-    self.subtree.write(Self::INDEX_FILENAME, &index_str)
-      .expect("Failed to write ruleset index file");
+  fn is_ruleset_filename(filename: &str) -> bool {
+    filename != Self::INDEX_FILENAME
+      && filename.starts_with(Self::FILENAME_PREFIX)
+      && filename.ends_with(Self::FILENAME_SUFFIX)
   }
 
-  pub(crate) fn delete(&mut self, name: &str) {
-    let entry = self.find_entry(name)
-      .expect(format!("Failed to find ruleset entry: {}", name).as_str());
-    self.subtree.delete(&entry.filename)
-      .expect(format!("Failed to delete ruleset file: {}", &entry.filename).as_str());
-    self.entries.retain(|entry| entry.name != name);
+  /**
+   * Pick a filename for a new ruleset.  Names are reduced to a short
+   * alphanumeric stem for readability only; uniqueness comes from the
+   * counter, which skips any file already present on disk.
+   */
+  fn new_filename(&self, name: &str) -> String {
+    let stem: String = name.chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+      .take(32)
+      .collect();
+    let mut counter = self.entries.len();
+    loop {
+      let filename = format!("{}{}_{}{}",
+        Self::FILENAME_PREFIX, counter, stem, Self::FILENAME_SUFFIX);
+      if !self.entries.iter().any(|e| e.filename == filename)
+        && !self.subtree.exists(&filename)
+      {
+        return filename;
+      }
+      counter += 1;
+    }
+  }
 
+  fn write_index(&self) -> io::Result<()> {
     let index_str = serde_json::to_string(&self.entries)
-      .expect("Failed to serialize ruleset index to JSON");
-    self.subtree.write(Self::INDEX_FILENAME, &index_str)
-      .expect("Failed to write ruleset index file");
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    self.subtree.write_atomic(Self::INDEX_FILENAME, index_str.as_bytes())
   }
 }
@@ -0,0 +1,140 @@
+use std::{ fs, io };
+use super::FileManagerSubtree;
+use crate::data::{
+  map::{ CellData, WorldDescriptor },
+  ruleset::FormatRules,
+  world_file::{ WorldFile, WorldFileReader },
+  VecMap,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct WorldStoreEntry {
+  pub(crate) name: String,
+  pub(crate) filename: String,
+  pub(crate) descriptor: WorldDescriptor,
+
+  // The format the cell data was generated with, kept so that the world
+  // can still be read after its ruleset has been edited or deleted.
+  pub(crate) format: FormatRules,
+}
+
+/**
+ * Store of generated worlds.  Each world is a binary world file, and
+ * `worlds.json` indexes them by name.
+ */
+pub(crate) struct WorldStore {
+  subtree: FileManagerSubtree,
+  entries: Vec<WorldStoreEntry>,
+}
+impl WorldStore {
+  const INDEX_FILENAME: &'static str = "worlds.json";
+
+  pub(crate) fn new(subtree: FileManagerSubtree, is_new: bool)
+    -> io::Result<Self>
+  {
+    let entries = if is_new {
+      subtree.write_atomic(Self::INDEX_FILENAME, b"[]")?;
+      Vec::new()
+    } else {
+      let index_json = subtree.read(Self::INDEX_FILENAME)?;
+      serde_json::from_str(&index_json)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?
+    };
+    Ok(Self { subtree, entries })
+  }
+
+  pub(crate) fn list(&self) -> Vec<WorldStoreEntry> {
+    self.entries.clone()
+  }
+
+  pub(crate) fn find_entry(&self, name: &str) -> Option<&WorldStoreEntry> {
+    self.entries.iter().find(|entry| entry.name == name)
+  }
+
+  pub(crate) fn write(&mut self,
+    descriptor: &WorldDescriptor,
+    format: &FormatRules,
+    cells: &VecMap<CellData>,
+  ) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    WorldFile::write(
+      &mut bytes,
+      descriptor.seed_u32(),
+      format.content_hash(),
+      cells,
+    )?;
+
+    let filename = match self.find_entry(&descriptor.name) {
+      Some(entry) => entry.filename.clone(),
+      None => self.new_filename(&descriptor.name),
+    };
+    self.subtree.write_atomic(&filename, &bytes)?;
+
+    let entry = WorldStoreEntry {
+      name: descriptor.name.clone(),
+      filename,
+      descriptor: descriptor.clone(),
+      format: format.clone(),
+    };
+    self.entries.retain(|e| e.name != entry.name);
+    self.entries.push(entry);
+    self.write_index()
+  }
+
+  /**
+   * Open the named world for reading.
+   */
+  pub(crate) fn open(&self, name: &str)
+    -> io::Result<(WorldStoreEntry, WorldFileReader<fs::File>)>
+  {
+    let entry = self.find_entry(name).cloned().ok_or_else(|| {
+      io::Error::new(io::ErrorKind::NotFound, format!("No such world: {}", name))
+    })?;
+    let reader = WorldFileReader::open(self.subtree.open(&entry.filename)?)?;
+    if reader.header().format_hash != entry.format.content_hash() {
+      return Err(io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("World file format does not match index: {}", name),
+      ));
+    }
+    Ok((entry, reader))
+  }
+
+  /**
+   * Point every world that uses ruleset `name` at `new_name` instead.
+   */
+  pub(crate) fn rename_ruleset(&mut self, name: &str, new_name: &str)
+    -> io::Result<()>
+  {
+    let mut changed = false;
+    for entry in self.entries.iter_mut() {
+      if entry.descriptor.ruleset_name == name {
+        entry.descriptor.ruleset_name = new_name.to_string();
+        changed = true;
+      }
+    }
+    if changed { self.write_index() } else { Ok(()) }
+  }
+
+  fn new_filename(&self, name: &str) -> String {
+    let stem: String = name.chars()
+      .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+      .take(32)
+      .collect();
+    let mut counter = self.entries.len();
+    loop {
+      let filename = format!("wld{}_{}.rrw", counter, stem);
+      if !self.entries.iter().any(|e| e.filename == filename) {
+        return filename;
+      }
+      counter += 1;
+    }
+  }
+
+  fn write_index(&self) -> io::Result<()> {
+    let index_str = serde_json::to_string(&self.entries)
+      .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+    self.subtree.write_atomic(Self::INDEX_FILENAME, index_str.as_bytes())
+  }
+}
@@ -14,6 +14,7 @@ pub(crate) fn world_descriptor() -> WorldDescriptor {
     seed: String::new(),
     dims: WorldDims::new(1000, 1000),
     ruleset_name: String::new(),
+    heightmap: None,
   }
 }
 
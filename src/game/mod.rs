@@ -1,7 +1,7 @@
 mod server;
 mod server_config;
-mod mode;
 
+pub(crate) mod mode;
 pub(crate) mod constants;
 pub(crate) mod defaults;
 
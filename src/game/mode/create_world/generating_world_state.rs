@@ -2,15 +2,15 @@ use crate::{
   cog::{ CogDevice, CogTask },
   data::Statistics,
   data_store,
+  game::mode::MapReadback,
   gpu::{
     task::create_world::{
       BorderFadeTask,
       ComputeHistogramTask,
       ComputeStatisticsTask,
       RandGenTask,
-      ReadMapDataTask,
-      ReadMinimapDataTask,
       RescaleMapDataTask,
+      ResampleMapDataTask,
     },
     CellDataBuffer,
     RandGenBuffer,
@@ -21,20 +21,15 @@ use crate::{
     CurrentGenerationPhaseCmd,
     CurrentGenerationPhaseRsp,
     GetMapDataCmd,
-    GetMapDataRsp,
     GetMinimapDataCmd,
-    GetMinimapDataRsp,
+    ExportMapLayerCmd,
     TakeGenerationStepCmd,
   },
-  shady_vm::{ ShadyProgram, ShadyProgramIndex, ShasmProgram },
+  shady_vm::{ ShadyProgram, ShadyProgramIndex, ShasmProgram, ShasmSymbols },
   data::{
-    map::{
-      CellData,
-      WorldDescriptor,
-    },
+    map::{ CellData, WorldDescriptor },
     ruleset::{
       FormatComponentSelector,
-      FormatComponentSelectorReadSpec,
       Ruleset,
     },
     GenerationCellDatumId,
@@ -56,13 +51,15 @@ pub(crate) struct GeneratingWorldState {
   programs: GeneratingWorldPrograms,
 }
 impl GeneratingWorldState {
-  pub(crate) fn new(descriptor: WorldDescriptor, ruleset: Ruleset) -> Self {
+  pub(crate) fn new(descriptor: WorldDescriptor, ruleset: Ruleset)
+    -> Result<Self, Vec<String>>
+  {
     let phase = GenerationPhase::NewlyCreated;
-    let device = CogDevice::new();
+    let device = CogDevice::new().map_err(|err| vec![err])?;
     let cell_data_buffer = CellDataBuffer::new(&device, descriptor.dims);
     let randgen_buffer = RandGenBuffer::new(&device, descriptor.dims);
-    let programs = GeneratingWorldPrograms::new(&device, &ruleset);
-    GeneratingWorldState {
+    let programs = GeneratingWorldPrograms::new(&device, &ruleset)?;
+    Ok(GeneratingWorldState {
       descriptor,
       ruleset,
       phase,
@@ -72,18 +69,27 @@ impl GeneratingWorldState {
       randgen_statistics: None,
       cell_data_buffer,
       programs,
-    }
+    })
+  }
+
+  pub(crate) fn ruleset_name(&self) -> &str {
+    &self.descriptor.ruleset_name
+  }
+
+  pub(crate) fn phase(&self) -> GenerationPhase {
+    self.phase
   }
 
   pub(crate) fn handle_take_generation_step_cmd(&mut self,
     cmd: TakeGenerationStepCmd,
+    data_store: &data_store::DataStore,
   ) -> CreateWorldSubcmdResponse {
     match cmd.kind {
-      GenerationStepKind::RandGen => self.step_rand_gen(),
+      GenerationStepKind::RandGen => self.step_rand_gen(data_store),
       GenerationStepKind::InitializeCell => self.step_initialize_cell(),
       GenerationStepKind::PairwiseStep => self.step_pairwise_step(),
       GenerationStepKind::PairwiseMerge => self.step_pairwise_merge(),
-      GenerationStepKind::Finalize => self.step_finalize(),
+      GenerationStepKind::Finalize => self.step_finalize(data_store),
     }
   }
 
@@ -101,152 +107,66 @@ impl GeneratingWorldState {
     cmd: GetMapDataCmd,
     _data_store: &data_store::DataStore
   ) -> CreateWorldSubcmdResponse {
-    // Validate datum ids.
-    if cmd.datum_ids.len() == 0 {
-      return CreateWorldSubcmdResponse::Failed(vec![
-        "No datum ids provided".to_string(),
-      ]);
-    }
-    if cmd.datum_ids.len() > 4 {
-      return CreateWorldSubcmdResponse::Failed(vec![
-        "Too many datum ids".to_string(),
-      ]);
-    }
-
-    if ! self.descriptor.dims.contains_coord(cmd.top_left) {
-      return CreateWorldSubcmdResponse::Failed(vec![
-        "Top left coordinate is out of bounds".to_string(),
-      ]);
-    }
-
-    let br = cmd.dims.bottom_right_inclusive(cmd.top_left);
-    if ! self.descriptor.dims.contains_coord(br) {
-      return CreateWorldSubcmdResponse::Failed(vec![
-        "Bottom right coordinate is out of bounds".to_string(),
-      ]);
-    }
-
-    // Convert them to selectors.
-    let mut selectors: Vec<FormatComponentSelectorReadSpec> = Vec::new();
-    for (i, datum_id) in cmd.datum_ids.iter().enumerate() {
-      match self.make_selector_for_datum_id(datum_id) {
-        Ok(sel) => selectors.push(
-          FormatComponentSelectorReadSpec::new(sel, i as u8)
-        ),
-        Err(err) => { return CreateWorldSubcmdResponse::Failed(err); }
-      }
-    }
-
-    // Create the output buffer.
-    let output_buffer = self.device.create_seq_buffer::<u32>(
-      cmd.dims.area() as usize  * selectors.len(),
-      "GetMapData_Output"
+    let result = self.map_readback().read_map_data(
+      cmd.top_left,
+      cmd.dims,
+      &cmd.datum_ids,
+      cmd.decode,
+      |datum_id| self.make_selector_for_datum_id(datum_id),
     );
-
-    // Run the command.
-    let read_map_data_task =
-      if matches!(self.phase, GenerationPhase::PreInitialize) {
-        // In PreInitialize phase, we need to read from the randgen buffer.
-        ReadMapDataTask::new(
-          self.descriptor.dims,
-          cmd.top_left,
-          cmd.dims,
-          selectors.clone(),
-          self.randgen_buffer.buffer().as_seq_buffer().clone(),
-          1,
-          output_buffer.clone()
-        )
-      } else {
-        // In all other phases, we read from the cell data buffer.
-        ReadMapDataTask::new(
-          self.descriptor.dims,
-          cmd.top_left,
-          cmd.dims,
-          selectors.clone(),
-          self.cell_data_buffer.as_u32_seq_buffer(),
-          CellData::NUM_WORDS,
-          output_buffer.clone()
-        )
-      };
-    self.device.encode_and_run("CreateWorld_ReadMapData", |enc| {
-      read_map_data_task.encode(enc);
-    });
-
-    // Read the result from the output buffer.
-    let mut result_vecs: Vec<Vec<u32>> = Vec::new();
-    output_buffer.read_mapped_full(|data| {
-      for sel_i in 0 .. selectors.len() {
-        result_vecs.push(Vec::new());
-        let subvec = &mut result_vecs[sel_i];
-        for entry_i in 0 .. cmd.dims.area() as usize {
-          let value = data[entry_i * selectors.len() + sel_i];
-          subvec.push(value);
-        }
-      }
-    });
-
-    CreateWorldSubcmdResponse::MapData(GetMapDataRsp {
-      top_left: cmd.top_left,
-      dims: cmd.dims,
-      data: result_vecs,
-    })
+    match result {
+      Ok(rsp) => CreateWorldSubcmdResponse::MapData(rsp),
+      Err(err) => CreateWorldSubcmdResponse::Failed(err),
+    }
   }
 
   pub(crate) fn handle_get_minimap_data_cmd(&self,
     cmd: GetMinimapDataCmd,
     _data_store: &data_store::DataStore
   ) -> CreateWorldSubcmdResponse {
-    // Create the output buffer.
-    let output_buffer = self.device.create_seq_buffer::<u32>(
-      cmd.mini_dims.area() as usize,
-      "GetMinimapData_Output"
-    );
-
-    let selector = match self.make_selector_for_datum_id(&cmd.datum_id) {
-      Ok(sel) => sel,
-      Err(err) => { return CreateWorldSubcmdResponse::Failed(err); }
-    };
+    match self.make_selector_for_datum_id(&cmd.datum_id) {
+      Ok(selector) => CreateWorldSubcmdResponse::MinimapData(
+        self.map_readback().read_minimap_data(cmd.mini_dims, selector)
+      ),
+      Err(err) => CreateWorldSubcmdResponse::Failed(err),
+    }
+  }
 
-    // Create the task
-    let task = 
+  /**
+   * Map reads come from the random values until the cells are
+   * initialized, and from the cells after.
+   */
+  fn map_readback(&self) -> MapReadback<'_> {
+    let (input_buffer, entry_size) =
       if matches!(self.phase, GenerationPhase::PreInitialize) {
-        ReadMinimapDataTask::new(
-          self.descriptor.dims,
-          cmd.mini_dims,
-          1,
-          selector,
-          self.randgen_buffer.buffer().as_seq_buffer(),
-          output_buffer.clone()
-        )
+        (self.randgen_buffer.buffer().as_seq_buffer().clone(), 1)
       } else {
-        ReadMinimapDataTask::new(
-          self.descriptor.dims,
-          cmd.mini_dims,
-          CellData::NUM_WORDS,
-          selector,
-          self.cell_data_buffer.as_u32_seq_buffer(),
-          output_buffer.clone()
-        )
+        (self.cell_data_buffer.as_u32_seq_buffer(), CellData::NUM_WORDS)
       };
+    MapReadback {
+      device: &self.device,
+      world_dims: self.descriptor.dims,
+      input_buffer,
+      entry_size,
+      format: &self.ruleset.terrain_gen.stage.format,
+      label: "CreateWorld",
+    }
+  }
 
-    // Run the task
-    self.device.encode_and_run("CreateWorld_ReadMinimapData", |enc| {
-      task.encode(enc);
-    });
-
-    // Read the result from the output buffer.
-    let mut result_vec: Vec<u32> = Vec::new();
-    output_buffer.read_mapped_full(|data| {
-      for entry_i in 0 .. cmd.mini_dims.area() as usize {
-        let value = data[entry_i];
-        result_vec.push(value);
-      }
-    });
-
-    CreateWorldSubcmdResponse::MinimapData(GetMinimapDataRsp {
-      mini_dims: cmd.mini_dims,
-      data: result_vec,
-    })
+  pub(crate) fn handle_export_map_layer_cmd(&self,
+    cmd: ExportMapLayerCmd,
+    data_store: &data_store::DataStore
+  ) -> CreateWorldSubcmdResponse {
+    let result = self.map_readback().export_layer(
+      &cmd,
+      data_store,
+      |datum_id| self.make_selector_for_datum_id(datum_id),
+      |name| Ok(self.ruleset.palette(name).cloned()),
+    );
+    match result {
+      Ok(rsp) => CreateWorldSubcmdResponse::ExportedMapLayer(rsp),
+      Err(err) => CreateWorldSubcmdResponse::Failed(err),
+    }
   }
 
   fn make_selector_for_datum_id(&self,
@@ -284,7 +204,13 @@ impl GeneratingWorldState {
   const HISTOGRAM_NUM_BUCKETS: u32 = 7;
   const PERLIN_TARGET_RANGE: [u32; 2] = [1024, 0xFFFF - 1024];
 
-  fn step_rand_gen(&mut self) -> CreateWorldSubcmdResponse {
+  // Keeps heightmap values small enough that rescaling them into
+  // PERLIN_TARGET_RANGE cannot overflow the shader's i32 arithmetic.
+  const HEIGHTMAP_MAX_VALUE: u32 = 0x7FFF;
+
+  fn step_rand_gen(&mut self, data_store: &data_store::DataStore)
+    -> CreateWorldSubcmdResponse
+  {
     let dims = self.descriptor.dims;
     let seed = self.descriptor.seed_u32();
     let randgen_buffer = self.randgen_buffer.clone();
@@ -298,6 +224,38 @@ impl GeneratingWorldState {
       ]);
     }
 
+    // An uploaded heightmap, when given, replaces the procedural noise.
+    let heightmap = match &self.descriptor.heightmap {
+      Some(name) => {
+        match data_store.heightmaps().and_then(|store| store.read(name)) {
+          Ok(heightmap) => Some(heightmap),
+          Err(err) => {
+            return CreateWorldSubcmdResponse::Failed(vec![
+              "Failed to load heightmap.".to_string(),
+              format!("{}: {}", name, err),
+            ]);
+          }
+        }
+      },
+      None => None,
+    };
+    let resample_task = heightmap.as_ref().map(|heightmap| {
+      let source_buffer = self.device.create_seq_buffer::<u32>(
+        heightmap.values.len(),
+        "CreateWorld_HeightmapSource"
+      );
+      source_buffer.write_slice(
+        0,
+        &heightmap.values_within(Self::HEIGHTMAP_MAX_VALUE)
+      );
+      ResampleMapDataTask::new(
+        heightmap.dims,
+        dims,
+        source_buffer,
+        randgen_buffer.buffer().as_seq_buffer().clone(),
+      )
+    });
+
     let randgen_task = RandGenTask::new(dims, seed, randgen_buffer.clone());
     let compute_histogram_task = ComputeHistogramTask::new(
       &self.device,
@@ -318,7 +276,10 @@ impl GeneratingWorldState {
 
     // Run the tasks.
     self.device.encode_and_run("CreateWorld_RandGen", |enc| {
-      randgen_task.encode(enc);
+      match &resample_task {
+        Some(resample_task) => resample_task.encode(enc),
+        None => randgen_task.encode(enc),
+      }
       compute_histogram_task.encode(enc);
       compute_stats_task.encode(enc);
     });
@@ -354,13 +315,21 @@ impl GeneratingWorldState {
       faded_randgen_buffer.buffer().as_seq_buffer(),
     );
 
+    // Heightmaps are drawn by hand, so their edges are left as drawn: a
+    // coastline or mountain range may deliberately run off the map.
     self.device.encode_and_run("CreateWorld_RescaleMapData", |enc| {
       rescale_map_data_task.encode(enc);
-      border_fade_task.encode(enc);
+      if heightmap.is_none() {
+        border_fade_task.encode(enc);
+      }
     });
 
     // Replace the randgen buffer with the rescaled and faded one.
-    self.randgen_buffer = faded_randgen_buffer;
+    self.randgen_buffer = if heightmap.is_none() {
+      faded_randgen_buffer
+    } else {
+      rescaled_randgen_buffer
+    };
 
     self.phase = GenerationPhase::PreInitialize;
     CreateWorldSubcmdResponse::Ok {}
@@ -405,7 +374,9 @@ impl GeneratingWorldState {
     CreateWorldSubcmdResponse::Ok {}
   }
 
-  fn step_finalize(&mut self) -> CreateWorldSubcmdResponse {
+  fn step_finalize(&mut self, data_store: &data_store::DataStore)
+    -> CreateWorldSubcmdResponse
+  {
     if self.phase != GenerationPhase::CellInitialized {
       return CreateWorldSubcmdResponse::Failed(vec![
         format!(
@@ -415,8 +386,28 @@ impl GeneratingWorldState {
       ]);
     }
     self.phase = GenerationPhase::Finalized;
+
+    // Save the finished world so that it can be viewed later.
+    if let Err(err) = self.save_world(data_store) {
+      log::error!("GeneratingWorldState::step_finalize: save failed: {}", err);
+      return CreateWorldSubcmdResponse::Failed(vec![
+        "Failed to save world.".to_string(),
+        err.to_string(),
+      ]);
+    }
     CreateWorldSubcmdResponse::Ok {}
   }
+
+  fn save_world(&self, data_store: &data_store::DataStore)
+    -> std::io::Result<()>
+  {
+    let cells = self.cell_data_buffer.read_cells();
+    data_store.worlds()?.write(
+      &self.descriptor,
+      &self.ruleset.terrain_gen.stage.format,
+      &cells,
+    )
+  }
 }
 
 pub(crate) struct GeneratingWorldPrograms {
@@ -427,14 +418,21 @@ pub(crate) struct GeneratingWorldPrograms {
   final_program_index: ShadyProgramIndex,
 }
 impl GeneratingWorldPrograms {
-  pub(crate) fn new(device: &CogDevice, ruleset: &Ruleset) -> Self {
+  pub(crate) fn new(device: &CogDevice, ruleset: &Ruleset)
+    -> Result<Self, Vec<String>>
+  {
     let mut program_buffer = ProgramBuffer::new(device);
     let stage = &ruleset.terrain_gen.stage;
+    let symbols = ruleset.terrain_gen.symbols();
 
-    let init_program = Self::parse_terminated(&stage.init_program);
-    let pairwise_program = Self::parse_terminated(&stage.pairwise_program);
-    let merge_program = Self::parse_terminated(&stage.merge_program);
-    let final_program = Self::parse_terminated(&stage.final_program);
+    let init_program =
+      Self::parse_terminated("init", &stage.init_program, &symbols)?;
+    let pairwise_program =
+      Self::parse_terminated("pairwise", &stage.pairwise_program, &symbols)?;
+    let merge_program =
+      Self::parse_terminated("merge", &stage.merge_program, &symbols)?;
+    let final_program =
+      Self::parse_terminated("final", &stage.final_program, &symbols)?;
 
     let init_program_index =
       program_buffer.add_program("TerrainGen_Init", init_program);
@@ -450,19 +448,33 @@ impl GeneratingWorldPrograms {
 
     program_buffer.sync_gpu_buffer();
 
-    GeneratingWorldPrograms {
+    Ok(GeneratingWorldPrograms {
       program_buffer,
       init_program_index,
       pairwise_program_index,
       merge_program_index,
       final_program_index,
-    }
+    })
   }
 
-  fn parse_terminated(shasm_program: &ShasmProgram) -> ShadyProgram {
-    let mut shady_program = shasm_program.parse_shady_program()
-      .expect("Failed to parse program");
+  /**
+   * Parse a stage program, ready to dispatch.  Rulesets are validated when
+   * saved, so this only fails if a stored ruleset is out of step with the
+   * assembler.
+   */
+  fn parse_terminated(
+    name: &str,
+    shasm_program: &ShasmProgram,
+    symbols: &dyn ShasmSymbols,
+  ) -> Result<ShadyProgram, Vec<String>> {
+    let mut shady_program = shasm_program.parse_shady_program(symbols)
+      .map_err(|errors| {
+        let mut messages =
+          vec![format!("Failed to parse the {} program.", name)];
+        messages.extend(errors.iter().map(|err| err.to_string()));
+        messages
+      })?;
     shady_program.append_terminal_instruction();
-    shady_program
+    Ok(shady_program)
   }
 } 
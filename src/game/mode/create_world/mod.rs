@@ -10,11 +10,16 @@ use crate::{
     CurrentDescriptorInputCmd,
     TakeGenerationStepCmd,
     UpdateDescriptorInputCmd,
+    PatchDescriptorInputCmd,
     CurrentGenerationPhaseCmd,
     GetMapDataCmd,
     GetMinimapDataCmd,
+    ExportMapLayerCmd,
+  },
+  data::{
+    map::{ WorldDescriptor, WorldDescriptorInput },
+    GenerationPhase,
   },
-  data::map::{ WorldDescriptor, WorldDescriptorInput }
 };
 use self::{
   specify_new_world_state::SpecifyNewWorldState,
@@ -34,11 +39,38 @@ impl CreateWorldMode {
   pub(crate) fn new_generate(
     descriptor: WorldDescriptor,
     data_store: &DataStore
-  ) -> Self {
-    let ruleset = data_store.rulesets().read(&descriptor.ruleset_name);
-    let generating_world_state = GeneratingWorldState::new(descriptor, ruleset);
+  ) -> Result<Self, Vec<String>> {
+    let ruleset = data_store.rulesets()
+      .and_then(|rulesets| rulesets.read(&descriptor.ruleset_name))
+      .map_err(|err| vec![
+        "Failed to load ruleset.".to_string(),
+        err.to_string(),
+      ])?;
+    let generating_world_state =
+      GeneratingWorldState::new(descriptor, ruleset)?;
     let state = CreateWorldState::GeneratingWorld(generating_world_state);
-    CreateWorldMode { state }
+    Ok(CreateWorldMode { state })
+  }
+
+  /**
+   * The ruleset the world being created uses, or has selected so far.
+   */
+  pub(crate) fn ruleset_name(&self) -> Option<&str> {
+    let name = match &self.state {
+      CreateWorldState::SpecifyNewWorld(st) => st.ruleset_name(),
+      CreateWorldState::GeneratingWorld(st) => st.ruleset_name(),
+    };
+    if name.is_empty() { None } else { Some(name) }
+  }
+
+  /**
+   * The phase of the world being generated, if generation has begun.
+   */
+  pub(crate) fn generation_phase(&self) -> Option<GenerationPhase> {
+    match &self.state {
+      CreateWorldState::SpecifyNewWorld(_) => None,
+      CreateWorldState::GeneratingWorld(st) => Some(st.phase()),
+    }
   }
 
   pub(crate) fn handle_subcommand(&mut self,
@@ -50,6 +82,8 @@ impl CreateWorldMode {
         self.handle_current_descriptor_input_cmd(cmd, data_store),
       CreateWorldSubcmdEnvelope::UpdateDescriptorInput(cmd) =>
         self.handle_update_descriptor_input_cmd(cmd, data_store),
+      CreateWorldSubcmdEnvelope::PatchDescriptorInput(cmd) =>
+        self.handle_patch_descriptor_input_cmd(cmd, data_store),
       CreateWorldSubcmdEnvelope::BeginGeneration(cmd) =>
         self.handle_begin_generation_cmd(cmd, data_store),
       CreateWorldSubcmdEnvelope::TakeGenerationStep(cmd) =>
@@ -60,6 +94,8 @@ impl CreateWorldMode {
         self.handle_get_map_data_cmd(cmd, data_store),
       CreateWorldSubcmdEnvelope::GetMinimapData(cmd) =>
         self.handle_get_minimap_data_cmd(cmd, data_store),
+      CreateWorldSubcmdEnvelope::ExportMapLayer(cmd) =>
+        self.handle_export_map_layer_cmd(cmd, data_store),
     }
   }
 
@@ -81,6 +117,15 @@ impl CreateWorldMode {
     })
   }
 
+  fn handle_patch_descriptor_input_cmd(&mut self,
+    cmd: PatchDescriptorInputCmd,
+    data_store: &DataStore,
+  ) -> CreateWorldSubcmdResponse {
+    self.in_specify_new_world_state("patch descriptor input", |st| {
+      st.handle_patch_descriptor_input_cmd(cmd, data_store)
+    })
+  }
+
   fn handle_begin_generation_cmd(
     &mut self,
     _begin_generation_cmd: BeginGenerationCmd,
@@ -106,16 +151,21 @@ impl CreateWorldMode {
         );
       }
     };
-    *self = CreateWorldMode::new_generate(descriptor, data_store);
-    CreateWorldSubcmdResponse::Ok {}
+    match CreateWorldMode::new_generate(descriptor, data_store) {
+      Ok(mode) => {
+        *self = mode;
+        CreateWorldSubcmdResponse::Ok {}
+      },
+      Err(err) => CreateWorldSubcmdResponse::Failed(err),
+    }
   }
 
   fn handle_take_generation_step_cmd(&mut self,
     cmd: TakeGenerationStepCmd,
-    _data_store: &DataStore,
+    data_store: &DataStore,
   ) -> CreateWorldSubcmdResponse {
     self.in_generating_world_state("take generation step", |st| {
-      st.handle_take_generation_step_cmd(cmd)
+      st.handle_take_generation_step_cmd(cmd, data_store)
     })
   }
 
@@ -145,6 +195,15 @@ impl CreateWorldMode {
     })
   }
 
+  fn handle_export_map_layer_cmd(&mut self,
+    cmd: ExportMapLayerCmd,
+    data_store: &DataStore,
+  ) -> CreateWorldSubcmdResponse {
+    self.in_generating_world_state("export map layer", |st| {
+      st.handle_export_map_layer_cmd(cmd, data_store)
+    })
+  }
+
   fn in_generating_world_state<F>(&mut self, reason: &str, func: F)
     -> CreateWorldSubcmdResponse
     where F: FnOnce(&mut GeneratingWorldState) -> CreateWorldSubcmdResponse
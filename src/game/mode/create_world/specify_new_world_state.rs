@@ -6,6 +6,16 @@ use crate::{
     CurrentDescriptorInputCmd,
     CurrentDescriptorInputRsp,
     UpdateDescriptorInputCmd,
+    PatchDescriptorInputCmd,
+  },
+  protocol::mode::define_rules::ValidationPatchRsp,
+  data::{
+    diff_json,
+    patch_input,
+    validation_json,
+    Diagnose,
+    DiagnosticCode,
+    ValidationMessage,
   },
   data::map::{
     WorldDescriptor,
@@ -22,6 +32,10 @@ impl SpecifyNewWorldState {
     SpecifyNewWorldState { descriptor_input }
   }
 
+  pub(crate) fn ruleset_name(&self) -> &str {
+    &self.descriptor_input.ruleset_name
+  }
+
   pub(crate) fn handle_current_descriptor_input_cmd(&mut self,
     _current_descriptor_input_cmd: CurrentDescriptorInputCmd,
     data_store: &DataStore,
@@ -47,13 +61,64 @@ impl SpecifyNewWorldState {
     }
   }
 
+  pub(crate) fn handle_patch_descriptor_input_cmd(&mut self,
+    patch_descriptor_input_cmd: PatchDescriptorInputCmd,
+    data_store: &DataStore,
+  ) -> CreateWorldSubcmdResponse {
+    let patch = patch_descriptor_input_cmd.patch;
+    let patched = match patch_input(&self.descriptor_input, &patch) {
+      Ok(patched) => patched,
+      Err(err) => return CreateWorldSubcmdResponse::Failed(vec![
+        "Failed to apply patch.".to_string(),
+        err,
+      ]),
+    };
+    // Descriptor validation is cheap, so it is simply redone in full.
+    let before = validation_json(self.validate_current(data_store).err().as_ref());
+    self.descriptor_input = patched;
+    let validation = self.validate_current(data_store).err();
+    let after = validation_json(validation.as_ref());
+    CreateWorldSubcmdResponse::DescriptorValidationPatch(ValidationPatchRsp {
+      valid: validation.is_none(),
+      validation_patch: diff_json(&before, &after),
+    })
+  }
+
   pub(crate) fn validate_current(&self, data_store: &DataStore)
     -> Result<WorldDescriptor, WorldDescriptorValidation>
   {
-    let ruleset_entries = data_store.rulesets().list().iter().map(
-      |entry| entry.clone().into_ruleset_entry()
-    ).collect::<Vec<_>>();
+    // An unreadable ruleset store is reported, and leaves no ruleset
+    // for the descriptor to name.
+    let (ruleset_entries, store_error) = match data_store.rulesets() {
+      Ok(store) => (
+        store.list().into_iter()
+          .map(|entry| entry.into_ruleset_entry())
+          .collect::<Vec<_>>(),
+        None,
+      ),
+      Err(err) => (
+        Vec::new(),
+        Some(format!("Failed to read ruleset store: {}", err)),
+      ),
+    };
+    // A missing or unreadable heightmap store just means none are uploaded.
+    let heightmap_entries = data_store.heightmaps()
+      .map(|store| store.list())
+      .unwrap_or_default()
+      .into_iter()
+      .map(|entry| entry.into_heightmap_entry())
+      .collect::<Vec<_>>();
     let limits = defaults::world_descriptor_limits();
-    self.descriptor_input.to_world_descriptor(limits, &ruleset_entries)
+    self.descriptor_input.to_world_descriptor(
+      limits,
+      &ruleset_entries,
+      &heightmap_entries,
+    ).map_err(|mut validation| {
+      validation.errors.extend(store_error.map(|err| {
+        ValidationMessage::new(DiagnosticCode::StoreError, err)
+      }));
+      validation.diagnostics = validation.diagnostics();
+      validation
+    })
   }
 }
@@ -8,19 +8,44 @@ use crate::{
     UpdateRulesCmd,
     CurrentRulesCmd,
     CurrentRulesRsp,
+    RulesHistoryRsp,
+    PatchRulesCmd,
+    ValidationPatchRsp,
+  },
+  data::{
+    diff_json,
+    patch_input,
+    validation_json,
+    ruleset::{ ResolvedTerrainGen, RulesetInput, TerrainGenStageCache },
   },
-  data::ruleset::RulesetInput,
 };
+use super::RulesHistory;
 
 pub(crate) struct DefineRulesMode {
   update_existing: Option<String>,
-  ruleset_input: RulesetInput,
+  history: RulesHistory,
+
+  // The validation of the current revision, kept between patches so that
+  // each patch only revalidates what it touches.
+  patch_base: Option<PatchBase>,
+
+  // The terrain generator's programs and test results as last validated,
+  // so that a patch to one program only reassembles and retests that one.
+  stage_cache: TerrainGenStageCache,
 }
+
+struct PatchBase {
+  resolved: ResolvedTerrainGen,
+  validation: serde_json::Value,
+}
+
 impl DefineRulesMode {
   pub(crate) fn new() -> Self {
     DefineRulesMode {
       update_existing: None,
-      ruleset_input: RulesetInput::new()
+      history: RulesHistory::new(RulesetInput::new()),
+      patch_base: None,
+      stage_cache: TerrainGenStageCache::default(),
     }
   }
 
@@ -28,6 +53,14 @@ impl DefineRulesMode {
     subcmd: DefineRulesSubcmdEnvelope,
     data_store: &mut DataStore
   ) -> DefineRulesSubcmdResponse {
+    if !matches!(subcmd,
+      DefineRulesSubcmdEnvelope::PatchRules(_) |
+      DefineRulesSubcmdEnvelope::CurrentRules(_) |
+      DefineRulesSubcmdEnvelope::RulesHistory(_)
+    ) {
+      // The input or the store may change.
+      self.patch_base = None;
+    }
     match subcmd {
       DefineRulesSubcmdEnvelope::UpdateRules(update_rules_cmd) =>
         self.handle_update_rules_cmd(update_rules_cmd, data_store),
@@ -40,9 +73,28 @@ impl DefineRulesMode {
 
       DefineRulesSubcmdEnvelope::LoadRules(load_rules_cmd) =>
         self.handle_load_rules_cmd(load_rules_cmd, data_store),
+
+      DefineRulesSubcmdEnvelope::UndoRules(_undo_rules_cmd) =>
+        self.handle_undo_rules_cmd(data_store),
+
+      DefineRulesSubcmdEnvelope::RedoRules(_redo_rules_cmd) =>
+        self.handle_redo_rules_cmd(data_store),
+
+      DefineRulesSubcmdEnvelope::RulesHistory(_rules_history_cmd) =>
+        self.handle_rules_history_cmd(),
+
+      DefineRulesSubcmdEnvelope::PatchRules(patch_rules_cmd) =>
+        self.handle_patch_rules_cmd(patch_rules_cmd, data_store),
     }
   }
 
+  /**
+   * The stored ruleset currently loaded for editing, if any.
+   */
+  pub(crate) fn loaded_ruleset_name(&self) -> Option<&str> {
+    self.update_existing_ref()
+  }
+
   fn update_existing_ref(&self) -> Option<&str> {
     self.update_existing.as_ref().map(|s| s.as_str())
   }
@@ -53,7 +105,7 @@ impl DefineRulesMode {
     -> DefineRulesSubcmdResponse
   {
     let input = update_rules_cmd.ruleset_input;
-    self.ruleset_input = input.clone();
+    self.history.push(input.clone());
     let maybe_rules = input.to_validated(data_store, self.update_existing_ref());
     match maybe_rules {
       Ok(_rules) => DefineRulesSubcmdResponse::Ok {},
@@ -61,18 +113,99 @@ impl DefineRulesMode {
     }
   }
 
+  fn handle_patch_rules_cmd(&mut self,
+    patch_rules_cmd: PatchRulesCmd,
+    data_store: &DataStore
+  ) -> DefineRulesSubcmdResponse {
+    let patch = patch_rules_cmd.patch;
+    let current = self.history.current().clone();
+    let patched = match patch_input(&current, &patch) {
+      Ok(patched) => patched,
+      Err(err) => return DefineRulesSubcmdResponse::Failed(vec![
+        "Failed to apply patch.".to_string(),
+        err,
+      ]),
+    };
+    let before = match self.patch_base.take() {
+      Some(patch_base) => patch_base,
+      None => self.validate_for_patch(&current, None, data_store),
+    };
+    let affects_terrain_gen = patch.iter().any(|op| {
+      current.patch_affects_terrain_gen(op) || patched.patch_affects_terrain_gen(op)
+    });
+    let reusable = (!affects_terrain_gen).then_some(before.resolved);
+    let after = self.validate_for_patch(&patched, reusable, data_store);
+
+    let validation_patch = diff_json(&before.validation, &after.validation);
+    let valid = after.validation.is_null();
+    self.history.push(patched);
+    self.patch_base = Some(after);
+    DefineRulesSubcmdResponse::ValidationPatch(ValidationPatchRsp {
+      valid,
+      validation_patch,
+    })
+  }
+
+  fn validate_for_patch(&mut self,
+    input: &RulesetInput,
+    resolved: Option<ResolvedTerrainGen>,
+    data_store: &DataStore,
+  ) -> PatchBase {
+    let update_existing = self.update_existing.as_deref();
+    let resolved = resolved.unwrap_or_else(|| {
+      input.resolve_terrain_gen(data_store, update_existing, &mut self.stage_cache)
+    });
+    let validation = input.to_validated_with(data_store, update_existing, &resolved)
+      .err();
+    PatchBase {
+      resolved,
+      validation: validation_json(validation.as_ref()),
+    }
+  }
+
   fn handle_current_rules_cmd(&mut self,
     _current_rules_cmd: CurrentRulesCmd,
     data_store: &DataStore
   ) -> DefineRulesSubcmdResponse {
-    let ruleset = self.ruleset_input.clone();
+    DefineRulesSubcmdResponse::CurrentRules(self.current_rules(data_store))
+  }
+
+  fn current_rules(&self, data_store: &DataStore) -> CurrentRulesRsp {
+    let ruleset = self.history.current().clone();
     let maybe_rules = ruleset.to_validated(data_store, self.update_existing_ref());
-    let validation = match maybe_rules {
-      Ok(_rules) => None,
-      Err(validation) => Some(validation),
+    let (validation, effective) = match maybe_rules {
+      Ok(rules) => (None, rules.base.is_some().then_some(rules)),
+      Err(validation) => (Some(validation), None),
     };
-    DefineRulesSubcmdResponse::CurrentRules(CurrentRulesRsp {
-      ruleset, validation
+    CurrentRulesRsp { ruleset, validation, effective }
+  }
+
+  fn handle_undo_rules_cmd(&mut self, data_store: &DataStore)
+    -> DefineRulesSubcmdResponse
+  {
+    if !self.history.undo() {
+      return DefineRulesSubcmdResponse::Failed(vec![
+        "Nothing to undo.".to_string()
+      ]);
+    }
+    DefineRulesSubcmdResponse::CurrentRules(self.current_rules(data_store))
+  }
+
+  fn handle_redo_rules_cmd(&mut self, data_store: &DataStore)
+    -> DefineRulesSubcmdResponse
+  {
+    if !self.history.redo() {
+      return DefineRulesSubcmdResponse::Failed(vec![
+        "Nothing to redo.".to_string()
+      ]);
+    }
+    DefineRulesSubcmdResponse::CurrentRules(self.current_rules(data_store))
+  }
+
+  fn handle_rules_history_cmd(&self) -> DefineRulesSubcmdResponse {
+    DefineRulesSubcmdResponse::RulesHistory(RulesHistoryRsp {
+      revisions: self.history.revision_infos(),
+      current: self.history.current_revision(),
     })
   }
 
@@ -90,19 +223,13 @@ impl DefineRulesMode {
   fn update_existing_ruleset(&self, name: &str, data_store: &DataStore)
     -> DefineRulesSubcmdResponse
   {
-    if name != &self.ruleset_input.name {
-      // If the name has changed, remove the old ruleset and replace
-      // it with the new one.
-      data_store.rulesets().delete(name);
-      return self.save_new_ruleset(data_store);
-    }
-
-    let maybe_rules = self.ruleset_input.to_validated(data_store, Some(name));
+    // A changed name is checked for conflicts by validation, and the
+    // store replaces the old entry with the renamed one.
+    let maybe_rules = self.history.current().to_validated(data_store, Some(name));
     match maybe_rules {
-      Ok(rules) => {
-        data_store.rulesets().write(name, &rules);
-        DefineRulesSubcmdResponse::Ok {}
-      },
+      Ok(rules) => Self::store_result(
+        data_store.rulesets().and_then(|mut store| store.write(name, &rules))
+      ),
       Err(_validation) => {
         DefineRulesSubcmdResponse::Failed(vec![
           "Ruleset is not valid.".to_string()
@@ -112,12 +239,12 @@ impl DefineRulesMode {
   }
 
   fn save_new_ruleset(&self, data_store: &DataStore) -> DefineRulesSubcmdResponse {
-    let maybe_rules = self.ruleset_input.to_validated(data_store, None);
+    let maybe_rules = self.history.current().to_validated(data_store, None);
     match maybe_rules {
-      Ok(rules) => {
-        data_store.rulesets().write(&rules.name, &rules);
-        DefineRulesSubcmdResponse::Ok {}
-      },
+      Ok(rules) => Self::store_result(
+        data_store.rulesets()
+          .and_then(|mut store| store.write(&rules.name, &rules))
+      ),
       Err(_validation) => {
         DefineRulesSubcmdResponse::Failed(vec![
           "Ruleset is not valid.".to_string()
@@ -126,20 +253,41 @@ impl DefineRulesMode {
     }
   }
 
+  fn store_result(result: std::io::Result<()>) -> DefineRulesSubcmdResponse {
+    match result {
+      Ok(()) => DefineRulesSubcmdResponse::Ok {},
+      Err(err) => DefineRulesSubcmdResponse::Failed(vec![
+        "Failed to save ruleset.".to_string(),
+        err.to_string(),
+      ]),
+    }
+  }
+
   fn handle_load_rules_cmd(&mut self,
     load_rules_cmd: LoadRulesCmd,
     data_store: &DataStore
   ) -> DefineRulesSubcmdResponse {
     let ruleset_name = load_rules_cmd.ruleset_name.clone();
-    let rulesets = data_store.rulesets().list();
-    if ! rulesets.iter().any(|entry| entry.name == ruleset_name) {
+    let rulesets = match data_store.rulesets() {
+      Ok(rulesets) => rulesets,
+      Err(err) => return DefineRulesSubcmdResponse::Failed(vec![
+        format!("Failed to read ruleset store: {}", err),
+      ]),
+    };
+    if ! rulesets.contains(&ruleset_name) {
       return DefineRulesSubcmdResponse::Failed(vec![
         "No such ruleset.".to_string(),
         ruleset_name.to_string(),
       ]);
     }
-    let rules = data_store.rulesets().read(&ruleset_name);
-    self.ruleset_input = rules.to_input();
+    let rules = match rulesets.read(&ruleset_name) {
+      Ok(rules) => rules,
+      Err(err) => return DefineRulesSubcmdResponse::Failed(vec![
+        "Failed to load ruleset.".to_string(),
+        err.to_string(),
+      ]),
+    };
+    self.history = RulesHistory::new(rules.to_input());
     self.update_existing = Some(ruleset_name);
     DefineRulesSubcmdResponse::LoadedRuleset(rules)
   }
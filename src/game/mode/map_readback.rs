@@ -0,0 +1,227 @@
+use crate::{
+  cog::{ CogDevice, CogSeqBuffer, CogTask },
+  data_store::DataStore,
+  gpu::task::create_world::{ ReadMapDataTask, ReadMinimapDataTask },
+  protocol::mode::create_world::{
+    ExportMapLayerCmd,
+    ExportMapLayerRsp,
+    GetMapDataRsp,
+    GetMinimapDataRsp,
+  },
+  data::{
+    map::{ CellCoord, WorldDims },
+    ruleset::{
+      FormatComponentSelector,
+      FormatComponentSelectorReadSpec,
+      FormatRules,
+      PaletteRules,
+    },
+    GenerationCellDatumId,
+  },
+};
+
+/**
+ * Reads map data for clients from a world's cells on the GPU.  Shared by
+ * the modes that hold a world there; each mode decides which datum ids
+ * it can read, and from which buffer.
+ */
+pub(crate) struct MapReadback<'a> {
+  pub(crate) device: &'a CogDevice,
+  pub(crate) world_dims: WorldDims,
+
+  // The cells, each `entry_size` u32s.
+  pub(crate) input_buffer: CogSeqBuffer<u32>,
+  pub(crate) entry_size: u32,
+
+  // The format that values are decoded with.
+  pub(crate) format: &'a FormatRules,
+
+  // Prefixes the labels of the GPU work, e.g. "ViewWorld".
+  pub(crate) label: &'a str,
+}
+impl<'a> MapReadback<'a> {
+  /** At most this many datum ids can be read at once. */
+  const MAX_DATUM_IDS: usize = 4;
+
+  /**
+   * Read the values of `datum_ids` for each cell of an area, one layer
+   * per datum id in row-major order.
+   */
+  pub(crate) fn read_map_data<F>(&self,
+    top_left: CellCoord,
+    dims: WorldDims,
+    datum_ids: &[GenerationCellDatumId],
+    decode: bool,
+    make_selector: F,
+  ) -> Result<GetMapDataRsp, Vec<String>>
+    where F: Fn(&GenerationCellDatumId)
+      -> Result<FormatComponentSelector, Vec<String>>
+  {
+    if datum_ids.is_empty() {
+      return Err(vec!["No datum ids provided".to_string()]);
+    }
+    if datum_ids.len() > Self::MAX_DATUM_IDS {
+      return Err(vec!["Too many datum ids".to_string()]);
+    }
+    if ! self.world_dims.contains_coord(top_left) {
+      return Err(vec!["Top left coordinate is out of bounds".to_string()]);
+    }
+    let br = dims.bottom_right_inclusive(top_left);
+    if ! self.world_dims.contains_coord(br) {
+      return Err(vec![
+        "Bottom right coordinate is out of bounds".to_string(),
+      ]);
+    }
+
+    let mut selectors: Vec<FormatComponentSelectorReadSpec> = Vec::new();
+    for (i, datum_id) in datum_ids.iter().enumerate() {
+      let selector = make_selector(datum_id)?;
+      selectors.push(FormatComponentSelectorReadSpec::new(selector, i as u8));
+    }
+
+    let output_buffer = self.device.create_seq_buffer::<u32>(
+      dims.area() as usize * selectors.len(),
+      &format!("{}_GetMapData_Output", self.label)
+    );
+    let read_map_data_task = ReadMapDataTask::new(
+      self.world_dims,
+      top_left,
+      dims,
+      selectors.clone(),
+      self.input_buffer.clone(),
+      self.entry_size,
+      output_buffer.clone()
+    );
+    let label = format!("{}_ReadMapData", self.label);
+    self.device.encode_and_run(&label, |enc| {
+      read_map_data_task.encode(enc);
+    });
+
+    // The output interleaves the layers; split them apart.
+    let mut result_vecs: Vec<Vec<u32>> = Vec::new();
+    output_buffer.read_mapped_full(|data| {
+      for sel_i in 0 .. selectors.len() {
+        result_vecs.push(Vec::new());
+        let subvec = &mut result_vecs[sel_i];
+        for entry_i in 0 .. dims.area() as usize {
+          subvec.push(data[entry_i * selectors.len() + sel_i]);
+        }
+      }
+    });
+
+    let decoded = decode.then(|| GenerationCellDatumId::decode_all(
+      datum_ids,
+      self.format,
+      &result_vecs,
+    ));
+    Ok(GetMapDataRsp {
+      top_left,
+      dims,
+      data: result_vecs,
+      decoded,
+    })
+  }
+
+  /**
+   * Read one datum for the whole world, sampled down to `mini_dims`.
+   */
+  pub(crate) fn read_minimap_data(&self,
+    mini_dims: WorldDims,
+    selector: FormatComponentSelector,
+  ) -> GetMinimapDataRsp {
+    let output_buffer = self.device.create_seq_buffer::<u32>(
+      mini_dims.area() as usize,
+      &format!("{}_GetMinimapData_Output", self.label)
+    );
+    let task = ReadMinimapDataTask::new(
+      self.world_dims,
+      mini_dims,
+      self.entry_size,
+      selector,
+      self.input_buffer.clone(),
+      output_buffer.clone()
+    );
+    let label = format!("{}_ReadMinimapData", self.label);
+    self.device.encode_and_run(&label, |enc| {
+      task.encode(enc);
+    });
+
+    let data = output_buffer.read_mapped_full(|data| {
+      data[0 .. mini_dims.area() as usize].to_vec()
+    });
+    GetMinimapDataRsp { mini_dims, data }
+  }
+
+  /**
+   * Write one datum, for an area or the whole map, to a file in the
+   * exports directory.  `find_palette` is only asked for a palette when
+   * the format needs one.
+   */
+  pub(crate) fn export_layer<F, P>(&self,
+    cmd: &ExportMapLayerCmd,
+    data_store: &DataStore,
+    make_selector: F,
+    find_palette: P,
+  ) -> Result<ExportMapLayerRsp, Vec<String>>
+    where F: Fn(&GenerationCellDatumId)
+        -> Result<FormatComponentSelector, Vec<String>>,
+      P: FnOnce(&str) -> Result<Option<PaletteRules>, Vec<String>>
+  {
+    let (top_left, dims) = match (cmd.top_left, cmd.dims) {
+      (None, None) => (CellCoord::zero(), self.world_dims),
+      (Some(top_left), Some(dims)) => (top_left, dims),
+      _ => {
+        return Err(vec![
+          "topLeft and dims must be given together".to_string(),
+        ]);
+      }
+    };
+    if dims.area() == 0
+      || ! self.world_dims.contains_coord(top_left)
+      || ! self.world_dims.contains_coord(dims.bottom_right_inclusive(top_left))
+    {
+      return Err(vec!["Export area is out of bounds".to_string()]);
+    }
+
+    let filename = cmd.format.filename(&cmd.filename).map_err(|err| vec![err])?;
+    let palette = match (&cmd.palette, cmd.format.needs_palette()) {
+      (Some(name), true) => match find_palette(name)? {
+        Some(palette) => Some(palette),
+        None => { return Err(vec![format!("Unknown palette: {}", name)]); }
+      },
+      (None, true) => {
+        return Err(vec![
+          "A palette is required for PalettePpm export".to_string(),
+        ]);
+      },
+      (_, false) => None,
+    };
+
+    let selector = make_selector(&cmd.datum_id)?;
+    let values = ReadMapDataTask::read_layer(
+      self.device,
+      self.world_dims,
+      top_left,
+      dims,
+      selector,
+      self.input_buffer.clone(),
+      self.entry_size,
+    );
+
+    let bytes = cmd.format.encode(dims, &values, palette.as_ref())
+      .map_err(|err| vec![err])?;
+    data_store.exports()
+      .and_then(|exports| exports.write_bytes(&filename, &bytes))
+      .map_err(|err| vec![
+        "Failed to write export file.".to_string(),
+        err.to_string(),
+      ])?;
+
+    Ok(ExportMapLayerRsp {
+      filename,
+      top_left,
+      dims,
+      bytes: bytes.len() as u64,
+    })
+  }
+}
@@ -1,12 +1,31 @@
 mod define_rules;
 mod create_world;
+mod view_world;
+mod rules_history;
+mod map_readback;
 
 pub(crate) use self::{
   define_rules::DefineRulesMode,
   create_world::CreateWorldMode,
+  view_world::ViewWorldMode,
+  rules_history::RulesHistory,
 };
+use self::map_readback::MapReadback;
 
 pub(crate) enum GameMode {
   DefineRules(DefineRulesMode),
   CreateWorld(CreateWorldMode),
+  ViewWorld(ViewWorldMode),
+}
+impl GameMode {
+  /**
+   * The stored ruleset this mode depends on, if any.
+   */
+  pub(crate) fn ruleset_in_use(&self) -> Option<&str> {
+    match self {
+      GameMode::DefineRules(mode) => mode.loaded_ruleset_name(),
+      GameMode::CreateWorld(mode) => mode.ruleset_name(),
+      GameMode::ViewWorld(_) => None,
+    }
+  }
 }
@@ -0,0 +1,108 @@
+use std::{
+  collections::VecDeque,
+  time::{ SystemTime, UNIX_EPOCH },
+};
+use crate::{
+  data::ruleset::RulesetInput,
+  protocol::mode::define_rules::RulesRevisionInfo,
+};
+
+/**
+ * A bounded history of the ruleset input being edited.
+ *
+ * Every update adds a revision.  Undo and redo move between revisions
+ * without discarding any, until a new update is made after an undo, which
+ * drops the revisions that were undone.
+ */
+pub(crate) struct RulesHistory {
+  revisions: VecDeque<RulesRevision>,
+  // Index into `revisions` of the current revision.
+  current: usize,
+  next_revision: u64,
+}
+
+struct RulesRevision {
+  revision: u64,
+  // Milliseconds since the Unix epoch.
+  timestamp: u64,
+  ruleset_input: RulesetInput,
+}
+
+impl RulesHistory {
+  /** The oldest revisions are dropped beyond this many. */
+  pub(crate) const MAX_REVISIONS: usize = 100;
+
+  pub(crate) fn new(ruleset_input: RulesetInput) -> Self {
+    let mut history = RulesHistory {
+      revisions: VecDeque::new(),
+      current: 0,
+      next_revision: 0,
+    };
+    history.push(ruleset_input);
+    history
+  }
+
+  pub(crate) fn current(&self) -> &RulesetInput {
+    &self.revisions[self.current].ruleset_input
+  }
+
+  /**
+   * Make `ruleset_input` the current revision, discarding any revisions
+   * that were undone.
+   */
+  pub(crate) fn push(&mut self, ruleset_input: RulesetInput) {
+    if !self.revisions.is_empty() {
+      self.revisions.truncate(self.current + 1);
+    }
+    self.revisions.push_back(RulesRevision {
+      revision: self.next_revision,
+      timestamp: now_millis(),
+      ruleset_input,
+    });
+    self.next_revision += 1;
+    if self.revisions.len() > Self::MAX_REVISIONS {
+      self.revisions.pop_front();
+    }
+    self.current = self.revisions.len() - 1;
+  }
+
+  /**
+   * Step back a revision.  Returns false if there is nothing to undo.
+   */
+  pub(crate) fn undo(&mut self) -> bool {
+    if self.current == 0 {
+      return false;
+    }
+    self.current -= 1;
+    true
+  }
+
+  /**
+   * Step forward a revision.  Returns false if there is nothing to redo.
+   */
+  pub(crate) fn redo(&mut self) -> bool {
+    if self.current + 1 >= self.revisions.len() {
+      return false;
+    }
+    self.current += 1;
+    true
+  }
+
+  pub(crate) fn current_revision(&self) -> u64 {
+    self.revisions[self.current].revision
+  }
+
+  pub(crate) fn revision_infos(&self) -> Vec<RulesRevisionInfo> {
+    self.revisions.iter().map(|revision| RulesRevisionInfo {
+      revision: revision.revision,
+      timestamp: revision.timestamp,
+      name: revision.ruleset_input.name.clone(),
+    }).collect()
+  }
+}
+
+fn now_millis() -> u64 {
+  SystemTime::now().duration_since(UNIX_EPOCH)
+    .map(|elapsed| elapsed.as_millis() as u64)
+    .unwrap_or(0)
+}
@@ -0,0 +1,161 @@
+use crate::{
+  cog::CogDevice,
+  data_store::DataStore,
+  gpu::CellDataBuffer,
+  protocol::mode::view_world::{
+    ExportMapLayerCmd,
+    GetMapDataCmd,
+    GetMinimapDataCmd,
+    ViewWorldSubcmdEnvelope,
+    ViewWorldSubcmdResponse,
+  },
+  data::{
+    map::{ CellData, WorldDescriptor },
+    ruleset::{ FormatComponentSelector, FormatRules },
+    GenerationCellDatumId,
+  },
+};
+use super::MapReadback;
+
+/**
+ * Browsing of a saved world.  The stored cell data is uploaded once on
+ * entry, and map queries are then served from the GPU exactly as in
+ * create-world mode.
+ */
+pub(crate) struct ViewWorldMode {
+  descriptor: WorldDescriptor,
+  format: FormatRules,
+  device: CogDevice,
+  cell_data_buffer: CellDataBuffer,
+}
+impl ViewWorldMode {
+  pub(crate) fn new(world_name: &str, data_store: &DataStore)
+    -> Result<Self, Vec<String>>
+  {
+    let load_error = |err: std::io::Error| vec![
+      "Failed to load world.".to_string(),
+      format!("{}: {}", world_name, err),
+    ];
+    let worlds = data_store.worlds().map_err(load_error)?;
+    let (entry, mut reader) = worlds.open(world_name).map_err(load_error)?;
+    // The whole map goes onto the GPU, so every chunk is read.
+    let cells = reader.read_all().map_err(load_error)?;
+
+    let device = CogDevice::new()
+      .map_err(|err| vec!["Failed to load world.".to_string(), err])?;
+    let cell_data_buffer = CellDataBuffer::new(&device, entry.descriptor.dims);
+    cell_data_buffer.write_cells(&cells);
+
+    Ok(ViewWorldMode {
+      descriptor: entry.descriptor,
+      format: entry.format,
+      device,
+      cell_data_buffer,
+    })
+  }
+
+  pub(crate) fn world_name(&self) -> &str {
+    &self.descriptor.name
+  }
+
+  pub(crate) fn handle_subcommand(&mut self,
+    subcmd: ViewWorldSubcmdEnvelope,
+    data_store: &mut DataStore,
+  ) -> ViewWorldSubcmdResponse {
+    match subcmd {
+      ViewWorldSubcmdEnvelope::GetMapData(cmd) =>
+        self.handle_get_map_data_cmd(cmd),
+      ViewWorldSubcmdEnvelope::GetMinimapData(cmd) =>
+        self.handle_get_minimap_data_cmd(cmd),
+      ViewWorldSubcmdEnvelope::ExportMapLayer(cmd) =>
+        self.handle_export_map_layer_cmd(cmd, data_store),
+    }
+  }
+
+  fn handle_get_map_data_cmd(&self, cmd: GetMapDataCmd)
+    -> ViewWorldSubcmdResponse
+  {
+    let result = self.map_readback().read_map_data(
+      cmd.top_left,
+      cmd.dims,
+      &cmd.datum_ids,
+      cmd.decode,
+      |datum_id| Self::selector_for_datum_id(&self.format, datum_id),
+    );
+    match result {
+      Ok(rsp) => ViewWorldSubcmdResponse::MapData(rsp),
+      Err(err) => ViewWorldSubcmdResponse::Failed(err),
+    }
+  }
+
+  fn handle_get_minimap_data_cmd(&self, cmd: GetMinimapDataCmd)
+    -> ViewWorldSubcmdResponse
+  {
+    match Self::selector_for_datum_id(&self.format, &cmd.datum_id) {
+      Ok(selector) => ViewWorldSubcmdResponse::MinimapData(
+        self.map_readback().read_minimap_data(cmd.mini_dims, selector)
+      ),
+      Err(err) => ViewWorldSubcmdResponse::Failed(err),
+    }
+  }
+
+  /**
+   * Palettes are not saved with the world, so they come from its ruleset
+   * as it is now stored.
+   */
+  fn handle_export_map_layer_cmd(&self,
+    cmd: ExportMapLayerCmd,
+    data_store: &DataStore,
+  ) -> ViewWorldSubcmdResponse {
+    let result = self.map_readback().export_layer(
+      &cmd.into(),
+      data_store,
+      |datum_id| Self::selector_for_datum_id(&self.format, datum_id),
+      |name| {
+        let ruleset_name = &self.descriptor.ruleset_name;
+        let ruleset = data_store.rulesets()
+          .and_then(|rulesets| rulesets.read(ruleset_name))
+          .map_err(|err| vec![
+            format!("Failed to load ruleset {}: {}", ruleset_name, err),
+          ])?;
+        Ok(ruleset.palette(name).cloned())
+      },
+    );
+    match result {
+      Ok(rsp) => ViewWorldSubcmdResponse::ExportedMapLayer(rsp),
+      Err(err) => ViewWorldSubcmdResponse::Failed(err),
+    }
+  }
+
+  fn map_readback(&self) -> MapReadback<'_> {
+    MapReadback {
+      device: &self.device,
+      world_dims: self.descriptor.dims,
+      input_buffer: self.cell_data_buffer.as_u32_seq_buffer(),
+      entry_size: CellData::NUM_WORDS,
+      format: &self.format,
+      label: "ViewWorld",
+    }
+  }
+
+  /**
+   * Resolve a datum id against a saved world's format.  Saved worlds keep
+   * only their cells, so there is no randgen layer to read.
+   */
+  pub(crate) fn selector_for_datum_id(
+    format: &FormatRules,
+    datum_id: &GenerationCellDatumId,
+  ) -> Result<FormatComponentSelector, Vec<String>> {
+    match datum_id {
+      GenerationCellDatumId::RandGen {} => Err(vec![
+        "RandGen datum id is not available for saved worlds".to_string(),
+      ]),
+      GenerationCellDatumId::Selector(sel) => {
+        sel.format_selector(format).ok_or_else(|| vec![
+          "Invalid selector".to_string(),
+          format!("{:?}", sel)
+        ])
+      },
+    }
+  }
+}
@@ -1,109 +1,443 @@
 use std::{
-  path::PathBuf, sync::mpsc, thread::JoinHandle
+  any::Any,
+  io,
+  panic::{ self, AssertUnwindSafe },
+  path::PathBuf,
+  sync::{ mpsc, Arc, Mutex },
+  thread::JoinHandle,
+  time::Instant,
+};
+use futures::channel::{
+  mpsc::{ unbounded, UnboundedReceiver, UnboundedSender },
+  oneshot,
 };
 use log;
 use crate::{
-  data_store::DataStore,
-  game::mode::{ CreateWorldMode, DefineRulesMode, GameMode },
+  cog::cog_adapter_info,
+  data::{
+    ruleset::{
+      RulesetBundle,
+      RulesetImportConflict,
+      RulesetImportError,
+      RulesetInput,
+      RULESET_SCHEMA_VERSION,
+    },
+    Heightmap,
+    HeightmapEntry,
+  },
+  data_store::{
+    DataStore,
+    JournalEntry,
+    RulesetMigrationResult,
+    RulesetStore,
+  },
+  game::mode::{ CreateWorldMode, DefineRulesMode, GameMode, ViewWorldMode },
   protocol::{
     mode::{
       create_world::{
         CreateWorldModeInfo,
         CreateWorldSubcmdEnvelope,
-        CreateWorldSubcmdResponse
+        CreateWorldSubcmdResponse,
+        CurrentGenerationPhaseCmd,
       },
       define_rules::{
+        CurrentRulesCmd,
         DefineRulesModeInfo,
         DefineRulesSubcmdEnvelope,
         DefineRulesSubcmdResponse,
-      }, GameModeInfo
+      },
+      view_world::{
+        ViewWorldModeInfo,
+        ViewWorldSubcmdEnvelope,
+        ViewWorldSubcmdResponse,
+      },
+      GameModeInfo
     },
+    get_protocol_docs,
+    CommandCategoryInfo,
     CommandEnvelope,
+    EventEnvelope,
+    GpuAdapterInfo,
+    HelloCmd,
+    ServerFeature,
+    ServerInfo,
+    PROTOCOL_VERSION,
     EnterMainMenuModeCmd,
     EnterModeCmd,
     FailedResponse,
     GetModeInfoCmd,
     ListRulesetsCmd,
+    ListWorldsCmd,
+    ListHeightmapsCmd,
+    UploadHeightmapCmd,
+    DeleteRulesetCmd,
+    RenameRulesetCmd,
+    DuplicateRulesetCmd,
+    ExportRulesetCmd,
+    ImportRulesetCmd,
+    GetJournalInfoCmd,
+    JournalInfo,
+    JournalReplay,
+    ReplayJournalCmd,
     ResponseEnvelope,
   },
+  utility::decode_base64,
 };
 use super::{ GameServerConfig, defaults };
 
+/**
+ * A command queued for the game thread, with where to send its response.
+ */
+type PendingCommand = (CommandEnvelope, oneshot::Sender<ResponseEnvelope>);
+
+/**
+ * The answers to read-only queries as of the last command the game thread
+ * finished, so that they can be given without waiting on the thread.  A
+ * query with no answer here, e.g. `CurrentRules` outside of the define
+ * rules mode, is queued like any other command.
+ */
+#[derive(Default)]
+struct CachedQueries {
+  mode_info: Option<ResponseEnvelope>,
+  rulesets: Option<ResponseEnvelope>,
+  worlds: Option<ResponseEnvelope>,
+  journal_info: Option<ResponseEnvelope>,
+  current_rules: Option<ResponseEnvelope>,
+  generation_phase: Option<ResponseEnvelope>,
+}
+impl CachedQueries {
+  fn answer(&self, command: &CommandEnvelope) -> Option<ResponseEnvelope> {
+    let answer = match command {
+      CommandEnvelope::GetModeInfo(_) => &self.mode_info,
+      CommandEnvelope::ListRulesets(_) => &self.rulesets,
+      CommandEnvelope::ListWorlds(_) => &self.worlds,
+      CommandEnvelope::GetJournalInfo(_) => &self.journal_info,
+      CommandEnvelope::DefineRulesSubcmd(
+        DefineRulesSubcmdEnvelope::CurrentRules(_)
+      ) => &self.current_rules,
+      CommandEnvelope::CreateWorldSubcmd(
+        CreateWorldSubcmdEnvelope::CurrentGenerationPhase(_)
+      ) => &self.generation_phase,
+      _ => return None,
+    };
+    answer.clone()
+  }
+}
+
+/**
+ * The channels events are pushed to, one per subscriber.  Shared between
+ * the game server and its thread.
+ */
+#[derive(Clone, Default)]
+struct EventSubscribers(Arc<Mutex<Vec<UnboundedSender<EventEnvelope>>>>);
+impl EventSubscribers {
+  fn subscribe(&self) -> UnboundedReceiver<EventEnvelope> {
+    let (event_tx, event_rx) = unbounded();
+    self.0.lock().unwrap().push(event_tx);
+    event_rx
+  }
+
+  fn publish(&self, event: EventEnvelope) {
+    log::debug!("GameServer: publishing {}", event.name());
+    // Subscribers that have gone away are dropped.
+    self.0.lock().unwrap().retain(|event_tx| {
+      event_tx.unbounded_send(event.clone()).is_ok()
+    });
+  }
+
+  /** Publish a last event, and end every subscriber's stream. */
+  fn close(&self, event: EventEnvelope) {
+    self.publish(event);
+    self.0.lock().unwrap().clear();
+  }
+}
+
 /**
  * A server that fronts the game engine running on another thread.
  * Provides a rust API for interacting with the game, and a generic
- * `perform_command` method for usage by the network server.
+ * `submit_command` method for usage by the network server.
  */
 pub(crate) struct GameServer {
+  // Kept to restart the game thread with, should it stop.
+  config: GameServerConfig,
+
   // The join-handle for the game thread.
-  _join_handle: Option<JoinHandle<()>>,
+  join_handle: Option<JoinHandle<()>>,
 
-  // A mpsc channel for sending messages to the game thread.
-  command_tx: mpsc::Sender<CommandEnvelope>,
+  // A mpsc channel for sending commands to the game thread.
+  command_tx: mpsc::Sender<PendingCommand>,
 
-  // An mpsc channel for receving responses and events from the game thread.
-  response_rx: mpsc::Receiver<ResponseEnvelope>,
+  // Answers to read-only queries, kept up by the game thread.
+  cached_queries: Arc<Mutex<CachedQueries>>,
+
+  // The answer to `Hello`, which does not change while the server runs.
+  server_info: ServerInfo,
+
+  event_subscribers: EventSubscribers,
 }
 impl GameServer {
-  pub(crate) fn new(config: &GameServerConfig) -> GameServer {
+  /**
+   * Start the game thread.  Fails if the data root cannot be opened, e.g.
+   * as another server holds its lock.
+   */
+  pub(crate) fn new(config: &GameServerConfig) -> io::Result<GameServer> {
     GameServerInner::start_thread(config)
   }
 
-  pub(crate) fn perform_command(&self, command: CommandEnvelope) -> ResponseEnvelope {
-    log::debug!("GameServer::perform_command");
-    self.command_tx.send(command).unwrap();
-    let response = match self.response_rx.recv() {
-      Ok(response) => response,
+  /**
+   * Queue a command for the game thread.  Commands are performed in the
+   * order they are submitted, and the returned receiver yields the
+   * response once the command is done.  The queries of `CachedQueries`,
+   * e.g. `GetModeInfo` and `ListWorlds`, are answered at once from the
+   * state left by the last command performed, so that they need not wait
+   * on a long one such as `TakeGenerationStep`.  `Hello` is answered at
+   * once as it does not depend on the game.  Every other command waits for
+   * those queued before it.
+   *
+   * If the game thread has stopped, it is restarted in the main menu
+   * before the command is queued.
+   */
+  pub(crate) fn submit_command(&mut self, command: CommandEnvelope)
+    -> oneshot::Receiver<ResponseEnvelope>
+  {
+    log::debug!("GameServer::submit_command");
+    let stopped = self.join_handle.as_ref()
+      .is_none_or(|join_handle| join_handle.is_finished());
+    if stopped {
+      log::error!("GameServer::submit_command: game thread stopped, restarting");
+      self.restart_thread();
+    }
+    let (response_tx, response_rx) = oneshot::channel();
+    if let Some(answer) = self.cached_queries.lock().unwrap().answer(&command) {
+      let _ = response_tx.send(answer);
+      return response_rx;
+    }
+    let command = match command {
+      CommandEnvelope::Hello(hello_cmd) => {
+        let _ = response_tx.send(self.handle_hello_cmd(hello_cmd));
+        return response_rx;
+      },
+      command => command,
+    };
+    if let Err(mpsc::SendError(pending)) =
+      self.command_tx.send((command, response_tx))
+    {
+      log::error!("GameServer::submit_command: game thread stopped, restarting");
+      self.restart_thread();
+      // If it cannot be restarted, the sender is dropped with the command
+      // and the receiver is cancelled.
+      if let Err(err) = self.command_tx.send(pending) {
+        log::error!("GameServer::submit_command: command channel errored: {:?}", err);
+      }
+    }
+    response_rx
+  }
+
+  fn handle_hello_cmd(&self, hello_cmd: HelloCmd) -> ResponseEnvelope {
+    log::debug!("GameServer::handle_hello_cmd");
+    if let Some(client_version) = hello_cmd.protocol_version {
+      if client_version != PROTOCOL_VERSION {
+        return ResponseEnvelope::Failed(FailedResponse::new(format!(
+          "Protocol version mismatch: the client speaks {}, the server \
+           speaks {}.",
+          client_version, PROTOCOL_VERSION
+        )));
+      }
+    }
+    ResponseEnvelope::ServerInfo(self.server_info.clone())
+  }
+
+  /**
+   * Start a new game thread in place of one that stopped.  The game is
+   * back in the main menu, and subscribers are told so.
+   */
+  fn restart_thread(&mut self) {
+    // Wait for the stopped thread to let go of the data root's lock.
+    if let Some(join_handle) = self.join_handle.take() {
+      let _ = join_handle.join();
+    }
+    let data_store = match GameServerInner::open_data_store(&self.config) {
+      Ok(data_store) => data_store,
       Err(err) => {
-        panic!("GameServer::perform_command: response channel errored: {:?}", err);
+        log::error!("GameServer::restart_thread: {}", err);
+        return;
       }
     };
-    response
+    *self.cached_queries.lock().unwrap() = CachedQueries::default();
+    let (join_handle, command_tx) = GameServerInner::spawn_thread(
+      data_store,
+      self.cached_queries.clone(),
+      self.event_subscribers.clone(),
+    );
+    self.join_handle = Some(join_handle);
+    self.command_tx = command_tx;
+    self.event_subscribers.publish(EventEnvelope::ModeChanged { mode: None });
+  }
+
+  /**
+   * Subscribe to the events the game pushes.  The stream ends when the
+   * server shuts down.
+   */
+  pub(crate) fn subscribe_events(&self) -> UnboundedReceiver<EventEnvelope> {
+    self.event_subscribers.subscribe()
+  }
+
+  /**
+   * Push an event that did not come from the game thread, e.g. a change
+   * of sessions.
+   */
+  pub(crate) fn publish_event(&self, event: EventEnvelope) {
+    self.event_subscribers.publish(event);
+  }
+
+  /**
+   * Tell subscribers that the server is shutting down, and end their
+   * streams.
+   */
+  pub(crate) fn shutdown(&self) {
+    log::info!("GameServer::shutdown");
+    self.event_subscribers.close(EventEnvelope::ServerShutdown {});
   }
 }
 
 pub(crate) struct GameServerInner {
-  command_rx: mpsc::Receiver<CommandEnvelope>,
-  response_tx: mpsc::Sender<ResponseEnvelope>,
+  command_rx: mpsc::Receiver<PendingCommand>,
+  cached_queries: Arc<Mutex<CachedQueries>>,
+  event_subscribers: EventSubscribers,
   mode: Option<GameMode>,
   data_store: DataStore,
   stop: bool,
 }
 impl GameServerInner {
-  pub(crate) fn start_thread(config: &GameServerConfig) -> GameServer {
-
-    let (command_tx, command_rx) = mpsc::channel::<CommandEnvelope>();
-    let (response_tx, response_rx) = mpsc::channel::<ResponseEnvelope>();
+  pub(crate) fn start_thread(config: &GameServerConfig)
+    -> io::Result<GameServer>
+  {
+    let cached_queries = Arc::new(Mutex::new(CachedQueries::default()));
+    let event_subscribers = EventSubscribers::default();
 
-    let path: PathBuf = config.data_root.clone().try_into().expect(
-      format!("Failed to convert data_root to PathBuf: {}", &config.data_root).as_str()
+    let data_store = Self::open_data_store(config)?;
+    Self::migrate_rulesets(&data_store);
+    let (join_handle, command_tx) = Self::spawn_thread(
+      data_store,
+      cached_queries.clone(),
+      event_subscribers.clone(),
     );
-    let data_store = DataStore::new(&path);
+
+    Ok(GameServer {
+      config: config.clone(),
+      join_handle: Some(join_handle),
+      command_tx,
+      cached_queries,
+      server_info: Self::server_info(),
+      event_subscribers,
+    })
+  }
+
+  fn open_data_store(config: &GameServerConfig) -> io::Result<DataStore> {
+    let path = PathBuf::from(&config.data_root);
+    DataStore::new(&path).map_err(|err| io::Error::new(
+      err.kind(),
+      format!("Failed to open data root {:?}: {}", path, err),
+    ))
+  }
+
+  fn spawn_thread(
+    data_store: DataStore,
+    cached_queries: Arc<Mutex<CachedQueries>>,
+    event_subscribers: EventSubscribers,
+  ) -> (JoinHandle<()>, mpsc::Sender<PendingCommand>) {
+    Self::set_aside_journal(&data_store);
+    let (command_tx, command_rx) = mpsc::channel::<PendingCommand>();
     let join_handle = std::thread::spawn(move || {
       let mut inner = GameServerInner {
         command_rx,
-        response_tx,
+        cached_queries,
+        event_subscribers,
         mode: None,
         data_store,
         stop: false,
       };
+      inner.refresh_cached_queries(true);
       inner.run();
     });
+    (join_handle, command_tx)
+  }
 
-    GameServer {
-      _join_handle: Some(join_handle),
-      command_tx,
-      response_rx,
+  /**
+   * Keep the journal of the last run for `ReplayJournal`, and start a new
+   * one.
+   */
+  fn set_aside_journal(data_store: &DataStore) {
+    let journal = match data_store.journal() {
+      Ok(journal) => journal,
+      Err(err) => {
+        log::error!("Failed to open the command journal: {}", err);
+        return;
+      }
+    };
+    if let Err(err) = journal.set_aside() {
+      log::error!("Failed to set aside the command journal: {}", err);
+      return;
+    }
+    match journal.read_previous::<CommandEnvelope>() {
+      Ok(entries) if !entries.is_empty() => log::info!(
+        "The command journal of the last run has {} commands; \
+         ReplayJournal rebuilds its mode",
+        entries.len()
+      ),
+      Ok(_) => {},
+      Err(err) => log::error!("Failed to read the command journal: {}", err),
+    }
+  }
+
+  fn migrate_rulesets(data_store: &DataStore) {
+    let rulesets = match data_store.rulesets() {
+      Ok(rulesets) => rulesets,
+      Err(err) => {
+        log::error!("Failed to open ruleset store for migration: {}", err);
+        return;
+      }
+    };
+    for (name, result) in rulesets.migrate_all() {
+      match result {
+        RulesetMigrationResult::UpToDate => {},
+        RulesetMigrationResult::Migrated { from_version } => log::info!(
+          "Migrated ruleset {:?} from schema version {} to {}",
+          name, from_version, RULESET_SCHEMA_VERSION
+        ),
+        RulesetMigrationResult::Failed(err) => log::error!(
+          "Failed to migrate ruleset {:?}: {}", name, err
+        ),
+      }
     }
   }
 
   fn run(&mut self) {
     loop {
       match self.command_rx.recv() {
-        Ok(command) => {
-          let response = self.handle_command(command);
-          if let Err(err) = self.response_tx.send(response) {
-            log::error!("GameServer thread: FailedToSendResponse: {:?}", err);
+        Ok((command, response_tx)) => {
+          let mode_before = self.current_mode_info();
+          let changes_rulesets = Self::changes_rulesets(&command);
+          let read_only = command.is_read_only();
+          let response = match panic::catch_unwind(AssertUnwindSafe(|| {
+            self.perform_command(command)
+          })) {
+            Ok(response) => {
+              if !read_only {
+                self.refresh_cached_queries(false);
+              }
+              response
+            },
+            Err(payload) => {
+              let response = self.recover_from_panic(payload);
+              // The journal was set aside, so its info is read again.
+              self.refresh_cached_queries(true);
+              response
+            },
+          };
+          self.publish_changes(mode_before, changes_rulesets, &response);
+          if response_tx.send(response).is_err() {
+            log::warn!("GameServer thread: response receiver dropped");
           }
           if self.stop {
             break;
@@ -117,6 +451,192 @@ impl GameServerInner {
     }
   }
 
+  /**
+   * Answer the cached queries anew, after a command that may have changed
+   * their answers.  The journal of the last run only changes when it is
+   * set aside, so its info is read again only if `journal` is set.
+   * Failures are not kept, so that the query is tried again on the thread.
+   */
+  fn refresh_cached_queries(&mut self, journal: bool) {
+    let mode_info = self.handle_get_mode_info_cmd(GetModeInfoCmd {});
+    let rulesets = self.handle_list_rulesets_cmd(ListRulesetsCmd {});
+    let worlds = self.handle_list_worlds_cmd(ListWorldsCmd {});
+    let journal_info = journal.then(|| {
+      self.handle_get_journal_info_cmd(GetJournalInfoCmd {})
+    });
+    let current_rules = match self.mode {
+      Some(GameMode::DefineRules(_)) => Some(self.handle_command(
+        CommandEnvelope::DefineRulesSubcmd(
+          DefineRulesSubcmdEnvelope::CurrentRules(CurrentRulesCmd {})
+        )
+      )),
+      _ => None,
+    };
+    let generation_phase = match self.mode {
+      Some(GameMode::CreateWorld(_)) => Some(self.handle_command(
+        CommandEnvelope::CreateWorldSubcmd(
+          CreateWorldSubcmdEnvelope::CurrentGenerationPhase(
+            CurrentGenerationPhaseCmd {}
+          )
+        )
+      )),
+      _ => None,
+    };
+
+    let succeeded = |response: &ResponseEnvelope| {
+      Self::failure_messages(response).is_none()
+    };
+    let mut cached_queries = self.cached_queries.lock().unwrap();
+    cached_queries.mode_info = Some(mode_info);
+    cached_queries.rulesets = Some(rulesets).filter(succeeded);
+    cached_queries.worlds = Some(worlds).filter(succeeded);
+    if let Some(journal_info) = journal_info {
+      cached_queries.journal_info = Some(journal_info).filter(succeeded);
+    }
+    cached_queries.current_rules = current_rules.filter(succeeded);
+    cached_queries.generation_phase = generation_phase.filter(succeeded);
+  }
+
+  /**
+   * Answer a command that panicked.  The mode it panicked in may be left
+   * inconsistent, so it is dropped and the game is back in the main menu.
+   */
+  fn recover_from_panic(&mut self, payload: Box<dyn Any + Send>)
+    -> ResponseEnvelope
+  {
+    let message = payload.downcast_ref::<&str>().map(|message| message.to_string())
+      .or_else(|| payload.downcast_ref::<String>().cloned())
+      .unwrap_or_else(|| "unknown error".to_string());
+    log::error!("GameServer thread: command panicked: {}", message);
+    self.mode = None;
+    // Keep what led up to the panic, so that it can be replayed.
+    Self::set_aside_journal(&self.data_store);
+    ResponseEnvelope::Failed(FailedResponse::new_vec(vec![
+      format!("The command failed unexpectedly: {}", message),
+      "The game has returned to the main menu.".to_string(),
+    ]))
+  }
+
+  /**
+   * Whether a command writes to the ruleset store, if it succeeds.
+   */
+  fn changes_rulesets(command: &CommandEnvelope) -> bool {
+    matches!(command,
+      CommandEnvelope::DeleteRuleset(_) |
+      CommandEnvelope::RenameRuleset(_) |
+      CommandEnvelope::DuplicateRuleset(_) |
+      CommandEnvelope::ImportRuleset(_) |
+      CommandEnvelope::DefineRulesSubcmd(DefineRulesSubcmdEnvelope::SaveRules(_))
+    )
+  }
+
+  /**
+   * Publish the events for the changes a command made to the mode and the
+   * ruleset store.
+   */
+  fn publish_changes(&self,
+    mode_before: Option<GameModeInfo>,
+    changes_rulesets: bool,
+    response: &ResponseEnvelope,
+  ) {
+    let mode = self.current_mode_info();
+    if mode != mode_before {
+      self.event_subscribers.publish(EventEnvelope::ModeChanged { mode });
+    }
+
+    let succeeded = matches!(response,
+      ResponseEnvelope::Ok {} |
+      ResponseEnvelope::ImportedRuleset(_) |
+      ResponseEnvelope::DefineRulesSubcmd(DefineRulesSubcmdResponse::Ok {})
+    );
+    if changes_rulesets && succeeded {
+      match self.data_store.rulesets() {
+        Ok(rulesets) => self.event_subscribers.publish(
+          EventEnvelope::RulesetsChanged {
+            rulesets: rulesets.list().into_iter()
+              .map(|entry| entry.into_ruleset_entry())
+              .collect(),
+          }
+        ),
+        Err(err) => log::error!(
+          "GameServer thread: failed to list changed rulesets: {}", err
+        ),
+      }
+    }
+  }
+
+  /**
+   * Handle a command, and record it in the journal if it changed the
+   * state of the mode.
+   */
+  fn perform_command(&mut self, command: CommandEnvelope) -> ResponseEnvelope {
+    let journaled = Self::is_journaled(&command).then(|| command.clone());
+    let response = self.handle_command(command);
+    if let Some(command) = journaled {
+      self.journal_command(&command, &response);
+    }
+    response
+  }
+
+  /**
+   * Whether a command is journaled: entering and leaving modes, and the
+   * subcommands that change a mode.  Commands that only write to the data
+   * store, like `SaveRules`, are left out, as the store keeps their
+   * results.
+   */
+  fn is_journaled(command: &CommandEnvelope) -> bool {
+    match command {
+      CommandEnvelope::EnterMode(_) |
+      CommandEnvelope::EnterMainMenuMode(_) => true,
+      CommandEnvelope::DefineRulesSubcmd(subcmd) =>
+        !command.is_read_only() &&
+          !matches!(subcmd, DefineRulesSubcmdEnvelope::SaveRules(_)),
+      CommandEnvelope::CreateWorldSubcmd(subcmd) =>
+        !command.is_read_only() &&
+          !matches!(subcmd, CreateWorldSubcmdEnvelope::ExportMapLayer(_)),
+      _ => false,
+    }
+  }
+
+  /**
+   * Record a journaled command.  Entering a mode starts the journal over,
+   * and returning to the main menu clears it.  Commands that failed are
+   * not recorded.
+   */
+  fn journal_command(&self,
+    command: &CommandEnvelope,
+    response: &ResponseEnvelope,
+  ) {
+    if Self::failure_messages(response).is_some() {
+      return;
+    }
+    let result = self.data_store.journal().and_then(|journal| {
+      match command {
+        CommandEnvelope::EnterMode(_) =>
+          journal.clear().and_then(|_| journal.append(command)),
+        CommandEnvelope::EnterMainMenuMode(_) => journal.clear(),
+        _ => journal.append(command),
+      }
+    });
+    if let Err(err) = result {
+      log::error!("GameServer thread: failed to journal command: {}", err);
+    }
+  }
+
+  /** The messages of a response that reports a failure. */
+  fn failure_messages(response: &ResponseEnvelope) -> Option<Vec<String>> {
+    match response {
+      ResponseEnvelope::Failed(failed) => Some(failed.messages().to_vec()),
+      ResponseEnvelope::DefineRulesSubcmd(
+        DefineRulesSubcmdResponse::Failed(messages)
+      ) |
+      ResponseEnvelope::CreateWorldSubcmd(
+        CreateWorldSubcmdResponse::Failed(messages)
+      ) => Some(messages.clone()),
+      _ => None,
+    }
+  }
+
   fn handle_command(&mut self, command: CommandEnvelope) -> ResponseEnvelope {
     match command {
       CommandEnvelope::EnterMode(enter_mode_cmd) => {
@@ -135,6 +655,63 @@ impl GameServerInner {
         let response = self.handle_list_rulesets_cmd(list_rulesets_cmd);
         return response;
       },
+      CommandEnvelope::DeleteRuleset(delete_ruleset_cmd) => {
+        let response = self.handle_delete_ruleset_cmd(delete_ruleset_cmd);
+        return response;
+      },
+      CommandEnvelope::RenameRuleset(rename_ruleset_cmd) => {
+        let response = self.handle_rename_ruleset_cmd(rename_ruleset_cmd);
+        return response;
+      },
+      CommandEnvelope::DuplicateRuleset(duplicate_ruleset_cmd) => {
+        let response =
+          self.handle_duplicate_ruleset_cmd(duplicate_ruleset_cmd);
+        return response;
+      },
+      CommandEnvelope::ExportRuleset(export_ruleset_cmd) => {
+        let response = self.handle_export_ruleset_cmd(export_ruleset_cmd);
+        return response;
+      },
+      CommandEnvelope::ImportRuleset(import_ruleset_cmd) => {
+        let response = self.handle_import_ruleset_cmd(import_ruleset_cmd);
+        return response;
+      },
+      CommandEnvelope::ListWorlds(list_worlds_cmd) => {
+        let response = self.handle_list_worlds_cmd(list_worlds_cmd);
+        return response;
+      },
+      CommandEnvelope::ListHeightmaps(list_heightmaps_cmd) => {
+        let response = self.handle_list_heightmaps_cmd(list_heightmaps_cmd);
+        return response;
+      },
+      CommandEnvelope::UploadHeightmap(upload_heightmap_cmd) => {
+        let response = self.handle_upload_heightmap_cmd(upload_heightmap_cmd);
+        return response;
+      },
+      CommandEnvelope::GetJournalInfo(get_journal_info_cmd) => {
+        let response = self.handle_get_journal_info_cmd(get_journal_info_cmd);
+        return response;
+      },
+      CommandEnvelope::ReplayJournal(replay_journal_cmd) => {
+        let response = self.handle_replay_journal_cmd(replay_journal_cmd);
+        return response;
+      },
+      CommandEnvelope::Hello(_) => {
+        // `submit_command` answers this, from the info gathered at startup.
+        return ResponseEnvelope::Failed(FailedResponse::new(
+          "Hello is not handled by the game thread."
+        ));
+      },
+      CommandEnvelope::GetSessionInfo(_) |
+      CommandEnvelope::TakeControl(_) |
+      CommandEnvelope::HandOffControl(_) |
+      CommandEnvelope::SetMapDataEncoding(_) => {
+        // Sessions and connections belong to the network server, which
+        // answers these.
+        return ResponseEnvelope::Failed(FailedResponse::new(
+          "Session commands are not handled by the game server."
+        ));
+      },
       CommandEnvelope::DefineRulesSubcmd(define_rules_subcmd) => {
         let envelope = self.handle_define_rules_subcmd(define_rules_subcmd);
         return ResponseEnvelope::DefineRulesSubcmd(envelope);
@@ -142,10 +719,54 @@ impl GameServerInner {
       CommandEnvelope::CreateWorldSubcmd(create_world_subcmd) => {
         let envelope = self.handle_create_world_subcmd(create_world_subcmd);
         return ResponseEnvelope::CreateWorldSubcmd(envelope);
-      }
+      },
+      CommandEnvelope::ViewWorldSubcmd(view_world_subcmd) => {
+        let envelope = self.handle_view_world_subcmd(view_world_subcmd);
+        return ResponseEnvelope::ViewWorldSubcmd(envelope);
+      },
     };
   }
 
+  /**
+   * Describe the server for `Hello`.  Gathering the command names
+   * serializes every protocol example, and finding the GPU adapter blocks,
+   * so this is done once, at startup.
+   */
+  fn server_info() -> ServerInfo {
+    let commands = get_protocol_docs().into_iter()
+      .map(|category| CommandCategoryInfo {
+        name: category.name,
+        commands: category.commands.into_iter()
+          .map(|command| command.name)
+          .collect(),
+      })
+      .collect();
+    let events = EventEnvelope::protocol_examples().iter()
+      .map(|event| event.name().to_string())
+      .collect();
+    let gpu_adapter = cog_adapter_info().map(|info| GpuAdapterInfo {
+      name: info.name,
+      backend: format!("{:?}", info.backend),
+      device_type: format!("{:?}", info.device_type),
+      driver: info.driver,
+    });
+    ServerInfo {
+      protocol_version: PROTOCOL_VERSION,
+      server_version: env!("CARGO_PKG_VERSION").to_string(),
+      commands,
+      events,
+      world_limits: defaults::world_descriptor_limits(),
+      gpu_adapter,
+      features: vec![
+        ServerFeature::RequestIds,
+        ServerFeature::Events,
+        ServerFeature::Sessions,
+        ServerFeature::BinaryMapData,
+        ServerFeature::CommandJournal,
+      ],
+    }
+  }
+
   fn handle_enter_mode_cmd(&mut self, enter_mode_cmd: EnterModeCmd)
     -> ResponseEnvelope
   {
@@ -180,6 +801,27 @@ impl GameServerInner {
         self.mode = Some(GameMode::CreateWorld(create_world_mode));
         ResponseEnvelope::Ok {}
       },
+      GameModeInfo::ViewWorld(view_world_info) => {
+        if self.mode.is_some() {
+          log::warn!(
+            "GameServerInner::handle_enter_mode_cmd: Already in a mode"
+          );
+          return ResponseEnvelope::Failed(FailedResponse::new(
+            "Cannot enter mode: already in a mode"
+          ));
+        }
+        let view_world_mode = match ViewWorldMode::new(
+          &view_world_info.world_name,
+          &self.data_store,
+        ) {
+          Ok(mode) => mode,
+          Err(messages) => {
+            return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+          }
+        };
+        self.mode = Some(GameMode::ViewWorld(view_world_mode));
+        ResponseEnvelope::Ok {}
+      },
     }
   }
 
@@ -195,16 +837,23 @@ impl GameServerInner {
     -> ResponseEnvelope
   {
     log::debug!("GameServerInner::handle_get_mode_info_cmd");
+    match self.current_mode_info() {
+      Some(mode_info) => ResponseEnvelope::InMode(mode_info),
+      None => ResponseEnvelope::InMainMenuMode {},
+    }
+  }
+
+  fn current_mode_info(&self) -> Option<GameModeInfo> {
     match &self.mode {
       Some(GameMode::DefineRules(_)) =>
-        ResponseEnvelope::InMode(
-          GameModeInfo::DefineRules(DefineRulesModeInfo {})
-        ),
+        Some(GameModeInfo::DefineRules(DefineRulesModeInfo {})),
       Some(GameMode::CreateWorld(_)) =>
-        ResponseEnvelope::InMode(
-          GameModeInfo::CreateWorld(CreateWorldModeInfo {})
-        ),
-      None => ResponseEnvelope::InMainMenuMode {},
+        Some(GameModeInfo::CreateWorld(CreateWorldModeInfo {})),
+      Some(GameMode::ViewWorld(view_world_mode)) =>
+        Some(GameModeInfo::ViewWorld(ViewWorldModeInfo {
+          world_name: view_world_mode.world_name().to_string(),
+        })),
+      None => None,
     }
   }
 
@@ -212,10 +861,365 @@ impl GameServerInner {
     -> ResponseEnvelope
   {
     log::debug!("GameServerInner::handle_list_rulesets_cmd");
-    let rulesets = self.data_store.rulesets().list().into_iter().map(
-      |rs| rs.into_ruleset_entry()
-    ).collect::<Vec<_>>();
-    ResponseEnvelope::RulesetList(rulesets)
+    match self.data_store.rulesets() {
+      Ok(rulesets) => ResponseEnvelope::RulesetList(
+        rulesets.list().into_iter().map(|rs| rs.into_ruleset_entry()).collect()
+      ),
+      Err(err) => ResponseEnvelope::Failed(FailedResponse::new(
+        format!("Failed to read ruleset store: {}", err)
+      )),
+    }
+  }
+
+  fn handle_delete_ruleset_cmd(&mut self, delete_ruleset_cmd: DeleteRulesetCmd)
+    -> ResponseEnvelope
+  {
+    log::debug!("GameServerInner::handle_delete_ruleset_cmd");
+    let name = &delete_ruleset_cmd.ruleset_name;
+    if let Err(messages) = self.check_ruleset_exists(name)
+      .and_then(|_| self.check_ruleset_not_in_use(name))
+      .and_then(|_| self.check_ruleset_unreferenced(name))
+    {
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+    }
+
+    let result = self.data_store.rulesets()
+      .and_then(|mut rulesets| rulesets.delete(name));
+    Self::ruleset_store_response(result)
+  }
+
+  fn handle_rename_ruleset_cmd(&mut self, rename_ruleset_cmd: RenameRulesetCmd)
+    -> ResponseEnvelope
+  {
+    log::debug!("GameServerInner::handle_rename_ruleset_cmd");
+    let RenameRulesetCmd { ruleset_name, new_name } = rename_ruleset_cmd;
+    if let Err(messages) = self.check_ruleset_exists(&ruleset_name)
+      .and_then(|_| self.check_ruleset_not_in_use(&ruleset_name))
+      .and_then(|_| self.check_new_ruleset_name(&new_name))
+    {
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+    }
+
+    let result = self.data_store.rulesets()
+      .and_then(|mut rulesets| rulesets.rename(&ruleset_name, &new_name));
+    if result.is_err() {
+      return Self::ruleset_store_response(result);
+    }
+
+    // Keep saved worlds pointing at their ruleset, or put the ruleset's
+    // name back so that they still find it.
+    let result = self.data_store.worlds()
+      .and_then(|mut worlds| worlds.rename_ruleset(&ruleset_name, &new_name));
+    if let Err(err) = result {
+      let mut messages = vec![
+        format!("Failed to update world store: {}", err),
+      ];
+      let rollback = self.data_store.rulesets()
+        .and_then(|mut rulesets| rulesets.rename(&new_name, &ruleset_name));
+      if let Err(err) = rollback {
+        messages.push(format!("Failed to restore ruleset name: {}", err));
+      }
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+    }
+    ResponseEnvelope::Ok {}
+  }
+
+  fn handle_duplicate_ruleset_cmd(&mut self,
+    duplicate_ruleset_cmd: DuplicateRulesetCmd
+  ) -> ResponseEnvelope {
+    log::debug!("GameServerInner::handle_duplicate_ruleset_cmd");
+    let DuplicateRulesetCmd { ruleset_name, new_name } = duplicate_ruleset_cmd;
+    if let Err(messages) = self.check_ruleset_exists(&ruleset_name)
+      .and_then(|_| self.check_new_ruleset_name(&new_name))
+    {
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+    }
+
+    let result = self.data_store.rulesets()
+      .and_then(|mut rulesets| rulesets.duplicate(&ruleset_name, &new_name));
+    Self::ruleset_store_response(result)
+  }
+
+  fn handle_export_ruleset_cmd(&mut self, export_ruleset_cmd: ExportRulesetCmd)
+    -> ResponseEnvelope
+  {
+    log::debug!("GameServerInner::handle_export_ruleset_cmd");
+    let ExportRulesetCmd { ruleset_name, include_programs } = export_ruleset_cmd;
+    if let Err(messages) = self.check_ruleset_exists(&ruleset_name) {
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+    }
+
+    let bundle = self.data_store.rulesets()
+      .and_then(|rulesets| rulesets.read(&ruleset_name))
+      .map_err(|err| format!("Failed to load ruleset: {}", err))
+      .and_then(|ruleset| RulesetBundle::new(&ruleset, include_programs));
+    match bundle {
+      Ok(bundle) => ResponseEnvelope::RulesetBundle(bundle),
+      Err(message) => ResponseEnvelope::Failed(FailedResponse::new(message)),
+    }
+  }
+
+  fn handle_import_ruleset_cmd(&mut self, import_ruleset_cmd: ImportRulesetCmd)
+    -> ResponseEnvelope
+  {
+    log::debug!("GameServerInner::handle_import_ruleset_cmd");
+    let ImportRulesetCmd { bundle, on_conflict } = import_ruleset_cmd;
+    if on_conflict == RulesetImportConflict::Overwrite {
+      // Only the bundled name can be overwritten, and it is guarded as a
+      // deletion would be.
+      let name = bundle.ruleset.get("name").and_then(|name| name.as_str());
+      if let Some(Err(messages)) = name.map(|name| {
+        self.check_ruleset_not_in_use(name)
+          .and_then(|_| self.check_ruleset_unreferenced(name))
+      }) {
+        return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+      }
+    }
+
+    match bundle.import(&self.data_store, on_conflict) {
+      Ok(ruleset) => ResponseEnvelope::ImportedRuleset(ruleset.entry()),
+      Err(RulesetImportError::Invalid(validation)) =>
+        ResponseEnvelope::InvalidRuleset(validation),
+      Err(RulesetImportError::Failed(messages)) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn ruleset_store_response(result: io::Result<()>) -> ResponseEnvelope {
+    match result {
+      Ok(()) => ResponseEnvelope::Ok {},
+      Err(err) => ResponseEnvelope::Failed(FailedResponse::new(
+        format!("Failed to update ruleset store: {}", err)
+      )),
+    }
+  }
+
+  fn read_ruleset_store(&self) -> Result<RulesetStore, Vec<String>> {
+    self.data_store.rulesets().map_err(|err| vec![
+      format!("Failed to read ruleset store: {}", err),
+    ])
+  }
+
+  fn check_ruleset_exists(&self, name: &str) -> Result<(), Vec<String>> {
+    if self.read_ruleset_store()?.contains(name) {
+      Ok(())
+    } else {
+      Err(vec![format!("No such ruleset: {}", name)])
+    }
+  }
+
+  fn check_ruleset_not_in_use(&self, name: &str) -> Result<(), Vec<String>> {
+    match self.mode.as_ref().and_then(|mode| mode.ruleset_in_use()) {
+      Some(in_use) if in_use == name => Err(vec![
+        format!("Ruleset is in use by the current mode: {}", name),
+      ]),
+      _ => Ok(()),
+    }
+  }
+
+  /**
+   * Check that no saved world uses a ruleset, and that no ruleset is
+   * derived from it.
+   */
+  fn check_ruleset_unreferenced(&self, name: &str) -> Result<(), Vec<String>> {
+    let worlds = self.data_store.worlds().map_err(|err| vec![
+      format!("Failed to read world store: {}", err),
+    ])?;
+    let using_worlds = worlds.list().into_iter()
+      .filter(|entry| entry.descriptor.ruleset_name == name)
+      .map(|entry| format!("Ruleset is used by saved world: {}", entry.name))
+      .collect::<Vec<_>>();
+    if !using_worlds.is_empty() {
+      return Err(using_worlds);
+    }
+
+    let derived = self.read_ruleset_store()?.derived_from(name);
+    if !derived.is_empty() {
+      return Err(derived.into_iter()
+        .map(|name| format!("Ruleset is the base of ruleset: {}", name))
+        .collect());
+    }
+    Ok(())
+  }
+
+  /**
+   * Check a name for a renamed or duplicated ruleset as saving a ruleset
+   * under that name would.
+   */
+  fn check_new_ruleset_name(&self, new_name: &str) -> Result<(), Vec<String>> {
+    let mut errors = Vec::new();
+    RulesetInput::validate_name(new_name, &self.data_store, None, &mut errors);
+    if errors.is_empty() {
+      Ok(())
+    } else {
+      Err(errors.into_iter().map(|error| error.text).collect())
+    }
+  }
+
+  fn handle_list_worlds_cmd(&mut self, _list_worlds_cmd: ListWorldsCmd)
+    -> ResponseEnvelope
+  {
+    log::debug!("GameServerInner::handle_list_worlds_cmd");
+    match self.data_store.worlds() {
+      Ok(worlds) => ResponseEnvelope::WorldList(
+        worlds.list().into_iter().map(|entry| entry.descriptor).collect()
+      ),
+      Err(err) => ResponseEnvelope::Failed(FailedResponse::new(
+        format!("Failed to read world store: {}", err)
+      )),
+    }
+  }
+
+  fn handle_list_heightmaps_cmd(&mut self,
+    _list_heightmaps_cmd: ListHeightmapsCmd
+  ) -> ResponseEnvelope {
+    log::debug!("GameServerInner::handle_list_heightmaps_cmd");
+    match self.data_store.heightmaps() {
+      Ok(heightmaps) => ResponseEnvelope::HeightmapList(
+        heightmaps.list().into_iter()
+          .map(|entry| entry.into_heightmap_entry())
+          .collect()
+      ),
+      Err(err) => ResponseEnvelope::Failed(FailedResponse::new(
+        format!("Failed to read heightmap store: {}", err)
+      )),
+    }
+  }
+
+  fn handle_upload_heightmap_cmd(&mut self,
+    upload_heightmap_cmd: UploadHeightmapCmd
+  ) -> ResponseEnvelope {
+    log::debug!("GameServerInner::handle_upload_heightmap_cmd");
+    let UploadHeightmapCmd { name, format, dims, data } = upload_heightmap_cmd;
+    if name.is_empty() || name.len() > 64 {
+      return ResponseEnvelope::Failed(FailedResponse::new(
+        "Heightmap name must be 1-64 characters."
+      ));
+    }
+    let max_dims = defaults::world_descriptor_limits().max_dims;
+    // Base64 encodes three bytes in four characters.
+    if data.len() / 4 * 3 > Heightmap::max_encoded_len(max_dims) {
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(vec![
+        "Invalid heightmap.".to_string(),
+        "Heightmap data is too large.".to_string(),
+      ]));
+    }
+    let heightmap = match decode_base64(&data)
+      .and_then(|bytes| Heightmap::decode(format, dims, max_dims, &bytes))
+    {
+      Ok(heightmap) => heightmap,
+      Err(err) => {
+        return ResponseEnvelope::Failed(FailedResponse::new_vec(vec![
+          "Invalid heightmap.".to_string(),
+          err,
+        ]));
+      }
+    };
+    let result = self.data_store.heightmaps()
+      .and_then(|mut store| store.write(&name, &heightmap));
+    match result {
+      Ok(()) => ResponseEnvelope::Heightmap(HeightmapEntry {
+        name,
+        dims: heightmap.dims,
+      }),
+      Err(err) => ResponseEnvelope::Failed(FailedResponse::new(
+        format!("Failed to save heightmap: {}", err)
+      )),
+    }
+  }
+
+  fn handle_get_journal_info_cmd(&mut self,
+    _get_journal_info_cmd: GetJournalInfoCmd
+  ) -> ResponseEnvelope {
+    log::debug!("GameServerInner::handle_get_journal_info_cmd");
+    let entries = match self.read_previous_journal() {
+      Ok(entries) => entries,
+      Err(messages) => {
+        return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+      }
+    };
+    let mode = match entries.first().map(|entry| &entry.command) {
+      Some(CommandEnvelope::EnterMode(enter_mode_cmd)) =>
+        Some(enter_mode_cmd.mode.clone()),
+      _ => None,
+    };
+    ResponseEnvelope::JournalInfo(JournalInfo {
+      commands: entries.len() as u32,
+      mode,
+      started_at: entries.first().map(|entry| entry.timestamp),
+      ended_at: entries.last().map(|entry| entry.timestamp),
+    })
+  }
+
+  fn read_previous_journal(&self)
+    -> Result<Vec<JournalEntry<CommandEnvelope>>, Vec<String>>
+  {
+    self.data_store.journal()
+      .and_then(|journal| journal.read_previous())
+      .map_err(|err| vec![
+      format!("Failed to read the command journal: {}", err),
+    ])
+  }
+
+  /**
+   * Perform the commands of the last run's journal again.  They go
+   * through `perform_command`, so the rebuilt mode is journaled anew.
+   */
+  fn handle_replay_journal_cmd(&mut self, _replay_journal_cmd: ReplayJournalCmd)
+    -> ResponseEnvelope
+  {
+    log::debug!("GameServerInner::handle_replay_journal_cmd");
+    if self.mode.is_some() {
+      return ResponseEnvelope::Failed(FailedResponse::new(
+        "Cannot replay the journal: already in a mode"
+      ));
+    }
+    let entries = match self.read_previous_journal() {
+      Ok(entries) => entries,
+      Err(messages) => {
+        return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+      }
+    };
+    let mut commands = entries.into_iter().map(|entry| entry.command);
+    let enter_mode = match commands.next() {
+      Some(command @ CommandEnvelope::EnterMode(_)) => command,
+      Some(_) => {
+        return ResponseEnvelope::Failed(FailedResponse::new(
+          "Cannot replay the journal: it does not begin by entering a mode"
+        ));
+      },
+      None => {
+        return ResponseEnvelope::Failed(FailedResponse::new(
+          "There is no journal to replay."
+        ));
+      },
+    };
+    if let Some(messages) =
+      Self::failure_messages(&self.perform_command(enter_mode))
+    {
+      return ResponseEnvelope::Failed(FailedResponse::new_vec(messages));
+    }
+
+    let mut replayed = 1;
+    let mut skipped = Vec::new();
+    for command in commands {
+      replayed += 1;
+      let response = self.perform_command(command);
+      if let Some(messages) = Self::failure_messages(&response) {
+        log::warn!(
+          "GameServerInner::handle_replay_journal_cmd: command {} failed: {:?}",
+          replayed, messages
+        );
+        skipped.extend(messages.into_iter().map(|message| {
+          format!("Command {}: {}", replayed, message)
+        }));
+      }
+    }
+    ResponseEnvelope::JournalReplayed(JournalReplay {
+      replayed,
+      mode: self.current_mode_info(),
+      skipped,
+    })
   }
 
   fn handle_define_rules_subcmd(&mut self, subcmd: DefineRulesSubcmdEnvelope)
@@ -237,7 +1241,43 @@ impl GameServerInner {
   {
     log::debug!("GameServerInner::handle_create_world_subcommand");
     if let Some(GameMode::CreateWorld(ref mut create_world_mode)) = self.mode {
-      create_world_mode.handle_subcommand(subcmd, &mut self.data_store)
+      let phase_before = create_world_mode.generation_phase();
+      let step_kind = match &subcmd {
+        CreateWorldSubcmdEnvelope::TakeGenerationStep(cmd) => Some(cmd.kind),
+        _ => None,
+      };
+      let started = Instant::now();
+      if let Some(kind) = step_kind {
+        self.event_subscribers.publish(
+          EventEnvelope::GenerationStepStarted { kind }
+        );
+      }
+
+      // A step that panics still finishes, unsuccessfully, before the
+      // panic is recovered from like any other command's.
+      let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        create_world_mode.handle_subcommand(subcmd, &mut self.data_store)
+      }));
+      if let Some(kind) = step_kind {
+        self.event_subscribers.publish(EventEnvelope::GenerationStepFinished {
+          kind,
+          ok: !matches!(&result,
+            Ok(CreateWorldSubcmdResponse::Failed(_)) | Err(_)
+          ),
+          elapsed_ms: started.elapsed().as_millis() as u64,
+        });
+      }
+      let response = result
+        .unwrap_or_else(|payload| panic::resume_unwind(payload));
+      match create_world_mode.generation_phase() {
+        Some(phase) if Some(phase) != phase_before => {
+          self.event_subscribers.publish(
+            EventEnvelope::GenerationPhaseChanged { phase }
+          );
+        },
+        _ => {},
+      }
+      response
     } else {
       log::warn!("GameServerInner::handle_create_world_subcommand: Bad game mode");
       CreateWorldSubcmdResponse::Failed(
@@ -245,4 +1285,18 @@ impl GameServerInner {
       )
     }
   }
+
+  fn handle_view_world_subcmd(&mut self, subcmd: ViewWorldSubcmdEnvelope)
+    -> ViewWorldSubcmdResponse
+  {
+    log::debug!("GameServerInner::handle_view_world_subcommand");
+    if let Some(GameMode::ViewWorld(ref mut view_world_mode)) = self.mode {
+      view_world_mode.handle_subcommand(subcmd, &mut self.data_store)
+    } else {
+      log::warn!("GameServerInner::handle_view_world_subcommand: Bad game mode");
+      ViewWorldSubcmdResponse::Failed(
+        vec!["No game mode to view world".to_string()]
+      )
+    }
+  }
 }
@@ -1,6 +1,9 @@
 use crate::{
   cog::{ CogDevice, CogMapBuffer, CogSeqBuffer },
-  data::map::{ CellData, WorldDims },
+  data::{
+    map::{ CellData, WorldDims },
+    VecMap,
+  },
 };
 
 #[derive(Clone)]
@@ -16,4 +19,26 @@ impl CellDataBuffer {
   pub(crate) fn as_u32_seq_buffer(&self) -> CogSeqBuffer<u32> {
     self.buffer.as_seq_buffer().cast_resized::<u32>()
   }
+
+  /**
+   * Upload the given cells, which must match the buffer's dimensions.
+   */
+  pub(crate) fn write_cells(&self, cells: &VecMap<CellData>) {
+    let dims = self.buffer.dims();
+    assert!(cells.dims() == dims, "Cell data dims do not match buffer");
+    let mut values = Vec::with_capacity(dims.area() as usize);
+    dims.each_index_by_row(|_index, coord| values.push(cells.get_copy(coord)));
+    self.buffer.as_seq_buffer().write_slice(0, &values);
+  }
+
+  /**
+   * Read the full contents of the buffer back to the CPU.
+   */
+  pub(crate) fn read_cells(&self) -> VecMap<CellData> {
+    let dims = self.buffer.dims();
+    let values = self.buffer.as_seq_buffer().read_mapped_full(|data| {
+      data.iter().map(|words| CellData::new(*words)).collect::<Vec<_>>()
+    });
+    VecMap::from_vec(dims, values)
+  }
 }
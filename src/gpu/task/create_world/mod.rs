@@ -6,6 +6,7 @@ mod rand_gen_task;
 mod read_map_data_task;
 mod read_minimap_data_task;
 mod rescale_map_data_task;
+mod resample_map_data_task;
 
 pub(crate) use self::{
   border_fade_task::BorderFadeTask,
@@ -15,4 +16,5 @@ pub(crate) use self::{
   read_map_data_task::ReadMapDataTask,
   read_minimap_data_task::ReadMinimapDataTask,
   rescale_map_data_task::RescaleMapDataTask,
+  resample_map_data_task::ResampleMapDataTask,
 };
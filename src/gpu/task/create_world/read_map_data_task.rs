@@ -1,5 +1,5 @@
 use crate::{
-  cog::{ CogEncoder, CogSeqBuffer, CogTask },
+  cog::{ CogDevice, CogEncoder, CogSeqBuffer, CogTask },
   gpu::wgsl::common::{
     ReadMapDataEntrypoint,
     ReadMapDataShaderScript,
@@ -7,7 +7,7 @@ use crate::{
   },
   data::{
     map::{ CellCoord, WorldDims },
-    ruleset::FormatComponentSelectorReadSpec,
+    ruleset::{ FormatComponentSelector, FormatComponentSelectorReadSpec },
   },
 };
 
@@ -54,6 +54,40 @@ impl ReadMapDataTask {
       output_buffer,
     }
   }
+
+  /**
+   * Run a read of a single component over an area and return the values
+   * in row-major order.
+   */
+  pub(crate) fn read_layer(
+    device: &CogDevice,
+    world_dims: WorldDims,
+    top_left: CellCoord,
+    area: WorldDims,
+    selector: FormatComponentSelector,
+    input_buffer: CogSeqBuffer<u32>,
+    input_entry_size: u32,
+  ) -> Vec<u32> {
+    let output_buffer = device.create_seq_buffer::<u32>(
+      area.area() as usize,
+      "ReadLayer_Output"
+    );
+    let task = ReadMapDataTask::new(
+      world_dims,
+      top_left,
+      area,
+      vec![FormatComponentSelectorReadSpec::new(selector, 0)],
+      input_buffer,
+      input_entry_size,
+      output_buffer.clone()
+    );
+    device.encode_and_run("ReadLayer", |enc| {
+      task.encode(enc);
+    });
+    output_buffer.read_mapped_full(|data| {
+      data[0 .. area.area() as usize].to_vec()
+    })
+  }
 }
 
 impl CogTask for ReadMapDataTask {
@@ -0,0 +1,59 @@
+use crate::{
+  cog::{ CogEncoder, CogSeqBuffer, CogTask },
+  gpu::wgsl::create_world::{
+    ResampleMapDataEntrypoint,
+    ResampleMapDataShaderScript,
+    ResampleMapDataUniforms,
+  },
+  data::map::WorldDims,
+};
+
+/**
+ * Bilinearly resample a map of single-u32 entries to new dimensions.
+ * The corner cells of the source map onto the corner cells of the output.
+ */
+pub(crate) struct ResampleMapDataTask {
+  src_dims: WorldDims,
+  dst_dims: WorldDims,
+  input_buffer: CogSeqBuffer<u32>,
+  output_buffer: CogSeqBuffer<u32>,
+}
+impl ResampleMapDataTask {
+  pub(crate) fn new(
+    src_dims: WorldDims,
+    dst_dims: WorldDims,
+    input_buffer: CogSeqBuffer<u32>,
+    output_buffer: CogSeqBuffer<u32>,
+  ) -> Self {
+    assert!(src_dims.area() > 0, "Source dims must be > 0");
+    assert!(dst_dims.area() > 0, "Destination dims must be > 0");
+    Self {
+      src_dims,
+      dst_dims,
+      input_buffer,
+      output_buffer,
+    }
+  }
+}
+impl CogTask for ResampleMapDataTask {
+  fn encode(&self, encoder: &mut CogEncoder) {
+    let uniforms = ResampleMapDataUniforms {
+      src_dims: self.src_dims,
+      dst_dims: self.dst_dims,
+    };
+    let device = encoder.device();
+    let shader = device.create_shader_module::<ResampleMapDataShaderScript>();
+    shader.add_compute_pass_2d::<ResampleMapDataEntrypoint, _>(
+      encoder,
+      uniforms,
+      [self.dst_dims.columns_u32(), self.dst_dims.rows_u32()],
+      "CreateWorld_ResampleMapDataTask",
+      |cpass| {
+        cpass.add_bind_group(|bg| {
+          bg.add_seq_buffer(&self.input_buffer)
+            .add_seq_buffer(&self.output_buffer)
+        });
+      }
+    );
+  }
+}
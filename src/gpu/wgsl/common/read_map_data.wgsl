@@ -154,6 +154,23 @@ const FORMAT_SELECTOR_SHIFT_BITS: u32 = 5u;
 const FORMAT_SELECTOR_COUNT_OFFSET: u32 = 10u;
 const FORMAT_SELECTOR_COUNT_BITS: u32 = 5u;
 
+/**
+ * The bit flagging an encoded format selector as two's complement.
+ * Bits 15-19 hold the out index of a read spec.
+ */
+const FORMAT_SELECTOR_SIGNED_OFFSET: u32 = 20u;
+
+/** Check whether an encoded format selector is two's complement. */
+fn format_selector_word_is_signed(value: u32) -> bool {
+  return ((value >> FORMAT_SELECTOR_SIGNED_OFFSET) & 1u) != 0u;
+}
+
+/** Sign-extend a component value read with a format selector. */
+fn format_selector_sign_extend(sel: FormatSelector, value: u32) -> i32 {
+  let unused: u32 = 32u - sel.count;
+  return i32(value << unused) >> unused;
+}
+
 /**
  * Encodes a format selector in a single u32.
  */
@@ -154,6 +154,23 @@ const FORMAT_SELECTOR_SHIFT_BITS: u32 = 5u;
 const FORMAT_SELECTOR_COUNT_OFFSET: u32 = 10u;
 const FORMAT_SELECTOR_COUNT_BITS: u32 = 5u;
 
+/**
+ * The bit flagging an encoded format selector as two's complement.
+ * Bits 15-19 hold the out index of a read spec.
+ */
+const FORMAT_SELECTOR_SIGNED_OFFSET: u32 = 20u;
+
+/** Check whether an encoded format selector is two's complement. */
+fn format_selector_word_is_signed(value: u32) -> bool {
+  return ((value >> FORMAT_SELECTOR_SIGNED_OFFSET) & 1u) != 0u;
+}
+
+/** Sign-extend a component value read with a format selector. */
+fn format_selector_sign_extend(sel: FormatSelector, value: u32) -> i32 {
+  let unused: u32 = 32u - sel.count;
+  return i32(value << unused) >> unused;
+}
+
 /**
  * Encodes a format selector in a single u32.
  */
@@ -277,6 +294,13 @@ fn calc_map_histo_leaf(
     FormatSelectorReadSpecWord(selector)
   );
 
+  // Signed values are bucketed in offset-binary order, so that the most
+  // negative value falls in the first bucket.
+  var sign_flip: u32 = 0u;
+  if (format_selector_word_is_signed(selector)) {
+    sign_flip = 1u << (sel_fmt.count - 1u);
+  }
+
   let output_idx: u32 = hexcell_index(grid_dims, grid_xy) * num_buckets;
   let tl_xy = grid_xy * area_dims;
   for (var j = 0u; j < area_dims.y; j++) {
@@ -290,7 +314,8 @@ fn calc_map_histo_leaf(
       let word_index = entry_index + sel_fmt.word;
       let word_value = input_buffer[word_index];
       let cell_value =
-        (word_value >> sel_fmt.shift) & format_selector_get_mask(sel_fmt);
+        ((word_value >> sel_fmt.shift) & format_selector_get_mask(sel_fmt))
+          ^ sign_flip;
       let cell_bucket = bucket_for_value(cell_value, value_range, num_buckets);
       let cell_bucket_clamped = clamp(cell_bucket, 0u, num_buckets - 1u);
       let bucket_index = output_idx + cell_bucket_clamped;
@@ -154,6 +154,23 @@ const FORMAT_SELECTOR_SHIFT_BITS: u32 = 5u;
 const FORMAT_SELECTOR_COUNT_OFFSET: u32 = 10u;
 const FORMAT_SELECTOR_COUNT_BITS: u32 = 5u;
 
+/**
+ * The bit flagging an encoded format selector as two's complement.
+ * Bits 15-19 hold the out index of a read spec.
+ */
+const FORMAT_SELECTOR_SIGNED_OFFSET: u32 = 20u;
+
+/** Check whether an encoded format selector is two's complement. */
+fn format_selector_word_is_signed(value: u32) -> bool {
+  return ((value >> FORMAT_SELECTOR_SIGNED_OFFSET) & 1u) != 0u;
+}
+
+/** Sign-extend a component value read with a format selector. */
+fn format_selector_sign_extend(sel: FormatSelector, value: u32) -> i32 {
+  let unused: u32 = 32u - sel.count;
+  return i32(value << unused) >> unused;
+}
+
 /**
  * Encodes a format selector in a single u32.
  */
@@ -570,6 +587,7 @@ fn calc_map_stats_leaf(
   let sel_fmt = format_selector_word_decode(
     FormatSelectorReadSpecWord(selector)
   );
+  let sel_signed = format_selector_word_is_signed(selector);
 
   var stats: Statistics = statistics_new();
 
@@ -586,7 +604,12 @@ fn calc_map_stats_leaf(
       let word_value = input_buffer[word_index];
       let cell_value =
         (word_value >> sel_fmt.shift) & format_selector_get_mask(sel_fmt);
-      stats = statistics_add_value(stats, int64_from_u32(cell_value));
+      if (sel_signed) {
+        let signed_value = format_selector_sign_extend(sel_fmt, cell_value);
+        stats = statistics_add_value(stats, int64_from_i32(signed_value));
+      } else {
+        stats = statistics_add_value(stats, int64_from_u32(cell_value));
+      }
     }
   }
 
@@ -1,6 +1,7 @@
 mod border_fade;
 mod rand_gen;
 mod rescale_map_data;
+mod resample_map_data;
 mod calc_map_histo_branch;
 mod calc_map_histo_leaf;
 mod calc_map_stats_branch;
@@ -37,6 +38,11 @@ pub(crate) use self::{
     RandGenShaderScript,
     RandGenUniforms,
   },
+  resample_map_data::{
+    ResampleMapDataEntrypoint,
+    ResampleMapDataShaderScript,
+    ResampleMapDataUniforms,
+  },
   rescale_map_data::{
     RescaleMapDataEntrypoint,
     RescaleMapDataShaderScript,
@@ -0,0 +1,37 @@
+use crate::{
+  cog::{ CogShaderEntrypoint2D, CogShaderScript, CogUniformType },
+  data::map::WorldDims,
+};
+
+pub(crate) struct ResampleMapDataShaderScript;
+impl CogShaderScript for ResampleMapDataShaderScript {
+  type Uniforms = ResampleMapDataUniforms;
+
+  const NAME: &'static str = "CreateWorld_ResampleMapDataTask";
+  const SOURCE: &'static str = include_str!("resample_map_data.wgsl");
+  const BIND_GROUPS: &'static [u32] = &[3];
+}
+
+pub(crate) struct ResampleMapDataEntrypoint;
+impl CogShaderEntrypoint2D<ResampleMapDataShaderScript>
+  for ResampleMapDataEntrypoint
+{
+  const NAME: &'static str = "resample_map_data";
+  const WORKGROUP_SIZE: [u32; 2] = [8, 8];
+}
+
+pub(crate) struct ResampleMapDataUniforms {
+  pub(crate) src_dims: WorldDims,
+  pub(crate) dst_dims: WorldDims,
+}
+impl CogUniformType for ResampleMapDataUniforms {
+  type GpuType = [u32; 4];
+}
+impl Into<[u32; 4]> for ResampleMapDataUniforms {
+  fn into(self) -> [u32; 4] {
+    [
+      self.src_dims.columns_u32(), self.src_dims.rows_u32(),
+      self.dst_dims.columns_u32(), self.dst_dims.rows_u32(),
+    ]
+  }
+}
@@ -0,0 +1,152 @@
+// LIBRARY(hex_geometry)
+// Hexagon directions
+const HEX_DIR_N: u32 = 0u;
+const HEX_DIR_NE: u32 = 1u;
+const HEX_DIR_SE: u32 = 2u;
+const HEX_DIR_S: u32 = 3u;
+const HEX_DIR_SW: u32 = 4u;
+const HEX_DIR_NW: u32 = 5u;
+
+const MIN_HEX_DIR: u32 = 0u;
+const MAX_HEX_DIR: u32 = 5u;
+
+const HEXCELL_INVALID: vec2<u32> = vec2<u32>(0xFFFFFFFFu, 0xFFFFFFFFu);
+
+/*
+ *          0   1   2   3   4   5   6
+ *         ___     ___     ___     ___
+ *   0    /   \___/   \___/   \___/   \
+ *        \___/   \___/ 2 \___/   \___/
+ *   1    /   \___/ 2 \___/ 2 \___/   \
+ *        \___/ 2 \___/ 1 \___/ 2 \___/
+ *   2    /   \___/ 1 \___/ 1 \___/   \
+ *        \___/ 2 \___/ * \___/ 2 \___/
+ *   3    /   \___/ 1 \___/ 1 \___/   \
+ *        \___/ 2 \___/ 1 \___/ 2 \___/
+ *   4    /   \___/ 2 \___/ 2 \___/   \
+ *        \___/   \___/ 2 \___/   \___/
+ *   5    /   \___/   \___/   \___/   \
+ *        \___/   \___/   \___/   \___/
+ *
+ */
+
+// Check if a tile is valid.
+fn hexcell_is_invalid(tile: vec2<u32>) -> bool {
+  return tile.x == HEXCELL_INVALID.x && tile.y == HEXCELL_INVALID.y;
+}
+
+// The index of a hex tile, given a set of dimensions
+fn hexcell_index(
+  dims: vec2<u32>,
+  tile: vec2<u32>
+) -> u32 {
+  return tile.y * dims.x + tile.x;
+}
+
+// Check if a tile is within bounds
+fn hexcell_checked(
+  dims: vec2<u32>,
+  tile: vec2<u32>
+) -> vec2<u32> {
+  if (tile.x >= dims.x || tile.y >= dims.y) {
+    return HEXCELL_INVALID;
+  } else {
+    return tile;
+  }
+}
+
+// Calculate tile in given direction
+fn hexcell_adjacent_unchecked(
+  tile: vec2<u32>,
+  dir: u32
+) -> vec2<u32> {
+  let col: u32 = tile.x;
+  let row: u32 = tile.y;
+  let col_odd: u32 = col & 1u;
+  let col_even: u32 = 1u - col_odd;
+  var d: u32 = dir % 6u;
+  switch d {
+    case 0u: { return vec2<u32>(col, row - 1u); }
+    case 1u: { return vec2<u32>(col + 1u, row - col_even); }
+    case 2u: { return vec2<u32>(col + 1u, row + col_odd); }
+    case 3u: { return vec2<u32>(col, row + 1u); }
+    case 4u: { return vec2<u32>(col - 1u, row + col_odd); }
+    default: { return vec2<u32>(col - 1u, row - col_even); }
+  }
+}
+
+// Calculate tile N units out in a given direction.
+fn hexcell_adjacent_n_unchecked(
+  tile: vec2<u32>,
+  dir: u32,
+  n: u32
+) -> vec2<u32> {
+  var out_tile = tile;
+  for (var i = 0u; i < n; i++) {
+    out_tile = hexcell_adjacent_unchecked(tile, dir);
+  }
+  return out_tile;
+}
+
+// Calculate tile in given direction
+fn hexcell_adjacent_checked(
+  dims: vec2<u32>,
+  tile: vec2<u32>,
+  dir: u32
+) -> vec2<u32> {
+  var adj = hexcell_adjacent_unchecked(tile, dir);
+  if (adj.x >= dims.x || adj.y >= dims.y) {
+    adj = HEXCELL_INVALID;
+  }
+  return adj;
+}
+// END_LIBRARY(hex_geometry)
+
+struct Uniforms {
+  src_dims: vec2<u32>,
+  dst_dims: vec2<u32>,
+};
+
+@group(0) @binding(0)
+var<uniform> uniforms: Uniforms;
+
+@group(0) @binding(1)
+var<storage, read> input_buffer: array<u32>;
+
+@group(0) @binding(2)
+var<storage, write> output_buffer: array<u32>;
+
+@compute
+@workgroup_size(8, 8)
+fn resample_map_data(
+  @builtin(global_invocation_id) global_id: vec3<u32>
+) {
+  let src_dims = uniforms.src_dims;
+  let dst_dims = uniforms.dst_dims;
+
+  // Bounds check.
+  let xy: vec2<u32> = global_id.xy;
+  if (xy.x >= dst_dims.x || xy.y >= dst_dims.y) {
+    return;
+  }
+
+  // Map the corners of the destination onto the corners of the source.
+  let src_max = vec2<f32>(src_dims - vec2<u32>(1u, 1u));
+  let dst_max = vec2<f32>(max(dst_dims - vec2<u32>(1u, 1u), vec2<u32>(1u, 1u)));
+  let pos: vec2<f32> = vec2<f32>(xy) * src_max / dst_max;
+
+  let p0: vec2<u32> = min(vec2<u32>(floor(pos)), src_dims - vec2<u32>(1u, 1u));
+  let p1: vec2<u32> = min(p0 + vec2<u32>(1u, 1u), src_dims - vec2<u32>(1u, 1u));
+  let t: vec2<f32> = pos - vec2<f32>(p0);
+
+  let v00 = f32(input_buffer[hexcell_index(src_dims, p0)]);
+  let v10 = f32(input_buffer[hexcell_index(src_dims, vec2<u32>(p1.x, p0.y))]);
+  let v01 = f32(input_buffer[hexcell_index(src_dims, vec2<u32>(p0.x, p1.y))]);
+  let v11 = f32(input_buffer[hexcell_index(src_dims, p1)]);
+
+  let top = mix(v00, v10, t.x);
+  let bottom = mix(v01, v11, t.x);
+  let value = mix(top, bottom, t.y);
+
+  output_buffer[hexcell_index(dst_dims, xy)] = u32(round(value));
+}
@@ -3,6 +3,7 @@ mod cog;
 mod game;
 mod gpu;
 mod network;
+mod offline;
 //mod gpu;
 mod protocol;
 mod shady_vm;
@@ -14,12 +15,27 @@ pub use self::{
   protocol::{
     ProtocolCommandDocumentation,
     ProtocolCategoryDocumentation,
+    ProtocolEventDocumentation,
     get_protocol_docs,
+    get_event_docs,
+    ProtocolSchemaDocument,
+    check_protocol_examples,
+    get_protocol_schemas,
+    schema_violations,
+    get_protocol_typescript,
   },
   network::{
     ws_serve,
     NetworkServerConfig,
-  }
+  },
+  offline::{
+    export_world_layer,
+    export_ruleset_bundle,
+    import_ruleset_bundle,
+    LayerExportOptions,
+    RulesetExportOptions,
+    RulesetImportOptions,
+  },
 };
 
 #[cfg(test)]
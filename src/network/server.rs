@@ -4,7 +4,8 @@ use std::{
 };
 use serde_json;
 use log;
-use futures_util::{ SinkExt, StreamExt, stream::SplitSink };
+use futures::channel::mpsc::{ self, Sender, UnboundedSender };
+use futures_util::{ SinkExt, StreamExt };
 use warp::{
   Filter,
   filters::ws::WebSocket,
@@ -12,31 +13,53 @@ use warp::{
 };
 use crate::{
   game::GameServerConfig,
-  protocol::CommandEnvelope
+  protocol::{
+    binary_map_data,
+    encode_map_data_frame,
+    CommandEnvelope,
+    CommandFrame,
+    EventEnvelope,
+    EventFrame,
+    FailedResponse,
+    MapDataEncoding,
+    ResponseEnvelope,
+    ResponseFrame,
+  },
 };
 use super::{ NetworkServerConfig, ServerState };
 
 /**
  * The network server.  Fronts a game-server over a socket.
+ *
+ * On Ctrl-C, clients are sent `ServerShutdown` and their connections
+ * closed before this returns.
  */
 pub async fn ws_serve(config: NetworkServerConfig) {
   let mut game_server_config = GameServerConfig::default();
   game_server_config.data_root = config.data_root.clone();
-  let server_state = ServerState::new(&game_server_config);
+  let server_state =
+    match ServerState::new(&game_server_config, config.max_sessions) {
+      Ok(server_state) => server_state,
+      Err(error) => {
+        log::error!("ws_serve: failed-to-start-game-server error={}", error);
+        return;
+      }
+    };
+
+  // Every open socket holds a clone of the sender, so the receiver ends
+  // once they have all closed.
+  let (socket_guard, mut sockets_closed) = mpsc::channel::<()>(0);
 
   log::debug!("serve");
   // Serve websocket on '/ws'
   let routes = warp::path::end()
     .and(warp::ws())
     .and(with_server_state(server_state.clone()))
-    .map(|ws: warp::ws::Ws, server_state: ServerState| {
+    .map(move |ws: warp::ws::Ws, server_state: ServerState| {
+      let socket_guard = socket_guard.clone();
       ws.on_upgrade(|websocket| async move {
         log::debug!("ws_serve: websocket-upgraded");
-        if server_state.has_client() {
-          log::warn!("ws_serve: already-has-client");
-          return;
-        }
-        serve_socket(websocket, server_state).await;
+        serve_socket(websocket, server_state, socket_guard).await;
       })
     });
 
@@ -47,17 +70,96 @@ pub async fn ws_serve(config: NetworkServerConfig) {
       return;
     }
   };
-  warp::serve(routes)
-    .run(addr).await;
+  let shutdown_state = server_state.clone();
+  let shutdown_signal = async move {
+    if let Err(error) = tokio::signal::ctrl_c().await {
+      log::error!("ws_serve: failed-to-listen-for-ctrl-c error={:?}", error);
+      futures::future::pending::<()>().await;
+    }
+    log::info!("ws_serve: shutting-down");
+    shutdown_state.shutdown();
+  };
+  let (_, server) = match warp::serve(routes)
+    .try_bind_with_graceful_shutdown(addr, shutdown_signal)
+  {
+    Ok(bound) => bound,
+    Err(error) => {
+      log::error!("ws_serve: failed-to-bind {} error={:?}", addr, error);
+      return;
+    }
+  };
+  server.await;
+
+  // Let the open sockets send `ServerShutdown` and close.
+  sockets_closed.next().await;
+  log::info!("ws_serve: shut-down");
 }
 
 /**
  * Handle a single websocket connection.
+ *
+ * Commands are queued as they arrive, and each response is sent as soon as
+ * it is ready, so a client may have several commands in flight and match
+ * up the responses by id.  Events are sent as they are published, between
+ * the responses.
+ *
+ * Each connection is a session, which controls the game or observes it.
+ * A connection beyond the most sessions allowed is told why, and closed.
  */
-async fn serve_socket(websocket: WebSocket, server_state: ServerState) {
+async fn serve_socket(
+  websocket: WebSocket,
+  server_state: ServerState,
+  _socket_guard: Sender<()>,
+) {
   log::debug!("serve_socket");
   let (mut tx, mut rx) = websocket.split();
 
+  let session = match server_state.open_session() {
+    Ok(session) => session,
+    Err(message) => {
+      log::warn!("NetworkServer.serve_socket: session-refused: {}", message);
+      // Say why before closing, rather than just dropping the socket.
+      let frame = ResponseFrame {
+        id: None,
+        response: ResponseEnvelope::Failed(FailedResponse::new(&message)),
+      };
+      if let Ok(text) = serde_json::to_string(&frame) {
+        let _ = tx.send(Message::text(text)).await;
+      }
+      let _ = tx.send(Message::close_with(1013u16, "Too many sessions")).await;
+      return;
+    }
+  };
+  let session_id = session.session_id;
+  log::info!("NetworkServer.serve_socket: session-opened: {}", session_id);
+
+  let (response_tx, mut response_rx) = mpsc::unbounded::<Message>();
+
+  let writer = tokio::spawn(async move {
+    while let Some(message) = response_rx.next().await {
+      if let Err(error) = tx.send(message).await {
+        log::error!("NetworkServer.serve_socket: failed-to-send-response: {:?}", error);
+        break;
+      }
+      log::debug!("NetworkServer.serve_socket: response-sent");
+    }
+  });
+
+  // Forward events until the game server shuts down, then close the
+  // connection.
+  let mut events = server_state.subscribe_events();
+  let event_tx = response_tx.clone();
+  send_frame(&event_tx, &EventFrame {
+    event: EventEnvelope::SessionStarted(session),
+  });
+  let event_forwarder = tokio::spawn(async move {
+    while let Some(event) = events.next().await {
+      send_frame(&event_tx, &EventFrame { event });
+    }
+    let _ = event_tx.unbounded_send(Message::close());
+  });
+
+  let mut map_data_encoding = MapDataEncoding::Json;
   while let Some(result) = rx.next().await {
     let message = match result {
       Ok(message) => message,
@@ -67,68 +169,143 @@ async fn serve_socket(websocket: WebSocket, server_state: ServerState) {
       }
     };
     log::info!("serve_socket: message: {:?}", message);
-    let ok = handle_message(&server_state, &mut tx, message).await;
+    let ok = handle_message(
+      &server_state,
+      session_id,
+      &mut map_data_encoding,
+      &response_tx,
+      message,
+    );
     if !ok {
       break;
     }
   }
 
-  log::info!("NetworkServer.serve_socket: client-disconnected");
-  server_state.set_has_client(false);
+  // Stop writing once the responses still in flight have been sent.
+  event_forwarder.abort();
+  drop(response_tx);
+  let _ = writer.await;
+
+  log::info!("NetworkServer.serve_socket: session-closed: {}", session_id);
+  server_state.close_session(session_id);
 }
 
 /**
- * Handle a message from the client.
+ * Handle a message from the client.  A command is queued, and its response
+ * sent on `response_tx` when it is ready.  The connection's map data
+ * encoding is set here, and applies to the commands that follow.
+ *
+ * A message that is not a command is answered with `Failed`, and the
+ * connection stays open.  Returns false once the client closes it.
  */
-async fn handle_message(server_state: &ServerState, tx: &mut SplitSink<WebSocket, Message>, message: Message) -> bool {
+fn handle_message(
+  server_state: &ServerState,
+  session_id: u64,
+  map_data_encoding: &mut MapDataEncoding,
+  response_tx: &UnboundedSender<Message>,
+  message: Message,
+) -> bool {
   if message.is_text() {
     let msgtext = message.to_str().unwrap();
     log::debug!("NetworkServer.handle_message: text-message: {:?}", msgtext);
-    // Deserialize the message to a command envelope using serde_json.
-    let command_envelope = match serde_json::from_str::<CommandEnvelope>(msgtext) {
-      Ok(message) => message,
-      Err(error) => {
+    let CommandFrame { id, command } = match CommandFrame::parse(msgtext) {
+      Ok(frame) => frame,
+      Err((id, error)) => {
         log::warn!("NetworkServer.handle_message: failed-to-deserialize: {:?}", error);
-        return false;
+        let response = ResponseEnvelope::Failed(FailedResponse::new(
+          format!("Malformed command: {}", error)
+        ));
+        send_frame(response_tx, &ResponseFrame { id, response });
+        return true;
       }
     };
 
-    // Handle the command.
-    let response_envelope = server_state.handle_command(command_envelope);
+    if let CommandEnvelope::SetMapDataEncoding(cmd) = command {
+      *map_data_encoding = cmd.encoding;
+      send_frame(response_tx, &ResponseFrame { id, response: ResponseEnvelope::Ok {} });
+      return true;
+    }
 
-    // Serialize the response and send it.
-    let response_string = match serde_json::to_string(&response_envelope) {
-      Ok(response) => response,
-      Err(error) => {
-        log::warn!("NetworkServer.handle_message: failed-to-serialize: {:?}", error);
-        return false;
-      }
-    };
+    // Queue the command, and send its response when it is done.
+    let pending_response = server_state.handle_command(session_id, command);
+    let response_tx = response_tx.clone();
+    let map_data_encoding = *map_data_encoding;
+    tokio::spawn(async move {
+      let response = pending_response.await.unwrap_or_else(|_| {
+        ResponseEnvelope::Failed(FailedResponse::new(
+          "The game server stopped before responding."
+        ))
+      });
+      send_response(&response_tx, id, response, map_data_encoding);
+    });
+    return true;
+  } else if message.is_ping() || message.is_pong() {
+    // The websocket layer answers pings with pongs as it reads them.
+    log::debug!("NetworkServer.handle_message: ping-or-pong");
+    return true;
+  } else if message.is_close() {
+    return false;
+  } else {
+    log::warn!("NetworkServer.handle_message: non-text-message-received");
+    let response = ResponseEnvelope::Failed(FailedResponse::new(
+      "Commands must be sent as text messages."
+    ));
+    send_frame(response_tx, &ResponseFrame { id: None, response });
+    return true;
+  }
+}
 
-    if response_string.len() > 256 {
-      log::debug!(
-        "NetworkServer.handle_message: sending-response: {:?}...",
-        &response_string[0..256]
-      );
-    } else {
-      log::debug!("NetworkServer.handle_message: sending-response: {:?}", response_string);
-    }
-    match tx.send(Message::text(response_string)).await {
-      Ok(_) => {
-        log::debug!("NetworkServer.handle_message: response-sent");
-        return true;
-      },
-      Err(error) => {
-        log::error!("NetworkServer.handle_message: failed-to-send-response: {:?}", error);
-        return false;
+/**
+ * Queue a response to be written to the socket, as a binary frame if it
+ * is map data and the connection asked for those.
+ */
+fn send_response(
+  response_tx: &UnboundedSender<Message>,
+  id: Option<u64>,
+  response: ResponseEnvelope,
+  map_data_encoding: MapDataEncoding,
+) {
+  if map_data_encoding == MapDataEncoding::Binary {
+    if let Some(map_data) = binary_map_data(&response) {
+      let frame = encode_map_data_frame(id, map_data);
+      log::debug!("NetworkServer.send_response: sending-binary-frame: {} bytes", frame.len());
+      if response_tx.unbounded_send(Message::binary(frame)).is_err() {
+        log::warn!("NetworkServer.send_response: socket-closed");
       }
+      return;
     }
-  } else if message.is_ping() {
-    log::warn!("NetworkServer.handle_message: ping received.  Ignoring.");
-    return true;
+  }
+  send_frame(response_tx, &ResponseFrame { id, response });
+}
+
+/**
+ * Serialize a response or event and queue it to be written to the socket.
+ */
+fn send_frame<F: serde::Serialize>(response_tx: &UnboundedSender<Message>, frame: &F) {
+  let response_string = match serde_json::to_string(frame) {
+    Ok(response) => response,
+    Err(error) => {
+      log::warn!("NetworkServer.send_frame: failed-to-serialize: {:?}", error);
+      return;
+    }
+  };
+
+  if response_string.len() > 256 {
+    // Cut on a char boundary, as the frame may hold multibyte text.
+    let end = response_string.char_indices()
+      .map(|(index, _)| index)
+      .take_while(|index| *index <= 256)
+      .last()
+      .unwrap_or(0);
+    log::debug!(
+      "NetworkServer.send_frame: sending-frame: {:?}...",
+      &response_string[0..end]
+    );
   } else {
-    log::warn!("NetworkServer.handle_message: non-text-message-received");
-    return false;
+    log::debug!("NetworkServer.send_frame: sending-frame: {:?}", response_string);
+  }
+  if response_tx.unbounded_send(Message::text(response_string)).is_err() {
+    log::warn!("NetworkServer.send_frame: socket-closed");
   }
 }
 
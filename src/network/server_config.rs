@@ -6,10 +6,13 @@ use std::env;
 pub struct NetworkServerConfig {
   pub serve_addr: String,
   pub data_root: String,
+
+  // Connections beyond this many are refused.
+  pub max_sessions: usize,
 }
 impl NetworkServerConfig {
   pub fn new(serve_addr: String, data_root: String) -> NetworkServerConfig {
-    NetworkServerConfig { serve_addr, data_root }
+    NetworkServerConfig { serve_addr, data_root, max_sessions: 16 }
   }
 
   pub fn default() -> NetworkServerConfig {
@@ -1,38 +1,228 @@
-use std::sync::{
-  Arc,
-  Mutex,
-  atomic::{ AtomicBool, Ordering },
-};
+use std::{ io, sync::{ Arc, Mutex } };
+use futures::channel::{ mpsc::UnboundedReceiver, oneshot };
 use crate::{
   game::{GameServer, GameServerConfig},
-  protocol::{ CommandEnvelope, ResponseEnvelope },
+  protocol::{
+    CommandEnvelope,
+    EventEnvelope,
+    FailedResponse,
+    ResponseEnvelope,
+    SessionInfo,
+    SessionRole,
+  },
 };
 
+/**
+ * The sessions open on the server, one per connection.  At most one of
+ * them is in control; the rest observe.
+ */
+struct Sessions {
+  next_id: u64,
+  ids: Vec<u64>,
+  controller_id: Option<u64>,
+}
+impl Sessions {
+  fn info(&self, session_id: u64) -> SessionInfo {
+    let role = if self.controller_id == Some(session_id) {
+      SessionRole::Controller
+    } else {
+      SessionRole::Observer
+    };
+    SessionInfo {
+      session_id,
+      role,
+      controller_id: self.controller_id,
+      session_ids: self.ids.clone(),
+    }
+  }
+
+  /** Check that a session may send a command: observers may only read. */
+  fn check_may_send(&self, session_id: u64, command: &CommandEnvelope)
+    -> Result<(), String>
+  {
+    if command.is_read_only() {
+      return Ok(());
+    }
+    match self.controller_id {
+      Some(controller_id) if controller_id == session_id => Ok(()),
+      Some(controller_id) => Err(format!(
+        "Session {} is observing.  Only the controlling session ({}) may \
+         change the game.",
+        session_id, controller_id
+      )),
+      None => Err(format!(
+        "Session {} is observing, and no session is in control.  Send \
+         TakeControl to change the game.",
+        session_id
+      )),
+    }
+  }
+
+  fn changed_event(&self) -> EventEnvelope {
+    EventEnvelope::SessionsChanged {
+      controller_id: self.controller_id,
+      session_ids: self.ids.clone(),
+    }
+  }
+}
+
 struct ServerStateInner {
   game_server: Mutex<GameServer>,
-  has_client: AtomicBool,
+  sessions: Mutex<Sessions>,
+  max_sessions: usize,
 }
 
 #[derive(Clone)]
 pub(crate) struct ServerState(Arc<ServerStateInner>);
 impl ServerState {
-  pub(crate) fn new(config: &GameServerConfig) -> ServerState {
+  pub(crate) fn new(config: &GameServerConfig, max_sessions: usize)
+    -> io::Result<ServerState>
+  {
     let inner = ServerStateInner {
-      game_server: Mutex::new(GameServer::new(config)),
-      has_client: AtomicBool::new(false),
+      game_server: Mutex::new(GameServer::new(config)?),
+      sessions: Mutex::new(Sessions {
+        next_id: 1,
+        ids: Vec::new(),
+        controller_id: None,
+      }),
+      max_sessions,
     };
-    ServerState(Arc::new(inner))
+    Ok(ServerState(Arc::new(inner)))
   }
-  pub(crate) fn has_client(&self) -> bool {
-    self.0.has_client.load(Ordering::SeqCst)
+
+  /**
+   * Open a session for a new connection.  It takes control if no session
+   * has it.  Fails if the server has as many sessions as it allows.
+   */
+  pub(crate) fn open_session(&self) -> Result<SessionInfo, String> {
+    let (info, event) = {
+      let mut sessions = self.0.sessions.lock().unwrap();
+      if sessions.ids.len() >= self.0.max_sessions {
+        return Err(format!(
+          "The server already has the most sessions it allows ({}).",
+          self.0.max_sessions
+        ));
+      }
+      let session_id = sessions.next_id;
+      sessions.next_id += 1;
+      sessions.ids.push(session_id);
+      if sessions.controller_id.is_none() {
+        sessions.controller_id = Some(session_id);
+      }
+      (sessions.info(session_id), sessions.changed_event())
+    };
+    self.publish_event(event);
+    Ok(info)
   }
-  pub(crate) fn set_has_client(&self, value: bool) {
-    self.0.has_client.store(value, Ordering::SeqCst);
+
+  /**
+   * Close a session.  If it was in control, control is left free.
+   */
+  pub(crate) fn close_session(&self, session_id: u64) {
+    let event = {
+      let mut sessions = self.0.sessions.lock().unwrap();
+      sessions.ids.retain(|id| *id != session_id);
+      if sessions.controller_id == Some(session_id) {
+        sessions.controller_id = None;
+      }
+      sessions.changed_event()
+    };
+    self.publish_event(event);
   }
+
+  /**
+   * Queue a command from a session, returning a receiver for its response.
+   * Session commands, and commands an observer may not send, are answered
+   * at once.  The sessions are held from checking the command until it is
+   * queued, so that control cannot change hands in between.  The locks are
+   * only held to queue the command, so commands can be in flight together.
+   */
   pub(crate) fn handle_command(&self,
+    session_id: u64,
     command_envelope: CommandEnvelope
-  ) -> ResponseEnvelope {
+  ) -> oneshot::Receiver<ResponseEnvelope> {
+    let response = match command_envelope {
+      CommandEnvelope::GetSessionInfo(_) => ResponseEnvelope::SessionInfo(
+        self.0.sessions.lock().unwrap().info(session_id)
+      ),
+      CommandEnvelope::TakeControl(_) => self.take_control(session_id),
+      CommandEnvelope::HandOffControl(cmd) =>
+        self.hand_off_control(session_id, cmd.session_id),
+      command_envelope => {
+        let sessions = self.0.sessions.lock().unwrap();
+        match sessions.check_may_send(session_id, &command_envelope) {
+          Ok(()) => {
+            let mut game_server = self.0.game_server.lock().unwrap();
+            return game_server.submit_command(command_envelope);
+          },
+          Err(message) =>
+            ResponseEnvelope::Failed(FailedResponse::new(message)),
+        }
+      },
+    };
+    let (response_tx, response_rx) = oneshot::channel();
+    let _ = response_tx.send(response);
+    response_rx
+  }
+
+  pub(crate) fn subscribe_events(&self) -> UnboundedReceiver<EventEnvelope> {
+    let game_server = self.0.game_server.lock().unwrap();
+    game_server.subscribe_events()
+  }
+
+  pub(crate) fn shutdown(&self) {
     let game_server = self.0.game_server.lock().unwrap();
-    game_server.perform_command(command_envelope)
+    game_server.shutdown();
+  }
+
+  fn publish_event(&self, event: EventEnvelope) {
+    let game_server = self.0.game_server.lock().unwrap();
+    game_server.publish_event(event);
+  }
+
+  fn take_control(&self, session_id: u64) -> ResponseEnvelope {
+    let (info, event) = {
+      let mut sessions = self.0.sessions.lock().unwrap();
+      match sessions.controller_id {
+        Some(controller_id) if controller_id == session_id => {
+          return ResponseEnvelope::SessionInfo(sessions.info(session_id));
+        },
+        Some(controller_id) => {
+          return ResponseEnvelope::Failed(FailedResponse::new(format!(
+            "Session {} is in control.  It must hand off control first.",
+            controller_id
+          )));
+        },
+        None => {},
+      }
+      sessions.controller_id = Some(session_id);
+      (sessions.info(session_id), sessions.changed_event())
+    };
+    self.publish_event(event);
+    ResponseEnvelope::SessionInfo(info)
+  }
+
+  fn hand_off_control(&self, session_id: u64, to_session_id: Option<u64>)
+    -> ResponseEnvelope
+  {
+    let (info, event) = {
+      let mut sessions = self.0.sessions.lock().unwrap();
+      if sessions.controller_id != Some(session_id) {
+        return ResponseEnvelope::Failed(FailedResponse::new(
+          "Only the controlling session may hand off control."
+        ));
+      }
+      if let Some(to_session_id) = to_session_id {
+        if !sessions.ids.contains(&to_session_id) {
+          return ResponseEnvelope::Failed(FailedResponse::new(
+            format!("No such session: {}", to_session_id)
+          ));
+        }
+      }
+      sessions.controller_id = to_session_id;
+      (sessions.info(session_id), sessions.changed_event())
+    };
+    self.publish_event(event);
+    ResponseEnvelope::SessionInfo(info)
   }
 }
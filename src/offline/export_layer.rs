@@ -0,0 +1,122 @@
+use std::{ fs, path::PathBuf };
+use crate::{
+  cog::CogDevice,
+  data_store::DataStore,
+  gpu::{
+    task::create_world::ReadMapDataTask,
+    CellDataBuffer,
+  },
+  data::{
+    map::{ CellCoord, CellData, WorldDims },
+    LayerExportFormat,
+  },
+};
+
+/**
+ * Options for exporting one layer of a saved world without a running
+ * server.
+ */
+#[derive(Debug, Clone)]
+pub struct LayerExportOptions {
+  pub data_root: PathBuf,
+  pub world_name: String,
+
+  // Component to export, named as `word.component`.
+  pub component: String,
+
+  // Area to export, as `(col, row)` and `(columns, rows)`.
+  // When `None`, the whole map is exported.
+  pub area: Option<((u16, u16), (u16, u16))>,
+
+  // One of `pgm`, `ppm`, `r16`, `r32`.
+  pub format: String,
+  pub palette: Option<String>,
+  pub output: PathBuf,
+}
+
+/**
+ * Export a layer of a saved world to a file.  Returns the number of bytes
+ * written.
+ */
+pub fn export_world_layer(options: &LayerExportOptions)
+  -> Result<u64, Vec<String>>
+{
+  let format = LayerExportFormat::from_name(&options.format).ok_or_else(|| {
+    vec![format!("Unknown export format: {}", options.format)]
+  })?;
+  let (word, component) = options.component.split_once('.').ok_or_else(|| {
+    vec![format!(
+      "Component must be given as word.component: {}",
+      options.component
+    )]
+  })?;
+
+  // The server may be running, so read without taking the lock.
+  let data_store = DataStore::new_read_only(&options.data_root);
+  let load_error = |err: std::io::Error| vec![
+    "Failed to load world.".to_string(),
+    format!("{}: {}", options.world_name, err),
+  ];
+  let worlds = data_store.worlds().map_err(load_error)?;
+  let (entry, mut reader) = worlds.open(&options.world_name)
+    .map_err(load_error)?;
+
+  let world_dims = entry.descriptor.dims;
+  let (top_left, dims) = match options.area {
+    Some(((col, row), (columns, rows))) =>
+      (CellCoord::new(col, row), WorldDims::new(columns, rows)),
+    None => (CellCoord::zero(), world_dims),
+  };
+  if dims.area() == 0
+    || ! world_dims.contains_coord(top_left)
+    || ! world_dims.contains_coord(dims.bottom_right_inclusive(top_left))
+  {
+    return Err(vec!["Export area is out of bounds".to_string()]);
+  }
+
+  let selector = entry.format.selector_for(word, component).ok_or_else(|| {
+    vec![format!("Unknown component: {}", options.component)]
+  })?;
+
+  // Palettes live in the world's ruleset, which may since have been
+  // edited or removed.
+  let palette = match (&options.palette, format.needs_palette()) {
+    (Some(name), true) => {
+      let ruleset_name = &entry.descriptor.ruleset_name;
+      let ruleset = data_store.rulesets()
+        .and_then(|rulesets| rulesets.read(ruleset_name))
+        .map_err(|err| vec![
+          format!("Failed to load ruleset {}: {}", ruleset_name, err),
+        ])?;
+      let palette = ruleset.palette(name).cloned().ok_or_else(|| {
+        vec![format!("Unknown palette: {}", name)]
+      })?;
+      Some(palette)
+    },
+    (None, true) => {
+      return Err(vec!["A palette is required for PPM export".to_string()]);
+    },
+    (_, false) => None,
+  };
+
+  let cells = reader.read_all().map_err(load_error)?;
+  let device = CogDevice::new().map_err(|err| vec![err])?;
+  let cell_data_buffer = CellDataBuffer::new(&device, world_dims);
+  cell_data_buffer.write_cells(&cells);
+  let values = ReadMapDataTask::read_layer(
+    &device,
+    world_dims,
+    top_left,
+    dims,
+    selector,
+    cell_data_buffer.as_u32_seq_buffer(),
+    CellData::NUM_WORDS,
+  );
+
+  let bytes = format.encode(dims, &values, palette.as_ref())
+    .map_err(|err| vec![err])?;
+  fs::write(&options.output, &bytes).map_err(|err| vec![
+    format!("Failed to write {}: {}", options.output.display(), err),
+  ])?;
+  Ok(bytes.len() as u64)
+}
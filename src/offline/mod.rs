@@ -0,0 +1,12 @@
+mod export_layer;
+mod ruleset_bundle;
+
+pub use self::{
+  export_layer::{ export_world_layer, LayerExportOptions },
+  ruleset_bundle::{
+    export_ruleset_bundle,
+    import_ruleset_bundle,
+    RulesetExportOptions,
+    RulesetImportOptions,
+  },
+};
@@ -0,0 +1,87 @@
+use std::{ fs, path::PathBuf };
+use crate::{
+  data_store::DataStore,
+  data::ruleset::{
+    RulesetBundle,
+    RulesetImportConflict,
+    RulesetImportError,
+  },
+};
+
+/**
+ * Options for writing a stored ruleset to a bundle file.
+ */
+#[derive(Debug, Clone)]
+pub struct RulesetExportOptions {
+  pub data_root: PathBuf,
+  pub ruleset_name: String,
+
+  // Whether to include the assembled terrain generator programs.
+  pub include_programs: bool,
+  pub output: PathBuf,
+}
+
+/**
+ * Options for adding a ruleset bundle file to a data root.
+ */
+#[derive(Debug, Clone)]
+pub struct RulesetImportOptions {
+  pub data_root: PathBuf,
+  pub input: PathBuf,
+
+  // One of `fail`, `rename`, `overwrite`.
+  pub on_conflict: String,
+}
+
+/**
+ * Export a stored ruleset to a bundle file.  Does not lock the data root,
+ * so it can be used while a server is running.
+ */
+pub fn export_ruleset_bundle(options: &RulesetExportOptions)
+  -> Result<(), Vec<String>>
+{
+  let data_store = DataStore::new_read_only(&options.data_root);
+  let ruleset = data_store.rulesets()
+    .and_then(|rulesets| rulesets.read(&options.ruleset_name))
+    .map_err(|err| vec![
+      "Failed to load ruleset.".to_string(),
+      format!("{}: {}", options.ruleset_name, err),
+    ])?;
+  let bundle = RulesetBundle::new(&ruleset, options.include_programs)
+    .map_err(|err| vec![err])?;
+  let bundle_json = serde_json::to_string_pretty(&bundle)
+    .map_err(|err| vec![format!("Failed to serialize bundle: {}", err)])?;
+  fs::write(&options.output, bundle_json).map_err(|err| vec![
+    format!("Failed to write {}: {}", options.output.display(), err),
+  ])
+}
+
+/**
+ * Import a ruleset bundle file into a data root.  Returns the name the
+ * ruleset was stored under.
+ */
+pub fn import_ruleset_bundle(options: &RulesetImportOptions)
+  -> Result<String, Vec<String>>
+{
+  let on_conflict = RulesetImportConflict::from_name(&options.on_conflict)
+    .ok_or_else(|| vec![
+      format!("Unknown conflict policy: {}", options.on_conflict),
+    ])?;
+  let bundle_json = fs::read_to_string(&options.input).map_err(|err| vec![
+    format!("Failed to read {}: {}", options.input.display(), err),
+  ])?;
+  let bundle: RulesetBundle = serde_json::from_str(&bundle_json)
+    .map_err(|err| vec![format!("Invalid ruleset bundle: {}", err)])?;
+
+  let data_store = DataStore::new(&options.data_root)
+    .map_err(|err| vec![err.to_string()])?;
+  match bundle.import(&data_store, on_conflict) {
+    Ok(ruleset) => Ok(ruleset.name),
+    Err(RulesetImportError::Invalid(validation)) => {
+      let details = serde_json::to_string_pretty(&validation)
+        .unwrap_or_default();
+      Err(vec!["Ruleset is not valid.".to_string(), details])
+    },
+    Err(RulesetImportError::Failed(messages)) => Err(messages),
+  }
+}
@@ -6,11 +6,27 @@ use super::{
   mode::{
     define_rules::DefineRulesSubcmdEnvelope,
     create_world::CreateWorldSubcmdEnvelope,
+    view_world::ViewWorldSubcmdEnvelope,
   },
+  hello_cmd::HelloCmd,
   enter_mode_cmd::EnterModeCmd,
   enter_main_menu_mode_cmd::EnterMainMenuModeCmd,
   get_mode_info_cmd::GetModeInfoCmd,
   list_rulesets_cmd::ListRulesetsCmd,
+  list_worlds_cmd::ListWorldsCmd,
+  list_heightmaps_cmd::ListHeightmapsCmd,
+  upload_heightmap_cmd::UploadHeightmapCmd,
+  delete_ruleset_cmd::DeleteRulesetCmd,
+  rename_ruleset_cmd::RenameRulesetCmd,
+  duplicate_ruleset_cmd::DuplicateRulesetCmd,
+  export_ruleset_cmd::ExportRulesetCmd,
+  import_ruleset_cmd::ImportRulesetCmd,
+  get_session_info_cmd::GetSessionInfoCmd,
+  take_control_cmd::TakeControlCmd,
+  hand_off_control_cmd::HandOffControlCmd,
+  set_map_data_encoding_cmd::SetMapDataEncodingCmd,
+  get_journal_info_cmd::GetJournalInfoCmd,
+  replay_journal_cmd::ReplayJournalCmd,
 };
 
 /** Base trait implemented by all commands. */
@@ -54,13 +70,106 @@ pub(crate) fn make_command_example<C: Command>()
 }
 
 /** Tagged union type for commands sent over transport channels. */
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum CommandEnvelope {
+  Hello(HelloCmd),
   EnterMode(EnterModeCmd),
   EnterMainMenuMode(EnterMainMenuModeCmd),
   GetModeInfo(GetModeInfoCmd),
   ListRulesets(ListRulesetsCmd),
+  ListWorlds(ListWorldsCmd),
+  ListHeightmaps(ListHeightmapsCmd),
+  UploadHeightmap(UploadHeightmapCmd),
+  DeleteRuleset(DeleteRulesetCmd),
+  RenameRuleset(RenameRulesetCmd),
+  DuplicateRuleset(DuplicateRulesetCmd),
+  ExportRuleset(ExportRulesetCmd),
+  ImportRuleset(ImportRulesetCmd),
+  GetSessionInfo(GetSessionInfoCmd),
+  TakeControl(TakeControlCmd),
+  HandOffControl(HandOffControlCmd),
+  SetMapDataEncoding(SetMapDataEncodingCmd),
+  GetJournalInfo(GetJournalInfoCmd),
+  ReplayJournal(ReplayJournalCmd),
   DefineRulesSubcmd(DefineRulesSubcmdEnvelope),
   CreateWorldSubcmd(CreateWorldSubcmdEnvelope),
+  ViewWorldSubcmd(ViewWorldSubcmdEnvelope),
+}
+impl CommandEnvelope {
+  /**
+   * Whether the command leaves the game as it is, so that observing
+   * sessions may send it.
+   */
+  pub(crate) fn is_read_only(&self) -> bool {
+    match self {
+      CommandEnvelope::Hello(_) |
+      CommandEnvelope::GetModeInfo(_) |
+      CommandEnvelope::ListRulesets(_) |
+      CommandEnvelope::ListWorlds(_) |
+      CommandEnvelope::ListHeightmaps(_) |
+      CommandEnvelope::ExportRuleset(_) |
+      CommandEnvelope::GetSessionInfo(_) |
+      CommandEnvelope::TakeControl(_) |
+      CommandEnvelope::HandOffControl(_) |
+      CommandEnvelope::SetMapDataEncoding(_) |
+      CommandEnvelope::GetJournalInfo(_) => true,
+
+      CommandEnvelope::EnterMode(_) |
+      CommandEnvelope::EnterMainMenuMode(_) |
+      CommandEnvelope::UploadHeightmap(_) |
+      CommandEnvelope::DeleteRuleset(_) |
+      CommandEnvelope::RenameRuleset(_) |
+      CommandEnvelope::DuplicateRuleset(_) |
+      CommandEnvelope::ImportRuleset(_) |
+      CommandEnvelope::ReplayJournal(_) => false,
+
+      CommandEnvelope::DefineRulesSubcmd(subcmd) => matches!(subcmd,
+        DefineRulesSubcmdEnvelope::CurrentRules(_) |
+        DefineRulesSubcmdEnvelope::RulesHistory(_)
+      ),
+      CommandEnvelope::CreateWorldSubcmd(subcmd) => matches!(subcmd,
+        CreateWorldSubcmdEnvelope::CurrentDescriptorInput(_) |
+        CreateWorldSubcmdEnvelope::CurrentGenerationPhase(_) |
+        CreateWorldSubcmdEnvelope::GetMapData(_) |
+        CreateWorldSubcmdEnvelope::GetMinimapData(_)
+      ),
+      CommandEnvelope::ViewWorldSubcmd(subcmd) => !matches!(subcmd,
+        ViewWorldSubcmdEnvelope::ExportMapLayer(_)
+      ),
+    }
+  }
+}
+
+/**
+ * A command as sent by a client, with an optional id that is echoed in
+ * its response.  The id sits beside the command's tag, e.g.
+ * `{ "id": 7, "GetModeInfo": {} }`.
+ */
+#[derive(Debug)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct CommandFrame {
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) id: Option<u64>,
+
+  #[serde(flatten)]
+  pub(crate) command: CommandEnvelope,
+}
+impl CommandFrame {
+  /**
+   * Parse a command frame.  If it does not parse, the error comes with
+   * the frame's id, if one can be found, so that the failure can be
+   * matched to the command.
+   */
+  pub(crate) fn parse(text: &str)
+    -> Result<CommandFrame, (Option<u64>, serde_json::Error)>
+  {
+    let value = serde_json::from_str::<serde_json::Value>(text)
+      .map_err(|error| (None, error))?;
+    let id = value.get("id").and_then(serde_json::Value::as_u64);
+    serde_json::from_value::<CommandFrame>(value).map_err(|error| (id, error))
+  }
 }
@@ -0,0 +1,65 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct DeleteRulesetCmd {
+  #[serde(rename = "rulesetName")]
+  pub(crate) ruleset_name: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum DeleteRulesetRsp {
+  Ok,
+  Error(Vec<String>),
+}
+impl Command for DeleteRulesetCmd {
+  type Response = DeleteRulesetRsp;
+  fn name() -> &'static str {
+    "DeleteRuleset"
+  }
+  fn description() -> &'static str {
+    "Delete a saved ruleset."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::DeleteRuleset(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      DeleteRulesetRsp::Ok => ResponseEnvelope::Ok {},
+      DeleteRulesetRsp::Error(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let delete_ruleset_example = DeleteRulesetCmd {
+      ruleset_name: "Example Ruleset".to_string(),
+    };
+
+    let delete_ruleset_ok_response = DeleteRulesetRsp::Ok;
+    let delete_ruleset_err_response = DeleteRulesetRsp::Error(vec![
+      "Ruleset is used by saved world: My World".to_string(),
+    ]);
+    (
+      vec![delete_ruleset_example],
+      vec![
+        delete_ruleset_ok_response,
+        delete_ruleset_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Fails if the ruleset is used by a saved world, or by the current \
+       mode (a world being created, or rules loaded for editing)."
+        .to_string(),
+      "Fails if another ruleset uses the ruleset as its base.".to_string(),
+    ]
+  }
+}
@@ -1,11 +1,27 @@
 use serde_json;
 use super::{
   command::Command,
-  mode::{ define_rules, create_world },
+  event::{ EventEnvelope, EventFrame },
+  mode::{ define_rules, create_world, view_world },
+  hello_cmd::HelloCmd,
   enter_main_menu_mode_cmd::EnterMainMenuModeCmd,
   enter_mode_cmd::EnterModeCmd,
   get_mode_info_cmd::GetModeInfoCmd,
   list_rulesets_cmd::ListRulesetsCmd,
+  list_worlds_cmd::ListWorldsCmd,
+  list_heightmaps_cmd::ListHeightmapsCmd,
+  upload_heightmap_cmd::UploadHeightmapCmd,
+  delete_ruleset_cmd::DeleteRulesetCmd,
+  rename_ruleset_cmd::RenameRulesetCmd,
+  duplicate_ruleset_cmd::DuplicateRulesetCmd,
+  export_ruleset_cmd::ExportRulesetCmd,
+  import_ruleset_cmd::ImportRulesetCmd,
+  get_session_info_cmd::GetSessionInfoCmd,
+  take_control_cmd::TakeControlCmd,
+  hand_off_control_cmd::HandOffControlCmd,
+  set_map_data_encoding_cmd::SetMapDataEncodingCmd,
+  get_journal_info_cmd::GetJournalInfoCmd,
+  replay_journal_cmd::ReplayJournalCmd,
 };
 
 pub struct ProtocolCommandDocumentation {
@@ -22,6 +38,12 @@ pub struct ProtocolCategoryDocumentation {
   pub commands: Vec<ProtocolCommandDocumentation>,
 }
 
+pub struct ProtocolEventDocumentation {
+  pub name: String,
+  pub description: String,
+  pub example: String,
+}
+
 /**
  * Get a list of examples of commands and responses, as json values.
  */
@@ -29,7 +51,9 @@ pub fn get_protocol_docs() -> Vec<ProtocolCategoryDocumentation> {
   let mut result = Vec::new();
   result.push(create_world::get_category_docs());
   result.push(define_rules::get_category_docs());
+  result.push(view_world::get_category_docs());
   result.push(main_category_docs());
+  result.push(session_category_docs());
   return result;
 }
 
@@ -38,10 +62,35 @@ fn main_category_docs() -> ProtocolCategoryDocumentation {
     name: "Main".to_string(),
     description: "Commands for entering and exiting modes".to_string(),
     commands: vec![
+      make_example::<HelloCmd>(),
       make_example::<EnterMainMenuModeCmd>(),
       make_example::<EnterModeCmd>(),
       make_example::<GetModeInfoCmd>(),
       make_example::<ListRulesetsCmd>(),
+      make_example::<DeleteRulesetCmd>(),
+      make_example::<RenameRulesetCmd>(),
+      make_example::<DuplicateRulesetCmd>(),
+      make_example::<ExportRulesetCmd>(),
+      make_example::<ImportRulesetCmd>(),
+      make_example::<ListWorldsCmd>(),
+      make_example::<ListHeightmapsCmd>(),
+      make_example::<UploadHeightmapCmd>(),
+      make_example::<GetJournalInfoCmd>(),
+      make_example::<ReplayJournalCmd>(),
+    ],
+  }
+}
+
+fn session_category_docs() -> ProtocolCategoryDocumentation {
+  ProtocolCategoryDocumentation {
+    name: "Session".to_string(),
+    description: "Commands for sharing the server between connections, \
+      and for how each connection is sent data".to_string(),
+    commands: vec![
+      make_example::<GetSessionInfoCmd>(),
+      make_example::<TakeControlCmd>(),
+      make_example::<HandOffControlCmd>(),
+      make_example::<SetMapDataEncodingCmd>(),
     ],
   }
 }
@@ -63,3 +112,16 @@ fn make_example<C: Command>() -> ProtocolCommandDocumentation {
     response_examples,
   }
 }
+
+/**
+ * Get an example of each event the server pushes, as json values.
+ */
+pub fn get_event_docs() -> Vec<ProtocolEventDocumentation> {
+  EventEnvelope::protocol_examples().into_iter().map(|event| {
+    ProtocolEventDocumentation {
+      name: event.name().to_string(),
+      description: event.description().to_string(),
+      example: serde_json::to_string_pretty(&EventFrame { event }).unwrap(),
+    }
+  }).collect()
+}
@@ -0,0 +1,65 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct DuplicateRulesetCmd {
+  #[serde(rename = "rulesetName")]
+  pub(crate) ruleset_name: String,
+
+  #[serde(rename = "newName")]
+  pub(crate) new_name: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum DuplicateRulesetRsp {
+  Ok,
+  Error(Vec<String>),
+}
+impl Command for DuplicateRulesetCmd {
+  type Response = DuplicateRulesetRsp;
+  fn name() -> &'static str {
+    "DuplicateRuleset"
+  }
+  fn description() -> &'static str {
+    "Save a copy of a ruleset under a new name."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::DuplicateRuleset(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      DuplicateRulesetRsp::Ok => ResponseEnvelope::Ok {},
+      DuplicateRulesetRsp::Error(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let duplicate_ruleset_example = DuplicateRulesetCmd {
+      ruleset_name: "Example Ruleset".to_string(),
+      new_name: "Example Ruleset Copy".to_string(),
+    };
+
+    let duplicate_ruleset_ok_response = DuplicateRulesetRsp::Ok;
+    let duplicate_ruleset_err_response = DuplicateRulesetRsp::Error(vec![
+      "No such ruleset: Example Ruleset".to_string(),
+    ]);
+    (
+      vec![duplicate_ruleset_example],
+      vec![
+        duplicate_ruleset_ok_response,
+        duplicate_ruleset_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+    ]
+  }
+}
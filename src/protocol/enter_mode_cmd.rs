@@ -10,6 +10,7 @@ use super::{
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct EnterModeCmd {
   pub(crate) mode: GameModeInfo,
 }
@@ -0,0 +1,133 @@
+use serde;
+use crate::data::{
+  ruleset::RulesetEntry,
+  GenerationPhase,
+  GenerationStepKind,
+};
+use super::{
+  get_session_info_cmd::SessionInfo,
+  mode::{
+    create_world::CreateWorldModeInfo,
+    GameModeInfo,
+  },
+};
+
+/**
+ * An event pushed by the server to every connected client, unprompted.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum EventEnvelope {
+  GenerationStepStarted {
+    kind: GenerationStepKind,
+  },
+  GenerationStepFinished {
+    kind: GenerationStepKind,
+    ok: bool,
+    #[serde(rename = "elapsedMs")]
+    elapsed_ms: u64,
+  },
+  GenerationPhaseChanged {
+    phase: GenerationPhase,
+  },
+
+  // `mode` is null in the main menu.
+  ModeChanged {
+    mode: Option<GameModeInfo>,
+  },
+
+  RulesetsChanged {
+    rulesets: Vec<RulesetEntry>,
+  },
+
+  // Sent only to a connection as it opens, with its own session.
+  SessionStarted(SessionInfo),
+  SessionsChanged {
+    #[serde(rename = "controllerId")]
+    controller_id: Option<u64>,
+    #[serde(rename = "sessionIds")]
+    session_ids: Vec<u64>,
+  },
+  ServerShutdown {},
+}
+impl EventEnvelope {
+  pub(crate) fn name(&self) -> &'static str {
+    match self {
+      EventEnvelope::GenerationStepStarted { .. } => "GenerationStepStarted",
+      EventEnvelope::GenerationStepFinished { .. } => "GenerationStepFinished",
+      EventEnvelope::GenerationPhaseChanged { .. } => "GenerationPhaseChanged",
+      EventEnvelope::ModeChanged { .. } => "ModeChanged",
+      EventEnvelope::RulesetsChanged { .. } => "RulesetsChanged",
+      EventEnvelope::SessionStarted(_) => "SessionStarted",
+      EventEnvelope::SessionsChanged { .. } => "SessionsChanged",
+      EventEnvelope::ServerShutdown {} => "ServerShutdown",
+    }
+  }
+
+  pub(crate) fn description(&self) -> &'static str {
+    match self {
+      EventEnvelope::GenerationStepStarted { .. } =>
+        "A world generation step has started.",
+      EventEnvelope::GenerationStepFinished { .. } =>
+        "A world generation step has finished, with how long it took.",
+      EventEnvelope::GenerationPhaseChanged { .. } =>
+        "The world being generated has moved to a new phase.",
+      EventEnvelope::ModeChanged { .. } =>
+        "The server has entered a mode, or returned to the main menu.",
+      EventEnvelope::RulesetsChanged { .. } =>
+        "A ruleset was saved, imported, renamed, duplicated or deleted.",
+      EventEnvelope::SessionStarted(_) =>
+        "Sent to a connection when it opens, with its session id and role.",
+      EventEnvelope::SessionsChanged { .. } =>
+        "A session opened or closed, or control changed hands.",
+      EventEnvelope::ServerShutdown {} =>
+        "The server is shutting down, and will close the connection.",
+    }
+  }
+
+  pub(crate) fn protocol_examples() -> Vec<EventEnvelope> {
+    vec![
+      EventEnvelope::GenerationStepStarted {
+        kind: GenerationStepKind::PairwiseStep,
+      },
+      EventEnvelope::GenerationStepFinished {
+        kind: GenerationStepKind::PairwiseStep,
+        ok: true,
+        elapsed_ms: 42,
+      },
+      EventEnvelope::GenerationPhaseChanged {
+        phase: GenerationPhase::PreMerge,
+      },
+      EventEnvelope::ModeChanged {
+        mode: Some(GameModeInfo::CreateWorld(CreateWorldModeInfo {})),
+      },
+      EventEnvelope::RulesetsChanged {
+        rulesets: vec![
+          RulesetEntry {
+            name: "Example Ruleset".to_string(),
+            description: "Example ruleset description".to_string(),
+          },
+        ],
+      },
+      EventEnvelope::SessionStarted(SessionInfo::new_example()),
+      EventEnvelope::SessionsChanged {
+        controller_id: Some(1),
+        session_ids: vec![1, 2],
+      },
+      EventEnvelope::ServerShutdown {},
+    ]
+  }
+}
+
+/**
+ * An event as sent to a client.  It is tagged `Event`, which no response
+ * uses, so that clients can tell events from responses.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct EventFrame {
+  #[serde(rename = "Event")]
+  pub(crate) event: EventEnvelope,
+}
@@ -0,0 +1,74 @@
+use serde;
+use crate::data::ruleset::RulesetBundle;
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ExportRulesetCmd {
+  #[serde(rename = "rulesetName")]
+  pub(crate) ruleset_name: String,
+
+  // Whether to include the assembled terrain generator programs.
+  #[serde(rename = "includePrograms")]
+  #[serde(default)]
+  pub(crate) include_programs: bool,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum ExportRulesetRsp {
+  Bundle(RulesetBundle),
+  Error(Vec<String>),
+}
+impl Command for ExportRulesetCmd {
+  type Response = ExportRulesetRsp;
+  fn name() -> &'static str {
+    "ExportRuleset"
+  }
+  fn description() -> &'static str {
+    "Package a stored ruleset as a self-describing bundle."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ExportRuleset(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      ExportRulesetRsp::Bundle(bundle) => ResponseEnvelope::RulesetBundle(bundle),
+      ExportRulesetRsp::Error(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let export_ruleset_example = ExportRulesetCmd {
+      ruleset_name: "Example Ruleset".to_string(),
+      include_programs: false,
+    };
+
+    let export_ruleset_ok_response =
+      ExportRulesetRsp::Bundle(RulesetBundle::new_example());
+    let export_ruleset_err_response = ExportRulesetRsp::Error(vec![
+      "No such ruleset: Example Ruleset".to_string(),
+    ]);
+    (
+      vec![export_ruleset_example],
+      vec![
+        export_ruleset_ok_response,
+        export_ruleset_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "`contentHash` is the hex SHA-256 of the JSON text of `ruleset`."
+        .to_string(),
+      "With `includePrograms`, `programs` holds each terrain generator \
+       program assembled to pairs of 32-bit instruction words."
+        .to_string(),
+    ]
+  }
+}
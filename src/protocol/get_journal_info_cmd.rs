@@ -0,0 +1,95 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  mode::{
+    define_rules::DefineRulesModeInfo,
+    GameModeInfo,
+  },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct GetJournalInfoCmd {}
+
+/**
+ * What the journal left by the server's last run would rebuild.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct JournalInfo {
+  // The number of commands recorded; zero if there is nothing to replay.
+  pub(crate) commands: u32,
+
+  // The mode the journal's first command entered.
+  pub(crate) mode: Option<GameModeInfo>,
+
+  // When the first and last commands were performed, in milliseconds
+  // since the Unix epoch.
+  #[serde(rename = "startedAt")]
+  pub(crate) started_at: Option<u64>,
+
+  #[serde(rename = "endedAt")]
+  pub(crate) ended_at: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum GetJournalInfoRsp {
+  JournalInfo(JournalInfo),
+  Failed(Vec<String>),
+}
+impl Command for GetJournalInfoCmd {
+  type Response = GetJournalInfoRsp;
+  fn name() -> &'static str {
+    "GetJournalInfo"
+  }
+  fn description() -> &'static str {
+    "Get what the command journal of the server's last run can rebuild."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::GetJournalInfo(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      GetJournalInfoRsp::JournalInfo(info) =>
+        ResponseEnvelope::JournalInfo(info),
+      GetJournalInfoRsp::Failed(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let get_journal_info_example = GetJournalInfoCmd {};
+
+    let journal_info_response = GetJournalInfoRsp::JournalInfo(JournalInfo {
+      commands: 42,
+      mode: Some(GameModeInfo::DefineRules(DefineRulesModeInfo {})),
+      started_at: Some(1760860800000),
+      ended_at: Some(1760864400000),
+    });
+    let empty_journal_response = GetJournalInfoRsp::JournalInfo(JournalInfo {
+      commands: 0,
+      mode: None,
+      started_at: None,
+      ended_at: None,
+    });
+    (
+      vec![get_journal_info_example],
+      vec![journal_info_response, empty_journal_response],
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "The server journals the commands that build up a mode: entering \
+       it, and then each subcommand that changes it, e.g. rule edits, \
+       descriptor updates and generation steps.  Returning to the main \
+       menu clears the journal.".to_string(),
+      "When the server starts, the journal of its last run is kept for \
+       `ReplayJournal`.  Clients may offer to replay it when `commands` \
+       is not zero.".to_string(),
+    ]
+  }
+}
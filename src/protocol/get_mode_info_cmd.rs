@@ -10,6 +10,7 @@ use super::{
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct GetModeInfoCmd {}
 
 #[derive(Debug, Clone)]
@@ -56,6 +57,8 @@ impl Command for GetModeInfoCmd {
   }
   fn protocol_notes() -> Vec<String> {
     vec![
+      "Answered at once, without waiting for commands in flight, so it \
+       reports the mode as of the last command that finished.".to_string(),
     ]
   }
 }
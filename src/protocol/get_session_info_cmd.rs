@@ -0,0 +1,86 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::ResponseEnvelope,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct GetSessionInfoCmd {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum SessionRole {
+  // May send any command.
+  Controller,
+
+  // May only send commands that do not change the game, and receives
+  // events.
+  Observer,
+}
+
+/**
+ * A connection's view of the sessions open on the server.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct SessionInfo {
+  #[serde(rename = "sessionId")]
+  pub(crate) session_id: u64,
+
+  pub(crate) role: SessionRole,
+
+  // Absent when no session is in control.
+  #[serde(rename = "controllerId")]
+  pub(crate) controller_id: Option<u64>,
+
+  #[serde(rename = "sessionIds")]
+  pub(crate) session_ids: Vec<u64>,
+}
+impl SessionInfo {
+  pub(crate) fn new_example() -> Self {
+    SessionInfo {
+      session_id: 2,
+      role: SessionRole::Observer,
+      controller_id: Some(1),
+      session_ids: vec![1, 2],
+    }
+  }
+}
+
+impl Command for GetSessionInfoCmd {
+  type Response = SessionInfo;
+  fn name() -> &'static str {
+    "GetSessionInfo"
+  }
+  fn description() -> &'static str {
+    "Get the id and role of this connection's session."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::GetSessionInfo(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::SessionInfo(response)
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let get_session_info_example = GetSessionInfoCmd {};
+    let session_info_response = SessionInfo::new_example();
+    (vec![get_session_info_example], vec![session_info_response])
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Each connection is a session.  The first to connect while no \
+       session is in control becomes the `Controller`; the rest are \
+       `Observer`s.".to_string(),
+      "Observers may query the game, e.g. list rulesets or get map data, \
+       but commands that change it fail.".to_string(),
+      "A new connection is sent a `SessionStarted` event with its session \
+       info, and every connection is sent `SessionsChanged` when sessions \
+       open or close or control changes hands.".to_string(),
+    ]
+  }
+}
@@ -0,0 +1,70 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  get_session_info_cmd::{ SessionInfo, SessionRole },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct HandOffControlCmd {
+  // The session to hand control to, or null to just give it up.
+  #[serde(rename = "sessionId")]
+  pub(crate) session_id: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum HandOffControlRsp {
+  SessionInfo(SessionInfo),
+  Failed(Vec<String>),
+}
+impl Command for HandOffControlCmd {
+  type Response = HandOffControlRsp;
+  fn name() -> &'static str {
+    "HandOffControl"
+  }
+  fn description() -> &'static str {
+    "Hand control from this session to another, or give it up."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::HandOffControl(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      HandOffControlRsp::SessionInfo(info) =>
+        ResponseEnvelope::SessionInfo(info),
+      HandOffControlRsp::Failed(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let hand_off_control_example = HandOffControlCmd { session_id: Some(2) };
+
+    let hand_off_control_ok_response =
+      HandOffControlRsp::SessionInfo(SessionInfo {
+        session_id: 1,
+        role: SessionRole::Observer,
+        controller_id: Some(2),
+        session_ids: vec![1, 2],
+      });
+    let hand_off_control_err_response = HandOffControlRsp::Failed(vec![
+      "No such session: 7".to_string(),
+    ]);
+    (
+      vec![hand_off_control_example],
+      vec![
+        hand_off_control_ok_response,
+        hand_off_control_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Only the controlling session may hand off control.  The response is \
+       this session's info after the hand-off.".to_string(),
+    ]
+  }
+}
@@ -0,0 +1,172 @@
+use serde;
+use crate::data::map::{ WorldDescriptorLimits, WorldDims };
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+/**
+ * The version of the protocol the server speaks.  Bumped whenever a change
+ * would break existing clients.
+ */
+pub(crate) const PROTOCOL_VERSION: u32 = 1;
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct HelloCmd {
+  // The protocol version the client speaks, if it wants it checked.
+  #[serde(default)]
+  #[serde(rename = "protocolVersion")]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) protocol_version: Option<u32>,
+}
+
+/**
+ * An optional part of the protocol, which clients can check for.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum ServerFeature {
+  // Commands may carry an `id`, echoed in their responses.
+  RequestIds,
+  // The server pushes events.
+  Events,
+  // Several connections, one in control.
+  Sessions,
+  // Map data as binary frames, via `SetMapDataEncoding`.
+  BinaryMapData,
+  // Commands are journaled, and `ReplayJournal` rebuilds the mode after
+  // a restart.
+  CommandJournal,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct CommandCategoryInfo {
+  pub(crate) name: String,
+  pub(crate) commands: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct GpuAdapterInfo {
+  pub(crate) name: String,
+  pub(crate) backend: String,
+  #[serde(rename = "deviceType")]
+  pub(crate) device_type: String,
+  pub(crate) driver: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ServerInfo {
+  #[serde(rename = "protocolVersion")]
+  pub(crate) protocol_version: u32,
+
+  #[serde(rename = "serverVersion")]
+  pub(crate) server_version: String,
+
+  pub(crate) commands: Vec<CommandCategoryInfo>,
+  pub(crate) events: Vec<String>,
+
+  #[serde(rename = "worldLimits")]
+  pub(crate) world_limits: WorldDescriptorLimits,
+
+  // Absent if the server found no GPU, in which case worlds cannot be
+  // generated.
+  #[serde(rename = "gpuAdapter")]
+  pub(crate) gpu_adapter: Option<GpuAdapterInfo>,
+
+  pub(crate) features: Vec<ServerFeature>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum HelloRsp {
+  ServerInfo(ServerInfo),
+  Failed(Vec<String>),
+}
+impl Command for HelloCmd {
+  type Response = HelloRsp;
+  fn name() -> &'static str {
+    "Hello"
+  }
+  fn description() -> &'static str {
+    "Get the server's protocol version and capabilities."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::Hello(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      HelloRsp::ServerInfo(info) => ResponseEnvelope::ServerInfo(info),
+      HelloRsp::Failed(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let hello_example = HelloCmd { protocol_version: Some(PROTOCOL_VERSION) };
+
+    let hello_ok_response = HelloRsp::ServerInfo(ServerInfo {
+      protocol_version: PROTOCOL_VERSION,
+      server_version: "0.1.0".to_string(),
+      commands: vec![
+        CommandCategoryInfo {
+          name: "Main".to_string(),
+          commands: vec![
+            "EnterMainMenuMode".to_string(),
+            "EnterMode".to_string(),
+            "GetModeInfo".to_string(),
+          ],
+        },
+      ],
+      events: vec![
+        "ModeChanged".to_string(),
+        "ServerShutdown".to_string(),
+      ],
+      world_limits: WorldDescriptorLimits {
+        min_dims: WorldDims::new(250, 250),
+        max_dims: WorldDims::new(1000, 1000),
+        max_description_length: 1000,
+      },
+      gpu_adapter: Some(GpuAdapterInfo {
+        name: "Example GPU".to_string(),
+        backend: "Vulkan".to_string(),
+        device_type: "DiscreteGpu".to_string(),
+        driver: "example-driver".to_string(),
+      }),
+      features: vec![
+        ServerFeature::RequestIds,
+        ServerFeature::Events,
+        ServerFeature::Sessions,
+        ServerFeature::BinaryMapData,
+        ServerFeature::CommandJournal,
+      ],
+    });
+    let hello_err_response = HelloRsp::Failed(vec![
+      "Protocol version mismatch: the client speaks 2, the server speaks 1."
+        .to_string(),
+    ]);
+    (
+      vec![hello_example],
+      vec![hello_ok_response, hello_err_response],
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Clients should send this first.  If `protocolVersion` is given and \
+       is not the server's, the command fails, so that a mismatched client \
+       stops before anything confusing happens.".to_string(),
+      "`commands` lists the commands of each category, as named in these \
+       docs.".to_string(),
+      "Answered at once, without waiting for commands in flight."
+        .to_string(),
+    ]
+  }
+}
@@ -0,0 +1,90 @@
+use serde;
+use crate::data::ruleset::{
+  RulesetBundle,
+  RulesetEntry,
+  RulesetImportConflict,
+  RulesetValidation,
+};
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ImportRulesetCmd {
+  pub(crate) bundle: RulesetBundle,
+
+  // What to do if a ruleset with the same name is already stored.
+  #[serde(rename = "onConflict")]
+  #[serde(default)]
+  pub(crate) on_conflict: RulesetImportConflict,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum ImportRulesetRsp {
+  Imported(RulesetEntry),
+  Invalid(RulesetValidation),
+  Error(Vec<String>),
+}
+impl Command for ImportRulesetCmd {
+  type Response = ImportRulesetRsp;
+  fn name() -> &'static str {
+    "ImportRuleset"
+  }
+  fn description() -> &'static str {
+    "Validate a ruleset bundle and add its ruleset to the store."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ImportRuleset(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      ImportRulesetRsp::Imported(entry) =>
+        ResponseEnvelope::ImportedRuleset(entry),
+      ImportRulesetRsp::Invalid(validation) =>
+        ResponseEnvelope::InvalidRuleset(validation),
+      ImportRulesetRsp::Error(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let import_ruleset_example = ImportRulesetCmd {
+      bundle: RulesetBundle::new_example(),
+      on_conflict: RulesetImportConflict::Rename,
+    };
+
+    let import_ruleset_ok_response = ImportRulesetRsp::Imported(RulesetEntry {
+      name: "Example Ruleset (2)".to_string(),
+      description: "An example ruleset.".to_string(),
+    });
+    let import_ruleset_invalid_response =
+      ImportRulesetRsp::Invalid(RulesetValidation::new_example());
+    let import_ruleset_err_response = ImportRulesetRsp::Error(vec![
+      "A ruleset with this name already exists: Example Ruleset".to_string(),
+    ]);
+    (
+      vec![import_ruleset_example],
+      vec![
+        import_ruleset_ok_response,
+        import_ruleset_invalid_response,
+        import_ruleset_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "`onConflict` is one of `Fail` (the default), `Rename` or `Overwrite`."
+        .to_string(),
+      "`Rename` imports under the first free name of the form `Name (2)`."
+        .to_string(),
+      "Bundles from older schema versions are migrated before validation."
+        .to_string(),
+      "`Overwrite` fails if the ruleset is in use by the current mode."
+        .to_string(),
+    ]
+  }
+}
@@ -0,0 +1,50 @@
+use serde;
+use crate::data::{ map::WorldDims, HeightmapEntry };
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::ResponseEnvelope,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ListHeightmapsCmd {}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct ListHeightmapsRsp {
+  heightmaps: Vec<HeightmapEntry>,
+}
+
+impl Command for ListHeightmapsCmd {
+  type Response = ListHeightmapsRsp;
+  fn name() -> &'static str {
+    "ListHeightmaps"
+  }
+  fn description() -> &'static str {
+    "List all uploaded heightmaps."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ListHeightmaps(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::HeightmapList(response.heightmaps)
+  }
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let list_heightmaps_example = ListHeightmapsCmd {};
+
+    let heightmaps_response = ListHeightmapsRsp {
+      heightmaps: vec![
+        HeightmapEntry {
+          name: "Sketched Continent".to_string(),
+          dims: WorldDims::new(512, 512),
+        },
+      ]
+    };
+
+    (
+      vec![list_heightmaps_example],
+      vec![heightmaps_response]
+    )
+  }
+}
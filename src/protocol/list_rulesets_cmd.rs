@@ -7,6 +7,7 @@ use super::{
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct ListRulesetsCmd {}
 
 #[derive(Debug, Clone)]
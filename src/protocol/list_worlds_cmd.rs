@@ -0,0 +1,60 @@
+use serde;
+use crate::data::map::{ WorldDescriptor, WorldDims };
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::ResponseEnvelope,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ListWorldsCmd {}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct ListWorldsRsp {
+  worlds: Vec<WorldDescriptor>,
+}
+
+impl Command for ListWorldsCmd {
+  type Response = ListWorldsRsp;
+  fn name() -> &'static str {
+    "ListWorlds"
+  }
+  fn description() -> &'static str {
+    "List all saved worlds."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ListWorlds(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::WorldList(response.worlds)
+  }
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let list_worlds_example = ListWorldsCmd {};
+
+    let worlds_response = ListWorldsRsp {
+      worlds: vec![
+        WorldDescriptor {
+          name: "My World".to_string(),
+          description: "My world description".to_string(),
+          seed: "12345".to_string(),
+          dims: WorldDims::new(1000, 1000),
+          ruleset_name: "Example Ruleset".to_string(),
+          heightmap: None,
+        },
+      ]
+    };
+
+    (
+      vec![list_worlds_example],
+      vec![worlds_response]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Worlds are saved when generation reaches the Finalized phase."
+        .to_string(),
+    ]
+  }
+}
@@ -0,0 +1,84 @@
+use super::{
+  mode::{
+    create_world::{ CreateWorldSubcmdResponse, GetMapDataRsp },
+    view_world::ViewWorldSubcmdResponse,
+  },
+  response::ResponseEnvelope,
+};
+
+/** Leads every binary map data frame. */
+const MAGIC: &[u8; 4] = b"RRMD";
+const VERSION: u16 = 1;
+
+// Flag bits.
+const HAS_ID: u32 = 1;
+
+pub(crate) const MAP_DATA_FRAME_DOC: &str =
+  "A binary map data frame is little-endian: the bytes `RRMD`; a u16 \
+   version (1); a u16 layer count; a u32 of flags (bit 0: the command had \
+   an id); a u64 command id (0 without one); u16s for the top-left column \
+   and row and the columns and rows; then one byte per layer giving the \
+   width of its values (2 or 4), padded with zeros to a multiple of 4 \
+   bytes.  The layers follow in the order of `datumIds`, each one value \
+   per cell in row-major order, as u16s or u32s.";
+
+/**
+ * The map data in a response, if it can be sent as a binary frame.
+ * Decoded values have no binary form.
+ */
+pub(crate) fn binary_map_data(response: &ResponseEnvelope)
+  -> Option<&GetMapDataRsp>
+{
+  let rsp = match response {
+    ResponseEnvelope::CreateWorldSubcmd(
+      CreateWorldSubcmdResponse::MapData(rsp)
+    ) => rsp,
+    ResponseEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdResponse::MapData(rsp)
+    ) => rsp,
+    _ => return None,
+  };
+  if rsp.decoded.is_some() { None } else { Some(rsp) }
+}
+
+/**
+ * Encode map data as a binary frame.  A layer whose values all fit in 16
+ * bits is packed as u16s.
+ */
+pub(crate) fn encode_map_data_frame(id: Option<u64>, rsp: &GetMapDataRsp)
+  -> Vec<u8>
+{
+  let widths = rsp.data.iter().map(|layer| {
+    if layer.iter().all(|value| *value <= u16::MAX as u32) { 2 } else { 4 }
+  }).collect::<Vec<u8>>();
+  let data_len = rsp.data.iter().zip(&widths)
+    .map(|(layer, width)| layer.len() * (*width as usize))
+    .sum::<usize>();
+
+  let mut frame = Vec::with_capacity(28 + widths.len() + 3 + data_len);
+  frame.extend_from_slice(MAGIC);
+  frame.extend_from_slice(&VERSION.to_le_bytes());
+  frame.extend_from_slice(&(rsp.data.len() as u16).to_le_bytes());
+  let flags = if id.is_some() { HAS_ID } else { 0 };
+  frame.extend_from_slice(&flags.to_le_bytes());
+  frame.extend_from_slice(&id.unwrap_or(0).to_le_bytes());
+  frame.extend_from_slice(&rsp.top_left.col.to_le_bytes());
+  frame.extend_from_slice(&rsp.top_left.row.to_le_bytes());
+  frame.extend_from_slice(&rsp.dims.columns.to_le_bytes());
+  frame.extend_from_slice(&rsp.dims.rows.to_le_bytes());
+  frame.extend_from_slice(&widths);
+  while frame.len() % 4 != 0 {
+    frame.push(0);
+  }
+
+  for (layer, width) in rsp.data.iter().zip(&widths) {
+    for value in layer {
+      if *width == 2 {
+        frame.extend_from_slice(&(*value as u16).to_le_bytes());
+      } else {
+        frame.extend_from_slice(&value.to_le_bytes());
+      }
+    }
+  }
+  frame
+}
@@ -1,17 +1,47 @@
 mod command;
 mod documentation;
+mod event;
 mod response;
 
+mod hello_cmd;
 mod enter_mode_cmd;
 mod enter_main_menu_mode_cmd;
 mod get_mode_info_cmd;
 mod list_rulesets_cmd;
+mod list_worlds_cmd;
+mod list_heightmaps_cmd;
+mod upload_heightmap_cmd;
+mod delete_ruleset_cmd;
+mod rename_ruleset_cmd;
+mod duplicate_ruleset_cmd;
+mod export_ruleset_cmd;
+mod import_ruleset_cmd;
+mod get_session_info_cmd;
+mod take_control_cmd;
+mod hand_off_control_cmd;
+mod set_map_data_encoding_cmd;
+mod get_journal_info_cmd;
+mod replay_journal_cmd;
+mod map_data_frame;
+mod schema;
+mod typescript;
 
 pub(crate) mod mode;
 
 pub(crate) use self::{
-  command::CommandEnvelope,
-  response::{ ResponseEnvelope, FailedResponse },
+  command::{ CommandEnvelope, CommandFrame },
+  event::{ EventEnvelope, EventFrame },
+  response::{ ResponseEnvelope, ResponseFrame, FailedResponse },
+
+  hello_cmd::{
+    CommandCategoryInfo,
+    GpuAdapterInfo,
+    HelloCmd,
+    HelloRsp,
+    ServerFeature,
+    ServerInfo,
+    PROTOCOL_VERSION,
+  },
 
   enter_mode_cmd::{ EnterModeCmd, EnterModeRsp },
   enter_main_menu_mode_cmd::{ EnterMainMenuModeCmd, EnterMainMenuModeRsp },
@@ -19,9 +49,42 @@ pub(crate) use self::{
   get_mode_info_cmd::{ GetModeInfoCmd, ModeInfoRsp },
 
   list_rulesets_cmd::{ ListRulesetsCmd, ListRulesetsRsp },
+
+  list_worlds_cmd::{ ListWorldsCmd, ListWorldsRsp },
+
+  list_heightmaps_cmd::{ ListHeightmapsCmd, ListHeightmapsRsp },
+  upload_heightmap_cmd::{ UploadHeightmapCmd, UploadHeightmapRsp },
+
+  delete_ruleset_cmd::{ DeleteRulesetCmd, DeleteRulesetRsp },
+  rename_ruleset_cmd::{ RenameRulesetCmd, RenameRulesetRsp },
+  duplicate_ruleset_cmd::{ DuplicateRulesetCmd, DuplicateRulesetRsp },
+  export_ruleset_cmd::{ ExportRulesetCmd, ExportRulesetRsp },
+  import_ruleset_cmd::{ ImportRulesetCmd, ImportRulesetRsp },
+
+  get_session_info_cmd::{ GetSessionInfoCmd, SessionInfo, SessionRole },
+  take_control_cmd::{ TakeControlCmd, TakeControlRsp },
+  hand_off_control_cmd::{ HandOffControlCmd, HandOffControlRsp },
+  set_map_data_encoding_cmd::{
+    MapDataEncoding,
+    SetMapDataEncodingCmd,
+    SetMapDataEncodingRsp,
+  },
+  map_data_frame::{ binary_map_data, encode_map_data_frame },
+
+  get_journal_info_cmd::{ GetJournalInfoCmd, GetJournalInfoRsp, JournalInfo },
+  replay_journal_cmd::{ JournalReplay, ReplayJournalCmd, ReplayJournalRsp },
 };
 pub use self::documentation::{
   ProtocolCommandDocumentation,
   ProtocolCategoryDocumentation,
+  ProtocolEventDocumentation,
   get_protocol_docs,
+  get_event_docs,
+};
+pub use self::schema::{
+  ProtocolSchemaDocument,
+  check_protocol_examples,
+  get_protocol_schemas,
+  schema_violations,
 };
+pub use self::typescript::get_protocol_typescript;
@@ -8,6 +8,7 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct BeginGenerationCmd {}
 
 #[derive(Debug, Clone)]
@@ -12,10 +12,12 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CurrentDescriptorInputCmd {}
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CurrentDescriptorInputRsp {
   pub(crate) descriptor: WorldDescriptorInput,
 
@@ -11,10 +11,12 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CurrentGenerationPhaseCmd {}
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CurrentGenerationPhaseRsp {
   pub(crate) phase: GenerationPhase,
 }
@@ -49,6 +51,9 @@ impl Command for CurrentGenerationPhaseCmd {
     )
   }
   fn protocol_notes() -> Vec<String> {
-    vec![]
+    vec![
+      "The server pushes a `GenerationPhaseChanged` event whenever the \
+       phase changes, so clients need not poll this.".to_string(),
+    ]
   }
 }
@@ -1,7 +1,30 @@
-use crate::ProtocolCategoryDocumentation;
+use crate::{
+  protocol::command::make_command_example,
+  ProtocolCategoryDocumentation,
+};
+use super::{
+  current_descriptor_input_cmd::CurrentDescriptorInputCmd,
+  update_descriptor_input_cmd::UpdateDescriptorInputCmd,
+  patch_descriptor_input_cmd::PatchDescriptorInputCmd,
+  begin_generation_cmd::BeginGenerationCmd,
+  take_generation_step_cmd::TakeGenerationStepCmd,
+  current_generation_phase_cmd::CurrentGenerationPhaseCmd,
+  get_map_data_cmd::GetMapDataCmd,
+  get_minimap_data_cmd::GetMinimapDataCmd,
+  export_map_layer_cmd::ExportMapLayerCmd,
+};
 
 pub fn get_category_docs() -> ProtocolCategoryDocumentation {
   let mut commands = Vec::new();
+  commands.push(make_command_example::<CurrentDescriptorInputCmd>());
+  commands.push(make_command_example::<UpdateDescriptorInputCmd>());
+  commands.push(make_command_example::<PatchDescriptorInputCmd>());
+  commands.push(make_command_example::<BeginGenerationCmd>());
+  commands.push(make_command_example::<TakeGenerationStepCmd>());
+  commands.push(make_command_example::<CurrentGenerationPhaseCmd>());
+  commands.push(make_command_example::<GetMapDataCmd>());
+  commands.push(make_command_example::<GetMinimapDataCmd>());
+  commands.push(make_command_example::<ExportMapLayerCmd>());
 
   ProtocolCategoryDocumentation {
     name: "Create World".to_string(),
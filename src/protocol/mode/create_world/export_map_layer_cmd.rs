@@ -0,0 +1,124 @@
+use serde;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::create_world::CreateWorldSubcmdResponse,
+    response::ResponseEnvelope
+  },
+  data::{
+    map::{ CellComponentSelector, CellCoord, WorldDims },
+    GenerationCellDatumId,
+    LayerExportFormat,
+  },
+};
+use super::CreateWorldSubcmdEnvelope;
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ExportMapLayerCmd {
+  #[serde(rename = "datumId")]
+  pub(crate) datum_id: GenerationCellDatumId,
+
+  // The area to export.  When omitted, the whole map is exported.
+  #[serde(rename = "topLeft")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) top_left: Option<CellCoord>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) dims: Option<WorldDims>,
+
+  pub(crate) format: LayerExportFormat,
+
+  // Name of the ruleset palette to colour with, for `PalettePpm`.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) palette: Option<String>,
+
+  // Base name of the output file.  The extension is chosen by format.
+  pub(crate) filename: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ExportMapLayerRsp {
+  pub(crate) filename: String,
+
+  #[serde(rename = "topLeft")]
+  pub(crate) top_left: CellCoord,
+  pub(crate) dims: WorldDims,
+
+  pub(crate) bytes: u64,
+}
+impl Command for ExportMapLayerCmd {
+  type Response = ExportMapLayerRsp;
+  fn name() -> &'static str {
+    "ExportMapLayer"
+  }
+  fn description() -> &'static str {
+    "Write a single map layer to an image or raw heightmap file."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::CreateWorldSubcmd(
+      CreateWorldSubcmdEnvelope::ExportMapLayer(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::CreateWorldSubcmd(
+      CreateWorldSubcmdResponse::ExportedMapLayer(response)
+    )
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let whole_map_example = ExportMapLayerCmd {
+      datum_id: GenerationCellDatumId::RandGen {},
+      top_left: None,
+      dims: None,
+      format: LayerExportFormat::Raw16,
+      palette: None,
+      filename: "randgen".to_string(),
+    };
+    let area_example = ExportMapLayerCmd {
+      datum_id: GenerationCellDatumId::Selector(CellComponentSelector {
+        word: "word0".to_string(),
+        component: "elevation".to_string(),
+      }),
+      top_left: Some(CellCoord::new(100, 50)),
+      dims: Some(WorldDims::new(200, 100)),
+      format: LayerExportFormat::PalettePpm,
+      palette: Some("Elevation".to_string()),
+      filename: "elevation_detail".to_string(),
+    };
+
+    let whole_map_response = ExportMapLayerRsp {
+      filename: "randgen.r16".to_string(),
+      top_left: CellCoord::new(0, 0),
+      dims: WorldDims::new(1000, 800),
+      bytes: 1_600_000,
+    };
+    let area_response = ExportMapLayerRsp {
+      filename: "elevation_detail.ppm".to_string(),
+      top_left: CellCoord::new(100, 50),
+      dims: WorldDims::new(200, 100),
+      bytes: 60_015,
+    };
+
+    (
+      vec![whole_map_example, area_example],
+      vec![whole_map_response, area_response],
+    )
+  }
+
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Files are written to the `exports` directory of the data root."
+        .to_string(),
+      "`topLeft` and `dims` must be given together, or both omitted."
+        .to_string(),
+      "`Pgm16` and `Raw16` clamp values above 65535.".to_string(),
+    ]
+  }
+}
@@ -7,6 +7,7 @@ use crate::{
   },
   data::{
     map::{ CellComponentSelector, CellCoord, WorldDims },
+    ruleset::FormatValue,
     GenerationCellDatumId,
   },
 };
@@ -14,6 +15,7 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct GetMapDataCmd {
   #[serde(rename = "topLeft")]
   pub(crate) top_left: CellCoord,
@@ -21,15 +23,25 @@ pub(crate) struct GetMapDataCmd {
 
   #[serde(rename = "datumIds")]
   pub(crate) datum_ids: Vec<GenerationCellDatumId>,
+
+  // Also return the values interpreted by component type.
+  #[serde(default)]
+  pub(crate) decode: bool,
 }
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct GetMapDataRsp {
   #[serde(rename = "topLeft")]
   pub(crate) top_left: CellCoord,
   pub(crate) dims: WorldDims,
   pub(crate) data: Vec<Vec<u32>>,
+
+  // `data`, decoded.  Only present if the command asked for it.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) decoded: Option<Vec<Vec<FormatValue>>>,
 }
 impl Command for GetMapDataCmd {
   type Response = GetMapDataRsp;
@@ -65,6 +77,23 @@ impl Command for GetMapDataCmd {
           component: "temperature".to_string(),
         })
       ],
+      decode: false,
+    };
+
+    let get_decoded_map_data_example = GetMapDataCmd {
+      top_left: CellCoord::new(198, 44),
+      dims: WorldDims::new(2, 1),
+      datum_ids: vec![
+        GenerationCellDatumId::Selector(CellComponentSelector {
+          word: "word2".to_string(),
+          component: "temperature".to_string(),
+        }),
+        GenerationCellDatumId::Selector(CellComponentSelector {
+          word: "word2".to_string(),
+          component: "biome".to_string(),
+        }),
+      ],
+      decode: true,
     };
 
     let get_map_data_ok_response = GetMapDataRsp {
@@ -73,13 +102,42 @@ impl Command for GetMapDataCmd {
       data: vec![
         vec![900, 905, 900, 893, 900, 895, 890, 899, 888],
         vec![50, 51, 50, 53, 55, 52, 51, 52, 52],
-      ]
+      ],
+      decoded: None,
+    };
+
+    let get_decoded_map_data_ok_response = GetMapDataRsp {
+      top_left: CellCoord::new(198, 44),
+      dims: WorldDims::new(2, 1),
+      data: vec![
+        vec![231, 4],
+        vec![3, 1],
+      ],
+      decoded: Some(vec![
+        vec![FormatValue::Number(-12.5), FormatValue::Number(2.0)],
+        vec![
+          FormatValue::Name("Forest".to_string()),
+          FormatValue::Name("Plains".to_string()),
+        ],
+      ]),
     };
 
-    (vec![get_map_data_example], vec![get_map_data_ok_response])
+    (
+      vec![get_map_data_example, get_decoded_map_data_example],
+      vec![get_map_data_ok_response, get_decoded_map_data_ok_response],
+    )
   }
 
   fn protocol_notes() -> Vec<String> {
-    vec![]
+    vec![
+      "`data` holds the raw bits of each component.".to_string(),
+      "With `decode`, `decoded` holds the same values interpreted by \
+       component type: signed and fixed-point components as numbers, and \
+       enum components as value names (or numbers, for unnamed values)."
+        .to_string(),
+      "After `SetMapDataEncoding` with `Binary`, undecoded map data is sent \
+       as a binary frame, which is far smaller for large areas."
+        .to_string(),
+    ]
   }
 }
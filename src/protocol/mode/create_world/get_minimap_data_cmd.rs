@@ -14,6 +14,7 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct GetMinimapDataCmd {
   #[serde(rename = "miniDims")]
   pub(crate) mini_dims: WorldDims,
@@ -24,6 +25,7 @@ pub(crate) struct GetMinimapDataCmd {
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct GetMinimapDataRsp {
   #[serde(rename = "miniDims")]
   pub(crate) mini_dims: WorldDims,
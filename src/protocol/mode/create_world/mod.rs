@@ -3,12 +3,14 @@ mod response;
 mod documentation;
 
 mod update_descriptor_input_cmd;
+mod patch_descriptor_input_cmd;
 mod current_descriptor_input_cmd;
 mod begin_generation_cmd;
 mod take_generation_step_cmd;
 mod current_generation_phase_cmd;
 mod get_map_data_cmd;
 mod get_minimap_data_cmd;
+mod export_map_layer_cmd;
 
 pub(crate) use self::{
   subcommand::CreateWorldSubcmdEnvelope,
@@ -19,6 +21,7 @@ pub(crate) use self::{
     UpdateDescriptorInputCmd,
     UpdateDescriptorInputRsp,
   },
+  patch_descriptor_input_cmd::PatchDescriptorInputCmd,
   current_descriptor_input_cmd::{
     CurrentDescriptorInputCmd,
     CurrentDescriptorInputRsp,
@@ -43,8 +46,13 @@ pub(crate) use self::{
     GetMinimapDataCmd,
     GetMinimapDataRsp,
   },
+  export_map_layer_cmd::{
+    ExportMapLayerCmd,
+    ExportMapLayerRsp,
+  },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CreateWorldModeInfo {}
@@ -0,0 +1,100 @@
+use serde;
+use serde_json::json;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::{
+      create_world::CreateWorldSubcmdResponse,
+      define_rules::ValidationPatchRsp,
+    },
+    response::ResponseEnvelope,
+  },
+  data::JsonPatchOp,
+};
+use super::CreateWorldSubcmdEnvelope;
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct PatchDescriptorInputCmd {
+  // Applied to the JSON form of the current descriptor input.
+  pub(crate) patch: Vec<JsonPatchOp>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum PatchDescriptorInputRsp {
+  ValidationPatch(ValidationPatchRsp),
+  Failed(Vec<String>),
+}
+impl Command for PatchDescriptorInputCmd {
+  type Response = PatchDescriptorInputRsp;
+  fn name() -> &'static str {
+    "PatchDescriptorInput"
+  }
+  fn description() -> &'static str {
+    "Apply a JSON Patch to the descriptor input when specifying a new world."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::CreateWorldSubcmd(
+      CreateWorldSubcmdEnvelope::PatchDescriptorInput(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    let subcmd_response = match response {
+      PatchDescriptorInputRsp::ValidationPatch(patch) =>
+        CreateWorldSubcmdResponse::DescriptorValidationPatch(patch),
+      PatchDescriptorInputRsp::Failed(messages) =>
+        CreateWorldSubcmdResponse::Failed(messages),
+    };
+    ResponseEnvelope::CreateWorldSubcmd(subcmd_response)
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let patch_descriptor_input_example = PatchDescriptorInputCmd {
+      patch: vec![
+        JsonPatchOp::Replace {
+          path: "/dims/columns".to_string(),
+          value: json!("200"),
+        },
+        JsonPatchOp::Add {
+          path: "/heightmap".to_string(),
+          value: json!("Alps"),
+        },
+      ],
+    };
+
+    let patch_descriptor_input_ok_response_example =
+      PatchDescriptorInputRsp::ValidationPatch(ValidationPatchRsp {
+        valid: true,
+        validation_patch: vec![
+          JsonPatchOp::Replace { path: "".to_string(), value: json!(null) },
+        ],
+      });
+    let patch_descriptor_input_err_response_example =
+      PatchDescriptorInputRsp::Failed(vec![
+        "Failed to apply patch.".to_string(),
+        "Patch operation 0 failed: Nothing at '/dims/cols'".to_string(),
+      ]);
+    (
+      vec![patch_descriptor_input_example],
+      vec![
+        patch_descriptor_input_ok_response_example,
+        patch_descriptor_input_err_response_example,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "`patch` is a JSON Patch (RFC 6902) against the JSON form of the \
+       current descriptor input, as returned by `CurrentDescriptorInput`.  \
+       If any operation fails, or the result is not a descriptor input, \
+       nothing changes.  Members that are left out when empty, such as \
+       `heightmap`, must be added before they can be replaced."
+        .to_string(),
+      "The response is a JSON Patch that turns the validation before this \
+       patch into the validation after it.  The validation of a valid \
+       descriptor is `null`.".to_string(),
+    ]
+  }
+}
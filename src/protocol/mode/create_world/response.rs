@@ -3,23 +3,28 @@ use crate::data::map::{
   WorldDescriptorInput,
   WorldDescriptorValidation,
 };
+use crate::protocol::mode::define_rules::ValidationPatchRsp;
 use super::{
   current_descriptor_input_cmd::CurrentDescriptorInputRsp,
   current_generation_phase_cmd::CurrentGenerationPhaseRsp,
   get_map_data_cmd::GetMapDataRsp,
   get_minimap_data_cmd::GetMinimapDataRsp,
+  export_map_layer_cmd::ExportMapLayerRsp,
 };
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum CreateWorldSubcmdResponse {
   Ok {},
   BeganNewWorld(WorldDescriptorInput),
   ValidWorldDescriptor(WorldDescriptor),
   InvalidWorldDescriptor(WorldDescriptorValidation),
+  DescriptorValidationPatch(ValidationPatchRsp),
   CurrentDescriptorInput(CurrentDescriptorInputRsp),
   Failed(Vec<String>),
   CurrentGenerationPhase(CurrentGenerationPhaseRsp),
   MapData(GetMapDataRsp),
   MinimapData(GetMinimapDataRsp),
+  ExportedMapLayer(ExportMapLayerRsp),
 }
@@ -1,21 +1,26 @@
 use super::{
   update_descriptor_input_cmd::UpdateDescriptorInputCmd,
+  patch_descriptor_input_cmd::PatchDescriptorInputCmd,
   current_descriptor_input_cmd::CurrentDescriptorInputCmd,
   begin_generation_cmd::BeginGenerationCmd,
   take_generation_step_cmd::TakeGenerationStepCmd,
   current_generation_phase_cmd::CurrentGenerationPhaseCmd,
   get_map_data_cmd::GetMapDataCmd,
   get_minimap_data_cmd::GetMinimapDataCmd,
+  export_map_layer_cmd::ExportMapLayerCmd,
 };
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum CreateWorldSubcmdEnvelope {
   CurrentDescriptorInput(CurrentDescriptorInputCmd),
   UpdateDescriptorInput(UpdateDescriptorInputCmd),
+  PatchDescriptorInput(PatchDescriptorInputCmd),
   BeginGeneration(BeginGenerationCmd),
   TakeGenerationStep(TakeGenerationStepCmd),
   CurrentGenerationPhase(CurrentGenerationPhaseCmd),
   GetMapData(GetMapDataCmd),
   GetMinimapData(GetMinimapDataCmd),
+  ExportMapLayer(ExportMapLayerCmd),
 }
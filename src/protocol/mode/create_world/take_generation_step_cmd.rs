@@ -11,6 +11,7 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct TakeGenerationStepCmd{
   pub(crate) kind: GenerationStepKind,
 }
@@ -5,6 +5,7 @@ use crate::{
     mode::create_world::CreateWorldSubcmdResponse,
     response::ResponseEnvelope,
   },
+  data::{ Diagnose, DiagnosticCode, ValidationMessage },
   data::map::{
     WorldDescriptor,
     WorldDescriptorInput,
@@ -18,6 +19,7 @@ use super::CreateWorldSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct UpdateDescriptorInputCmd {
   pub(crate) descriptor: WorldDescriptorInput,
 }
@@ -62,6 +64,7 @@ impl Command for UpdateDescriptorInputCmd {
           rows: "100".to_string(),
         },
         ruleset_name: "Example Ruleset".to_string(),
+        heightmap: String::new(),
       }
     };
 
@@ -72,23 +75,41 @@ impl Command for UpdateDescriptorInputCmd {
         seed: "12345".to_string(),
         dims: WorldDims::new(1000, 1000),
         ruleset_name: "Example Ruleset".to_string(),
+        heightmap: None,
       });
     
-    let update_descriptor_input_err_response_example =
-      UpdateDescriptorInputRsp::Invalid(WorldDescriptorValidation {
+    let mut validation_example = WorldDescriptorValidation {
+      errors: vec![],
+      name: vec![
+        ValidationMessage::new(DiagnosticCode::Required, "Name is empty."),
+      ],
+      description: vec![
+        ValidationMessage::new(
+          DiagnosticCode::TooLong,
+          "Description is too long.",
+        ).param("max", 1000),
+      ],
+      seed: vec![],
+      dims: WorldDimsValidation {
         errors: vec![
-          "Name is required.".to_string(),
+          ValidationMessage::new(DiagnosticCode::Required, "Columns is empty.")
+            .at("columns"),
         ],
-        name: vec![],
-        description: vec!["Description is too long.".to_string()],
-        seed: vec![],
-        dims: WorldDimsValidation {
-          errors: vec!["Columns is required.".to_string()],
-          columns: vec![],
-          rows: vec!["Rows must be a positive number.".to_string()],
-        },
-        ruleset_name: vec!["Ruleset name is required.".to_string()],
-      });
+        columns: vec![],
+        rows: vec![
+          ValidationMessage::new(
+            DiagnosticCode::NotANumber,
+            "Rows must be a number.",
+          ).param("value", "many"),
+        ],
+      },
+      ruleset_name: vec![],
+      heightmap: vec![],
+      diagnostics: vec![],
+    };
+    validation_example.diagnostics = validation_example.diagnostics();
+    let update_descriptor_input_err_response_example =
+      UpdateDescriptorInputRsp::Invalid(validation_example);
     (
       vec![update_descriptor_input_example],
       vec![
@@ -99,6 +120,10 @@ impl Command for UpdateDescriptorInputCmd {
   }
   fn protocol_notes() -> Vec<String> {
     vec![
+      "An invalid descriptor's messages are given twice: nested by field, \
+       and flattened in `diagnostics`, where each has a stable `code`, the \
+       JSON Pointer `path` of the input it concerns, a `severity`, and the \
+       `params` substituted into its message.".to_string(),
     ]
   }
 }
@@ -11,15 +11,22 @@ use super::DefineRulesSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CurrentRulesCmd {}
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct CurrentRulesRsp {
   pub(crate) ruleset: RulesetInput,
 
   #[serde(skip_serializing_if = "Option::is_none")]
   pub(crate) validation: Option<RulesetValidation>,
+
+  // For a valid derived ruleset, the rules it resolves to.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) effective: Option<Ruleset>,
 }
 impl Command for CurrentRulesCmd {
   type Response = CurrentRulesRsp;
@@ -46,14 +53,27 @@ impl Command for CurrentRulesCmd {
     let current_rules_response_example = CurrentRulesRsp {
       ruleset: Ruleset::new_example().to_input(),
       validation: Some(RulesetValidation::new_example()),
+      effective: None,
+    };
+    let derived = Ruleset::new_derived_example();
+    let current_rules_derived_response_example = CurrentRulesRsp {
+      ruleset: derived.to_input(),
+      validation: None,
+      effective: Some(derived),
     };
     (
       vec![current_rules_example],
-      vec![current_rules_response_example],
+      vec![
+        current_rules_response_example,
+        current_rules_derived_response_example,
+      ],
     )
   }
   fn protocol_notes() -> Vec<String> {
     vec![
+      "`effective` is only given for a valid ruleset with a `base`.  It is \
+       the ruleset with the chain of bases resolved and overrides applied."
+        .to_string(),
     ]
   }
 }
@@ -3,14 +3,26 @@ use crate::{
   ProtocolCategoryDocumentation,
 };
 use super::{
+  current_rules_cmd::CurrentRulesCmd,
+  load_rules_cmd::LoadRulesCmd,
   save_rules_cmd::SaveRulesCmd,
   update_rules_cmd::UpdateRulesCmd,
+  patch_rules_cmd::PatchRulesCmd,
+  undo_rules_cmd::UndoRulesCmd,
+  redo_rules_cmd::RedoRulesCmd,
+  rules_history_cmd::RulesHistoryCmd,
 };
 
 pub fn get_category_docs() -> ProtocolCategoryDocumentation {
   let mut commands = Vec::new();
+  commands.push(make_command_example::<CurrentRulesCmd>());
   commands.push(make_command_example::<UpdateRulesCmd>());
+  commands.push(make_command_example::<PatchRulesCmd>());
   commands.push(make_command_example::<SaveRulesCmd>());
+  commands.push(make_command_example::<LoadRulesCmd>());
+  commands.push(make_command_example::<UndoRulesCmd>());
+  commands.push(make_command_example::<RedoRulesCmd>());
+  commands.push(make_command_example::<RulesHistoryCmd>());
 
   ProtocolCategoryDocumentation {
     name: "Define Rules".to_string(),
@@ -5,12 +5,18 @@ use crate::{
     mode::define_rules::DefineRulesSubcmdResponse,
     response::ResponseEnvelope,
   },
-  data::ruleset::{ Ruleset, TerrainGenRules },
+  data::ruleset::{
+    Ruleset,
+    RulesetOverrides,
+    TerrainGenRules,
+    RULESET_SCHEMA_VERSION,
+  },
 };
 use super::DefineRulesSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct LoadRulesCmd {
   #[serde(rename = "rulesetName")]
   pub(crate) ruleset_name: String,
@@ -52,11 +58,17 @@ impl Command for LoadRulesCmd {
 
     let load_rules_ok_response_example = LoadRulesRsp::Loaded(
       Ruleset {
+        schema_version: RULESET_SCHEMA_VERSION,
         name: "FreeCiv".to_string(),
         description: "FreeCiv ruleset".to_string(),
+        base: None,
+        overrides: RulesetOverrides::default(),
         terrain_gen: TerrainGenRules::new_example(),
+        palettes: vec![],
       }
     );
+    let load_rules_derived_response_example =
+      LoadRulesRsp::Loaded(Ruleset::new_derived_example());
     let load_rules_err_response_example = LoadRulesRsp::Failed(vec![
       "No such ruleset.".to_string(),
     ]);
@@ -64,12 +76,16 @@ impl Command for LoadRulesCmd {
       vec![load_rules_example],
       vec![
         load_rules_ok_response_example,
+        load_rules_derived_response_example,
         load_rules_err_response_example,
       ]
     )
   }
   fn protocol_notes() -> Vec<String> {
     vec![
+      "A derived ruleset is returned with its `base` and `overrides`, and \
+       with `terrainGen` and `palettes` resolved against its base."
+        .to_string(),
     ]
   }
 }
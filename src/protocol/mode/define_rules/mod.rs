@@ -5,6 +5,10 @@ mod update_rules_cmd;
 mod save_rules_cmd;
 mod load_rules_cmd;
 mod current_rules_cmd;
+mod undo_rules_cmd;
+mod redo_rules_cmd;
+mod rules_history_cmd;
+mod patch_rules_cmd;
 
 pub(crate) use self::{
   subcommand::DefineRulesSubcmdEnvelope,
@@ -14,8 +18,13 @@ pub(crate) use self::{
   save_rules_cmd::SaveRulesCmd,
   load_rules_cmd::LoadRulesCmd,
   current_rules_cmd::{ CurrentRulesCmd, CurrentRulesRsp },
+  undo_rules_cmd::UndoRulesCmd,
+  redo_rules_cmd::RedoRulesCmd,
+  rules_history_cmd::{ RulesHistoryCmd, RulesHistoryRsp, RulesRevisionInfo },
+  patch_rules_cmd::{ PatchRulesCmd, ValidationPatchRsp },
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct DefineRulesModeInfo {}
@@ -0,0 +1,124 @@
+use serde;
+use serde_json::json;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::define_rules::DefineRulesSubcmdResponse,
+    response::ResponseEnvelope,
+  },
+  data::JsonPatchOp,
+};
+use super::DefineRulesSubcmdEnvelope;
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct PatchRulesCmd {
+  // Applied to the JSON form of the current ruleset input.
+  pub(crate) patch: Vec<JsonPatchOp>,
+}
+
+/**
+ * The change in validation made by a patch.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ValidationPatchRsp {
+  pub(crate) valid: bool,
+
+  // Turns the validation before the patch into the validation after it.
+  // The validation of a valid input is `null`.
+  #[serde(rename = "validationPatch")]
+  pub(crate) validation_patch: Vec<JsonPatchOp>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum PatchRulesRsp {
+  ValidationPatch(ValidationPatchRsp),
+  Failed(Vec<String>),
+}
+impl Command for PatchRulesCmd {
+  type Response = PatchRulesRsp;
+  fn name() -> &'static str {
+    "PatchRules"
+  }
+  fn description() -> &'static str {
+    "Apply a JSON Patch to the ruleset input being edited."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::DefineRulesSubcmd(
+      DefineRulesSubcmdEnvelope::PatchRules(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    let subcmd_response = match response {
+      PatchRulesRsp::ValidationPatch(patch) =>
+        DefineRulesSubcmdResponse::ValidationPatch(patch),
+      PatchRulesRsp::Failed(messages) =>
+        DefineRulesSubcmdResponse::Failed(messages),
+    };
+    ResponseEnvelope::DefineRulesSubcmd(subcmd_response)
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let patch_rules_example = PatchRulesCmd {
+      patch: vec![
+        JsonPatchOp::Replace {
+          path: "/terrainGen/stage/initProgram".to_string(),
+          value: json!("add r0, r1, 33\n"),
+        },
+        JsonPatchOp::Add {
+          path: "/palettes/-".to_string(),
+          value: json!({
+            "name": "Dusk",
+            "stops": [{ "value": 0, "color": [32, 16, 64] }],
+          }),
+        },
+      ],
+    };
+
+    let patch_rules_ok_response_example = PatchRulesRsp::ValidationPatch(
+      ValidationPatchRsp {
+        valid: false,
+        validation_patch: vec![
+          JsonPatchOp::Remove {
+            path: "/terrainGen/stage/initProgram".to_string(),
+          },
+          JsonPatchOp::Replace {
+            path: "/name".to_string(),
+            value: json!(["The name is required."]),
+          },
+        ],
+      }
+    );
+    let patch_rules_err_response_example = PatchRulesRsp::Failed(vec![
+      "Failed to apply patch.".to_string(),
+      "Patch operation 0 failed: Nothing at '/terrainGen/stage/initProgram'"
+        .to_string(),
+    ]);
+    (
+      vec![patch_rules_example],
+      vec![
+        patch_rules_ok_response_example,
+        patch_rules_err_response_example,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "`patch` is a JSON Patch (RFC 6902) against the JSON form of the \
+       current ruleset input, as returned by `CurrentRules`.  If any \
+       operation fails, or the result is not a ruleset input, nothing \
+       changes.  A successful patch adds a revision to the edit history."
+        .to_string(),
+      "The terrain generator, whose programs are the costly part to \
+       validate, is only revalidated when the patch touches `terrainGen`, \
+       `base` or `overrides` (or `name`, for a derived ruleset).".to_string(),
+      "The response is a JSON Patch that turns the validation before this \
+       patch into the validation after it, so only the nodes that changed \
+       are sent.".to_string(),
+    ]
+  }
+}
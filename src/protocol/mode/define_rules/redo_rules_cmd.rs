@@ -0,0 +1,73 @@
+use serde;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::define_rules::DefineRulesSubcmdResponse,
+    response::ResponseEnvelope,
+  },
+  data::ruleset::Ruleset,
+};
+use super::{ CurrentRulesRsp, DefineRulesSubcmdEnvelope };
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RedoRulesCmd {}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum RedoRulesRsp {
+  CurrentRules(CurrentRulesRsp),
+  Failed(Vec<String>),
+}
+impl Command for RedoRulesCmd {
+  type Response = RedoRulesRsp;
+  fn name() -> &'static str {
+    "RedoRules"
+  }
+  fn description() -> &'static str {
+    "Restore the ruleset revision that was last undone."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::DefineRulesSubcmd(
+      DefineRulesSubcmdEnvelope::RedoRules(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    let subcmd_response = match response {
+      RedoRulesRsp::CurrentRules(current) =>
+        DefineRulesSubcmdResponse::CurrentRules(current),
+      RedoRulesRsp::Failed(messages) =>
+        DefineRulesSubcmdResponse::Failed(messages),
+    };
+    ResponseEnvelope::DefineRulesSubcmd(subcmd_response)
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let redo_rules_example = RedoRulesCmd {};
+
+    let redo_rules_ok_response_example = RedoRulesRsp::CurrentRules(
+      CurrentRulesRsp {
+        ruleset: Ruleset::new_example().to_input(),
+        validation: None,
+        effective: None,
+      }
+    );
+    let redo_rules_err_response_example = RedoRulesRsp::Failed(vec![
+      "Nothing to redo.".to_string(),
+    ]);
+    (
+      vec![redo_rules_example],
+      vec![
+        redo_rules_ok_response_example,
+        redo_rules_err_response_example,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Redo is only possible after `UndoRules`, and until the next \
+       `UpdateRules` or `LoadRules`.".to_string(),
+    ]
+  }
+}
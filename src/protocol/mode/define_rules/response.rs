@@ -1,12 +1,19 @@
 use crate::data::ruleset::{ Ruleset, RulesetValidation };
-use super::current_rules_cmd::CurrentRulesRsp;
+use super::{
+  current_rules_cmd::CurrentRulesRsp,
+  rules_history_cmd::RulesHistoryRsp,
+  patch_rules_cmd::ValidationPatchRsp,
+};
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum DefineRulesSubcmdResponse {
   InvalidRuleset(RulesetValidation),
   CurrentRules(CurrentRulesRsp),
   LoadedRuleset(Ruleset),
+  RulesHistory(RulesHistoryRsp),
+  ValidationPatch(ValidationPatchRsp),
   Ok {},
   Failed(Vec<String>)
 }
@@ -0,0 +1,95 @@
+use serde;
+use crate::protocol::{
+  command::{ Command, CommandEnvelope },
+  mode::define_rules::DefineRulesSubcmdResponse,
+  response::ResponseEnvelope,
+};
+use super::DefineRulesSubcmdEnvelope;
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesHistoryCmd {}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesHistoryRsp {
+  // Oldest first.
+  pub(crate) revisions: Vec<RulesRevisionInfo>,
+
+  // The revision being edited.
+  pub(crate) current: u64,
+}
+
+/**
+ * A summary of a revision in the edit history.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RulesRevisionInfo {
+  pub(crate) revision: u64,
+
+  // Milliseconds since the Unix epoch.
+  pub(crate) timestamp: u64,
+
+  // The ruleset's name at this revision.
+  pub(crate) name: String,
+}
+
+impl Command for RulesHistoryCmd {
+  type Response = RulesHistoryRsp;
+  fn name() -> &'static str {
+    "RulesHistory"
+  }
+  fn description() -> &'static str {
+    "List the revisions in the edit history of the ruleset."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::DefineRulesSubcmd(
+      DefineRulesSubcmdEnvelope::RulesHistory(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::DefineRulesSubcmd(
+      DefineRulesSubcmdResponse::RulesHistory(response)
+    )
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let rules_history_example = RulesHistoryCmd {};
+
+    let rules_history_response_example = RulesHistoryRsp {
+      revisions: vec![
+        RulesRevisionInfo {
+          revision: 0,
+          timestamp: 1760000000000,
+          name: "FreeCiv".to_string(),
+        },
+        RulesRevisionInfo {
+          revision: 1,
+          timestamp: 1760000012345,
+          name: "FreeCiv".to_string(),
+        },
+        RulesRevisionInfo {
+          revision: 2,
+          timestamp: 1760000020000,
+          name: "FreeCiv Islands".to_string(),
+        },
+      ],
+      current: 1,
+    };
+    (
+      vec![rules_history_example],
+      vec![rules_history_response_example],
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Revisions after `current` have been undone and can be redone.  \
+       Revision numbers keep increasing across the session, and only the \
+       most recent revisions are kept.".to_string(),
+    ]
+  }
+}
@@ -8,6 +8,7 @@ use super::DefineRulesSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct SaveRulesCmd {}
 
 #[derive(Debug, Clone)]
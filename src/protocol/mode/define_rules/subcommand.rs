@@ -1,11 +1,25 @@
 
-use super::{ UpdateRulesCmd, SaveRulesCmd, LoadRulesCmd, CurrentRulesCmd };
+use super::{
+  UpdateRulesCmd,
+  SaveRulesCmd,
+  LoadRulesCmd,
+  CurrentRulesCmd,
+  UndoRulesCmd,
+  RedoRulesCmd,
+  RulesHistoryCmd,
+  PatchRulesCmd,
+};
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum DefineRulesSubcmdEnvelope {
   UpdateRules(UpdateRulesCmd),
   CurrentRules(CurrentRulesCmd),
   SaveRules(SaveRulesCmd),
   LoadRules(LoadRulesCmd),
+  UndoRules(UndoRulesCmd),
+  RedoRules(RedoRulesCmd),
+  RulesHistory(RulesHistoryCmd),
+  PatchRules(PatchRulesCmd),
 }
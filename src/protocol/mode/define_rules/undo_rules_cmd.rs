@@ -0,0 +1,77 @@
+use serde;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::define_rules::DefineRulesSubcmdResponse,
+    response::ResponseEnvelope,
+  },
+  data::ruleset::{ Ruleset, RulesetValidation },
+};
+use super::{ CurrentRulesRsp, DefineRulesSubcmdEnvelope };
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct UndoRulesCmd {}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum UndoRulesRsp {
+  CurrentRules(CurrentRulesRsp),
+  Failed(Vec<String>),
+}
+impl Command for UndoRulesCmd {
+  type Response = UndoRulesRsp;
+  fn name() -> &'static str {
+    "UndoRules"
+  }
+  fn description() -> &'static str {
+    "Revert the ruleset being edited to its previous revision."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::DefineRulesSubcmd(
+      DefineRulesSubcmdEnvelope::UndoRules(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    let subcmd_response = match response {
+      UndoRulesRsp::CurrentRules(current) =>
+        DefineRulesSubcmdResponse::CurrentRules(current),
+      UndoRulesRsp::Failed(messages) =>
+        DefineRulesSubcmdResponse::Failed(messages),
+    };
+    ResponseEnvelope::DefineRulesSubcmd(subcmd_response)
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let undo_rules_example = UndoRulesCmd {};
+
+    let undo_rules_ok_response_example = UndoRulesRsp::CurrentRules(
+      CurrentRulesRsp {
+        ruleset: Ruleset::new_example().to_input(),
+        validation: Some(RulesetValidation::new_example()),
+        effective: None,
+      }
+    );
+    let undo_rules_err_response_example = UndoRulesRsp::Failed(vec![
+      "Nothing to undo.".to_string(),
+    ]);
+    (
+      vec![undo_rules_example],
+      vec![
+        undo_rules_ok_response_example,
+        undo_rules_err_response_example,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Every `UpdateRules` adds a revision to the edit history, and \
+       `LoadRules` starts a new history.  Undoing does not discard \
+       revisions until the next `UpdateRules`, so they can be redone."
+        .to_string(),
+      "The response is the same as that of `CurrentRules`, for the \
+       revision undone to.".to_string(),
+    ]
+  }
+}
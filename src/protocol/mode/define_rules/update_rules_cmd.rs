@@ -6,18 +6,29 @@ use crate::{
     response::ResponseEnvelope,
   },
   shady_vm::{ ShasmParseError, ShasmProgramValidation },
+  data::{ Diagnose, DiagnosticCode, ValidationMessage },
   data::ruleset::{
     FormatComponentInput,
+    FormatComponentType,
     FormatComponentValidation,
+    FormatEnumValue,
     FormatInput,
     FormatValidation,
     FormatWordInput,
     FormatWordValidation,
+    Ruleset,
     RulesetInput,
+    RulesetOverridesInput,
     RulesetValidation,
     TerrainGenInput,
+    TerrainGenParamInput,
+    TerrainGenParamRules,
+    TerrainGenParamValidation,
     TerrainGenPerlinInput,
     TerrainGenPerlinValidation,
+    TerrainGenProgramKind,
+    TerrainGenProgramTest,
+    TerrainGenProgramTestValidation,
     TerrainGenStageInput,
     TerrainGenStageValidation,
     TerrainGenValidation,
@@ -27,6 +38,7 @@ use super::DefineRulesSubcmdEnvelope;
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct UpdateRulesCmd {
   #[serde(rename = "rulesetInput")]
   pub(crate) ruleset_input: RulesetInput,
@@ -69,6 +81,8 @@ impl Command for UpdateRulesCmd {
       ruleset_input: RulesetInput {
         name: "Example Ruleset".to_string(),
         description: "Example ruleset description".to_string(),
+        base: None,
+        overrides: RulesetOverridesInput::default(),
         terrain_gen: TerrainGenInput {
           stage: TerrainGenStageInput {
             format: FormatInput {
@@ -80,11 +94,20 @@ impl Command for UpdateRulesCmd {
                       name: "component_0".to_string(),
                       offset: "".to_string(),
                       bits: "-99".to_string(),
+                      kind: FormatComponentType::Signed,
                     },
                     FormatComponentInput {
                       name: "component_1".to_string(),
                       offset: "foobar".to_string(),
                       bits: "33".to_string(),
+                      kind: FormatComponentType::Enum {
+                        values: vec![
+                          FormatEnumValue {
+                            name: "Forest".to_string(),
+                            value: 3,
+                          },
+                        ],
+                      },
                     },
                   ]
                 }
@@ -94,123 +117,178 @@ impl Command for UpdateRulesCmd {
             pairwise_program: "add r0, r1, 33\n".to_string(),
             merge_program: "add r0, r1, 33\n".to_string(),
             final_program: "add r0, r1, 33\n".to_string(),
+            tests: vec![
+              TerrainGenProgramTest {
+                name: "AddsConstant".to_string(),
+                program: TerrainGenProgramKind::Pairwise,
+                inputs: [("r1".to_string(), 7)].into(),
+                neighbour: vec![],
+                expected: [("r0".to_string(), 40)].into(),
+              },
+            ],
           },
           perlin: TerrainGenPerlinInput {
             register: "4".to_string(),
-          }
-        }
+          },
+          params: vec![
+            TerrainGenParamInput {
+              name: "SeaLevel".to_string(),
+              description: String::new(),
+              default: "2000".to_string(),
+              min: "0".to_string(),
+              max: "1000".to_string(),
+            },
+          ],
+        },
+        palettes: vec![],
       }
     };
 
     let validate_ok_response_example = UpdateRulesRsp::Ok {};
-    let validate_failed_response_example = UpdateRulesRsp::Invalid(
-      RulesetValidation {
+    let mut validation_example = RulesetValidation {
+      errors: vec![
+        "error_1".into(),
+        "error_2".into(),
+      ],
+      name: vec![
+        "error_1a".into(),
+        "error_2a".into(),
+      ],
+      description: vec![
+        "error_1b".into(),
+        "error_2b".into(),
+      ],
+      base: vec![
+        ValidationMessage::new(
+          DiagnosticCode::NotFound,
+          "No such base ruleset: Example Ruleset",
+        ).param("name", "Example Ruleset"),
+      ],
+      terrain_gen: Some(TerrainGenValidation {
         errors: vec![
-          "error_1".to_string(),
-          "error_2".to_string(),
-        ],
-        name: vec![
-          "error_1a".to_string(),
-          "error_2a".to_string(),
-        ],
-        description: vec![
-          "error_1b".to_string(),
-          "error_2b".to_string(),
+          "error_3".into(),
+          "error_4".into(),
         ],
-        terrain_gen: Some(TerrainGenValidation {
+        stage: Some(TerrainGenStageValidation {
           errors: vec![
-            "error_3".to_string(),
-            "error_4".to_string(),
+            "error_5".into(),
+            "error_6".into(),
           ],
-          stage: Some(TerrainGenStageValidation {
+          format: Some(FormatValidation {
             errors: vec![
-              "error_5".to_string(),
-              "error_6".to_string(),
+              "error_7".into(),
+              "error_8".into(),
+            ],
+            word_formats: vec![
+              FormatWordValidation {
+                errors: vec![
+                  "error_9".into(),
+                  "error_10".into(),
+                ],
+                components: vec![
+                  FormatComponentValidation {
+                    errors: vec![
+                      "error_11".into(),
+                      "error_12".into(),
+                    ],
+                    name: vec![],
+                    offset: vec![],
+                    bits: vec![],
+                    kind: vec![],
+                  },
+                  FormatComponentValidation {
+                    errors: vec![
+                      "error_13".into(),
+                      "error_14".into(),
+                    ],
+                    name: vec![],
+                    offset: vec!["error_15".into()],
+                    bits: vec![],
+                    kind: vec!["error_16".into()],
+                  }
+                ],
+              }
             ],
-            format: Some(FormatValidation {
-              errors: vec![
-                "error_7".to_string(),
-                "error_8".to_string(),
-              ],
-              word_formats: vec![
-                FormatWordValidation {
-                  errors: vec![
-                    "error_9".to_string(),
-                    "error_10".to_string(),
-                  ],
-                  components: vec![
-                    FormatComponentValidation {
-                      errors: vec![
-                        "error_11".to_string(),
-                        "error_12".to_string(),
-                      ],
-                      name: vec![],
-                      offset: vec![],
-                      bits: vec![],
-                    },
-                    FormatComponentValidation {
-                      errors: vec![
-                        "error_13".to_string(),
-                        "error_14".to_string(),
-                      ],
-                      name: vec![],
-                      offset: vec!["error_15".to_string()],
-                      bits: vec![],
-                    }
-                  ],
-                }
-              ],
-            }),
-            init_program: Some(ShasmProgramValidation {
-              errors: vec![
-                ShasmParseError {
-                  line_no: 1,
-                  message: "error_16".to_string(),
-                }
-              ],
-            }),
-            pairwise_program: Some(ShasmProgramValidation {
-              errors: vec![
-                ShasmParseError {
-                  line_no: 3,
-                  message: "error_18".to_string(),
-                }
-              ],
-            }),
-            merge_program: Some(ShasmProgramValidation {
-              errors: vec![
-                ShasmParseError {
-                  line_no: 5,
-                  message: "error_20".to_string(),
-                }
-              ],
-            }),
-            final_program: Some(ShasmProgramValidation {
-              errors: vec![
-                ShasmParseError {
-                  line_no: 7,
-                  message: "error_21".to_string(),
-                }
-              ],
-            }),
           }),
-          perlin: Some(TerrainGenPerlinValidation {
+          init_program: Some(ShasmProgramValidation {
             errors: vec![
-              "error_15".to_string(),
-              "error_16".to_string(),
+              ShasmParseError {
+                line_no: 1,
+                message: "error_16".to_string(),
+              }
             ],
-            seed: vec!["error_17".to_string()],
-            octaves: vec!["error_18".to_string()],
-            frequency: vec!["error_19".to_string()],
-            amplitude: vec!["error_20".to_string()],
-            register: vec!["error_21".to_string()],
           }),
-        })
-      }
-    );
+          pairwise_program: Some(ShasmProgramValidation {
+            errors: vec![
+              ShasmParseError {
+                line_no: 3,
+                message: "error_18".to_string(),
+              }
+            ],
+          }),
+          merge_program: Some(ShasmProgramValidation {
+            errors: vec![
+              ShasmParseError {
+                line_no: 5,
+                message: "error_20".to_string(),
+              }
+            ],
+          }),
+          final_program: Some(ShasmProgramValidation {
+            errors: vec![
+              ShasmParseError {
+                line_no: 7,
+                message: "error_21".to_string(),
+              }
+            ],
+          }),
+          tests: vec![
+            TerrainGenProgramTestValidation {
+              name: "AddsConstant".to_string(),
+              index: 0,
+              errors: vec!["r0: expected 40, got 39.".into()],
+            },
+          ],
+        }),
+        perlin: Some(TerrainGenPerlinValidation {
+          errors: vec![
+            "error_15".into(),
+            "error_16".into(),
+          ],
+          seed: vec!["error_17".into()],
+          octaves: vec!["error_18".into()],
+          frequency: vec!["error_19".into()],
+          amplitude: vec!["error_20".into()],
+          register: vec!["error_21".into()],
+        }),
+        params: vec![
+          TerrainGenParamValidation {
+            errors: vec![],
+            name: vec![],
+            default: vec![
+              ValidationMessage::new(
+                DiagnosticCode::OutOfRange,
+                "The default must be between 0 and 1000.",
+              ).param("min", 0).param("max", 1000),
+            ],
+            min: vec![],
+            max: vec![],
+          },
+        ],
+      }),
+      palettes: vec![],
+      diagnostics: vec![],
+    };
+    validation_example.diagnostics = validation_example.diagnostics();
+    let validate_failed_response_example =
+      UpdateRulesRsp::Invalid(validation_example);
+
+    let derived_example = UpdateRulesCmd {
+      ruleset_input: Ruleset::new_derived_example().to_input(),
+    };
 
     (
-      vec![validate_example],
+      vec![validate_example, derived_example],
       vec![
         validate_ok_response_example,
         validate_failed_response_example,
@@ -219,6 +297,37 @@ impl Command for UpdateRulesCmd {
   }
   fn protocol_notes() -> Vec<String> {
     vec![
+      "A ruleset with a `base` derives from that stored ruleset.  Its \
+       `terrainGen` and `palettes` are ignored; the base's are used instead, \
+       with `overrides` applied.  Overrides can replace the noise \
+       parameters and individual programs, add format components, and \
+       replace or add palettes.".to_string(),
+      format!(
+        "`terrainGen.params` declares up to {} integer parameters, each \
+         with a default and an inclusive range.  The defaults are preloaded \
+         into registers starting at r{} when test vectors run.  Programs \
+         refer to a parameter's register as `r#param.Name`.",
+        TerrainGenParamRules::MAX_PARAMS, TerrainGenParamRules::FIRST_REG
+      ),
+      format!(
+        "`terrainGen.stage.tests` are test vectors for the programs.  Each \
+         names a `program` (Init, Pairwise, Merge or Final), the registers \
+         to set beforehand (`inputs`, e.g. `{{\"r1\": 7}}`) and the values \
+         `expected` afterwards.  Parameters start at their defaults.  A \
+         pairwise test's `neighbour` values are loaded into registers \
+         from r{}.  The tests run on a CPU interpreter whenever the ruleset \
+         is validated, and each failing test is reported by name and \
+         `index` under `terrainGen.stage.tests`.",
+        TerrainGenProgramTest::FIRST_NEIGHBOUR_REG
+      ),
+      "Validation errors for the merged terrain generator are reported \
+       under `terrainGen`, against the merged format.".to_string(),
+      "Besides the nested messages, an invalid ruleset lists every message \
+       in `diagnostics`, each with a stable `code`, the JSON Pointer `path` \
+       of the input it concerns, a `severity`, and the `params` \
+       substituted into its `message`.  Program errors give their `line` \
+       as a param.  The nested shape is derived from the same messages and \
+       will be retired once clients use `diagnostics`.".to_string(),
     ]
   }
 }
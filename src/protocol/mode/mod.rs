@@ -1,16 +1,20 @@
 pub(crate) mod define_rules;
 pub(crate) mod create_world;
+pub(crate) mod view_world;
 
 use self::{
   define_rules::DefineRulesModeInfo,
   create_world::CreateWorldModeInfo,
+  view_world::ViewWorldModeInfo,
 };
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum GameModeInfo {
   DefineRules(DefineRulesModeInfo),
   CreateWorld(CreateWorldModeInfo),
+  ViewWorld(ViewWorldModeInfo),
 }
 impl GameModeInfo {
 }
@@ -0,0 +1,23 @@
+use crate::{
+  protocol::command::make_command_example,
+  ProtocolCategoryDocumentation,
+};
+use super::{
+  get_map_data_cmd::GetMapDataCmd,
+  get_minimap_data_cmd::GetMinimapDataCmd,
+  export_map_layer_cmd::ExportMapLayerCmd,
+};
+
+pub fn get_category_docs() -> ProtocolCategoryDocumentation {
+  let commands = vec![
+    make_command_example::<GetMapDataCmd>(),
+    make_command_example::<GetMinimapDataCmd>(),
+    make_command_example::<ExportMapLayerCmd>(),
+  ];
+
+  ProtocolCategoryDocumentation {
+    name: "View World".to_string(),
+    description: "Commands for browsing saved game worlds.".to_string(),
+    commands,
+  }
+}
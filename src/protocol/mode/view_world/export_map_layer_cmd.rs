@@ -0,0 +1,107 @@
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::create_world::{ self, ExportMapLayerRsp },
+    response::ResponseEnvelope,
+  },
+  data::{
+    map::{ CellComponentSelector, CellCoord, WorldDims },
+    GenerationCellDatumId,
+    LayerExportFormat,
+  },
+};
+use super::{ ViewWorldSubcmdEnvelope, ViewWorldSubcmdResponse };
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "ViewWorldExportMapLayerCmd")]
+pub(crate) struct ExportMapLayerCmd {
+  #[serde(rename = "datumId")]
+  pub(crate) datum_id: GenerationCellDatumId,
+
+  // The area to export.  When omitted, the whole map is exported.
+  #[serde(rename = "topLeft")]
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) top_left: Option<CellCoord>,
+
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) dims: Option<WorldDims>,
+
+  pub(crate) format: LayerExportFormat,
+
+  // Name of the palette to colour with, for `PalettePpm`, from the
+  // ruleset the world was created with.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) palette: Option<String>,
+
+  // Base name of the output file.  The extension is chosen by format.
+  pub(crate) filename: String,
+}
+impl From<ExportMapLayerCmd> for create_world::ExportMapLayerCmd {
+  fn from(cmd: ExportMapLayerCmd) -> Self {
+    create_world::ExportMapLayerCmd {
+      datum_id: cmd.datum_id,
+      top_left: cmd.top_left,
+      dims: cmd.dims,
+      format: cmd.format,
+      palette: cmd.palette,
+      filename: cmd.filename,
+    }
+  }
+}
+impl Command for ExportMapLayerCmd {
+  type Response = ExportMapLayerRsp;
+  fn name() -> &'static str {
+    "ExportMapLayer"
+  }
+  fn description() -> &'static str {
+    "Write a single layer of a saved world to an image or raw heightmap \
+     file."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdEnvelope::ExportMapLayer(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdResponse::ExportedMapLayer(response)
+    )
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let export_example = ExportMapLayerCmd {
+      datum_id: GenerationCellDatumId::Selector(CellComponentSelector {
+        word: "word0".to_string(),
+        component: "elevation".to_string(),
+      }),
+      top_left: None,
+      dims: None,
+      format: LayerExportFormat::Pgm16,
+      palette: None,
+      filename: "elevation".to_string(),
+    };
+
+    let export_response = ExportMapLayerRsp {
+      filename: "elevation.pgm".to_string(),
+      top_left: CellCoord::new(0, 0),
+      dims: WorldDims::new(1000, 800),
+      bytes: 1_600_017,
+    };
+
+    (vec![export_example], vec![export_response])
+  }
+
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Works as the CreateWorld `ExportMapLayer` command does, except that \
+       only `Selector` datum ids are valid.".to_string(),
+      "Palettes are read from the world's ruleset as it is now stored."
+        .to_string(),
+    ]
+  }
+}
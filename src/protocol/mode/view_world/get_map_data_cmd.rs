@@ -0,0 +1,84 @@
+use serde;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::create_world::GetMapDataRsp,
+    response::ResponseEnvelope,
+  },
+  data::{
+    map::{ CellComponentSelector, CellCoord, WorldDims },
+    GenerationCellDatumId,
+  },
+};
+use super::{ ViewWorldSubcmdEnvelope, ViewWorldSubcmdResponse };
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "ViewWorldGetMapDataCmd")]
+pub(crate) struct GetMapDataCmd {
+  #[serde(rename = "topLeft")]
+  pub(crate) top_left: CellCoord,
+  pub(crate) dims: WorldDims,
+
+  #[serde(rename = "datumIds")]
+  pub(crate) datum_ids: Vec<GenerationCellDatumId>,
+
+  // Also return the values interpreted by component type.
+  #[serde(default)]
+  pub(crate) decode: bool,
+}
+impl Command for GetMapDataCmd {
+  type Response = GetMapDataRsp;
+  fn name() -> &'static str {
+    "GetMapData"
+  }
+  fn description() -> &'static str {
+    "Retrieve saved world map data by id."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdEnvelope::GetMapData(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdResponse::MapData(response)
+    )
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let get_map_data_example = GetMapDataCmd {
+      top_left: CellCoord::new(198, 44),
+      dims: WorldDims::new(3, 3),
+      datum_ids: vec![
+        GenerationCellDatumId::Selector(CellComponentSelector {
+          word: "word0".to_string(),
+          component: "elevation".to_string(),
+        }),
+      ],
+      decode: false,
+    };
+
+    let get_map_data_ok_response = GetMapDataRsp {
+      top_left: CellCoord::new(198, 44),
+      dims: WorldDims::new(3, 3),
+      data: vec![
+        vec![900, 905, 900, 893, 900, 895, 890, 899, 888],
+      ],
+      decoded: None,
+    };
+
+    (vec![get_map_data_example], vec![get_map_data_ok_response])
+  }
+
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Only `Selector` datum ids are valid; saved worlds keep no randgen data."
+        .to_string(),
+      "`decode` works as for the CreateWorld `GetMapData` command.".to_string(),
+      "After `SetMapDataEncoding` with `Binary`, undecoded map data is sent \
+       as a binary frame.".to_string(),
+    ]
+  }
+}
@@ -0,0 +1,65 @@
+use serde;
+use crate::{
+  protocol::{
+    command::{ Command, CommandEnvelope },
+    mode::create_world::GetMinimapDataRsp,
+    response::ResponseEnvelope,
+  },
+  data::{
+    map::{ CellComponentSelector, WorldDims },
+    GenerationCellDatumId,
+  },
+};
+use super::{ ViewWorldSubcmdEnvelope, ViewWorldSubcmdResponse };
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+#[schemars(rename = "ViewWorldGetMinimapDataCmd")]
+pub(crate) struct GetMinimapDataCmd {
+  #[serde(rename = "miniDims")]
+  pub(crate) mini_dims: WorldDims,
+
+  #[serde(rename = "datumId")]
+  pub(crate) datum_id: GenerationCellDatumId,
+}
+impl Command for GetMinimapDataCmd {
+  type Response = GetMinimapDataRsp;
+  fn name() -> &'static str {
+    "GetMinimapData"
+  }
+  fn description() -> &'static str {
+    "Retrieve saved world minimap data by datum id."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdEnvelope::GetMinimapData(self.clone())
+    )
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdResponse::MinimapData(response)
+    )
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let get_minimap_data_example = GetMinimapDataCmd {
+      mini_dims: WorldDims::new(3, 3),
+      datum_id: GenerationCellDatumId::Selector(CellComponentSelector {
+        word: "word0".to_string(),
+        component: "elevation".to_string(),
+      }),
+    };
+
+    let get_minimap_data_ok_response = GetMinimapDataRsp {
+      mini_dims: WorldDims::new(3, 3),
+      data: vec![900, 905, 900, 893, 900, 895, 890, 899, 888],
+    };
+
+    (vec![get_minimap_data_example], vec![get_minimap_data_ok_response])
+  }
+
+  fn protocol_notes() -> Vec<String> {
+    vec![]
+  }
+}
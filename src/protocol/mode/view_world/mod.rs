@@ -0,0 +1,25 @@
+mod subcommand;
+mod response;
+mod documentation;
+
+mod get_map_data_cmd;
+mod get_minimap_data_cmd;
+mod export_map_layer_cmd;
+
+pub(crate) use self::{
+  subcommand::ViewWorldSubcmdEnvelope,
+  response::ViewWorldSubcmdResponse,
+  documentation::get_category_docs,
+
+  get_map_data_cmd::GetMapDataCmd,
+  get_minimap_data_cmd::GetMinimapDataCmd,
+  export_map_layer_cmd::ExportMapLayerCmd,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ViewWorldModeInfo {
+  #[serde(rename = "worldName")]
+  pub(crate) world_name: String,
+}
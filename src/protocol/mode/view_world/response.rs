@@ -0,0 +1,16 @@
+use crate::protocol::mode::create_world::{
+  ExportMapLayerRsp,
+  GetMapDataRsp,
+  GetMinimapDataRsp,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum ViewWorldSubcmdResponse {
+  Ok {},
+  Failed(Vec<String>),
+  MapData(GetMapDataRsp),
+  MinimapData(GetMinimapDataRsp),
+  ExportedMapLayer(ExportMapLayerRsp),
+}
@@ -0,0 +1,14 @@
+use super::{
+  get_map_data_cmd::GetMapDataCmd,
+  get_minimap_data_cmd::GetMinimapDataCmd,
+  export_map_layer_cmd::ExportMapLayerCmd,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum ViewWorldSubcmdEnvelope {
+  GetMapData(GetMapDataCmd),
+  GetMinimapData(GetMinimapDataCmd),
+  ExportMapLayer(ExportMapLayerCmd),
+}
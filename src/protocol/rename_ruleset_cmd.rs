@@ -0,0 +1,68 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct RenameRulesetCmd {
+  #[serde(rename = "rulesetName")]
+  pub(crate) ruleset_name: String,
+
+  #[serde(rename = "newName")]
+  pub(crate) new_name: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum RenameRulesetRsp {
+  Ok,
+  Error(Vec<String>),
+}
+impl Command for RenameRulesetCmd {
+  type Response = RenameRulesetRsp;
+  fn name() -> &'static str {
+    "RenameRuleset"
+  }
+  fn description() -> &'static str {
+    "Rename a saved ruleset."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::RenameRuleset(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      RenameRulesetRsp::Ok => ResponseEnvelope::Ok {},
+      RenameRulesetRsp::Error(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let rename_ruleset_example = RenameRulesetCmd {
+      ruleset_name: "Example Ruleset".to_string(),
+      new_name: "Renamed Ruleset".to_string(),
+    };
+
+    let rename_ruleset_ok_response = RenameRulesetRsp::Ok;
+    let rename_ruleset_err_response = RenameRulesetRsp::Error(vec![
+      "A ruleset with this name already exists.".to_string(),
+    ]);
+    (
+      vec![rename_ruleset_example],
+      vec![
+        rename_ruleset_ok_response,
+        rename_ruleset_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Saved worlds that use the ruleset are updated to the new name."
+        .to_string(),
+      "Fails if the ruleset is in use by the current mode.".to_string(),
+    ]
+  }
+}
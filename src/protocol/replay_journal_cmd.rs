@@ -0,0 +1,88 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  mode::{
+    define_rules::DefineRulesModeInfo,
+    GameModeInfo,
+  },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ReplayJournalCmd {}
+
+/**
+ * The outcome of replaying the journal of the server's last run.
+ */
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct JournalReplay {
+  // The number of commands replayed, including any that failed.
+  pub(crate) replayed: u32,
+
+  // The mode the game is in afterwards.
+  pub(crate) mode: Option<GameModeInfo>,
+
+  // A message for each replayed command that failed, and was skipped.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub(crate) skipped: Vec<String>,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum ReplayJournalRsp {
+  JournalReplayed(JournalReplay),
+  Failed(Vec<String>),
+}
+impl Command for ReplayJournalCmd {
+  type Response = ReplayJournalRsp;
+  fn name() -> &'static str {
+    "ReplayJournal"
+  }
+  fn description() -> &'static str {
+    "Rebuild the mode the server was in when it last stopped, by replaying \
+     its command journal."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::ReplayJournal(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      ReplayJournalRsp::JournalReplayed(replay) =>
+        ResponseEnvelope::JournalReplayed(replay),
+      ReplayJournalRsp::Failed(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let replay_journal_example = ReplayJournalCmd {};
+
+    let replayed_response = ReplayJournalRsp::JournalReplayed(JournalReplay {
+      replayed: 42,
+      mode: Some(GameModeInfo::DefineRules(DefineRulesModeInfo {})),
+      skipped: vec![],
+    });
+    let replay_err_response = ReplayJournalRsp::Failed(vec![
+      "Cannot replay the journal: already in a mode".to_string(),
+    ]);
+    (
+      vec![replay_journal_example],
+      vec![replayed_response, replay_err_response],
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Only allowed from the main menu.  The commands are performed as if \
+       just sent, so clients get the usual events, and they are journaled \
+       again.".to_string(),
+      "A replayed command that fails is skipped, and its messages are \
+       given in `skipped`.  The replay fails outright only if the journal \
+       cannot be read or its mode cannot be entered.".to_string(),
+    ]
+  }
+}
@@ -1,34 +1,91 @@
 use serde;
-use crate::data::ruleset::RulesetEntry;
-use super::mode::{
-  define_rules::DefineRulesSubcmdResponse,
-  create_world::CreateWorldSubcmdResponse,
-  GameModeInfo
+use crate::data::{
+  map::WorldDescriptor,
+  Diagnostic,
+  DiagnosticCode,
+  ruleset::{ RulesetBundle, RulesetEntry, RulesetValidation },
+  HeightmapEntry,
+  ValidationMessage,
+};
+use super::{
+  get_session_info_cmd::SessionInfo,
+  get_journal_info_cmd::JournalInfo,
+  replay_journal_cmd::JournalReplay,
+  hello_cmd::ServerInfo,
+  mode::{
+    define_rules::DefineRulesSubcmdResponse,
+    create_world::CreateWorldSubcmdResponse,
+    view_world::ViewWorldSubcmdResponse,
+    GameModeInfo
+  },
 };
 
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) enum ResponseEnvelope {
   Ok {},
   Failed(FailedResponse),
   InMode(GameModeInfo),
   InMainMenuMode {},
   RulesetList(Vec<RulesetEntry>),
+  RulesetBundle(RulesetBundle),
+  ImportedRuleset(RulesetEntry),
+  InvalidRuleset(RulesetValidation),
+  WorldList(Vec<WorldDescriptor>),
+  HeightmapList(Vec<HeightmapEntry>),
+  Heightmap(HeightmapEntry),
+  SessionInfo(SessionInfo),
+  ServerInfo(ServerInfo),
+  JournalInfo(JournalInfo),
+  JournalReplayed(JournalReplay),
   DefineRulesSubcmd(DefineRulesSubcmdResponse),
   CreateWorldSubcmd(CreateWorldSubcmdResponse),
+  ViewWorldSubcmd(ViewWorldSubcmdResponse),
 }
 
+/**
+ * A response as sent to a client, carrying the id of the command it
+ * answers, if the command had one.
+ */
 #[derive(Debug, Clone)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct ResponseFrame {
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) id: Option<u64>,
+
+  #[serde(flatten)]
+  pub(crate) response: ResponseEnvelope,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct FailedResponse {
   messages: Vec<String>,
+
+  // The messages as diagnostics with the code `Failed`, so that clients
+  // can treat every error alike.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  diagnostics: Vec<Diagnostic>,
 }
 impl FailedResponse {
   pub(crate) fn new<S: AsRef<str>>(message: S) -> FailedResponse {
-    FailedResponse { messages: vec![message.as_ref().to_owned()] }
+    FailedResponse::new_vec(vec![message.as_ref().to_owned()])
   }
 
   pub(crate) fn new_vec(messages: Vec<String>) -> FailedResponse {
-    FailedResponse { messages }
+    let diagnostics = messages.iter().map(|message| {
+      ValidationMessage::new(DiagnosticCode::Failed, message.clone())
+        .to_diagnostic("")
+    }).collect();
+    FailedResponse { messages, diagnostics }
+  }
+
+  pub(crate) fn messages(&self) -> &[String] {
+    &self.messages
   }
 }
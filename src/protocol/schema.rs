@@ -0,0 +1,341 @@
+use schemars::{ gen::SchemaSettings, JsonSchema };
+use serde_json::{ self, Map, Value };
+use super::{
+  command::{ CommandEnvelope, CommandFrame },
+  documentation::{ get_event_docs, get_protocol_docs },
+  event::EventFrame,
+  response::{ ResponseEnvelope, ResponseFrame },
+};
+
+/**
+ * A JSON Schema document for one of the protocol's message types.  The
+ * subcommands and payloads it reaches are under its `definitions`.
+ */
+pub struct ProtocolSchemaDocument {
+  pub name: String,
+  pub schema: Value,
+}
+
+/**
+ * Get the JSON Schema documents of the protocol: the command, response
+ * and event frames as sent over the wire, and the bare envelopes, which
+ * are what the protocol examples show.
+ */
+pub fn get_protocol_schemas() -> Vec<ProtocolSchemaDocument> {
+  vec![
+    make_schema::<CommandFrame>("CommandFrame"),
+    make_schema::<ResponseFrame>("ResponseFrame"),
+    make_schema::<EventFrame>("EventFrame"),
+    make_schema::<CommandEnvelope>("CommandEnvelope"),
+    make_schema::<ResponseEnvelope>("ResponseEnvelope"),
+  ]
+}
+
+/**
+ * Check every protocol example against the schema of its type, returning
+ * a message for each way in which one does not match.
+ */
+pub fn check_protocol_examples() -> Vec<String> {
+  let schemas = get_protocol_schemas();
+  let schema_named = |name: &str| {
+    &schemas.iter().find(|document| document.name == name).unwrap().schema
+  };
+
+  let mut examples = Vec::new();
+  for category in get_protocol_docs() {
+    for command in category.commands {
+      for example in command.command_examples {
+        examples.push((command.name.clone(), "CommandEnvelope", example));
+      }
+      for example in command.response_examples {
+        examples.push((command.name.clone(), "ResponseEnvelope", example));
+      }
+    }
+  }
+  for event in get_event_docs() {
+    examples.push((event.name, "EventFrame", event.example));
+  }
+
+  let mut problems = Vec::new();
+  for (name, schema_name, example) in examples {
+    let value = match serde_json::from_str::<Value>(&example) {
+      Ok(value) => value,
+      Err(err) => {
+        problems.push(format!("{}: example is not JSON: {}", name, err));
+        continue;
+      },
+    };
+    let violations = schema_violations(&value, schema_named(schema_name));
+    problems.extend(violations.into_iter().map(|violation| {
+      format!("{} ({}): {}", name, schema_name, violation)
+    }));
+  }
+  problems
+}
+
+/**
+ * Check a value against a schema document, returning a message for each
+ * way in which it does not match.  Only the keywords that schemars emits
+ * are understood; integer bounds are checked through `format`.
+ */
+pub fn schema_violations(value: &Value, schema: &Value) -> Vec<String> {
+  let mut violations = Vec::new();
+  check_value(value, schema, schema, "", &mut violations);
+  violations
+}
+
+fn make_schema<T: JsonSchema>(name: &str) -> ProtocolSchemaDocument {
+  let generator = SchemaSettings::draft07().into_generator();
+  let schema = generator.into_root_schema_for::<T>();
+  let mut schema = serde_json::to_value(schema).unwrap();
+  merge_flattened_properties(&mut schema);
+  ProtocolSchemaDocument { name: name.to_string(), schema }
+}
+
+/**
+ * A frame flattens its envelope beside its own fields, but the variants
+ * of the envelope forbid other properties, which would reject the `id`.
+ * Copy the frame's own properties into each variant.
+ */
+fn merge_flattened_properties(schema: &mut Value) {
+  let Some(object) = schema.as_object_mut() else { return };
+  let Some(properties) = object.get("properties").cloned() else { return };
+  let Some(Value::Array(variants)) = object.get_mut("oneOf") else { return };
+  for variant in variants {
+    if variant.get("additionalProperties") != Some(&Value::Bool(false)) {
+      continue;
+    }
+    let Some(Value::Object(variant_properties)) =
+      variant.get_mut("properties") else { continue };
+    for (name, property) in properties.as_object().unwrap() {
+      variant_properties.insert(name.clone(), property.clone());
+    }
+  }
+}
+
+fn check_value(
+  value: &Value,
+  schema: &Value,
+  root: &Value,
+  path: &str,
+  out: &mut Vec<String>,
+) {
+  let schema = match schema {
+    Value::Bool(true) => return,
+    Value::Bool(false) => {
+      out.push(format!("{}: no value is allowed here", path_or_root(path)));
+      return;
+    },
+    Value::Object(schema) => schema,
+    _ => return,
+  };
+
+  if let Some(Value::String(reference)) = schema.get("$ref") {
+    match resolve_ref(reference, root) {
+      Some(target) => check_value(value, target, root, path, out),
+      None => out.push(format!("{}: unknown $ref {}", path, reference)),
+    }
+  }
+
+  if let Some(expected) = schema.get("type") {
+    if !type_matches(value, expected) {
+      out.push(format!("{}: expected {}, found {}",
+        path_or_root(path), expected, value));
+      return;
+    }
+  }
+  if let Some(Value::Array(allowed)) = schema.get("enum") {
+    if !allowed.contains(value) {
+      out.push(format!("{}: {} is not one of {}",
+        path_or_root(path), value, Value::Array(allowed.clone())));
+    }
+  }
+  if let Some(Value::String(format)) = schema.get("format") {
+    check_format(value, format, path, out);
+  }
+
+  if let Value::Object(members) = value {
+    check_object(members, schema, root, path, out);
+  }
+  if let Value::Array(elements) = value {
+    check_array(elements, schema, root, path, out);
+  }
+
+  if let Some(Value::Array(subschemas)) = schema.get("allOf") {
+    for subschema in subschemas {
+      check_value(value, subschema, root, path, out);
+    }
+  }
+  if let Some(Value::Array(subschemas)) = schema.get("anyOf") {
+    if count_matches(value, subschemas, root, path) == 0 {
+      report_no_match(value, subschemas, root, path, out);
+    }
+  }
+  if let Some(Value::Array(subschemas)) = schema.get("oneOf") {
+    match count_matches(value, subschemas, root, path) {
+      0 => report_no_match(value, subschemas, root, path, out),
+      1 => {},
+      matches => out.push(format!("{}: matches {} of the {} alternatives",
+        path_or_root(path), matches, subschemas.len())),
+    }
+  }
+}
+
+fn check_object(
+  members: &Map<String, Value>,
+  schema: &Map<String, Value>,
+  root: &Value,
+  path: &str,
+  out: &mut Vec<String>,
+) {
+  let empty = Map::new();
+  let properties = match schema.get("properties") {
+    Some(Value::Object(properties)) => properties,
+    _ => &empty,
+  };
+  if let Some(Value::Array(required)) = schema.get("required") {
+    for name in required.iter().filter_map(Value::as_str) {
+      if !members.contains_key(name) {
+        out.push(format!("{}: missing `{}`", path_or_root(path), name));
+      }
+    }
+  }
+  for (name, member) in members {
+    let member_path = format!("{}/{}", path, name);
+    match (properties.get(name), schema.get("additionalProperties")) {
+      (Some(property), _) =>
+        check_value(member, property, root, &member_path, out),
+      (None, Some(additional)) =>
+        check_value(member, additional, root, &member_path, out),
+      (None, None) => {},
+    }
+  }
+}
+
+fn check_array(
+  elements: &[Value],
+  schema: &Map<String, Value>,
+  root: &Value,
+  path: &str,
+  out: &mut Vec<String>,
+) {
+  match schema.get("items") {
+    Some(Value::Array(items)) => {
+      for (index, (element, item)) in elements.iter().zip(items).enumerate() {
+        check_value(element, item, root, &format!("{}/{}", path, index), out);
+      }
+    },
+    Some(item) => {
+      for (index, element) in elements.iter().enumerate() {
+        check_value(element, item, root, &format!("{}/{}", path, index), out);
+      }
+    },
+    None => {},
+  }
+  let len = elements.len() as u64;
+  if let Some(min_items) = schema.get("minItems").and_then(Value::as_u64) {
+    if len < min_items {
+      out.push(format!("{}: fewer than {} items", path_or_root(path), min_items));
+    }
+  }
+  if let Some(max_items) = schema.get("maxItems").and_then(Value::as_u64) {
+    if len > max_items {
+      out.push(format!("{}: more than {} items", path_or_root(path), max_items));
+    }
+  }
+}
+
+fn check_format(value: &Value, format: &str, path: &str, out: &mut Vec<String>) {
+  let range = match format {
+    "uint8" => (0, u8::MAX as i128),
+    "uint16" => (0, u16::MAX as i128),
+    "uint32" => (0, u32::MAX as i128),
+    "uint64" | "uint" => (0, u64::MAX as i128),
+    "int32" => (i32::MIN as i128, i32::MAX as i128),
+    "int64" => (i64::MIN as i128, i64::MAX as i128),
+    _ => return,
+  };
+  let number = value.as_i64().map(i128::from)
+    .or_else(|| value.as_u64().map(i128::from));
+  if let Some(number) = number {
+    if number < range.0 || number > range.1 {
+      out.push(format!("{}: {} does not fit {}", path_or_root(path), value, format));
+    }
+  }
+}
+
+fn count_matches(value: &Value, subschemas: &[Value], root: &Value, path: &str)
+  -> usize
+{
+  subschemas.iter().filter(|subschema| {
+    let mut violations = Vec::new();
+    check_value(value, subschema, root, path, &mut violations);
+    violations.is_empty()
+  }).count()
+}
+
+/**
+ * Report a value that matches no alternative.  If just one of them has
+ * the value's type and tag, say why the value does not match that one.
+ */
+fn report_no_match(
+  value: &Value,
+  subschemas: &[Value],
+  root: &Value,
+  path: &str,
+  out: &mut Vec<String>,
+) {
+  let tagged = subschemas.iter().filter(|subschema| {
+    let subschema = resolve_refs(subschema, root);
+    let has_type = subschema.get("type")
+      .is_none_or(|expected| type_matches(value, expected));
+    let tag_matches = match (value, subschema.get("required")) {
+      (Value::Object(members), Some(Value::Array(required))) =>
+        required.iter().filter_map(Value::as_str)
+          .all(|name| members.contains_key(name)),
+      _ => true,
+    };
+    has_type && tag_matches
+  }).collect::<Vec<_>>();
+  if let [subschema] = tagged.as_slice() {
+    check_value(value, subschema, root, path, out);
+  } else {
+    out.push(format!("{}: matches none of the {} alternatives",
+      path_or_root(path), subschemas.len()));
+  }
+}
+
+fn type_matches(value: &Value, expected: &Value) -> bool {
+  match expected {
+    Value::String(name) => match name.as_str() {
+      "null" => value.is_null(),
+      "boolean" => value.is_boolean(),
+      "object" => value.is_object(),
+      "array" => value.is_array(),
+      "string" => value.is_string(),
+      "number" => value.is_number(),
+      "integer" => value.is_i64() || value.is_u64(),
+      _ => false,
+    },
+    Value::Array(names) => names.iter().any(|name| type_matches(value, name)),
+    _ => false,
+  }
+}
+
+fn resolve_ref<'a>(reference: &str, root: &'a Value) -> Option<&'a Value> {
+  root.pointer(reference.strip_prefix('#')?)
+}
+
+fn resolve_refs<'a>(schema: &'a Value, root: &'a Value) -> &'a Value {
+  match schema.get("$ref").and_then(Value::as_str) {
+    Some(reference) => match resolve_ref(reference, root) {
+      Some(target) => resolve_refs(target, root),
+      None => schema,
+    },
+    None => schema,
+  }
+}
+
+fn path_or_root(path: &str) -> &str {
+  if path.is_empty() { "/" } else { path }
+}
@@ -0,0 +1,70 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::ResponseEnvelope,
+};
+
+/**
+ * How a connection is sent map data.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) enum MapDataEncoding {
+  #[default]
+  Json,
+
+  // Binary WebSocket frames; see `map_data_frame`.
+  Binary,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct SetMapDataEncodingCmd {
+  pub(crate) encoding: MapDataEncoding,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum SetMapDataEncodingRsp {
+  Ok,
+}
+impl Command for SetMapDataEncodingCmd {
+  type Response = SetMapDataEncodingRsp;
+  fn name() -> &'static str {
+    "SetMapDataEncoding"
+  }
+  fn description() -> &'static str {
+    "Choose whether this connection is sent map data as JSON or binary."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::SetMapDataEncoding(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      SetMapDataEncodingRsp::Ok => ResponseEnvelope::Ok {},
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let set_map_data_encoding_example = SetMapDataEncodingCmd {
+      encoding: MapDataEncoding::Binary,
+    };
+    (
+      vec![set_map_data_encoding_example],
+      vec![SetMapDataEncodingRsp::Ok],
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "The encoding applies to the `GetMapData` commands sent after this \
+       one, in both the `CreateWorld` and `ViewWorld` modes.  It starts as \
+       `Json`.".to_string(),
+      "With `Binary`, map data is sent as a binary frame instead of a \
+       `MapData` response, unless `decode` was asked for, in which case it \
+       stays JSON.  Failures are still JSON.".to_string(),
+      super::map_data_frame::MAP_DATA_FRAME_DOC.to_string(),
+    ]
+  }
+}
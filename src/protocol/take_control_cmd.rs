@@ -0,0 +1,65 @@
+use serde;
+use super::{
+  command::{ Command, CommandEnvelope },
+  get_session_info_cmd::{ SessionInfo, SessionRole },
+  response::{ FailedResponse, ResponseEnvelope },
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct TakeControlCmd {}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) enum TakeControlRsp {
+  SessionInfo(SessionInfo),
+  Failed(Vec<String>),
+}
+impl Command for TakeControlCmd {
+  type Response = TakeControlRsp;
+  fn name() -> &'static str {
+    "TakeControl"
+  }
+  fn description() -> &'static str {
+    "Make this session the controlling session, if none is."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::TakeControl(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    match response {
+      TakeControlRsp::SessionInfo(info) => ResponseEnvelope::SessionInfo(info),
+      TakeControlRsp::Failed(messages) =>
+        ResponseEnvelope::Failed(FailedResponse::new_vec(messages)),
+    }
+  }
+
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let take_control_example = TakeControlCmd {};
+
+    let take_control_ok_response = TakeControlRsp::SessionInfo(SessionInfo {
+      session_id: 2,
+      role: SessionRole::Controller,
+      controller_id: Some(2),
+      session_ids: vec![2, 3],
+    });
+    let take_control_err_response = TakeControlRsp::Failed(vec![
+      "Session 1 is in control.  It must hand off control first."
+        .to_string(),
+    ]);
+    (
+      vec![take_control_example],
+      vec![
+        take_control_ok_response,
+        take_control_err_response,
+      ]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Control is free once the controlling session disconnects.  Taking \
+       control while already in control succeeds.".to_string(),
+    ]
+  }
+}
@@ -0,0 +1,227 @@
+use std::collections::BTreeMap;
+use serde_json::{ Map, Value };
+use super::schema::{ get_protocol_schemas, ProtocolSchemaDocument };
+
+const HEADER: &str =
+  "// Generated by `protocol_typescript` from the Rust protocol types.\n\
+   // Do not edit; regenerate it instead.\n";
+
+/**
+ * Get TypeScript declarations of the protocol's types, as the text of a
+ * module.  They are derived from the protocol's JSON Schema, so they
+ * follow the serde renames, leave out what `skip_serializing_if` may
+ * skip, and give externally tagged enums as unions of one-member objects.
+ */
+pub fn get_protocol_typescript() -> String {
+  let documents = get_protocol_schemas();
+
+  let mut definitions = BTreeMap::new();
+  for document in &documents {
+    if let Some(Value::Object(document_definitions)) =
+      document.schema.get("definitions")
+    {
+      for (name, schema) in document_definitions {
+        definitions.insert(name.clone(), schema.clone());
+      }
+    }
+  }
+
+  let mut out = String::from(HEADER);
+  for (name, schema) in &definitions {
+    out.push('\n');
+    out.push_str(&declaration(name, schema));
+  }
+  for document in &documents {
+    if definitions.contains_key(&document.name) {
+      continue;
+    }
+    out.push('\n');
+    out.push_str(&document_declaration(document, &documents));
+  }
+  out
+}
+
+/**
+ * A frame's schema flattens its envelope beside its own fields, and
+ * repeats those fields in every variant.  Declare it as its fields joined
+ * to the envelope's type instead.
+ */
+fn document_declaration(
+  document: &ProtocolSchemaDocument,
+  documents: &[ProtocolSchemaDocument],
+) -> String {
+  let schema = &document.schema;
+  let envelope_name = document.name.replace("Frame", "Envelope");
+  let is_flattened_frame = schema.get("oneOf").is_some()
+    && schema.get("properties").is_some()
+    && documents.iter().any(|other| other.name == envelope_name);
+  if !is_flattened_frame {
+    return declaration(&document.name, schema);
+  }
+
+  let Value::Object(object) = schema else { unreachable!() };
+  let fields = Map::from_iter(object.iter()
+    .filter(|(key, _)| !matches!(key.as_str(), "oneOf" | "description"))
+    .map(|(key, value)| (key.clone(), value.clone())));
+  format!("{}export type {} = {} & {};\n",
+    doc_comment(schema, ""),
+    document.name,
+    ts_type(&Value::Object(fields), ""),
+    envelope_name)
+}
+
+fn declaration(name: &str, schema: &Value) -> String {
+  let alternatives = union_members(schema);
+  let body = if alternatives.len() > 1 {
+    alternatives.iter()
+      .map(|alternative| format!("\n  | {}", ts_type(alternative, "  ")))
+      .collect::<String>()
+  } else {
+    format!(" {}", ts_type(schema, ""))
+  };
+  format!("{}export type {} ={};\n", doc_comment(schema, ""), name, body)
+}
+
+/** The alternatives of a schema that is just a union, or else nothing. */
+fn union_members(schema: &Value) -> Vec<Value> {
+  let Value::Object(object) = schema else { return Vec::new() };
+  let has_only = |keyword: &str| object.keys().all(|key| {
+    matches!(key.as_str(), "description" | "title" | "$schema" | "definitions")
+      || key == keyword
+  });
+  for keyword in ["oneOf", "anyOf"] {
+    if let Some(Value::Array(alternatives)) = object.get(keyword) {
+      if has_only(keyword) {
+        return alternatives.iter().flat_map(|alternative| {
+          // Unit variants are grouped as one string enum; list them apart.
+          match alternative.get("enum") {
+            Some(Value::Array(values))
+              if alternative.get("type") == Some(&Value::from("string")) =>
+                values.iter().map(|value| {
+                  let mut single = Map::new();
+                  single.insert("const".to_string(), value.clone());
+                  Value::Object(single)
+                }).collect(),
+            _ => vec![alternative.clone()],
+          }
+        }).collect();
+      }
+    }
+  }
+  Vec::new()
+}
+
+fn ts_type(schema: &Value, indent: &str) -> String {
+  let object = match schema {
+    Value::Bool(true) => return "unknown".to_string(),
+    Value::Bool(false) => return "never".to_string(),
+    Value::Object(object) => object,
+    _ => return "unknown".to_string(),
+  };
+
+  if let Some(Value::String(reference)) = object.get("$ref") {
+    return reference.rsplit('/').next().unwrap().to_string();
+  }
+  if let Some(value) = object.get("const") {
+    return value.to_string();
+  }
+  if let Some(Value::Array(values)) = object.get("enum") {
+    return values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | ");
+  }
+  if let Some(Value::Array(subschemas)) = object.get("allOf") {
+    return join_types(subschemas, " & ", indent);
+  }
+  for keyword in ["oneOf", "anyOf"] {
+    if let Some(Value::Array(subschemas)) = object.get(keyword) {
+      return join_types(subschemas, " | ", indent);
+    }
+  }
+
+  match object.get("type") {
+    Some(Value::String(type_name)) => ts_type_named(type_name, object, indent),
+    Some(Value::Array(type_names)) => type_names.iter()
+      .filter_map(Value::as_str)
+      .map(|type_name| ts_type_named(type_name, object, indent))
+      .collect::<Vec<_>>()
+      .join(" | "),
+    _ => "unknown".to_string(),
+  }
+}
+
+fn ts_type_named(type_name: &str, object: &Map<String, Value>, indent: &str)
+  -> String
+{
+  match type_name {
+    "string" => "string".to_string(),
+    "integer" | "number" => "number".to_string(),
+    "boolean" => "boolean".to_string(),
+    "null" => "null".to_string(),
+    "array" => match object.get("items") {
+      Some(Value::Array(items)) => format!("[{}]", join_types(items, ", ", indent)),
+      Some(item) => {
+        let item = ts_type(item, indent);
+        if item.contains(' ') { format!("({})[]", item) } else { format!("{}[]", item) }
+      },
+      None => "unknown[]".to_string(),
+    },
+    "object" => ts_object(object, indent),
+    _ => "unknown".to_string(),
+  }
+}
+
+fn ts_object(object: &Map<String, Value>, indent: &str) -> String {
+  let properties = match object.get("properties") {
+    Some(Value::Object(properties)) if !properties.is_empty() => properties,
+    _ => return match object.get("additionalProperties") {
+      Some(additional @ Value::Object(_)) =>
+        format!("{{ [key: string]: {} }}", ts_type(additional, indent)),
+      _ => "{}".to_string(),
+    },
+  };
+  let required = match object.get("required") {
+    Some(Value::Array(required)) =>
+      required.iter().filter_map(Value::as_str).collect::<Vec<_>>(),
+    _ => Vec::new(),
+  };
+
+  let inner = format!("{}  ", indent);
+  let mut out = String::from("{\n");
+  for (name, property) in properties {
+    let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+    out.push_str(&doc_comment(property, &inner));
+    out.push_str(&format!("{}{}{}: {},\n",
+      inner, property_name(name), optional, ts_type(property, &inner)));
+  }
+  out.push_str(indent);
+  out.push('}');
+  out
+}
+
+fn join_types(subschemas: &[Value], separator: &str, indent: &str) -> String {
+  subschemas.iter()
+    .map(|subschema| ts_type(subschema, indent))
+    .collect::<Vec<_>>()
+    .join(separator)
+}
+
+fn property_name(name: &str) -> String {
+  let is_identifier = name.chars().next().is_some_and(|c| !c.is_ascii_digit())
+    && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+  if is_identifier { name.to_string() } else { Value::from(name).to_string() }
+}
+
+fn doc_comment(schema: &Value, indent: &str) -> String {
+  let Some(description) = schema.get("description").and_then(Value::as_str)
+    else { return String::new() };
+  let mut out = format!("{}/**\n", indent);
+  for line in description.lines() {
+    let line = line.replace("*/", "*\\/");
+    if line.is_empty() {
+      out.push_str(&format!("{} *\n", indent));
+    } else {
+      out.push_str(&format!("{} * {}\n", indent, line));
+    }
+  }
+  out.push_str(&format!("{} */\n", indent));
+  out
+}
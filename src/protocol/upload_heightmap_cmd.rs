@@ -0,0 +1,86 @@
+use serde;
+use crate::data::{
+  map::WorldDims,
+  HeightmapEntry,
+  HeightmapFormat,
+};
+use super::{
+  command::{ Command, CommandEnvelope },
+  response::ResponseEnvelope,
+};
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
+pub(crate) struct UploadHeightmapCmd {
+  pub(crate) name: String,
+  pub(crate) format: HeightmapFormat,
+
+  // Required for raw formats, which carry no header.
+  #[serde(default)]
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub(crate) dims: Option<WorldDims>,
+
+  // The file contents, base64 encoded.
+  pub(crate) data: String,
+}
+
+#[derive(Debug, Clone)]
+#[derive(serde::Serialize, serde::Deserialize)]
+pub(crate) struct UploadHeightmapRsp {
+  heightmap: HeightmapEntry,
+}
+
+impl Command for UploadHeightmapCmd {
+  type Response = UploadHeightmapRsp;
+  fn name() -> &'static str {
+    "UploadHeightmap"
+  }
+  fn description() -> &'static str {
+    "Upload a heightmap to use in place of procedural noise."
+  }
+  fn to_queue_command(&self) -> CommandEnvelope {
+    CommandEnvelope::UploadHeightmap(self.clone())
+  }
+  fn embed_response(response: Self::Response) -> ResponseEnvelope {
+    ResponseEnvelope::Heightmap(response.heightmap)
+  }
+  fn protocol_examples() -> (Vec<Self>, Vec<Self::Response>) {
+    let upload_pgm_example = UploadHeightmapCmd {
+      name: "Sketched Continent".to_string(),
+      format: HeightmapFormat::Pgm,
+      dims: None,
+      data: "UDUKMiAyCjI1NQoAQIDA".to_string(),
+    };
+    let upload_raw_example = UploadHeightmapCmd {
+      name: "Island".to_string(),
+      format: HeightmapFormat::Raw16,
+      dims: Some(WorldDims::new(2, 1)),
+      data: "AAD//w==".to_string(),
+    };
+
+    let upload_response = UploadHeightmapRsp {
+      heightmap: HeightmapEntry {
+        name: "Sketched Continent".to_string(),
+        dims: WorldDims::new(2, 2),
+      },
+    };
+
+    (
+      vec![upload_pgm_example, upload_raw_example],
+      vec![upload_response]
+    )
+  }
+  fn protocol_notes() -> Vec<String> {
+    vec![
+      "Uploading with an existing name replaces that heightmap.".to_string(),
+      "Reference the heightmap by name in a world descriptor's `heightmap` \
+       field; it is resampled to the world's dimensions at RandGen."
+        .to_string(),
+      "Unlike procedural noise, a heightmap is not faded towards the world's \
+       border: its edges are kept as drawn.".to_string(),
+      "A heightmap may have at most the columns and rows of `maxDims` in \
+       the Hello response's world limits.".to_string(),
+    ]
+  }
+}
@@ -0,0 +1,158 @@
+use super::{
+  bitcode::{ self, ControlFlow, OperationKind, SrcWord },
+  register_file::{ self, ShadyRegisterFile },
+  ShadyProgram,
+};
+
+/**
+ * A CPU implementation of the shady VM, for running programs outside of a
+ * shader: to test them, and to check what the GPU computes.
+ *
+ * Execution starts at the first instruction and ends when the program
+ * runs off its end, jumps to itself, or returns from the outermost call.
+ */
+pub(crate) struct ShadyInterpreter<'a> {
+  program: &'a ShadyProgram,
+  max_steps: usize,
+}
+impl<'a> ShadyInterpreter<'a> {
+  const MAX_STEPS: usize = 100_000;
+
+  /** Same as the call stack of the shader VM. */
+  const MAX_CALL_DEPTH: usize = 4;
+
+  /** All condition flags set, as in a freshly started VM. */
+  const INITIAL_FLAGS: u32 = 0x7;
+
+  pub(crate) fn new(program: &'a ShadyProgram) -> Self {
+    ShadyInterpreter { program, max_steps: Self::MAX_STEPS }
+  }
+
+  /**
+   * Run the program on `registers`.  Returns the number of instructions
+   * executed.
+   */
+  pub(crate) fn run(&self, registers: &mut ShadyRegisterFile)
+    -> Result<usize, String>
+  {
+    let instrs = &self.program.bitcode;
+    let mut pc = 0_usize;
+    let mut flags = Self::INITIAL_FLAGS;
+    let mut call_stack = Vec::<usize>::new();
+    let mut steps = 0_usize;
+
+    registers.write_reg(register_file::SHADY_REG_VMID, 0);
+    while pc < instrs.len() {
+      if steps >= self.max_steps {
+        return Err(format!(
+          "The program did not finish within {} steps.", self.max_steps
+        ));
+      }
+      steps += 1;
+
+      let instr = &instrs[pc];
+      let op = &instr.op_word;
+      registers.write_reg(register_file::SHADY_REG_PC, pc as i32);
+      if (op.cond as u32) & flags == 0 {
+        pc += 1;
+        continue;
+      }
+
+      let src1 = Self::read_src(registers, instr.src1_word, op.ind_src1, op.shift16_src2);
+      let mut src2 = Self::read_src(registers, instr.src2_word, op.ind_src2, false);
+      if op.shift16_src2 {
+        src2 = src2.wrapping_shl(16);
+      }
+      let mut result = Self::compute(op.kind, src1, src2)
+        .wrapping_add(instr.dst_word.bump as i32);
+      if instr.dst_word.negate {
+        result = result.wrapping_neg();
+      }
+      if op.set_flags {
+        flags = match result {
+          0 => bitcode::Condition::Equal as u32,
+          r if r < 0 => bitcode::Condition::Less as u32,
+          _ => bitcode::Condition::Greater as u32,
+        };
+      }
+
+      let mut dst = instr.dst_word.reg;
+      if op.ind_dst {
+        dst = (registers.read_reg(dst) & 0xFF) as u8;
+      }
+      let target = match op.cflow {
+        ControlFlow::Ret => match call_stack.pop() {
+          Some(return_pc) => return_pc,
+          None => return Ok(steps),
+        },
+        ControlFlow::Call => {
+          if call_stack.len() >= Self::MAX_CALL_DEPTH {
+            return Err(format!(
+              "Call stack overflow at instruction {}.", pc
+            ));
+          }
+          call_stack.push(pc + 1);
+          Self::jump_target(pc, result)?
+        },
+        ControlFlow::Write => Self::jump_target(pc, result)?,
+        ControlFlow::None if dst == register_file::SHADY_REG_PC =>
+          Self::jump_target(pc, result)?,
+        ControlFlow::None => {
+          registers.write_reg(dst, result);
+          pc + 1
+        },
+      };
+      if target == pc && op.cflow != ControlFlow::Call {
+        // A jump to self terminates the program.
+        return Ok(steps);
+      }
+      pc = target;
+    }
+    Ok(steps)
+  }
+
+  fn read_src(
+    registers: &ShadyRegisterFile,
+    src: SrcWord,
+    indirect: bool,
+    zero_extend: bool,
+  ) -> i32 {
+    match src {
+      // The low half of a 32-bit immediate load must not be sign-extended.
+      SrcWord::Immediate { value } if zero_extend => value as u16 as i32,
+      SrcWord::Immediate { value } => value as i32,
+      SrcWord::Register { reg, negate, shift } => {
+        let mut value = registers.read_reg(reg);
+        if indirect {
+          value = registers.read_reg((value & 0xFF) as u8);
+        }
+        value = if shift >= 0 {
+          value.wrapping_shl(shift as u32)
+        } else {
+          value >> (-(shift as i32)).min(31)
+        };
+        if negate { value.wrapping_neg() } else { value }
+      },
+    }
+  }
+
+  /** Integer division and modulus by zero follow WGSL. */
+  fn compute(kind: OperationKind, a: i32, b: i32) -> i32 {
+    match kind {
+      OperationKind::Add => a.wrapping_add(b),
+      OperationKind::Mul => a.wrapping_mul(b),
+      OperationKind::Div => if b == 0 { a } else { a.wrapping_div(b) },
+      OperationKind::Mod => if b == 0 { 0 } else { a.wrapping_rem(b) },
+      OperationKind::BitAnd => a & b,
+      OperationKind::BitOr => a | b,
+      OperationKind::BitXor => a ^ b,
+      OperationKind::Max => a.max(b),
+    }
+  }
+
+  fn jump_target(pc: usize, target: i32) -> Result<usize, String> {
+    usize::try_from(target).map_err(|_| {
+      format!("Jump to invalid address {} at instruction {}.", target, pc)
+    })
+  }
+}
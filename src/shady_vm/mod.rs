@@ -2,13 +2,21 @@ mod shasm;
 mod assembler;
 mod register_file;
 mod program;
+mod interpreter;
 
 pub(crate) mod bytecode;
 pub(crate) mod bitcode;
 
 pub(crate) use self::{
-  register_file::{ ShadyRegisterFile, ShadyRegister, SHADY_REG_COUNT },
+  register_file::{
+    ShadyRegisterFile,
+    ShadyRegister,
+    SHADY_FIRST_INPUT_REG,
+    SHADY_REG_LAST_GP,
+    SHADY_REG_COUNT,
+  },
   assembler::ShadyAssembler,
+  interpreter::ShadyInterpreter,
   program::{ ShadyProgram, ShadyProgramGpuBuffer, ShadyProgramIndex },
   shasm::{
     shasm_instr_parser,
@@ -17,5 +25,6 @@ pub(crate) use self::{
     ShasmParseError,
     ShasmProgram,
     ShasmProgramValidation,
+    ShasmSymbols,
   },
 };
@@ -2,6 +2,7 @@ use crate::cog::CogBufferType;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[derive(serde::Serialize, serde::Deserialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct ShadyRegister(u8);
 impl ShadyRegister {
   pub(crate) const fn new(val: u8) -> Self {
@@ -21,9 +22,17 @@ pub(crate) struct ShadyRegisterFile {
   regs: [i32; SHADY_REG_COUNT]
 }
 impl ShadyRegisterFile {
+  pub(crate) fn new_zeroed() -> Self {
+    ShadyRegisterFile { regs: [0; SHADY_REG_COUNT] }
+  }
+
   pub(crate) fn read_reg(&self, reg: u8) -> i32 {
     self.regs[reg as usize]
   }
+
+  pub(crate) fn write_reg(&mut self, reg: u8, value: i32) {
+    self.regs[reg as usize] = value;
+  }
 }
 impl CogBufferType for ShadyRegisterFile {
   type GpuType = [i32; SHADY_REG_COUNT];
@@ -44,3 +53,11 @@ pub(crate) const SHADY_REG_COUNT: usize = 256;
 pub(crate) const SHADY_REG_LAST_GP: u8 = 239;
 pub(crate) const SHADY_REG_VMID: u8 = 240;
 pub(crate) const SHADY_REG_PC: u8 = 241;
+
+/**
+ * The VM's input block, which a dispatch fills before programs run.  In
+ * `shady_vm.wgsl` it follows the 64 output registers at r56-r119; only
+ * its first registers are used, so that they stay below
+ * `SHADY_REG_LAST_GP`.
+ */
+pub(crate) const SHADY_FIRST_INPUT_REG: u8 = 120;
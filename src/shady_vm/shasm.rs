@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use chumsky::{error::EmptyErr, Parser};
 use regex::Regex;
+use crate::data::{ Diagnose, Diagnostic, DiagnosticCode, DiagnosticSeverity };
 use super::{
   bitcode::{
     self,
@@ -20,6 +21,7 @@ use super::{
 
 #[derive(Debug, Clone)]
 #[derive(serde::Deserialize, serde::Serialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct ShasmProgram {
   #[serde(rename = "programText")]
   pub(crate) program_text: String,
@@ -31,21 +33,54 @@ impl ShasmProgram {
   }
 
   pub(crate) fn to_validated(text: &str) -> Result<ShasmProgram, ShasmProgramValidation> {
-    match shasm_program_parser(text) {
+    Self::to_validated_with_symbols(text, &NoShasmSymbols)
+  }
+
+  pub(crate) fn to_validated_with_symbols(
+    text: &str,
+    symbols: &dyn ShasmSymbols,
+  ) -> Result<ShasmProgram, ShasmProgramValidation> {
+    match shasm_program_parser(text, symbols) {
       Ok(_program) => Ok(ShasmProgram { program_text: text.to_string() }),
       Err(errors) => Err(ShasmProgramValidation { errors }),
     }
   }
 
-  pub(crate) fn parse_shady_program(&self)
+  pub(crate) fn parse_shady_program(&self, symbols: &dyn ShasmSymbols)
     -> Result<ShadyProgram, Vec<ShasmParseError>>
   {
-    shasm_program_parser(&self.program_text)
+    shasm_program_parser(&self.program_text, symbols)
+  }
+}
+
+/**
+ * Named values that a program can use in place of integer literals.
+ *
+ * A symbol is written `#name` or `#name(argument)`, where the name is a
+ * dotted path of identifiers.  It is replaced by the integer it resolves
+ * to before the instruction is parsed, so it may appear anywhere a
+ * literal may.
+ */
+pub(crate) trait ShasmSymbols {
+  fn resolve_symbol(&self, name: &str, argument: Option<&str>)
+    -> Result<i64, String>;
+}
+
+/**
+ * The symbols of a program with no context: there are none.
+ */
+pub(crate) struct NoShasmSymbols;
+impl ShasmSymbols for NoShasmSymbols {
+  fn resolve_symbol(&self, name: &str, _argument: Option<&str>)
+    -> Result<i64, String>
+  {
+    Err(format!("Unknown symbol '#{}'", name))
   }
 }
 
 #[derive(Debug, Clone)]
 #[derive(serde::Deserialize, serde::Serialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct ShasmProgramValidation {
   pub(crate) errors: Vec<ShasmParseError>,
 }
@@ -54,9 +89,22 @@ impl ShasmProgramValidation {
     self.errors.is_empty()
   }
 }
+impl Diagnose for ShasmProgramValidation {
+  /** `path` is that of the program text; lines are given as a param. */
+  fn diagnose(&self, path: &str, out: &mut Vec<Diagnostic>) {
+    out.extend(self.errors.iter().map(|error| Diagnostic {
+      code: DiagnosticCode::ParseError,
+      path: path.to_string(),
+      severity: DiagnosticSeverity::Error,
+      message: error.to_string(),
+      params: [("line".to_string(), error.line_no.into())].into(),
+    }));
+  }
+}
 
 #[derive(Debug, Clone)]
 #[derive(serde::Deserialize, serde::Serialize)]
+#[derive(schemars::JsonSchema)]
 pub(crate) struct ShasmParseError {
   #[serde(rename = "lineNo")]
   pub(crate) line_no: usize,
@@ -74,11 +122,14 @@ impl ShasmParseError {
 }
 
 /**
- * Parse an entire shady program.
+ * Parse an entire shady program, resolving symbols with `symbols`.
  */
-pub(crate) fn shasm_program_parser<'a>(program_text: &'a str)
-  -> Result<ShadyProgram, Vec<ShasmParseError>>
-{
+pub(crate) fn shasm_program_parser<'a>(
+  program_text: &'a str,
+  symbols: &dyn ShasmSymbols,
+) -> Result<ShadyProgram, Vec<ShasmParseError>> {
+  let symbol_regex =
+    Regex::new(r"#([A-Za-z_]\w*(?:\.[A-Za-z_]\w*)*)(?:\(([^)]*)\))?").unwrap();
   let mut instrs = Vec::<bitcode::Instruction>::new();
   let mut labels = HashMap::<String, LabelInfo>::new();
   let mut errors = Vec::<ShasmParseError>::new();
@@ -91,13 +142,21 @@ pub(crate) fn shasm_program_parser<'a>(program_text: &'a str)
 
     let label_regex = Regex::new(r"^\s*@\w+:\s*$").unwrap();
     if label_regex.is_match(line) {
-      let label = line.trim()[1..].trim();
+      let label = line[1..].trim_end_matches(':').trim();
       let ent = labels.entry(label.to_string()).or_insert(LabelInfo::new());
-      ent.bind_offset = Some(line_no);
+      ent.bind_offset = Some((instrs.len(), line_no));
       continue;
     }
 
-    let parse_result = shasm_instr_parser().parse(line);
+    let line = match substitute_symbols(&symbol_regex, line, symbols) {
+      Ok(line) => line,
+      Err(message) => {
+        errors.push(ShasmParseError { line_no, message });
+        continue;
+      }
+    };
+
+    let parse_result = shasm_instr_parser().parse(&line);
     if parse_result.errors().len() > 0 {
       for err in parse_result.errors() {
         let message = format!("Error parsing instruction: {}", err);
@@ -109,14 +168,14 @@ pub(crate) fn shasm_program_parser<'a>(program_text: &'a str)
     instrs.push(instr.instr);
     if let Some(label) = &instr.path_label {
       let ent = labels.entry(label.clone()).or_insert(LabelInfo::new());
-      ent.use_offsets.push(line_no);
+      ent.use_offsets.push((instrs.len() - 1, line_no));
     }
   }
 
   // Patch up the labels.
   for (label, info) in labels.iter() {
     // Ensure the label was bound.
-    let bind_offset = match info.bind_offset {
+    let (bind_offset, bind_line_no) = match info.bind_offset {
       Some(offset) => offset,
       None => {
         errors.push(ShasmParseError {
@@ -126,21 +185,21 @@ pub(crate) fn shasm_program_parser<'a>(program_text: &'a str)
         continue;
       }
     };
-    // Validate binding offset.
-    if bind_offset >= instrs.len() {
+    // Validate binding offset.  A label may follow the last instruction.
+    if bind_offset > instrs.len() {
       errors.push(ShasmParseError {
-        line_no: bind_offset,
+        line_no: bind_line_no,
         message: format!("Label '{}' bind offset out of bounds", label),
       });
       continue;
     }
     // Patch Uses.
-    for &use_offset in &info.use_offsets {
+    for &(use_offset, use_line_no) in &info.use_offsets {
       let offset_delta = bind_offset as i32 - use_offset as i32;
       if offset_delta < SHADY_INS_SRC_IMM_MIN as i32
       || offset_delta > SHADY_INS_SRC_IMM_MAX as i32 {
         errors.push(ShasmParseError {
-          line_no: use_offset,
+          line_no: use_line_no,
           message: format!("Label '{}' offset delta out of bounds", label),
         });
         continue;
@@ -160,9 +219,30 @@ pub(crate) fn shasm_program_parser<'a>(program_text: &'a str)
   Ok(ShadyProgram::new(instrs))
 }
 
+fn substitute_symbols(
+  symbol_regex: &Regex,
+  line: &str,
+  symbols: &dyn ShasmSymbols,
+) -> Result<String, String> {
+  let mut result = String::new();
+  let mut last_end = 0;
+  for captures in symbol_regex.captures_iter(line) {
+    let whole = captures.get(0).unwrap();
+    let name = captures.get(1).unwrap().as_str();
+    let argument = captures.get(2).map(|arg| arg.as_str().trim());
+    let value = symbols.resolve_symbol(name, argument)?;
+    result.push_str(&line[last_end .. whole.start()]);
+    result.push_str(&value.to_string());
+    last_end = whole.end();
+  }
+  result.push_str(&line[last_end ..]);
+  Ok(result)
+}
+
+// Offsets are instruction indices, each paired with its line number.
 struct LabelInfo {
-  bind_offset: Option<usize>,
-  use_offsets: Vec<usize>,
+  bind_offset: Option<(usize, usize)>,
+  use_offsets: Vec<(usize, usize)>,
 }
 impl LabelInfo {
   fn new() -> LabelInfo {
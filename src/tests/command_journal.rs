@@ -0,0 +1,47 @@
+use std::fs;
+use crate::{
+  data_store::DataStore,
+  protocol::{ CommandEnvelope, EnterMainMenuModeCmd, ListWorldsCmd },
+};
+use super::TempDataRoot;
+
+fn previous_commands(data_store: &DataStore) -> usize {
+  data_store.journal().unwrap().read_previous::<CommandEnvelope>().unwrap().len()
+}
+
+#[test]
+fn journal_is_kept_until_a_new_run_journals() {
+  let root = TempDataRoot::new("journal_kept");
+  let data_store = DataStore::new(&root).unwrap();
+  let journal = data_store.journal().unwrap();
+  journal.append(&CommandEnvelope::ListWorlds(ListWorldsCmd {})).unwrap();
+  journal.append(&CommandEnvelope::ListWorlds(ListWorldsCmd {})).unwrap();
+
+  journal.set_aside().unwrap();
+  assert_eq!(previous_commands(&data_store), 2);
+
+  // Restarting again before anything is journaled keeps it.
+  journal.set_aside().unwrap();
+  assert_eq!(previous_commands(&data_store), 2);
+
+  // A run that cleared its journal replaces it.
+  journal.clear().unwrap();
+  journal.set_aside().unwrap();
+  assert_eq!(previous_commands(&data_store), 0);
+}
+
+#[test]
+fn journal_stops_at_a_torn_line() {
+  let root = TempDataRoot::new("journal_torn");
+  let data_store = DataStore::new(&root).unwrap();
+  let journal = data_store.journal().unwrap();
+  journal.append(&CommandEnvelope::EnterMainMenuMode(EnterMainMenuModeCmd {}))
+    .unwrap();
+  let current = root.join("journal").join("current.jsonl");
+  let mut text = fs::read_to_string(&current).unwrap();
+  text.push_str("{\"timestamp\":1,\"command\":{\"ListWor");
+  fs::write(&current, text).unwrap();
+
+  journal.set_aside().unwrap();
+  assert_eq!(previous_commands(&data_store), 1);
+}
@@ -0,0 +1,189 @@
+use std::{ fs, os::unix::fs::symlink, path::Path };
+use futures::executor::block_on;
+use crate::{
+  game::{ GameServer, GameServerConfig },
+  protocol::{
+    mode::{ define_rules::DefineRulesModeInfo, GameModeInfo },
+    CommandEnvelope,
+    CommandFrame,
+    EnterModeCmd,
+    EventEnvelope,
+    EnterMainMenuModeCmd,
+    GetModeInfoCmd,
+    ListHeightmapsCmd,
+    ListWorldsCmd,
+    ResponseEnvelope,
+  },
+};
+use super::TempDataRoot;
+
+fn start_server(root: &Path) -> GameServer {
+  GameServer::new(&GameServerConfig::new(root.to_str().unwrap().to_string()))
+    .unwrap()
+}
+
+fn perform(server: &mut GameServer, command: CommandEnvelope)
+  -> Option<ResponseEnvelope>
+{
+  block_on(server.submit_command(command)).ok()
+}
+
+fn list_worlds() -> CommandEnvelope {
+  CommandEnvelope::ListWorlds(ListWorldsCmd {})
+}
+
+fn list_heightmaps() -> CommandEnvelope {
+  CommandEnvelope::ListHeightmaps(ListHeightmapsCmd {})
+}
+
+fn enter_define_rules() -> CommandEnvelope {
+  CommandEnvelope::EnterMode(EnterModeCmd {
+    mode: GameModeInfo::DefineRules(DefineRulesModeInfo {}),
+  })
+}
+
+/**
+ * Make the data store fail to create `name` under the root: a dangling
+ * symlink does not exist, and cannot be created as a directory either.
+ */
+fn break_subdir(root: &Path, name: &str) {
+  let path = root.join(name);
+  let _ = fs::remove_dir_all(&path);
+  symlink(root.join("missing"), &path).unwrap();
+}
+
+fn failed_messages(response: Option<ResponseEnvelope>) -> Vec<String> {
+  match response {
+    Some(ResponseEnvelope::Failed(failed)) => failed.messages().to_vec(),
+    other => panic!("Expected a Failed response, got {:?}", other),
+  }
+}
+
+#[test]
+fn parse_command_frame_with_id() {
+  let frame = CommandFrame::parse(r#"{ "id": 7, "ListWorlds": {} }"#)
+    .unwrap();
+  assert_eq!(frame.id, Some(7));
+  assert!(matches!(frame.command, CommandEnvelope::ListWorlds(_)));
+
+  let frame = CommandFrame::parse(r#"{ "GetModeInfo": {} }"#).unwrap();
+  assert_eq!(frame.id, None);
+  assert!(matches!(frame.command, CommandEnvelope::GetModeInfo(_)));
+}
+
+#[test]
+fn parse_command_frame_keeps_id_of_unknown_command() {
+  let (id, _) = CommandFrame::parse(r#"{ "id": 12, "NoSuchCommand": {} }"#)
+    .unwrap_err();
+  assert_eq!(id, Some(12));
+
+  let (id, _) = CommandFrame::parse(r#"{ "id": 13, "ListWorlds": 5 }"#)
+    .unwrap_err();
+  assert_eq!(id, Some(13));
+}
+
+#[test]
+fn parse_command_frame_without_usable_id() {
+  let (id, _) = CommandFrame::parse(r#"{ "id": 7, "ListWorlds": "#)
+    .unwrap_err();
+  assert_eq!(id, None);
+
+  let (id, _) = CommandFrame::parse(r#"{ "id": "seven", "ListWorlds": {} }"#)
+    .unwrap_err();
+  assert_eq!(id, None);
+
+  let (id, _) = CommandFrame::parse("[1, 2]").unwrap_err();
+  assert_eq!(id, None);
+}
+
+#[test]
+fn store_failure_answers_failed_and_keeps_the_mode() {
+  let root = TempDataRoot::new("recover_store_failure");
+  let mut server = start_server(&root);
+  assert!(matches!(
+    perform(&mut server, enter_define_rules()),
+    Some(ResponseEnvelope::Ok {})
+  ));
+
+  break_subdir(&root, "heightmaps");
+  let messages = failed_messages(perform(&mut server, list_heightmaps()));
+  assert!(messages[0].starts_with("Failed to read heightmap store"));
+  assert!(matches!(
+    perform(&mut server, CommandEnvelope::GetModeInfo(GetModeInfoCmd {})),
+    Some(ResponseEnvelope::InMode(GameModeInfo::DefineRules(_)))
+  ));
+
+  // The game carries on once the cause is gone.
+  fs::remove_file(root.join("heightmaps")).unwrap();
+  assert!(matches!(
+    perform(&mut server, list_heightmaps()),
+    Some(ResponseEnvelope::HeightmapList(_))
+  ));
+}
+
+#[test]
+fn cached_queries_answer_as_of_the_last_command() {
+  let root = TempDataRoot::new("recover_cached_queries");
+  let mut server = start_server(&root);
+  assert!(matches!(
+    perform(&mut server, enter_define_rules()),
+    Some(ResponseEnvelope::Ok {})
+  ));
+
+  // `ListWorlds` is answered without reading the store again.
+  break_subdir(&root, "worlds");
+  assert!(matches!(
+    perform(&mut server, list_worlds()),
+    Some(ResponseEnvelope::WorldList(_))
+  ));
+
+  // A command that may change the store has the answer read again, and a
+  // failed answer is not kept.
+  perform(&mut server, CommandEnvelope::EnterMainMenuMode(
+    EnterMainMenuModeCmd {}
+  ));
+  let messages = failed_messages(perform(&mut server, list_worlds()));
+  assert!(messages[0].starts_with("Failed to read world store"));
+  fs::remove_file(root.join("worlds")).unwrap();
+  assert!(matches!(
+    perform(&mut server, list_worlds()),
+    Some(ResponseEnvelope::WorldList(_))
+  ));
+}
+
+#[test]
+fn journal_failure_does_not_stop_the_game_thread() {
+  let root = TempDataRoot::new("recover_journal_failure");
+  let mut server = start_server(&root);
+  let mut events = server.subscribe_events();
+
+  // Commands are still performed, though they cannot be journaled.
+  break_subdir(&root, "journal");
+  assert!(matches!(
+    perform(&mut server, enter_define_rules()),
+    Some(ResponseEnvelope::Ok {})
+  ));
+  assert!(matches!(
+    perform(&mut server, list_worlds()),
+    Some(ResponseEnvelope::WorldList(_))
+  ));
+
+  // The thread was not restarted, which would have reset the mode.
+  while let Ok(Some(event)) = events.try_next() {
+    assert!(!matches!(event, EventEnvelope::ModeChanged { mode: None }));
+  }
+  assert!(matches!(
+    perform(&mut server, CommandEnvelope::GetModeInfo(GetModeInfoCmd {})),
+    Some(ResponseEnvelope::InMode(GameModeInfo::DefineRules(_)))
+  ));
+}
+
+#[test]
+fn server_does_not_start_on_an_unusable_data_root() {
+  let root = TempDataRoot::new("recover_unusable_root");
+  fs::create_dir_all(root.parent().unwrap()).unwrap();
+  fs::write(&*root, "not a directory").unwrap();
+  let config = GameServerConfig::new(root.to_str().unwrap().to_string());
+  let err = GameServer::new(&config).err().expect("Server started");
+  assert!(err.to_string().starts_with("Failed to open data root"), "{}", err);
+}
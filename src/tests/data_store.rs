@@ -0,0 +1,125 @@
+use std::{ fs, io };
+use crate::{
+  data::ruleset::Ruleset,
+  data_store::{ DataStore, FileManager },
+};
+use super::TempDataRoot;
+
+fn ruleset(name: &str) -> Ruleset {
+  Ruleset {
+    name: name.to_string(),
+    ..Ruleset::new_example()
+  }
+}
+
+fn ruleset_names(data_store: &DataStore) -> Vec<String> {
+  let mut names = data_store.rulesets().unwrap().list().into_iter()
+    .map(|entry| entry.name)
+    .collect::<Vec<_>>();
+  names.sort();
+  names
+}
+
+#[test]
+fn lock_is_held_while_the_store_is_open() {
+  let root = TempDataRoot::new("store_lock_held");
+  let data_store = DataStore::new(&root).unwrap();
+  let lock = fs::read_to_string(root.join(".lock")).unwrap();
+  assert_eq!(lock, std::process::id().to_string());
+
+  let err = DataStore::new(&root).err().expect("Lock was taken twice");
+  assert_eq!(err.kind(), io::ErrorKind::WouldBlock);
+
+  // Reading does not need the lock, and leaves it alone.
+  drop(DataStore::new_read_only(&root));
+  assert!(root.join(".lock").exists());
+
+  drop(data_store);
+  assert!(!root.join(".lock").exists());
+  DataStore::new(&root).unwrap();
+}
+
+#[test]
+fn lock_of_an_exited_process_is_taken_over() {
+  if !std::path::Path::new("/proc").exists() {
+    // Without `/proc`, no lock is ever taken to be stale.
+    return;
+  }
+  let root = TempDataRoot::new("store_lock_stale");
+  fs::create_dir_all(&*root).unwrap();
+  for contents in [u32::MAX.to_string(), "garbage".to_string()] {
+    fs::write(root.join(".lock"), contents).unwrap();
+    let data_store = DataStore::new(&root).unwrap();
+    let lock = fs::read_to_string(root.join(".lock")).unwrap();
+    assert_eq!(lock, std::process::id().to_string());
+    drop(data_store);
+  }
+}
+
+#[test]
+fn unusable_directories_are_errors() {
+  let root = TempDataRoot::new("store_unusable_dirs");
+  let data_store = DataStore::new(&root).unwrap();
+  fs::write(root.join("rulesets"), "").unwrap();
+  fs::write(root.join("journal"), "").unwrap();
+  fs::write(root.join("exports"), "").unwrap();
+  assert!(data_store.rulesets().is_err());
+  assert!(data_store.journal().is_err());
+  assert!(data_store.exports().is_err());
+}
+
+#[test]
+fn write_atomic_replaces_contents_without_leaving_temp_files() {
+  let root = TempDataRoot::new("store_write_atomic");
+  fs::create_dir_all(&*root).unwrap();
+  let files = FileManager::new(&root).root();
+
+  files.write_atomic("data.bin", b"first").unwrap();
+  assert_eq!(fs::read(root.join("data.bin")).unwrap(), b"first");
+
+  // A temporary file left by an interrupted write is overwritten.
+  fs::write(root.join("data.bin.tmp"), b"torn").unwrap();
+  files.write_atomic("data.bin", b"second").unwrap();
+  assert_eq!(fs::read(root.join("data.bin")).unwrap(), b"second");
+  assert_eq!(files.list().unwrap(), vec!["data.bin"]);
+}
+
+#[test]
+fn missing_ruleset_index_is_rebuilt() {
+  let root = TempDataRoot::new("store_index_missing");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut rulesets = data_store.rulesets().unwrap();
+  rulesets.write("First", &ruleset("First")).unwrap();
+  rulesets.write("Second", &ruleset("Second")).unwrap();
+
+  let index = root.join("rulesets").join("rulesets.json");
+  fs::remove_file(&index).unwrap();
+  assert_eq!(ruleset_names(&data_store), vec!["First", "Second"]);
+  assert!(index.exists());
+  assert_eq!(
+    data_store.rulesets().unwrap().read("Second").unwrap().name,
+    "Second"
+  );
+}
+
+#[test]
+fn corrupt_ruleset_index_is_rebuilt_skipping_bad_files() {
+  let root = TempDataRoot::new("store_index_corrupt");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut rulesets = data_store.rulesets().unwrap();
+  rulesets.write("First", &ruleset("First")).unwrap();
+  rulesets.write("Second", &ruleset("Second")).unwrap();
+
+  let dir = root.join("rulesets");
+  fs::write(dir.join("rulesets.json"), "[{ \"name\": ").unwrap();
+  // An unreadable ruleset, and a later file repeating a name.
+  fs::write(dir.join("rls8_Broken.json"), "{").unwrap();
+  let first = serde_json::to_string(&ruleset("First")).unwrap();
+  fs::write(dir.join("rls9_First.json"), first).unwrap();
+
+  assert_eq!(ruleset_names(&data_store), vec!["First", "Second"]);
+  assert!(dir.join("rls8_Broken.json").exists());
+  let index = fs::read_to_string(dir.join("rulesets.json")).unwrap();
+  assert!(!index.contains("rls8_Broken.json"));
+  assert!(!index.contains("rls9_First.json"));
+}
@@ -0,0 +1,91 @@
+use serde_json::{ json, Value };
+use crate::{
+  data::{
+    diff_json,
+    ruleset::{ Ruleset, RulesetInput, RulesetValidation },
+    Diagnostic,
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  data_store::DataStore,
+};
+use super::TempDataRoot;
+
+const COMPONENTS: &str = "/terrainGen/stage/format/wordFormats/0/components";
+
+/** Validate the example ruleset's input, edited as JSON. */
+fn invalid(root_name: &str, edit: impl FnOnce(&mut Value))
+  -> RulesetValidation
+{
+  let root = TempDataRoot::new(root_name);
+  let data_store = DataStore::new(&root).unwrap();
+  let mut input = serde_json::to_value(Ruleset::new_example().to_input())
+    .unwrap();
+  edit(&mut input);
+  let input: RulesetInput = serde_json::from_value(input).unwrap();
+  input.to_validated(&data_store, None).err().expect("Ruleset was valid")
+}
+
+fn find<'a>(validation: &'a RulesetValidation, path: &str) -> &'a Diagnostic {
+  validation.diagnostics.iter()
+    .find(|diagnostic| diagnostic.path == path)
+    .unwrap_or_else(|| panic!(
+      "No diagnostic at {}: {:?}", path, validation.diagnostics
+    ))
+}
+
+#[test]
+fn overlap_is_reported_at_the_later_component_bits() {
+  let validation = invalid("diagnostics_overlap", |input| {
+    input.pointer_mut(&format!("{}/1/offset", COMPONENTS)).unwrap()
+      .clone_from(&json!("4"));
+  });
+  let diagnostic = find(&validation, &format!("{}/1/bits", COMPONENTS));
+  assert_eq!(diagnostic.code, DiagnosticCode::Overlap);
+  assert_eq!(
+    diagnostic.message,
+    "Bits 4-11 overlap component 'Component1' (bits 0-7)."
+  );
+  assert_eq!(diagnostic.params["other"], "Component1");
+  // Only the later component is blamed.
+  let first = format!("{}/0", COMPONENTS);
+  assert!(!validation.diagnostics.iter()
+    .any(|diagnostic| diagnostic.path.starts_with(&first)));
+}
+
+#[test]
+fn diagnostics_carry_codes_and_paths() {
+  let validation = invalid("diagnostics_codes", |input| {
+    input["name"] = json!("");
+    input.pointer_mut(&format!("{}/1/name", COMPONENTS)).unwrap()
+      .clone_from(&json!("Component1"));
+    input.pointer_mut(&format!("{}/0/bits", COMPONENTS)).unwrap()
+      .clone_from(&json!("eight"));
+  });
+  assert_eq!(find(&validation, "/name").code, DiagnosticCode::Required);
+
+  let duplicate = find(&validation, &format!("{}/1/name", COMPONENTS));
+  assert_eq!(duplicate.code, DiagnosticCode::Duplicate);
+  assert_eq!(duplicate.params["name"], "Component1");
+
+  let bits = find(&validation, &format!("{}/0/bits", COMPONENTS));
+  assert_eq!(bits.code, DiagnosticCode::NotANumber);
+}
+
+#[test]
+fn path_tokens_are_escaped() {
+  let message = ValidationMessage::new(DiagnosticCode::Other, "Bad.")
+    .at("a/b")
+    .at("c~d")
+    .within(3);
+  assert_eq!(message.to_diagnostic("/list").path, "/list/3/a~1b/c~0d");
+
+  let ops = diff_json(
+    &json!({ "a/b": 1, "c~d": 2 }),
+    &json!({ "a/b": 3, "c~d": 4 }),
+  );
+  let paths = serde_json::to_value(&ops).unwrap().as_array().unwrap().iter()
+    .map(|op| op["path"].as_str().unwrap().to_string())
+    .collect::<Vec<_>>();
+  assert_eq!(paths, vec!["/a~1b", "/c~0d"]);
+}
@@ -0,0 +1,138 @@
+use crate::{
+  data::ruleset::{
+    FormatComponentRules,
+    FormatComponentType,
+    FormatEnumValue,
+    FormatRules,
+    FormatValue,
+    FormatWordRules,
+  },
+  shady_vm::{ ShasmProgram, ShasmSymbols },
+};
+
+fn biomes() -> FormatComponentType {
+  let value = |name: &str, value| FormatEnumValue {
+    name: name.to_string(),
+    value,
+  };
+  FormatComponentType::Enum {
+    values: vec![value("Water", 0), value("Forest", 1), value("Desert", 5)],
+  }
+}
+
+fn half_steps() -> FormatComponentType {
+  FormatComponentType::Fixed { scale: 0.5, signed: true }
+}
+
+/** A word with a signed 8-bit height, in half steps, and a 4-bit biome. */
+fn format() -> FormatRules {
+  let component = |name: &str, offset, bits, kind| FormatComponentRules {
+    name: name.to_string(),
+    offset,
+    bits,
+    kind,
+  };
+  FormatRules {
+    word_formats: vec![FormatWordRules {
+      name: "Terrain".to_string(),
+      components: vec![
+        component("Height", 0, 8, half_steps()),
+        component("Biome", 8, 4, biomes()),
+      ],
+    }],
+  }
+}
+
+/** Truncate an encoded literal to the raw bits of a component. */
+fn raw_bits(bits: u8, count: i64) -> u32 {
+  (count as u32) & ((1_u32 << bits) - 1)
+}
+
+fn assembled(text: &str, symbols: &dyn ShasmSymbols) -> Vec<[u32; 2]> {
+  ShasmProgram::to_validated_with_symbols(text, symbols).unwrap()
+    .parse_shady_program(symbols).unwrap()
+    .iter_instructions()
+    .map(|&instr| instr.into())
+    .collect()
+}
+
+#[test]
+fn signed_values_are_sign_extended() {
+  let signed = FormatComponentType::Signed;
+  assert_eq!(signed.decode(8, 0xFF), FormatValue::Integer(-1));
+  assert_eq!(signed.decode(8, 0x80), FormatValue::Integer(-128));
+  assert_eq!(signed.decode(8, 0x7F), FormatValue::Integer(127));
+  assert_eq!(signed.decode(4, 0x9), FormatValue::Integer(-7));
+  assert_eq!(signed.decode(32, u32::MAX), FormatValue::Integer(-1));
+  assert_eq!(
+    FormatComponentType::Unsigned.decode(8, 0xFF),
+    FormatValue::Integer(255)
+  );
+
+  for value in [-128, -12, -1, 0, 1, 127] {
+    let count = signed.encode_literal(8, &value.to_string()).unwrap();
+    assert_eq!(count, value);
+    assert_eq!(
+      signed.decode(8, raw_bits(8, count)),
+      FormatValue::Integer(value)
+    );
+  }
+  assert!(signed.encode_literal(8, "128").is_err());
+  assert!(signed.encode_literal(8, "-129").is_err());
+  assert!(FormatComponentType::Unsigned.encode_literal(8, "-1").is_err());
+}
+
+#[test]
+fn fixed_point_values_count_multiples_of_the_scale() {
+  let fixed = half_steps();
+  assert_eq!(fixed.encode_literal(8, "-12.5"), Ok(-25));
+  assert_eq!(fixed.decode(8, raw_bits(8, -25)), FormatValue::Number(-12.5));
+  assert_eq!(fixed.decode(8, 0x7F), FormatValue::Number(63.5));
+  assert_eq!(fixed.encode_literal(8, "-64"), Ok(-128));
+  assert!(fixed.encode_literal(8, "64").is_err());
+  assert!(fixed.encode_literal(8, "high").is_err());
+
+  // Unsigned fixed point does not sign-extend.
+  let unsigned = FormatComponentType::Fixed { scale: 0.25, signed: false };
+  assert_eq!(unsigned.decode(8, 0xFF), FormatValue::Number(63.75));
+  assert_eq!(unsigned.encode_literal(8, "1.3"), Ok(5));
+}
+
+#[test]
+fn enum_values_are_looked_up_by_name_and_value() {
+  let kind = biomes();
+  assert_eq!(kind.decode(4, 1), FormatValue::Name("Forest".to_string()));
+  assert_eq!(kind.decode(4, 5), FormatValue::Name("Desert".to_string()));
+  // A raw value without a name decodes as its number.
+  assert_eq!(kind.decode(4, 3), FormatValue::Integer(3));
+
+  assert_eq!(kind.encode_literal(4, "Forest"), Ok(1));
+  assert_eq!(kind.encode_literal(4, "3"), Ok(3));
+  assert_eq!(
+    kind.encode_literal(4, "Tundra"),
+    Err("'Tundra' is not a value of this enum".to_string())
+  );
+  assert!(kind.encode_literal(4, "16").is_err());
+}
+
+#[test]
+fn shasm_literals_are_encoded_with_the_component_type() {
+  let format = format();
+  assert_eq!(format.resolve_symbol("Terrain.Height", Some("-12.5")), Ok(-25));
+  assert_eq!(format.resolve_symbol("Terrain.Biome.Forest", None), Ok(1));
+  assert_eq!(format.resolve_symbol("Terrain.Biome", Some("Forest")), Ok(1));
+  assert_eq!(
+    format.resolve_symbol("Terrain.Biome.Tundra", None),
+    Err("#Terrain.Biome.Tundra: 'Tundra' is not a value of this enum"
+      .to_string())
+  );
+
+  assert_eq!(
+    assembled("add r0, r1, #Terrain.Height(-12.5)", &format),
+    assembled("add r0, r1, -25", &format)
+  );
+  assert_eq!(
+    assembled("imm32load r0, #Terrain.Biome.Forest", &format),
+    assembled("imm32load r0, 1", &format)
+  );
+}
@@ -0,0 +1,173 @@
+use crate::{
+  data::{
+    map::CELL_DATA_NUM_WORDS,
+    ruleset::{
+      FormatComponentInput,
+      FormatComponentRules,
+      FormatComponentType,
+      FormatInput,
+      FormatRules,
+      FormatValidation,
+      FormatValue,
+      FormatWordInput,
+      FormatWordRules,
+    },
+    DiagnosticCode,
+    ValidationMessage,
+  },
+  shady_vm::ShasmSymbols,
+};
+
+fn component(name: &str, offset: u8, bits: u8) -> FormatComponentInput {
+  FormatComponentInput {
+    name: name.to_string(),
+    offset: offset.to_string(),
+    bits: bits.to_string(),
+    kind: FormatComponentType::Unsigned,
+  }
+}
+
+fn word(name: &str, components: Vec<FormatComponentInput>)
+  -> FormatWordInput
+{
+  FormatWordInput { name: name.to_string(), components }
+}
+
+fn invalid(word_formats: Vec<FormatWordInput>) -> FormatValidation {
+  FormatInput { word_formats }.to_validated().err().unwrap()
+}
+
+fn texts(messages: &[ValidationMessage]) -> Vec<&str> {
+  messages.iter().map(|message| message.text.as_str()).collect()
+}
+
+#[test]
+fn reports_overlap_on_later_component() {
+  let validation = invalid(vec![
+    word("Terrain", vec![
+      component("Elevation", 0, 12),
+      component("Moisture", 12, 8),
+      component("Kind", 16, 8),
+    ]),
+  ]);
+  let components = &validation.word_formats[0].components;
+  assert_eq!(components.len(), 3);
+  assert!(components[0].is_valid());
+  assert!(components[1].is_valid());
+  assert_eq!(
+    texts(&components[2].errors),
+    ["Bits 16-23 overlap component 'Moisture' (bits 12-19)."]
+  );
+  assert_eq!(components[2].errors[0].code, DiagnosticCode::Overlap);
+  assert_eq!(components[2].errors[0].params["other"], "Moisture");
+}
+
+#[test]
+fn reports_duplicate_component_name() {
+  let validation = invalid(vec![
+    word("Terrain", vec![
+      component("Elevation", 0, 8),
+      component("Kind", 8, 4),
+      component("Elevation", 12, 8),
+    ]),
+  ]);
+  let components = &validation.word_formats[0].components;
+  assert!(components[0].is_valid());
+  assert!(components[1].is_valid());
+  assert_eq!(
+    texts(&components[2].name),
+    ["The name 'Elevation' is already used by component 0."]
+  );
+  assert_eq!(components[2].name[0].code, DiagnosticCode::Duplicate);
+  assert!(components[2].errors.is_empty());
+}
+
+#[test]
+fn reports_duplicate_word_name() {
+  let validation = invalid(vec![
+    word("Terrain", vec![component("Elevation", 0, 8)]),
+    word("Climate", vec![component("Rain", 0, 8)]),
+    word("Terrain", vec![component("Kind", 0, 8)]),
+  ]);
+  let words = &validation.word_formats;
+  assert_eq!(words.len(), 3);
+  assert!(words[0].is_valid());
+  assert!(words[1].is_valid());
+  assert_eq!(
+    texts(&words[2].errors),
+    ["The name 'Terrain' is already used by word 0."]
+  );
+  assert_eq!(words[2].errors[0].code, DiagnosticCode::Duplicate);
+  assert_eq!(words[2].errors[0].subpath, "/name");
+}
+
+#[test]
+fn reports_zero_bit_component() {
+  let validation = invalid(vec![
+    word("Terrain", vec![
+      component("Elevation", 0, 8),
+      component("Empty", 8, 0),
+    ]),
+  ]);
+  let components = &validation.word_formats[0].components;
+  assert!(components[0].is_valid());
+  assert_eq!(texts(&components[1].bits), ["The bits must be at least 1."]);
+  assert_eq!(components[1].bits[0].code, DiagnosticCode::OutOfRange);
+  // An invalid component is not checked for overlaps.
+  assert!(components[1].errors.is_empty());
+}
+
+#[test]
+fn reports_too_many_words() {
+  let words = (0 ..= CELL_DATA_NUM_WORDS)
+    .map(|i| word(&format!("Word{}", i), vec![component("Value", 0, 32)]))
+    .collect::<Vec<_>>();
+  let validation = invalid(words);
+  assert_eq!(texts(&validation.errors), [format!(
+    "At most {} words are allowed, but {} are defined.",
+    CELL_DATA_NUM_WORDS, CELL_DATA_NUM_WORDS + 1
+  )]);
+  assert_eq!(validation.errors[0].code, DiagnosticCode::TooMany);
+  assert!(validation.word_formats.iter().all(|word| word.is_valid()));
+
+  // Exactly as many words as a cell holds is fine.
+  let words = (0 .. CELL_DATA_NUM_WORDS)
+    .map(|i| word(&format!("Word{}", i), vec![component("Value", 0, 32)]))
+    .collect::<Vec<_>>();
+  assert!(FormatInput { word_formats: words }.to_validated().is_ok());
+}
+
+/**
+ * Stored rules are not revalidated, so zero-bit components must not
+ * crash literal encoding or decoding.
+ */
+#[test]
+fn zero_bit_components_hold_only_zero() {
+  let zero_bits = |kind| FormatComponentRules {
+    name: "Empty".to_string(),
+    offset: 0,
+    bits: 0,
+    kind,
+  };
+  let format = FormatRules {
+    word_formats: vec![FormatWordRules {
+      name: "Terrain".to_string(),
+      components: vec![zero_bits(FormatComponentType::Signed)],
+    }],
+  };
+  assert_eq!(format.resolve_symbol("Terrain.Empty", Some("0")), Ok(0));
+  assert!(format.resolve_symbol("Terrain.Empty", Some("1")).is_err());
+  assert!(format.resolve_symbol("Terrain.Empty", Some("-1")).is_err());
+
+  for kind in [
+    FormatComponentType::Unsigned,
+    FormatComponentType::Signed,
+    FormatComponentType::Fixed { scale: 0.5, signed: true },
+  ] {
+    let value = zero_bits(kind.clone()).decode(0);
+    assert!(
+      matches!(value, FormatValue::Integer(0) | FormatValue::Number(0.0)),
+      "{:?} decoded to {:?}", kind, value
+    );
+  }
+}
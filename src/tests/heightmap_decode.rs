@@ -0,0 +1,229 @@
+use crate::{
+  cog::{ CogDevice, CogTask },
+  data::{
+    map::WorldDims,
+    Heightmap,
+    HeightmapFormat,
+  },
+  gpu::{
+    task::create_world::ResampleMapDataTask,
+    wgsl::create_world::ResampleMapDataUniforms,
+  },
+  utility::decode_base64,
+};
+
+const MAX_DIMS: WorldDims = WorldDims::new(1000, 1000);
+
+fn decode_pgm(bytes: &[u8]) -> Result<Heightmap, String> {
+  Heightmap::decode(HeightmapFormat::Pgm, None, MAX_DIMS, bytes)
+}
+
+fn decode_raw(format: HeightmapFormat, dims: WorldDims, bytes: &[u8])
+  -> Result<Heightmap, String>
+{
+  Heightmap::decode(format, Some(dims), MAX_DIMS, bytes)
+}
+
+#[test]
+fn plain_pgm_with_comments() {
+  let heightmap = decode_pgm(b"P2\n# A sketch\n3 2\n# maxval\n9\n\
+    0 1 2\n3 4 9\n").unwrap();
+  assert_eq!(heightmap.dims, WorldDims::new(3, 2));
+  assert_eq!(heightmap.values, vec![0, 1, 2, 3, 4, 9]);
+}
+
+#[test]
+fn binary_pgm_8_bit() {
+  let heightmap = decode_pgm(b"P5\n2 2\n255\n\x00\x40\x80\xC0").unwrap();
+  assert_eq!(heightmap.dims, WorldDims::new(2, 2));
+  assert_eq!(heightmap.values, vec![0x00, 0x40, 0x80, 0xC0]);
+}
+
+#[test]
+fn binary_pgm_16_bit_is_big_endian() {
+  let heightmap = decode_pgm(b"P5 2 1 65535 \x01\x02\xFF\xFE").unwrap();
+  assert_eq!(heightmap.values, vec![0x0102, 0xFFFE]);
+}
+
+#[test]
+fn plain_pgm_16_bit() {
+  let heightmap = decode_pgm(b"P2 2 1 65535 0 65535").unwrap();
+  assert_eq!(heightmap.values, vec![0, 65535]);
+}
+
+#[test]
+fn truncated_pgm() {
+  assert_eq!(
+    decode_pgm(b"P5\n2 2\n255\n\x00\x40\x80").unwrap_err(),
+    "PGM raster is truncated"
+  );
+  assert_eq!(
+    decode_pgm(b"P2 2 2 255 0 1 2").unwrap_err(),
+    "PGM header is truncated"
+  );
+  assert_eq!(decode_pgm(b"P5\n2 2\n").unwrap_err(), "PGM header is truncated");
+}
+
+#[test]
+fn pgm_sample_above_maxval() {
+  assert_eq!(
+    decode_pgm(b"P2 2 1 100 0 101").unwrap_err(),
+    "PGM sample exceeds maxval"
+  );
+  assert_eq!(
+    decode_pgm(b"P5 2 1 100 \x00\x65").unwrap_err(),
+    "PGM sample exceeds maxval"
+  );
+}
+
+#[test]
+fn pgm_header_beyond_max_dims_is_rejected_before_reading_samples() {
+  // A tiny file claiming a 65535x65535 raster must not reserve memory
+  // for it, nor be parsed as far as its (missing) samples.
+  let err = decode_pgm(b"P2 65535 65535 255 0").unwrap_err();
+  assert_eq!(
+    err,
+    "Heightmap is 65535x65535, larger than the maximum 1000x1000"
+  );
+
+  let err = decode_pgm(b"P5 1001 1 255 ").unwrap_err();
+  assert!(err.starts_with("Heightmap is 1001x1"), "{}", err);
+}
+
+#[test]
+fn pgm_rejects_other_formats_and_bad_headers() {
+  assert!(decode_pgm(b"P6 1 1 255 \x00\x00\x00").unwrap_err()
+    .starts_with("Not a PGM file"));
+  assert!(decode_pgm(b"P2 0 1 255").unwrap_err()
+    .starts_with("Unsupported PGM dimensions"));
+  assert!(decode_pgm(b"P2 1 1 65536 0").unwrap_err()
+    .starts_with("Unsupported PGM maxval"));
+  assert!(decode_pgm(b"P2 x 1 255 0").unwrap_err()
+    .starts_with("Invalid number in PGM file"));
+}
+
+#[test]
+fn flat_heightmap_is_rejected() {
+  assert_eq!(
+    decode_pgm(b"P2 2 1 255 7 7").unwrap_err(),
+    "Heightmap is flat"
+  );
+}
+
+#[test]
+fn raw16_and_raw32_are_little_endian() {
+  let heightmap = decode_raw(
+    HeightmapFormat::Raw16,
+    WorldDims::new(2, 1),
+    &[0x01, 0x02, 0xFF, 0xFF],
+  ).unwrap();
+  assert_eq!(heightmap.values, vec![0x0201, 0xFFFF]);
+
+  let heightmap = decode_raw(
+    HeightmapFormat::Raw32,
+    WorldDims::new(1, 2),
+    &[0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00],
+  ).unwrap();
+  assert_eq!(heightmap.dims, WorldDims::new(1, 2));
+  assert_eq!(heightmap.values, vec![1, 0x10000]);
+}
+
+#[test]
+fn raw_requires_matching_size_and_dims() {
+  assert_eq!(
+    decode_raw(HeightmapFormat::Raw16, WorldDims::new(2, 2), &[0; 6])
+      .unwrap_err(),
+    "Raw heightmap has 6 bytes, expected 8 for 2x2"
+  );
+  assert_eq!(
+    Heightmap::decode(HeightmapFormat::Raw32, None, MAX_DIMS, &[0; 8])
+      .unwrap_err(),
+    "Dimensions are required for raw heightmaps"
+  );
+  assert!(
+    decode_raw(HeightmapFormat::Raw16, WorldDims::new(1, 1001), &[])
+      .unwrap_err()
+      .starts_with("Heightmap is 1x1001")
+  );
+}
+
+#[test]
+fn raw32_round_trips_through_to_raw32() {
+  let heightmap = Heightmap {
+    dims: WorldDims::new(3, 1),
+    values: vec![0, 70000, 3],
+  };
+  let decoded = decode_raw(
+    HeightmapFormat::Raw32,
+    heightmap.dims,
+    &heightmap.to_raw32(),
+  ).unwrap();
+  assert_eq!(decoded.values, heightmap.values);
+}
+
+#[test]
+fn base64_padding_is_optional() {
+  assert_eq!(decode_base64("AAD//w==").unwrap(), vec![0, 0, 0xFF, 0xFF]);
+  assert_eq!(decode_base64("AAD//w").unwrap(), vec![0, 0, 0xFF, 0xFF]);
+  assert_eq!(decode_base64("TWE=").unwrap(), b"Ma".to_vec());
+  assert_eq!(decode_base64("TWFu").unwrap(), b"Man".to_vec());
+  assert_eq!(decode_base64("").unwrap(), Vec::<u8>::new());
+}
+
+#[test]
+fn base64_ignores_whitespace() {
+  assert_eq!(
+    decode_base64(" UDUK\nMiAy\r\n\tCjI1 NQoA\nQIDA\n").unwrap(),
+    decode_base64("UDUKMiAyCjI1NQoAQIDA").unwrap()
+  );
+}
+
+#[test]
+fn base64_rejects_truncated_and_invalid_data() {
+  assert_eq!(decode_base64("TWFuT").unwrap_err(), "Truncated base64 data");
+  assert_eq!(
+    decode_base64("TW=E").unwrap_err(),
+    "Unexpected base64 data after padding at 3"
+  );
+  assert_eq!(
+    decode_base64("TW-u").unwrap_err(),
+    "Invalid base64 character at 2"
+  );
+}
+
+#[test]
+fn resample_uniforms_pack_source_then_destination() {
+  let uniforms = ResampleMapDataUniforms {
+    src_dims: WorldDims::new(3, 4),
+    dst_dims: WorldDims::new(250, 300),
+  };
+  let packed: [u32; 4] = uniforms.into();
+  assert_eq!(packed, [3, 4, 250, 300]);
+}
+
+#[test]
+fn resample_task_maps_corners_onto_corners() {
+  // The task can only run where there is a GPU adapter.
+  let device = match CogDevice::new() {
+    Ok(device) => device,
+    Err(_) => return,
+  };
+  let src_dims = WorldDims::new(2, 2);
+  let dst_dims = WorldDims::new(3, 3);
+  let input = device.create_seq_buffer::<u32>(4, "ResampleTestInput");
+  input.write_slice(0, &[0, 100, 200, 300]);
+  let output = device.create_seq_buffer::<u32>(9, "ResampleTestOutput");
+  let task = ResampleMapDataTask::new(
+    src_dims,
+    dst_dims,
+    input,
+    output.clone(),
+  );
+  device.encode_and_run("ResampleTest", |encoder| task.encode(encoder));
+  let values = output.read_mapped_full(|data| data.to_vec());
+  assert_eq!(values, vec![
+    0, 50, 100,
+    100, 150, 200,
+    200, 250, 300,
+  ]);
+}
@@ -0,0 +1,139 @@
+use serde_json::{ json, Value };
+use crate::data::{ diff_json, patch_input, JsonPatchOp };
+
+fn ops(patch: Value) -> Vec<JsonPatchOp> {
+  serde_json::from_value(patch).unwrap()
+}
+
+fn apply(document: Value, patch: Value) -> Result<Value, String> {
+  patch_input(&document, &ops(patch))
+}
+
+#[test]
+fn add_appends_with_dash_and_inserts_at_index() {
+  let document = json!({ "list": [1, 2] });
+  assert_eq!(
+    apply(document.clone(), json!([{ "op": "add", "path": "/list/-", "value": 3 }])),
+    Ok(json!({ "list": [1, 2, 3] }))
+  );
+  assert_eq!(
+    apply(document.clone(), json!([{ "op": "add", "path": "/list/1", "value": 9 }])),
+    Ok(json!({ "list": [1, 9, 2] }))
+  );
+  assert_eq!(
+    apply(document, json!([{ "op": "add", "path": "/list/2", "value": 9 }])),
+    Ok(json!({ "list": [1, 2, 9] }))
+  );
+}
+
+#[test]
+fn remove_takes_out_members_and_elements() {
+  let document = json!({ "a": 1, "list": [1, 2, 3] });
+  assert_eq!(
+    apply(document, json!([
+      { "op": "remove", "path": "/a" },
+      { "op": "remove", "path": "/list/0" },
+    ])),
+    Ok(json!({ "list": [2, 3] }))
+  );
+}
+
+#[test]
+fn replace_of_a_missing_path_fails() {
+  let document = json!({ "a": 1 });
+  assert!(apply(document, json!([{ "op": "replace", "path": "/b", "value": 2 }]))
+    .is_err());
+}
+
+#[test]
+fn move_into_its_own_child_is_rejected() {
+  let document = json!({ "a": { "b": 1 } });
+  assert!(apply(document.clone(), json!([{ "op": "move", "from": "/a", "path": "/a/c" }]))
+    .is_err());
+  assert_eq!(
+    apply(document, json!([{ "op": "move", "from": "/a/b", "path": "/c" }])),
+    Ok(json!({ "a": {}, "c": 1 }))
+  );
+}
+
+#[test]
+fn copy_duplicates_a_value() {
+  let document = json!({ "a": { "b": [1] } });
+  assert_eq!(
+    apply(document, json!([{ "op": "copy", "from": "/a/b", "path": "/c" }])),
+    Ok(json!({ "a": { "b": [1] }, "c": [1] }))
+  );
+}
+
+#[test]
+fn failed_test_leaves_the_document_unchanged() {
+  let document = json!({ "a": 1 });
+  let result = apply(document.clone(), json!([
+    { "op": "replace", "path": "/a", "value": 2 },
+    { "op": "test", "path": "/a", "value": 3 },
+  ]));
+  assert!(result.is_err());
+  assert_eq!(
+    apply(document, json!([{ "op": "test", "path": "/a", "value": 1 }])),
+    Ok(json!({ "a": 1 }))
+  );
+}
+
+#[test]
+fn tokens_are_unescaped() {
+  let document = json!({ "a/b": 1, "m~n": 2 });
+  assert_eq!(
+    apply(document, json!([
+      { "op": "replace", "path": "/a~1b", "value": 3 },
+      { "op": "replace", "path": "/m~0n", "value": 4 },
+    ])),
+    Ok(json!({ "a/b": 3, "m~n": 4 }))
+  );
+}
+
+#[test]
+fn indices_with_leading_zeros_or_signs_are_rejected() {
+  let document = json!({ "list": [1, 2, 3] });
+  for index in ["01", "+1", "-1", " 1", ""] {
+    let path = format!("/list/{}", index);
+    assert!(
+      apply(document.clone(), json!([{ "op": "remove", "path": path }])).is_err(),
+      "index {:?} was accepted", index
+    );
+  }
+}
+
+#[test]
+fn diff_applies_back_to_the_target() {
+  let from = json!({
+    "name": "a",
+    "gone": true,
+    "list": [1, 2, 3],
+    "nested": { "x/y": [1, { "z": 1 }], "w~": 1 },
+  });
+  let to = json!({
+    "name": "b",
+    "added": null,
+    "list": [1, 2],
+    "nested": { "x/y": [1, { "z": 2 }], "w~": 2 },
+  });
+  let patch = diff_json(&from, &to);
+  assert_eq!(patch_input(&from, &patch), Ok(to.clone()));
+  assert_eq!(diff_json(&to, &to), Vec::new());
+}
+
+#[test]
+fn touches_paths_at_under_and_above_a_prefix() {
+  let replace = |path: &str| JsonPatchOp::Replace {
+    path: path.to_string(),
+    value: Value::Null,
+  };
+  assert!(replace("/terrainGen/stage").touches("/terrainGen"));
+  assert!(replace("/terrainGen").touches("/terrainGen/stage"));
+  assert!(!replace("/terrainGenX").touches("/terrainGen"));
+  assert!(!replace("/name").touches("/terrainGen"));
+
+  // The root is above every path, and every path is under it.
+  assert!(replace("").touches("/terrainGen"));
+  assert!(replace("/name").touches(""));
+}
@@ -0,0 +1,156 @@
+use serde_json::json;
+use crate::{
+  data::{
+    map::WorldDims,
+    ruleset::PaletteRules,
+    LayerExportFormat,
+  },
+  protocol::{
+    mode::view_world::ViewWorldSubcmdEnvelope,
+    CommandEnvelope,
+  },
+};
+
+/** Three columns and two rows, with values that clamp in 16 bits. */
+const VALUES: [u32; 6] = [0, 1, 0xFFFF, 0x1_0000, 0x1234, u32::MAX];
+
+fn dims() -> WorldDims {
+  WorldDims::new(3, 2)
+}
+
+fn palette_with(stops: serde_json::Value) -> PaletteRules {
+  serde_json::from_value(json!({ "name": "Test", "stops": stops })).unwrap()
+}
+
+fn grey() -> PaletteRules {
+  palette_with(json!([
+    { "value": 100, "color": [0, 0, 0] },
+    { "value": 200, "color": [200, 100, 50] },
+  ]))
+}
+
+fn encode(format: LayerExportFormat, palette: Option<&PaletteRules>)
+  -> Vec<u8>
+{
+  format.encode(dims(), &VALUES, palette).unwrap()
+}
+
+#[test]
+fn pgm16_is_big_endian_and_clamped() {
+  let bytes = encode(LayerExportFormat::Pgm16, None);
+  let header = b"P5\n3 2\n65535\n";
+  assert_eq!(&bytes[.. header.len()], header);
+  assert_eq!(&bytes[header.len() ..], &[
+    0x00, 0x00, 0x00, 0x01, 0xFF, 0xFF,
+    0xFF, 0xFF, 0x12, 0x34, 0xFF, 0xFF,
+  ]);
+}
+
+#[test]
+fn palette_ppm_colours_each_value() {
+  let palette = grey();
+  let values = [50, 100, 150, 200, 250, 175];
+  let bytes = LayerExportFormat::PalettePpm
+    .encode(dims(), &values, Some(&palette))
+    .unwrap();
+  let header = b"P6\n3 2\n255\n";
+  assert_eq!(&bytes[.. header.len()], header);
+  assert_eq!(&bytes[header.len() ..], &[
+    0, 0, 0, 0, 0, 0, 100, 50, 25,
+    200, 100, 50, 200, 100, 50, 150, 75, 37,
+  ]);
+
+  assert_eq!(
+    LayerExportFormat::PalettePpm.encode(dims(), &values, None),
+    Err("A palette is required for PPM export".to_string())
+  );
+}
+
+#[test]
+fn raw_formats_are_little_endian() {
+  assert_eq!(encode(LayerExportFormat::Raw16, None), vec![
+    0x00, 0x00, 0x01, 0x00, 0xFF, 0xFF,
+    0xFF, 0xFF, 0x34, 0x12, 0xFF, 0xFF,
+  ]);
+  let raw32 = encode(LayerExportFormat::Raw32, None);
+  assert_eq!(raw32.len(), VALUES.len() * 4);
+  assert_eq!(&raw32[12 .. 16], &[0x00, 0x00, 0x01, 0x00]);
+  assert_eq!(&raw32[16 .. 20], &[0x34, 0x12, 0x00, 0x00]);
+  assert_eq!(&raw32[20 ..], &[0xFF; 4]);
+}
+
+#[test]
+fn encode_checks_the_number_of_values() {
+  assert_eq!(
+    LayerExportFormat::Raw32.encode(dims(), &VALUES[1 ..], None),
+    Err("Layer has 5 values, expected 6".to_string())
+  );
+}
+
+#[test]
+fn filenames_stay_inside_the_exports_directory() {
+  assert_eq!(
+    LayerExportFormat::PalettePpm.filename("valley-north_2"),
+    Ok("valley-north_2.ppm".to_string())
+  );
+  assert_eq!(
+    LayerExportFormat::Raw16.filename("heights"),
+    Ok("heights.r16".to_string())
+  );
+  for stem in ["", "../escape", "a.b", "with space", &"x".repeat(65)] {
+    assert!(LayerExportFormat::Pgm16.filename(stem).is_err(), "{}", stem);
+  }
+  assert_eq!(
+    LayerExportFormat::from_name("PGM"),
+    Some(LayerExportFormat::Pgm16)
+  );
+  assert_eq!(
+    LayerExportFormat::from_name("raw32"),
+    Some(LayerExportFormat::Raw32)
+  );
+  assert_eq!(LayerExportFormat::from_name("png"), None);
+}
+
+#[test]
+fn palette_colours_interpolate_between_stops() {
+  let palette = grey();
+  assert_eq!(palette.color_for(0), [0, 0, 0]);
+  assert_eq!(palette.color_for(125), [50, 25, 12]);
+  assert_eq!(palette.color_for(200), [200, 100, 50]);
+  assert_eq!(palette.color_for(u32::MAX), [200, 100, 50]);
+
+  let example = PaletteRules::new_example();
+  assert_eq!(example.color_for(0x7FFF), [0x40, 0xA0, 0x40]);
+  assert_eq!(example.color_for(0xFFFF), [0xF0, 0xF0, 0xF0]);
+
+  assert_eq!(palette_with(json!([])).color_for(7), [0, 0, 0]);
+  let single = palette_with(json!([{ "value": 10, "color": [1, 2, 3] }]));
+  assert_eq!(single.color_for(0), [1, 2, 3]);
+  assert_eq!(single.color_for(20), [1, 2, 3]);
+}
+
+#[test]
+fn saved_worlds_export_map_layers() {
+  let command: CommandEnvelope = serde_json::from_str(r#"{
+    "ViewWorldSubcmd": {
+      "ExportMapLayer": {
+        "datumId": {
+          "Selector": { "word": "Terrain", "component": "Height" }
+        },
+        "format": "Pgm16",
+        "filename": "height"
+      }
+    }
+  }"#).unwrap();
+  match &command {
+    CommandEnvelope::ViewWorldSubcmd(
+      ViewWorldSubcmdEnvelope::ExportMapLayer(cmd)
+    ) => {
+      assert_eq!(cmd.format, LayerExportFormat::Pgm16);
+      assert!(cmd.top_left.is_none() && cmd.dims.is_none());
+    },
+    other => panic!("Expected a ViewWorld export, got {:?}", other),
+  }
+  // Exports write files, so observers may not send them.
+  assert!(!command.is_read_only());
+}
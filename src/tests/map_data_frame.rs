@@ -0,0 +1,141 @@
+use crate::{
+  data::map::{ CellCoord, WorldDims },
+  protocol::{
+    encode_map_data_frame,
+    mode::create_world::GetMapDataRsp,
+  },
+};
+
+/** A reader over a frame that checks each field's offset as it goes. */
+struct FrameReader<'a> {
+  frame: &'a [u8],
+  offset: usize,
+}
+impl<'a> FrameReader<'a> {
+  fn new(frame: &'a [u8]) -> Self {
+    FrameReader { frame, offset: 0 }
+  }
+
+  fn take<const N: usize>(&mut self, offset: usize) -> [u8; N] {
+    assert_eq!(self.offset, offset, "field offset");
+    let bytes = self.frame[self.offset .. self.offset + N].try_into().unwrap();
+    self.offset += N;
+    bytes
+  }
+
+  fn u16_at(&mut self, offset: usize) -> u16 {
+    u16::from_le_bytes(self.take(offset))
+  }
+
+  fn u32_at(&mut self, offset: usize) -> u32 {
+    u32::from_le_bytes(self.take(offset))
+  }
+
+  fn u64_at(&mut self, offset: usize) -> u64 {
+    u64::from_le_bytes(self.take(offset))
+  }
+
+  fn u16(&mut self) -> u16 {
+    self.u16_at(self.offset)
+  }
+
+  fn u32(&mut self) -> u32 {
+    self.u32_at(self.offset)
+  }
+}
+
+fn map_data(data: Vec<Vec<u32>>) -> GetMapDataRsp {
+  GetMapDataRsp {
+    top_left: CellCoord::new(300, 7),
+    dims: WorldDims::new(3, 2),
+    data,
+    decoded: None,
+  }
+}
+
+/**
+ * Decode the fixed header of `frame`, checking it against `rsp`, and
+ * return a reader at the start of the layers with their widths.
+ */
+fn read_header<'a>(
+  frame: &'a [u8],
+  id: Option<u64>,
+  rsp: &GetMapDataRsp,
+) -> (FrameReader<'a>, Vec<u8>) {
+  let mut reader = FrameReader::new(frame);
+  assert_eq!(&reader.take::<4>(0), b"RRMD");
+  assert_eq!(reader.u16_at(4), 1);
+  assert_eq!(reader.u16_at(6) as usize, rsp.data.len());
+  assert_eq!(reader.u32_at(8), if id.is_some() { 1 } else { 0 });
+  assert_eq!(reader.u64_at(12), id.unwrap_or(0));
+  assert_eq!(reader.u16_at(20), rsp.top_left.col);
+  assert_eq!(reader.u16_at(22), rsp.top_left.row);
+  assert_eq!(reader.u16_at(24), rsp.dims.columns);
+  assert_eq!(reader.u16_at(26), rsp.dims.rows);
+
+  let widths = frame[28 .. 28 + rsp.data.len()].to_vec();
+  let layers_start = (28 + widths.len()).next_multiple_of(4);
+  assert!(
+    frame[28 + widths.len() .. layers_start].iter().all(|b| *b == 0),
+    "padding is zero"
+  );
+  reader.offset = layers_start;
+  (reader, widths)
+}
+
+#[test]
+fn encodes_header_and_mixed_width_layers() {
+  let rsp = map_data(vec![
+    vec![0, 1, 2, 3, 4, u16::MAX as u32],
+    vec![0, 1, 2, 3, 4, u16::MAX as u32 + 1],
+    vec![7; 6],
+  ]);
+  let frame = encode_map_data_frame(Some(0x0102_0304_0506_0708), &rsp);
+  let (mut reader, widths) =
+    read_header(&frame, Some(0x0102_0304_0506_0708), &rsp);
+
+  // A layer is widened only if some value needs more than 16 bits.
+  assert_eq!(widths, [2, 4, 2]);
+  // Three width bytes after the 28-byte header pad to 32.
+  assert_eq!(reader.offset, 32);
+
+  let first: Vec<u32> = (0 .. 6).map(|_| reader.u16() as u32).collect();
+  assert_eq!(first, rsp.data[0]);
+  let second: Vec<u32> = (0 .. 6).map(|_| reader.u32()).collect();
+  assert_eq!(second, rsp.data[1]);
+  let third: Vec<u32> = (0 .. 6).map(|_| reader.u16() as u32).collect();
+  assert_eq!(third, rsp.data[2]);
+  assert_eq!(reader.offset, frame.len());
+  assert_eq!(frame.len(), 32 + 6 * 2 + 6 * 4 + 6 * 2);
+}
+
+#[test]
+fn encodes_frame_without_id() {
+  let rsp = map_data(vec![vec![70_000, 1, 2, 3, 4, 5]]);
+  let frame = encode_map_data_frame(None, &rsp);
+  let (mut reader, widths) = read_header(&frame, None, &rsp);
+  assert_eq!(widths, [4]);
+  assert_eq!(&frame[29 .. 32], [0, 0, 0]);
+
+  let layer: Vec<u32> = (0 .. 6).map(|_| reader.u32()).collect();
+  assert_eq!(layer, rsp.data[0]);
+  assert_eq!(frame.len(), 32 + 6 * 4);
+}
+
+#[test]
+fn pads_only_to_next_multiple_of_four() {
+  // Four width bytes end the header on a 4-byte boundary already.
+  let rsp = map_data(vec![vec![1; 6]; 4]);
+  let frame = encode_map_data_frame(Some(3), &rsp);
+  let (reader, widths) = read_header(&frame, Some(3), &rsp);
+  assert_eq!(widths, [2; 4]);
+  assert_eq!(reader.offset, 32);
+  assert_eq!(frame.len(), 32 + 4 * 6 * 2);
+
+  // Five spill into the next word and pad by three.
+  let rsp = map_data(vec![vec![1; 6]; 5]);
+  let frame = encode_map_data_frame(Some(3), &rsp);
+  let (reader, _) = read_header(&frame, Some(3), &rsp);
+  assert_eq!(reader.offset, 36);
+  assert_eq!(frame.len(), 36 + 5 * 6 * 2);
+}
@@ -1 +1,59 @@
+use std::{ env, fs, ops::Deref, path::{ Path, PathBuf } };
 
+mod command_journal;
+mod command_recovery;
+mod data_store;
+mod diagnostics;
+mod format_type;
+mod format_validation;
+mod heightmap_decode;
+mod json_patch;
+mod layer_export;
+mod map_data_frame;
+mod protocol_schema;
+mod protocol_typescript;
+mod rules_history;
+mod ruleset_bundle;
+mod ruleset_commands;
+mod ruleset_migration;
+mod ruleset_store;
+mod server_events;
+mod server_sessions;
+mod shady_interpreter;
+mod terrain_gen_params;
+mod terrain_gen_stage_cache;
+mod view_world;
+mod world_file;
+
+/**
+ * A data root under the temporary directory, unique to the test `name`
+ * and this process, and removed with everything in it when dropped.
+ * Declare it before any store opened on it, so that it is dropped last.
+ */
+pub(crate) struct TempDataRoot {
+  path: PathBuf,
+}
+impl TempDataRoot {
+  pub(crate) fn new(name: &str) -> Self {
+    let path = env::temp_dir()
+      .join(format!("renfrew_river_test_{}_{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&path);
+    TempDataRoot { path }
+  }
+}
+impl Deref for TempDataRoot {
+  type Target = Path;
+  fn deref(&self) -> &Path {
+    &self.path
+  }
+}
+impl Drop for TempDataRoot {
+  fn drop(&mut self) {
+    // A test may have put a file in the directory's place.
+    if self.path.is_dir() {
+      let _ = fs::remove_dir_all(&self.path);
+    } else {
+      let _ = fs::remove_file(&self.path);
+    }
+  }
+}
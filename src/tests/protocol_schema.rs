@@ -0,0 +1,41 @@
+use serde_json::json;
+use crate::{ check_protocol_examples, get_protocol_schemas, schema_violations };
+
+fn schema_named(name: &str) -> serde_json::Value {
+  get_protocol_schemas().into_iter()
+    .find(|document| document.name == name)
+    .unwrap()
+    .schema
+}
+
+#[test]
+fn protocol_examples_match_schemas() {
+  let problems = check_protocol_examples();
+  assert!(problems.is_empty(), "{}", problems.join("\n"));
+}
+
+#[test]
+fn command_frame_schema_allows_id() {
+  let schema = schema_named("CommandFrame");
+  let frame = json!({ "id": 7, "GetModeInfo": {} });
+  assert_eq!(schema_violations(&frame, &schema), Vec::<String>::new());
+}
+
+#[test]
+fn command_frame_schema_rejects_unknown_commands() {
+  let schema = schema_named("CommandFrame");
+  let frame = json!({ "id": 7, "NoSuchCommand": {} });
+  assert!(!schema_violations(&frame, &schema).is_empty());
+}
+
+#[test]
+fn schema_definition_names_are_distinct() {
+  // schemars numbers types that share a name, which clients would see.
+  for document in get_protocol_schemas() {
+    let definitions = document.schema["definitions"].as_object().unwrap();
+    for name in definitions.keys() {
+      assert!(!name.ends_with(|c: char| c.is_ascii_digit()),
+        "{} has a numbered definition: {}", document.name, name);
+    }
+  }
+}
@@ -0,0 +1,136 @@
+use std::{ fs, path::{ Path, PathBuf } };
+use crate::{ get_protocol_schemas, get_protocol_typescript };
+
+#[test]
+fn typescript_declares_every_schema_type() {
+  let typescript = get_protocol_typescript();
+  for document in get_protocol_schemas() {
+    let definitions = document.schema["definitions"].as_object().unwrap();
+    let names = definitions.keys().chain(std::iter::once(&document.name));
+    for name in names {
+      let declaration = format!("export type {} =", name);
+      assert!(typescript.contains(&declaration), "{} is not declared", name);
+    }
+  }
+}
+
+/** The declaration of a type, from `export type` to its closing `;`. */
+fn declaration<'a>(typescript: &'a str, name: &str) -> &'a str {
+  let start = typescript.find(&format!("export type {} =", name))
+    .unwrap_or_else(|| panic!("{} is not declared", name));
+  let rest = &typescript[start ..];
+  &rest[.. rest.find(";\n").unwrap() + 1]
+}
+
+/** The names of the members of a declared object, or the tags of a union. */
+fn member_names(declaration: &str) -> Vec<String> {
+  declaration.lines().skip(1)
+    .map(|line| line.trim_start_matches(|c| c == ' ' || c == '|' || c == '{'))
+    .filter_map(|line| line.split_once(':'))
+    .map(|(name, _)| name.trim().trim_end_matches('?').to_string())
+    .filter(|name| name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_'))
+    .filter(|name| !name.is_empty())
+    .collect()
+}
+
+#[test]
+fn typescript_follows_serde_renames() {
+  let typescript = get_protocol_typescript();
+  let ruleset = declaration(&typescript, "Ruleset");
+  assert!(
+    ruleset.contains("\n  terrainGen: TerrainGenRules,\n"),
+    "{}", ruleset
+  );
+  assert!(!ruleset.contains("terrain_gen"));
+  assert!(declaration(&typescript, "RulesetBundle")
+    .contains("\n  contentHash: string,\n"));
+}
+
+#[test]
+fn typescript_makes_skippable_fields_optional() {
+  let typescript = get_protocol_typescript();
+  let ruleset = declaration(&typescript, "Ruleset");
+  assert!(ruleset.contains("\n  base?: string | null,\n"), "{}", ruleset);
+  assert!(ruleset.contains("\n  palettes?: PaletteRules[],\n"));
+  assert!(ruleset.contains("\n  name: string,\n"));
+  assert!(declaration(&typescript, "RulesetBundle")
+    .contains("\n  programs?: RulesetBundlePrograms | null,\n"));
+}
+
+#[test]
+fn typescript_gives_enums_as_unions() {
+  let typescript = get_protocol_typescript();
+  assert_eq!(
+    declaration(&typescript, "GameModeInfo"),
+    "export type GameModeInfo =\n\
+    \x20 | {\n\
+    \x20   DefineRules: DefineRulesModeInfo,\n\
+    \x20 }\n\
+    \x20 | {\n\
+    \x20   CreateWorld: CreateWorldModeInfo,\n\
+    \x20 }\n\
+    \x20 | {\n\
+    \x20   ViewWorld: ViewWorldModeInfo,\n\
+    \x20 };"
+  );
+  assert_eq!(
+    declaration(&typescript, "RulesetImportConflict"),
+    "export type RulesetImportConflict = \"Fail\" | \"Rename\" | \"Overwrite\";"
+  );
+}
+
+/**
+ * The client's hand-written types may lag behind the protocol, but every
+ * member they declare must still be in the generated declaration.
+ */
+#[test]
+fn client_types_agree_with_the_generated_declarations() {
+  let typescript = get_protocol_typescript();
+  let types_dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+    .join("clients/protocol-client/src/types");
+  let mut checked = 0;
+  for path in typescript_files(&types_dir) {
+    let source = fs::read_to_string(&path).unwrap();
+    let mut lines = source.lines().peekable();
+    while let Some(line) = lines.next() {
+      let Some(name) = line.trim_start_matches("export ")
+        .strip_prefix("type ")
+        .and_then(|rest| rest.split_once(" ="))
+        .map(|(name, _)| name)
+      else { continue };
+      let mut client = line.to_string();
+      while let Some(next) = lines.next_if(|next| {
+        next.starts_with(' ') || next.starts_with('}')
+      }) {
+        client.push('\n');
+        client.push_str(next);
+      }
+      if !typescript.contains(&format!("export type {} =", name)) {
+        continue;
+      }
+      let generated = member_names(declaration(&typescript, name));
+      for member in member_names(&client) {
+        assert!(
+          generated.contains(&member),
+          "{:?}: {}.{} is not in the protocol", path, name, member
+        );
+      }
+      checked += 1;
+    }
+  }
+  assert!(checked >= 10, "Only {} client types were checked", checked);
+}
+
+fn typescript_files(dir: &Path) -> Vec<PathBuf> {
+  let mut files = Vec::new();
+  for entry in fs::read_dir(dir).unwrap() {
+    let path = entry.unwrap().path();
+    if path.is_dir() {
+      files.extend(typescript_files(&path));
+    } else if path.extension().is_some_and(|extension| extension == "ts") {
+      files.push(path);
+    }
+  }
+  files.sort();
+  files
+}
@@ -0,0 +1,97 @@
+use crate::{
+  data::ruleset::RulesetInput,
+  game::mode::RulesHistory,
+};
+
+fn input(name: &str) -> RulesetInput {
+  RulesetInput {
+    name: name.to_string(),
+    ..RulesetInput::new()
+  }
+}
+
+fn current_name(history: &RulesHistory) -> &str {
+  &history.current().name
+}
+
+fn revisions(history: &RulesHistory) -> Vec<(u64, String)> {
+  history.revision_infos().into_iter()
+    .map(|info| (info.revision, info.name))
+    .collect()
+}
+
+#[test]
+fn undo_and_redo_move_between_revisions() {
+  let mut history = RulesHistory::new(input("A"));
+  assert!(!history.undo());
+  assert!(!history.redo());
+
+  history.push(input("B"));
+  history.push(input("C"));
+  assert_eq!(current_name(&history), "C");
+  assert_eq!(history.current_revision(), 2);
+
+  assert!(history.undo());
+  assert!(history.undo());
+  assert_eq!(current_name(&history), "A");
+  assert_eq!(history.current_revision(), 0);
+  assert!(!history.undo());
+
+  assert!(history.redo());
+  assert_eq!(current_name(&history), "B");
+  assert!(history.redo());
+  assert!(!history.redo());
+  assert_eq!(current_name(&history), "C");
+
+  // Undo and redo keep every revision.
+  assert_eq!(revisions(&history), vec![
+    (0, "A".to_string()),
+    (1, "B".to_string()),
+    (2, "C".to_string()),
+  ]);
+  let infos = history.revision_infos();
+  assert!(infos.windows(2).all(|pair| pair[0].timestamp <= pair[1].timestamp));
+}
+
+#[test]
+fn pushing_after_undo_drops_the_undone_revisions() {
+  let mut history = RulesHistory::new(input("A"));
+  history.push(input("B"));
+  history.push(input("C"));
+  history.undo();
+  history.undo();
+
+  history.push(input("D"));
+  assert_eq!(current_name(&history), "D");
+  assert!(!history.redo());
+  // Revision numbers are not reused.
+  assert_eq!(revisions(&history), vec![
+    (0, "A".to_string()),
+    (3, "D".to_string()),
+  ]);
+  assert!(history.undo());
+  assert_eq!(current_name(&history), "A");
+}
+
+#[test]
+fn oldest_revisions_are_evicted() {
+  let mut history = RulesHistory::new(input("0"));
+  let pushes = RulesHistory::MAX_REVISIONS + 4;
+  for i in 1 ..= pushes {
+    history.push(input(&i.to_string()));
+  }
+
+  let infos = history.revision_infos();
+  assert_eq!(infos.len(), RulesHistory::MAX_REVISIONS);
+  assert_eq!(infos[0].revision, 5);
+  assert_eq!(infos[0].name, "5");
+  assert_eq!(history.current_revision(), pushes as u64);
+
+  // Undo stops at the oldest revision kept.
+  let mut undone = 0;
+  while history.undo() {
+    undone += 1;
+  }
+  assert_eq!(undone, RulesHistory::MAX_REVISIONS - 1);
+  assert_eq!(current_name(&history), "5");
+}
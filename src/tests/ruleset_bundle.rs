@@ -0,0 +1,160 @@
+use serde_json::{ json, Value };
+use crate::{
+  data::ruleset::{
+    Ruleset,
+    RulesetBundle,
+    RulesetImportConflict,
+    RulesetImportError,
+    RULESET_SCHEMA_VERSION,
+  },
+  data_store::DataStore,
+};
+use super::TempDataRoot;
+
+fn unpack_errors(bundle: &RulesetBundle) -> Vec<String> {
+  bundle.unpack().err().expect("Bundle unpacked")
+}
+
+/** Replace the bundled ruleset, keeping the content hash right. */
+fn set_ruleset(bundle: &mut RulesetBundle, ruleset: Value) {
+  bundle.content_hash = sha256::digest(ruleset.to_string());
+  bundle.ruleset = ruleset;
+}
+
+fn import_names(data_store: &DataStore, bundle: &RulesetBundle, times: usize)
+  -> Vec<String>
+{
+  (0 .. times)
+    .map(|_| {
+      bundle.import(data_store, RulesetImportConflict::Rename).unwrap().name
+    })
+    .collect()
+}
+
+#[test]
+fn pack_and_unpack_round_trip() {
+  let ruleset = Ruleset::new_example();
+  for include_programs in [false, true] {
+    let bundle = RulesetBundle::new(&ruleset, include_programs).unwrap();
+    assert_eq!(bundle.programs.is_some(), include_programs);
+    assert_eq!(bundle.schema_version, RULESET_SCHEMA_VERSION);
+    let json = serde_json::to_string(&bundle).unwrap();
+    let bundle: RulesetBundle = serde_json::from_str(&json).unwrap();
+    assert_eq!(
+      serde_json::to_value(bundle.unpack().unwrap()).unwrap(),
+      serde_json::to_value(&ruleset).unwrap()
+    );
+  }
+}
+
+#[test]
+fn derived_rulesets_are_bundled_flattened() {
+  let bundle = RulesetBundle::new(&Ruleset::new_derived_example(), false)
+    .unwrap();
+  let ruleset = bundle.unpack().unwrap();
+  assert_eq!(ruleset.base, None);
+  assert!(ruleset.overrides.is_empty());
+}
+
+#[test]
+fn unpack_rejects_a_hash_mismatch() {
+  let mut bundle = RulesetBundle::new_example();
+  bundle.ruleset["description"] = json!("Edited by hand.");
+  assert_eq!(
+    unpack_errors(&bundle),
+    vec!["Bundle content hash does not match its ruleset."]
+  );
+}
+
+#[test]
+fn unpack_checks_the_schema_version() {
+  let mut bundle = RulesetBundle::new_example();
+  bundle.schema_version = RULESET_SCHEMA_VERSION + 1;
+  assert_eq!(unpack_errors(&bundle), vec![format!(
+    "Bundle schema version {} is newer than supported version {}",
+    RULESET_SCHEMA_VERSION + 1, RULESET_SCHEMA_VERSION
+  )]);
+
+  let mut bundle = RulesetBundle::new_example();
+  bundle.schema_version = 0;
+  assert_eq!(unpack_errors(&bundle), vec![format!(
+    "Bundle schema version 0 does not match ruleset version {}",
+    RULESET_SCHEMA_VERSION
+  )]);
+
+  // A ruleset without a version takes the bundle's.
+  let mut bundle = RulesetBundle::new_example();
+  let mut ruleset = bundle.ruleset.clone();
+  ruleset.as_object_mut().unwrap().remove("schemaVersion");
+  set_ruleset(&mut bundle, ruleset);
+  assert_eq!(bundle.unpack().unwrap().schema_version, RULESET_SCHEMA_VERSION);
+}
+
+#[test]
+fn unpack_rejects_programs_that_do_not_match() {
+  let mut bundle = RulesetBundle::new(&Ruleset::new_example(), true).unwrap();
+  let programs = bundle.programs.as_mut().unwrap();
+  programs.init_program[0][0] ^= 1;
+  assert_eq!(
+    unpack_errors(&bundle),
+    vec!["Bundled programs do not match the ruleset's program text."]
+  );
+}
+
+#[test]
+fn unpack_rejects_other_kinds() {
+  let mut bundle = RulesetBundle::new_example();
+  bundle.kind = "Something".to_string();
+  assert_eq!(
+    unpack_errors(&bundle),
+    vec!["Not a ruleset bundle (kind \"Something\")"]
+  );
+}
+
+#[test]
+fn import_conflicts_fail_or_rename() {
+  let root = TempDataRoot::new("bundle_import_conflicts");
+  let data_store = DataStore::new(&root).unwrap();
+  let bundle = RulesetBundle::new_example();
+
+  assert_eq!(import_names(&data_store, &bundle, 1), vec!["Example Ruleset"]);
+  match bundle.import(&data_store, RulesetImportConflict::Fail) {
+    Err(RulesetImportError::Failed(messages)) => assert_eq!(
+      messages,
+      vec!["A ruleset with this name already exists: Example Ruleset"]
+    ),
+    other => panic!("Expected a failed import, got {:?}", other),
+  }
+
+  // Renamed imports take the first free name, from `(2)` up.
+  assert_eq!(
+    import_names(&data_store, &bundle, 2),
+    vec!["Example Ruleset (2)", "Example Ruleset (3)"]
+  );
+  let mut rulesets = data_store.rulesets().unwrap();
+  rulesets.delete("Example Ruleset (2)").unwrap();
+  assert_eq!(
+    import_names(&data_store, &bundle, 2),
+    vec!["Example Ruleset (2)", "Example Ruleset (4)"]
+  );
+}
+
+#[test]
+fn import_overwrite_replaces_the_stored_ruleset() {
+  let root = TempDataRoot::new("bundle_import_overwrite");
+  let data_store = DataStore::new(&root).unwrap();
+  RulesetBundle::new_example()
+    .import(&data_store, RulesetImportConflict::Fail)
+    .unwrap();
+
+  let mut bundle = RulesetBundle::new_example();
+  let mut ruleset = bundle.ruleset.clone();
+  ruleset["description"] = json!("Replaced.");
+  set_ruleset(&mut bundle, ruleset);
+  bundle.import(&data_store, RulesetImportConflict::Overwrite).unwrap();
+
+  let rulesets = data_store.rulesets().unwrap();
+  assert_eq!(rulesets.list().len(), 1);
+  let ruleset = rulesets.read("Example Ruleset").unwrap();
+  assert_eq!(ruleset.description, "Replaced.");
+}
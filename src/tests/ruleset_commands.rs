@@ -0,0 +1,317 @@
+use std::{ fs, path::Path };
+use futures::executor::block_on;
+use crate::{
+  data::{
+    map::{ CellData, WorldDescriptor, WorldDims, CELL_DATA_NUM_WORDS },
+    ruleset::{ Ruleset, RulesetBundle, RulesetImportConflict },
+    VecMap,
+  },
+  data_store::DataStore,
+  game::{ GameServer, GameServerConfig },
+  protocol::{
+    mode::{
+      define_rules::{
+        DefineRulesModeInfo,
+        DefineRulesSubcmdEnvelope,
+        LoadRulesCmd,
+      },
+      GameModeInfo,
+    },
+    CommandEnvelope,
+    DeleteRulesetCmd,
+    DuplicateRulesetCmd,
+    EnterModeCmd,
+    ImportRulesetCmd,
+    ListWorldsCmd,
+    RenameRulesetCmd,
+    ResponseEnvelope,
+  },
+};
+use super::TempDataRoot;
+
+fn ruleset(name: &str, base: Option<&str>) -> Ruleset {
+  Ruleset {
+    name: name.to_string(),
+    base: base.map(|base| base.to_string()),
+    ..Ruleset::new_example()
+  }
+}
+
+/**
+ * Store rulesets `Base`, `Derived` (based on `Base`), `Lone` and `Used`,
+ * and a world `Valley` generated with `Used`.
+ */
+fn populate(root: &Path) {
+  let data_store = DataStore::new(root).unwrap();
+  let mut rulesets = data_store.rulesets().unwrap();
+  for ruleset in [
+    ruleset("Base", None),
+    ruleset("Derived", Some("Base")),
+    ruleset("Lone", None),
+    ruleset("Used", None),
+  ] {
+    rulesets.write(&ruleset.name, &ruleset).unwrap();
+  }
+
+  let dims = WorldDims::new(2, 2);
+  let descriptor = WorldDescriptor {
+    name: "Valley".to_string(),
+    description: String::new(),
+    seed: "seed".to_string(),
+    dims,
+    ruleset_name: "Used".to_string(),
+    heightmap: None,
+  };
+  let cells = VecMap::new(dims, CellData::new([0; CELL_DATA_NUM_WORDS]));
+  let format = Ruleset::new_example().terrain_gen.stage.format;
+  data_store.worlds().unwrap().write(&descriptor, &format, &cells).unwrap();
+}
+
+fn start_server(root: &Path) -> GameServer {
+  populate(root);
+  GameServer::new(&GameServerConfig::new(root.to_str().unwrap().to_string()))
+    .unwrap()
+}
+
+fn perform(server: &mut GameServer, command: CommandEnvelope)
+  -> ResponseEnvelope
+{
+  block_on(server.submit_command(command)).unwrap()
+}
+
+fn delete(server: &mut GameServer, name: &str) -> ResponseEnvelope {
+  perform(server, CommandEnvelope::DeleteRuleset(DeleteRulesetCmd {
+    ruleset_name: name.to_string(),
+  }))
+}
+
+fn rename(server: &mut GameServer, name: &str, new_name: &str)
+  -> ResponseEnvelope
+{
+  perform(server, CommandEnvelope::RenameRuleset(RenameRulesetCmd {
+    ruleset_name: name.to_string(),
+    new_name: new_name.to_string(),
+  }))
+}
+
+fn duplicate(server: &mut GameServer, name: &str, new_name: &str)
+  -> ResponseEnvelope
+{
+  perform(server, CommandEnvelope::DuplicateRuleset(DuplicateRulesetCmd {
+    ruleset_name: name.to_string(),
+    new_name: new_name.to_string(),
+  }))
+}
+
+/** Import a bundle of ruleset `name`, overwriting the stored one. */
+fn overwrite(server: &mut GameServer, name: &str) -> ResponseEnvelope {
+  let bundle = RulesetBundle::new(&ruleset(name, None), false).unwrap();
+  perform(server, CommandEnvelope::ImportRuleset(ImportRulesetCmd {
+    bundle,
+    on_conflict: RulesetImportConflict::Overwrite,
+  }))
+}
+
+fn load_rules(server: &mut GameServer, name: &str) {
+  perform(server, CommandEnvelope::EnterMode(EnterModeCmd {
+    mode: GameModeInfo::DefineRules(DefineRulesModeInfo {}),
+  }));
+  perform(server, CommandEnvelope::DefineRulesSubcmd(
+    DefineRulesSubcmdEnvelope::LoadRules(LoadRulesCmd {
+      ruleset_name: name.to_string(),
+    })
+  ));
+}
+
+fn assert_ok(response: ResponseEnvelope) {
+  assert!(matches!(response, ResponseEnvelope::Ok {}), "{:?}", response);
+}
+
+fn failed_messages(response: ResponseEnvelope) -> Vec<String> {
+  match response {
+    ResponseEnvelope::Failed(failed) => failed.messages().to_vec(),
+    other => panic!("Expected a Failed response, got {:?}", other),
+  }
+}
+
+/** The names of the stored rulesets, sorted. */
+fn stored_names(root: &Path) -> Vec<String> {
+  let rulesets = DataStore::new_read_only(root).rulesets().unwrap();
+  let mut names = rulesets.list().into_iter()
+    .map(|entry| entry.name)
+    .collect::<Vec<_>>();
+  names.sort();
+  names
+}
+
+fn world_ruleset_name(server: &mut GameServer) -> String {
+  match perform(server, CommandEnvelope::ListWorlds(ListWorldsCmd {})) {
+    ResponseEnvelope::WorldList(worlds) => worlds[0].ruleset_name.clone(),
+    other => panic!("Expected a WorldList response, got {:?}", other),
+  }
+}
+
+#[test]
+fn delete_removes_an_unreferenced_ruleset() {
+  let root = TempDataRoot::new("ruleset_cmd_delete");
+  let mut server = start_server(&root);
+  assert_ok(delete(&mut server, "Lone"));
+  assert_eq!(stored_names(&root), vec!["Base", "Derived", "Used"]);
+  assert_ok(delete(&mut server, "Derived"));
+  assert_ok(delete(&mut server, "Base"));
+  assert_eq!(stored_names(&root), vec!["Used"]);
+}
+
+#[test]
+fn delete_refuses_referenced_rulesets() {
+  let root = TempDataRoot::new("ruleset_cmd_delete_refused");
+  let mut server = start_server(&root);
+  assert_eq!(
+    failed_messages(delete(&mut server, "Base")),
+    vec!["Ruleset is the base of ruleset: Derived"]
+  );
+  assert_eq!(
+    failed_messages(delete(&mut server, "Used")),
+    vec!["Ruleset is used by saved world: Valley"]
+  );
+  assert_eq!(
+    failed_messages(delete(&mut server, "Missing")),
+    vec!["No such ruleset: Missing"]
+  );
+
+  load_rules(&mut server, "Lone");
+  assert_eq!(
+    failed_messages(delete(&mut server, "Lone")),
+    vec!["Ruleset is in use by the current mode: Lone"]
+  );
+  assert_eq!(stored_names(&root), vec!["Base", "Derived", "Lone", "Used"]);
+}
+
+#[test]
+fn rename_updates_worlds_and_derived_rulesets() {
+  let root = TempDataRoot::new("ruleset_cmd_rename");
+  let mut server = start_server(&root);
+  assert_ok(rename(&mut server, "Used", "In Use (v2)"));
+  assert_eq!(world_ruleset_name(&mut server), "In Use (v2)");
+
+  assert_ok(rename(&mut server, "Base", "New Base"));
+  let rulesets = DataStore::new_read_only(&root).rulesets().unwrap();
+  let derived = rulesets.read("Derived").unwrap();
+  assert_eq!(derived.base.as_deref(), Some("New Base"));
+  assert_eq!(
+    stored_names(&root),
+    vec!["Derived", "In Use (v2)", "Lone", "New Base"]
+  );
+}
+
+#[test]
+fn rename_refuses_rulesets_in_use_and_bad_names() {
+  let root = TempDataRoot::new("ruleset_cmd_rename_refused");
+  let mut server = start_server(&root);
+  assert_eq!(
+    failed_messages(rename(&mut server, "Lone", "Base")),
+    vec!["A ruleset with this name already exists."]
+  );
+  let invalid_name = vec![
+    "The name must be letters, digits, spaces and _-.'(), \
+     not starting or ending with a space."
+  ];
+  assert_eq!(
+    failed_messages(rename(&mut server, "Lone", "Lone/Copy")),
+    invalid_name
+  );
+  assert_eq!(
+    failed_messages(rename(&mut server, "Lone", " Lone")),
+    invalid_name
+  );
+  assert_eq!(
+    failed_messages(rename(&mut server, "Lone", &"L".repeat(65))),
+    vec!["The name is too long."]
+  );
+  assert_eq!(
+    failed_messages(rename(&mut server, "Lone", "")),
+    vec!["The name is required."]
+  );
+
+  load_rules(&mut server, "Lone");
+  assert_eq!(
+    failed_messages(rename(&mut server, "Lone", "Other")),
+    vec!["Ruleset is in use by the current mode: Lone"]
+  );
+  assert_eq!(stored_names(&root), vec!["Base", "Derived", "Lone", "Used"]);
+}
+
+#[test]
+fn rename_is_undone_when_worlds_cannot_be_updated() {
+  let root = TempDataRoot::new("ruleset_cmd_rename_rollback");
+  let mut server = start_server(&root);
+  fs::write(root.join("worlds").join("worlds.json"), "not json").unwrap();
+
+  let messages = failed_messages(rename(&mut server, "Used", "Renamed"));
+  assert_eq!(messages.len(), 1);
+  assert!(messages[0].starts_with("Failed to update world store"));
+  assert_eq!(stored_names(&root), vec!["Base", "Derived", "Lone", "Used"]);
+}
+
+#[test]
+fn duplicate_copies_a_ruleset_under_a_new_name() {
+  let root = TempDataRoot::new("ruleset_cmd_duplicate");
+  let mut server = start_server(&root);
+
+  // A ruleset in use, or referenced, may still be copied.
+  load_rules(&mut server, "Used");
+  assert_ok(duplicate(&mut server, "Used", "Used copy"));
+  assert_ok(duplicate(&mut server, "Derived", "Derived copy"));
+  assert_eq!(
+    stored_names(&root),
+    vec!["Base", "Derived", "Derived copy", "Lone", "Used", "Used copy"]
+  );
+  let rulesets = DataStore::new_read_only(&root).rulesets().unwrap();
+  let copy = rulesets.read("Derived copy").unwrap();
+  assert_eq!(copy.name, "Derived copy");
+  assert_eq!(copy.base.as_deref(), Some("Base"));
+  assert_eq!(world_ruleset_name(&mut server), "Used");
+}
+
+#[test]
+fn duplicate_refuses_taken_and_missing_names() {
+  let root = TempDataRoot::new("ruleset_cmd_duplicate_refused");
+  let mut server = start_server(&root);
+  assert_eq!(
+    failed_messages(duplicate(&mut server, "Lone", "Used")),
+    vec!["A ruleset with this name already exists."]
+  );
+  assert_eq!(
+    failed_messages(duplicate(&mut server, "Missing", "Copy")),
+    vec!["No such ruleset: Missing"]
+  );
+  assert_eq!(stored_names(&root), vec!["Base", "Derived", "Lone", "Used"]);
+}
+
+#[test]
+fn import_overwrite_refuses_referenced_rulesets() {
+  let root = TempDataRoot::new("ruleset_cmd_overwrite_refused");
+  let mut server = start_server(&root);
+  assert_eq!(
+    failed_messages(overwrite(&mut server, "Base")),
+    vec!["Ruleset is the base of ruleset: Derived"]
+  );
+  assert_eq!(
+    failed_messages(overwrite(&mut server, "Used")),
+    vec!["Ruleset is used by saved world: Valley"]
+  );
+  load_rules(&mut server, "Lone");
+  assert_eq!(
+    failed_messages(overwrite(&mut server, "Lone")),
+    vec!["Ruleset is in use by the current mode: Lone"]
+  );
+
+  // A ruleset that nothing refers to may be overwritten.
+  assert!(matches!(
+    overwrite(&mut server, "Derived"),
+    ResponseEnvelope::ImportedRuleset(_)
+  ));
+  let rulesets = DataStore::new_read_only(&root).rulesets().unwrap();
+  assert_eq!(rulesets.read("Derived").unwrap().base, None);
+  assert_eq!(stored_names(&root), vec!["Base", "Derived", "Lone", "Used"]);
+}
@@ -0,0 +1,105 @@
+use std::fs;
+use serde_json::Value;
+use crate::{
+  data::ruleset::{ parse_ruleset_json, Ruleset, RULESET_SCHEMA_VERSION },
+  data_store::{ DataStore, RulesetMigrationResult },
+};
+use super::TempDataRoot;
+
+/** The example ruleset as written before versioning, without a version. */
+fn v0_fixture(name: &str) -> String {
+  let ruleset = Ruleset {
+    name: name.to_string(),
+    ..Ruleset::new_example()
+  };
+  let mut value = serde_json::to_value(ruleset).unwrap();
+  value.as_object_mut().unwrap().remove("schemaVersion");
+  value.to_string()
+}
+
+fn stored_version(json: &str) -> Option<u64> {
+  let value: Value = serde_json::from_str(json).unwrap();
+  value.get("schemaVersion").and_then(|version| version.as_u64())
+}
+
+#[test]
+fn v0_ruleset_is_migrated_to_v1() {
+  let migrated = parse_ruleset_json(&v0_fixture("Old")).unwrap();
+  assert_eq!(migrated.from_version, 0);
+  assert!(migrated.was_migrated());
+  assert_eq!(migrated.ruleset.schema_version, 1);
+  assert_eq!(migrated.ruleset.name, "Old");
+
+  let json = serde_json::to_string(&Ruleset::new_example()).unwrap();
+  let current = parse_ruleset_json(&json).unwrap();
+  assert_eq!(current.from_version, RULESET_SCHEMA_VERSION);
+  assert!(!current.was_migrated());
+}
+
+#[test]
+fn missing_schema_version_deserializes_as_v0() {
+  let ruleset: Ruleset = serde_json::from_str(&v0_fixture("Old")).unwrap();
+  assert_eq!(ruleset.schema_version, 0);
+}
+
+#[test]
+fn unknown_schema_versions_are_rejected() {
+  let mut value: Value = serde_json::from_str(&v0_fixture("New")).unwrap();
+  value["schemaVersion"] = Value::from(RULESET_SCHEMA_VERSION + 1);
+  assert_eq!(
+    parse_ruleset_json(&value.to_string()).err().unwrap(),
+    format!(
+      "Ruleset schema version {} is newer than supported version {}",
+      RULESET_SCHEMA_VERSION + 1, RULESET_SCHEMA_VERSION
+    )
+  );
+
+  value["schemaVersion"] = Value::from("one");
+  assert_eq!(
+    parse_ruleset_json(&value.to_string()).err().unwrap(),
+    "Invalid schemaVersion: \"one\""
+  );
+}
+
+#[test]
+fn migrate_all_rewrites_old_rulesets_on_disk() {
+  let root = TempDataRoot::new("ruleset_migrate_all");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut rulesets = data_store.rulesets().unwrap();
+  for name in ["Current", "Old", "Broken"] {
+    let ruleset = Ruleset {
+      name: name.to_string(),
+      ..Ruleset::new_example()
+    };
+    rulesets.write(name, &ruleset).unwrap();
+  }
+  let dir = root.join("rulesets");
+  let filename = |name: &str| {
+    rulesets.list().into_iter()
+      .find(|entry| entry.name == name)
+      .unwrap()
+      .filename
+  };
+  let (old_file, broken_file) = (filename("Old"), filename("Broken"));
+  fs::write(dir.join(&old_file), v0_fixture("Old")).unwrap();
+  fs::write(dir.join(&broken_file), "{").unwrap();
+
+  let mut results = rulesets.migrate_all();
+  results.sort_by(|a, b| a.0.cmp(&b.0));
+  assert_eq!(results[0].0, "Broken");
+  assert!(matches!(results[0].1, RulesetMigrationResult::Failed(_)));
+  assert_eq!(results[1..], [
+    ("Current".to_string(), RulesetMigrationResult::UpToDate),
+    ("Old".to_string(), RulesetMigrationResult::Migrated { from_version: 0 }),
+  ]);
+
+  let old_json = fs::read_to_string(dir.join(&old_file)).unwrap();
+  assert_eq!(stored_version(&old_json), Some(RULESET_SCHEMA_VERSION as u64));
+  assert_eq!(fs::read_to_string(dir.join(&broken_file)).unwrap(), "{");
+
+  // Once rewritten, the ruleset is up to date.
+  let results = rulesets.migrate_all();
+  assert!(results.contains(
+    &("Old".to_string(), RulesetMigrationResult::UpToDate)
+  ));
+}
@@ -0,0 +1,151 @@
+use std::io;
+use crate::{
+  data::ruleset::{ Ruleset, RulesetOverrides },
+  data_store::{ DataStore, RulesetStore },
+  shady_vm::{ ShadyRegister, ShasmProgram },
+};
+use super::TempDataRoot;
+
+fn base_ruleset() -> Ruleset {
+  Ruleset {
+    name: "Base".to_string(),
+    ..Ruleset::new_example()
+  }
+}
+
+/**
+ * A ruleset derived from `base`, stored with the base's rules rather than
+ * its effective ones, so that reading it must apply the overrides.
+ */
+fn derived_ruleset(name: &str, base: &Ruleset) -> Ruleset {
+  let mut overrides = RulesetOverrides::new_example();
+  overrides.perlin = Some(base.terrain_gen.perlin.clone());
+  overrides.perlin.as_mut().unwrap().register = ShadyRegister::new(93);
+  Ruleset {
+    name: name.to_string(),
+    base: Some(base.name.clone()),
+    overrides,
+    ..base.clone()
+  }
+}
+
+fn store_all(store: &mut RulesetStore, rulesets: &[Ruleset]) {
+  for ruleset in rulesets {
+    store.write(&ruleset.name, ruleset).unwrap();
+  }
+}
+
+fn has_added_component(ruleset: &Ruleset) -> bool {
+  ruleset.terrain_gen.stage.format
+    .component("ExampleWord", "Component3")
+    .is_some()
+}
+
+#[test]
+fn reads_derived_ruleset_with_overrides_applied() {
+  let root = TempDataRoot::new("ruleset_overrides");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut store = data_store.rulesets().unwrap();
+  let base = base_ruleset();
+  store_all(&mut store, &[base.clone(), derived_ruleset("Derived", &base)]);
+
+  let read_base = store.read("Base").unwrap();
+  assert!(!has_added_component(&read_base));
+  assert_eq!(read_base.terrain_gen.perlin.register.to_u8(), 92);
+
+  let derived = store.read("Derived").unwrap();
+  assert_eq!(derived.base.as_deref(), Some("Base"));
+  assert!(has_added_component(&derived));
+  assert_eq!(derived.terrain_gen.perlin.register.to_u8(), 93);
+
+  // Changes to the base carry through to what it does not override.
+  let mut changed_base = base.clone();
+  changed_base.terrain_gen.stage.pairwise_program =
+    ShasmProgram { program_text: "add r0, r2, r1".to_string() };
+  store.write("Base", &changed_base).unwrap();
+  let derived = store.read("Derived").unwrap();
+  assert_eq!(
+    derived.terrain_gen.stage.pairwise_program.program_text,
+    "add r0, r2, r1"
+  );
+  assert_eq!(derived.terrain_gen.perlin.register.to_u8(), 93);
+}
+
+#[test]
+fn follows_chain_of_bases() {
+  let root = TempDataRoot::new("ruleset_chain");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut store = data_store.rulesets().unwrap();
+  let base = base_ruleset();
+  let middle = derived_ruleset("Middle", &base);
+  let top = Ruleset {
+    name: "Top".to_string(),
+    base: Some("Middle".to_string()),
+    overrides: RulesetOverrides::default(),
+    ..base.clone()
+  };
+  store_all(&mut store, &[base, middle, top]);
+
+  assert_eq!(store.base_chain("Top").unwrap(), ["Top", "Middle", "Base"]);
+  assert_eq!(store.base_chain("Base").unwrap(), ["Base"]);
+  // The overrides of every ruleset along the chain apply.
+  assert!(has_added_component(&store.read("Top").unwrap()));
+  assert_eq!(store.derived_from("Base"), ["Middle"]);
+  assert_eq!(store.derived_from("Middle"), ["Top"]);
+  assert!(store.derived_from("Top").is_empty());
+}
+
+#[test]
+fn rejects_inheritance_cycles() {
+  let root = TempDataRoot::new("ruleset_cycle");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut store = data_store.rulesets().unwrap();
+  let based_on = |name: &str, base: &str| Ruleset {
+    name: name.to_string(),
+    base: Some(base.to_string()),
+    ..Ruleset::new_example()
+  };
+  store_all(&mut store, &[
+    based_on("A", "B"),
+    based_on("B", "A"),
+    based_on("Self", "Self"),
+  ]);
+
+  for name in ["A", "B", "Self"] {
+    let err = store.read(name).err().unwrap();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  }
+  let err = store.read("A").err().unwrap();
+  assert_eq!(err.to_string(), "Ruleset inheritance cycle: A -> B -> A");
+  let err = store.base_chain("A").unwrap_err();
+  assert_eq!(err.to_string(), "Ruleset inheritance cycle: A -> B -> A");
+  let err = store.base_chain("Self").unwrap_err();
+  assert_eq!(err.to_string(), "Ruleset inheritance cycle: Self -> Self");
+}
+
+#[test]
+fn renaming_base_updates_derived_rulesets() {
+  let root = TempDataRoot::new("ruleset_rename_base");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut store = data_store.rulesets().unwrap();
+  let base = base_ruleset();
+  store_all(&mut store, &[
+    base.clone(),
+    derived_ruleset("First", &base),
+    derived_ruleset("Second", &base),
+  ]);
+
+  store.rename("Base", "Renamed").unwrap();
+  assert!(!store.contains("Base"));
+  assert!(store.derived_from("Base").is_empty());
+
+  // A freshly opened store reads the same index and files.
+  let store = data_store.rulesets().unwrap();
+  let mut derived = store.derived_from("Renamed");
+  derived.sort();
+  assert_eq!(derived, ["First", "Second"]);
+  assert_eq!(store.base_chain("First").unwrap(), ["First", "Renamed"]);
+  let first = store.read("First").unwrap();
+  assert_eq!(first.base.as_deref(), Some("Renamed"));
+  assert!(has_added_component(&first));
+}
@@ -0,0 +1,171 @@
+use std::path::Path;
+use futures::{ channel::mpsc::UnboundedReceiver, executor::block_on };
+use crate::{
+  data::ruleset::{ Ruleset, RulesetBundle, RulesetImportConflict },
+  game::{ GameServer, GameServerConfig },
+  protocol::{
+    mode::{
+      define_rules::DefineRulesModeInfo,
+      view_world::ViewWorldModeInfo,
+      GameModeInfo,
+    },
+    CommandEnvelope,
+    DeleteRulesetCmd,
+    EnterMainMenuModeCmd,
+    EnterModeCmd,
+    EventEnvelope,
+    ImportRulesetCmd,
+    ListWorldsCmd,
+    RenameRulesetCmd,
+    ResponseEnvelope,
+  },
+};
+use super::TempDataRoot;
+
+fn start_server(root: &Path) -> GameServer {
+  GameServer::new(&GameServerConfig::new(root.to_str().unwrap().to_string()))
+    .unwrap()
+}
+
+fn perform(server: &mut GameServer, command: CommandEnvelope)
+  -> ResponseEnvelope
+{
+  block_on(server.submit_command(command)).unwrap()
+}
+
+/**
+ * The events published so far.  They are published before the command's
+ * response is sent, so every command performed has published its events.
+ */
+fn published(events: &mut UnboundedReceiver<EventEnvelope>)
+  -> Vec<EventEnvelope>
+{
+  let mut published = Vec::new();
+  while let Ok(Some(event)) = events.try_next() {
+    published.push(event);
+  }
+  published
+}
+
+fn enter_mode(mode: GameModeInfo) -> CommandEnvelope {
+  CommandEnvelope::EnterMode(EnterModeCmd { mode })
+}
+
+fn import(name: &str) -> CommandEnvelope {
+  let ruleset = Ruleset {
+    name: name.to_string(),
+    ..Ruleset::new_example()
+  };
+  CommandEnvelope::ImportRuleset(ImportRulesetCmd {
+    bundle: RulesetBundle::new(&ruleset, false).unwrap(),
+    on_conflict: RulesetImportConflict::Fail,
+  })
+}
+
+fn ruleset_names(event: &EventEnvelope) -> Vec<String> {
+  match event {
+    EventEnvelope::RulesetsChanged { rulesets } => {
+      let mut names = rulesets.iter()
+        .map(|entry| entry.name.clone())
+        .collect::<Vec<_>>();
+      names.sort();
+      names
+    },
+    other => panic!("Expected RulesetsChanged, got {:?}", other),
+  }
+}
+
+#[test]
+fn mode_changes_are_published() {
+  let root = TempDataRoot::new("events_mode_changes");
+  let mut server = start_server(&root);
+  let mut events = server.subscribe_events();
+
+  let define_rules = GameModeInfo::DefineRules(DefineRulesModeInfo {});
+  perform(&mut server, enter_mode(define_rules.clone()));
+  assert!(matches!(
+    published(&mut events)[..],
+    [EventEnvelope::ModeChanged { mode: Some(GameModeInfo::DefineRules(_)) }]
+  ));
+
+  // Commands that leave the mode as it was publish nothing.
+  perform(&mut server, CommandEnvelope::ListWorlds(ListWorldsCmd {}));
+  assert!(matches!(
+    perform(&mut server, enter_mode(define_rules)),
+    ResponseEnvelope::Failed(_)
+  ));
+  assert!(published(&mut events).is_empty());
+
+  perform(&mut server, CommandEnvelope::EnterMainMenuMode(
+    EnterMainMenuModeCmd {}
+  ));
+  assert!(matches!(
+    published(&mut events)[..],
+    [EventEnvelope::ModeChanged { mode: None }]
+  ));
+
+  // A mode that fails to start is not entered.
+  let missing_world = GameModeInfo::ViewWorld(ViewWorldModeInfo {
+    world_name: "Missing".to_string(),
+  });
+  assert!(matches!(
+    perform(&mut server, enter_mode(missing_world)),
+    ResponseEnvelope::Failed(_)
+  ));
+  assert!(published(&mut events).is_empty());
+}
+
+#[test]
+fn ruleset_store_changes_are_published() {
+  let root = TempDataRoot::new("events_ruleset_changes");
+  let mut server = start_server(&root);
+  let mut events = server.subscribe_events();
+
+  perform(&mut server, import("First"));
+  perform(&mut server, import("Second"));
+  let changes = published(&mut events);
+  assert_eq!(changes.len(), 2);
+  assert_eq!(ruleset_names(&changes[0]), vec!["First"]);
+  assert_eq!(ruleset_names(&changes[1]), vec!["First", "Second"]);
+
+  perform(&mut server, CommandEnvelope::RenameRuleset(RenameRulesetCmd {
+    ruleset_name: "First".to_string(),
+    new_name: "Renamed".to_string(),
+  }));
+  let changes = published(&mut events);
+  assert_eq!(changes.len(), 1);
+  assert_eq!(ruleset_names(&changes[0]), vec!["Renamed", "Second"]);
+
+  // Commands that fail leave the store as it was, and publish nothing.
+  assert!(matches!(
+    perform(&mut server, import("Second")),
+    ResponseEnvelope::Failed(_)
+  ));
+  assert!(matches!(
+    perform(&mut server, CommandEnvelope::DeleteRuleset(DeleteRulesetCmd {
+      ruleset_name: "Missing".to_string(),
+    })),
+    ResponseEnvelope::Failed(_)
+  ));
+  assert!(published(&mut events).is_empty());
+}
+
+#[test]
+fn shutdown_is_published_last() {
+  let root = TempDataRoot::new("events_shutdown");
+  let server = start_server(&root);
+  let mut first = server.subscribe_events();
+  let mut second = server.subscribe_events();
+  // Subscribers that have gone away do not stop the others hearing.
+  drop(server.subscribe_events());
+
+  server.shutdown();
+  for events in [&mut first, &mut second] {
+    assert!(matches!(
+      events.try_next(),
+      Ok(Some(EventEnvelope::ServerShutdown {}))
+    ));
+    // The stream then ends.
+    assert!(matches!(events.try_next(), Ok(None)));
+  }
+}
@@ -0,0 +1,205 @@
+use futures::executor::block_on;
+use crate::{
+  game::GameServerConfig,
+  network::ServerState,
+  protocol::{
+    mode::{ define_rules::DefineRulesModeInfo, GameModeInfo },
+    CommandEnvelope,
+    EnterMainMenuModeCmd,
+    EnterModeCmd,
+    EventEnvelope,
+    GetSessionInfoCmd,
+    HandOffControlCmd,
+    ListWorldsCmd,
+    ResponseEnvelope,
+    SessionInfo,
+    SessionRole,
+    TakeControlCmd,
+  },
+};
+use super::TempDataRoot;
+
+fn start_state(root: &TempDataRoot, max_sessions: usize) -> ServerState {
+  let config = GameServerConfig::new(root.to_str().unwrap().to_string());
+  ServerState::new(&config, max_sessions).unwrap()
+}
+
+fn perform(state: &ServerState, session_id: u64, command: CommandEnvelope)
+  -> ResponseEnvelope
+{
+  block_on(state.handle_command(session_id, command)).unwrap()
+}
+
+fn session_info(response: ResponseEnvelope) -> SessionInfo {
+  match response {
+    ResponseEnvelope::SessionInfo(info) => info,
+    other => panic!("Expected a SessionInfo response, got {:?}", other),
+  }
+}
+
+fn failed_messages(response: ResponseEnvelope) -> Vec<String> {
+  match response {
+    ResponseEnvelope::Failed(failed) => failed.messages().to_vec(),
+    other => panic!("Expected a Failed response, got {:?}", other),
+  }
+}
+
+fn enter_define_rules() -> CommandEnvelope {
+  CommandEnvelope::EnterMode(EnterModeCmd {
+    mode: GameModeInfo::DefineRules(DefineRulesModeInfo {}),
+  })
+}
+
+fn enter_main_menu() -> CommandEnvelope {
+  CommandEnvelope::EnterMainMenuMode(EnterMainMenuModeCmd {})
+}
+
+fn hand_off(to_session_id: Option<u64>) -> CommandEnvelope {
+  CommandEnvelope::HandOffControl(HandOffControlCmd {
+    session_id: to_session_id,
+  })
+}
+
+fn take_control() -> CommandEnvelope {
+  CommandEnvelope::TakeControl(TakeControlCmd {})
+}
+
+#[test]
+fn first_session_controls_and_later_ones_observe() {
+  let root = TempDataRoot::new("sessions_roles");
+  let state = start_state(&root, 2);
+  let controller = state.open_session().unwrap();
+  let observer = state.open_session().unwrap();
+  assert_eq!(controller.role, SessionRole::Controller);
+  assert_eq!(observer.role, SessionRole::Observer);
+  assert_eq!(observer.controller_id, Some(controller.session_id));
+  assert_eq!(
+    observer.session_ids,
+    vec![controller.session_id, observer.session_id]
+  );
+
+  let info = session_info(perform(
+    &state,
+    observer.session_id,
+    CommandEnvelope::GetSessionInfo(GetSessionInfoCmd {}),
+  ));
+  assert_eq!(info.role, SessionRole::Observer);
+}
+
+#[test]
+fn observers_may_only_read() {
+  let root = TempDataRoot::new("sessions_observers");
+  let state = start_state(&root, 2);
+  let controller = state.open_session().unwrap().session_id;
+  let observer = state.open_session().unwrap().session_id;
+
+  assert!(matches!(
+    perform(&state, observer, CommandEnvelope::ListWorlds(ListWorldsCmd {})),
+    ResponseEnvelope::WorldList(_)
+  ));
+  assert_eq!(
+    failed_messages(perform(&state, observer, enter_define_rules())),
+    vec![format!(
+      "Session {} is observing.  Only the controlling session ({}) may \
+       change the game.",
+      observer, controller
+    )]
+  );
+  assert!(matches!(
+    perform(&state, controller, enter_define_rules()),
+    ResponseEnvelope::Ok {}
+  ));
+}
+
+#[test]
+fn control_is_handed_off_and_taken() {
+  let root = TempDataRoot::new("sessions_hand_off");
+  let state = start_state(&root, 3);
+  let first = state.open_session().unwrap().session_id;
+  let second = state.open_session().unwrap().session_id;
+
+  assert_eq!(
+    failed_messages(perform(&state, second, hand_off(Some(second)))),
+    vec!["Only the controlling session may hand off control."]
+  );
+  assert_eq!(
+    failed_messages(perform(&state, first, hand_off(Some(99)))),
+    vec!["No such session: 99"]
+  );
+  assert_eq!(
+    failed_messages(perform(&state, second, take_control())),
+    vec![format!(
+      "Session {} is in control.  It must hand off control first.", first
+    )]
+  );
+
+  let info = session_info(perform(&state, first, hand_off(Some(second))));
+  assert_eq!(info.role, SessionRole::Observer);
+  assert_eq!(info.controller_id, Some(second));
+  assert!(matches!(
+    perform(&state, second, enter_define_rules()),
+    ResponseEnvelope::Ok {}
+  ));
+  assert!(matches!(
+    perform(&state, first, enter_main_menu()),
+    ResponseEnvelope::Failed(_)
+  ));
+
+  // Control given up is free for any session to take.
+  let info = session_info(perform(&state, second, hand_off(None)));
+  assert_eq!(info.controller_id, None);
+  assert_eq!(
+    failed_messages(perform(&state, second, enter_main_menu())),
+    vec![format!(
+      "Session {} is observing, and no session is in control.  Send \
+       TakeControl to change the game.",
+      second
+    )]
+  );
+  let info = session_info(perform(&state, first, take_control()));
+  assert_eq!(info.role, SessionRole::Controller);
+  assert!(matches!(
+    perform(&state, first, enter_main_menu()),
+    ResponseEnvelope::Ok {}
+  ));
+}
+
+#[test]
+fn sessions_are_limited_and_closing_frees_control() {
+  let root = TempDataRoot::new("sessions_max");
+  let state = start_state(&root, 2);
+  let mut events = state.subscribe_events();
+  let first = state.open_session().unwrap().session_id;
+  let second = state.open_session().unwrap().session_id;
+  assert_eq!(
+    state.open_session().err().unwrap(),
+    "The server already has the most sessions it allows (2)."
+  );
+
+  // The next session to open takes the control left free.
+  state.close_session(first);
+  let info = session_info(perform(
+    &state,
+    second,
+    CommandEnvelope::GetSessionInfo(GetSessionInfoCmd {}),
+  ));
+  assert_eq!(info.controller_id, None);
+  let third = state.open_session().unwrap();
+  assert_eq!(third.role, SessionRole::Controller);
+  assert_eq!(third.session_ids, vec![second, third.session_id]);
+
+  let mut changes = Vec::new();
+  while let Ok(Some(event)) = events.try_next() {
+    if let EventEnvelope::SessionsChanged { controller_id, session_ids } =
+      event
+    {
+      changes.push((controller_id, session_ids));
+    }
+  }
+  assert_eq!(changes, vec![
+    (Some(first), vec![first]),
+    (Some(first), vec![first, second]),
+    (None, vec![second]),
+    (Some(third.session_id), vec![second, third.session_id]),
+  ]);
+}
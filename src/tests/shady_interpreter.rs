@@ -0,0 +1,285 @@
+use std::collections::BTreeMap;
+use crate::{
+  data::{
+    ruleset::{
+      TerrainGenProgramKind,
+      TerrainGenProgramTest,
+      TerrainGenProgramTestValidation,
+      TerrainGenRules,
+      TerrainGenStageCache,
+    },
+    DiagnosticCode,
+  },
+  shady_vm::{
+    shasm_program_parser,
+    ShadyInterpreter,
+    ShadyRegisterFile,
+    ShasmSymbols,
+  },
+};
+
+struct NoSymbols;
+impl ShasmSymbols for NoSymbols {
+  fn resolve_symbol(&self, name: &str, _argument: Option<&str>)
+    -> Result<i64, String>
+  {
+    Err(format!("Unknown symbol '#{}'", name))
+  }
+}
+
+/**
+ * Assemble `text` and run it on registers with `inputs` loaded.
+ */
+fn run(text: &str, inputs: &[(u8, i32)])
+  -> (Result<usize, String>, ShadyRegisterFile)
+{
+  let program = shasm_program_parser(text, &NoSymbols)
+    .unwrap_or_else(|errors| panic!("Failed to assemble: {:?}", errors));
+  let mut registers = ShadyRegisterFile::new_zeroed();
+  for (reg, value) in inputs {
+    registers.write_reg(*reg, *value);
+  }
+  let result = ShadyInterpreter::new(&program).run(&mut registers);
+  (result, registers)
+}
+
+fn run_ok(text: &str, inputs: &[(u8, i32)]) -> ShadyRegisterFile {
+  let (result, registers) = run(text, inputs);
+  result.unwrap();
+  registers
+}
+
+/**
+ * A program that nests calls `depth` deep, the innermost incrementing r0.
+ */
+fn nested_calls(depth: usize) -> String {
+  let mut text = String::from("call f1\ngoto end\n");
+  for level in 1 ..= depth {
+    text.push_str(&format!("@f{}:\n", level));
+    if level < depth {
+      text.push_str(&format!("call f{}\n", level + 1));
+    } else {
+      text.push_str("add r0, r0, 1\n");
+    }
+    text.push_str("ret\n");
+  }
+  text.push_str("@end:\n");
+  text
+}
+
+#[test]
+fn computes_arithmetic() {
+  let registers = run_ok(
+    "add r0, r1, r2\n\
+     mul r3, r1, r2\n\
+     div r4, r1, r2\n\
+     mod r5, r1, r2\n\
+     max r6, r1, r2\n\
+     bitxor r7, r1, r2\n\
+     add (bump 2; neg) r8, r1, 0\n\
+     add r9, r1 shift 2, 0",
+    &[(1, -7), (2, 2)],
+  );
+  assert_eq!(registers.read_reg(0), -5);
+  assert_eq!(registers.read_reg(3), -14);
+  assert_eq!(registers.read_reg(4), -3);
+  assert_eq!(registers.read_reg(5), -1);
+  assert_eq!(registers.read_reg(6), 2);
+  assert_eq!(registers.read_reg(7), -7 ^ 2);
+  assert_eq!(registers.read_reg(8), 5);
+  assert_eq!(registers.read_reg(9), -28);
+}
+
+#[test]
+fn divides_by_zero_as_wgsl_does() {
+  let registers = run_ok("div r0, r1, 0\nmod r2, r1, 0", &[(1, 9)]);
+  assert_eq!(registers.read_reg(0), 9);
+  assert_eq!(registers.read_reg(2), 0);
+}
+
+#[test]
+fn loads_32_bit_immediates() {
+  let registers = run_ok(
+    "imm32load r0, 100000\nimm32load r1, -100000\nimm32load r2, 65535",
+    &[],
+  );
+  assert_eq!(registers.read_reg(0), 100000);
+  assert_eq!(registers.read_reg(1), -100000);
+  assert_eq!(registers.read_reg(2), 65535);
+}
+
+#[test]
+fn loops_on_condition_flags() {
+  // Sums r1 down to 1.
+  let (result, registers) = run(
+    "add r0, 0, 0\n\
+     @loop:\n\
+     add r0, r0, r1\n\
+     add r1, r1, -1\n\
+     ifgt goto loop",
+    &[(1, 10)],
+  );
+  assert_eq!(registers.read_reg(0), 55);
+  assert_eq!(registers.read_reg(1), 0);
+  assert_eq!(result, Ok(1 + 3 * 10));
+}
+
+#[test]
+fn skips_instructions_whose_condition_fails() {
+  let program =
+    "add r9, r1, 0\n\
+     noflags iflt add r0, 0, -1\n\
+     noflags ifeq add r0, 0, 0\n\
+     noflags ifgt add r0, 0, 1";
+  assert_eq!(run_ok(program, &[(1, -4)]).read_reg(0), -1);
+  assert_eq!(run_ok(program, &[(1, 0)]).read_reg(0), 0);
+  assert_eq!(run_ok(program, &[(1, 4)]).read_reg(0), 1);
+}
+
+#[test]
+fn calls_and_returns() {
+  let registers = run_ok(
+    "call double\n\
+     add r2, r1, 1\n\
+     goto end\n\
+     @double:\n\
+     mul r1, r1, 2\n\
+     ret\n\
+     @end:",
+    &[(1, 3)],
+  );
+  assert_eq!(registers.read_reg(1), 6);
+  assert_eq!(registers.read_reg(2), 7);
+}
+
+#[test]
+fn returning_from_outermost_call_ends_program() {
+  let (result, registers) = run("add r0, 0, 1\nret\nadd r0, 0, 2", &[]);
+  assert_eq!(result, Ok(2));
+  assert_eq!(registers.read_reg(0), 1);
+}
+
+#[test]
+fn jump_to_self_ends_program() {
+  let (result, registers) = run(
+    "add r0, 0, 1\n@halt:\ngoto halt\nadd r0, 0, 2",
+    &[],
+  );
+  assert_eq!(result, Ok(2));
+  assert_eq!(registers.read_reg(0), 1);
+}
+
+#[test]
+fn limits_call_depth() {
+  assert_eq!(run_ok(&nested_calls(4), &[]).read_reg(0), 1);
+
+  // The fifth call is at instruction 8, after two per enclosing level.
+  let (result, registers) = run(&nested_calls(5), &[]);
+  assert_eq!(result, Err("Call stack overflow at instruction 8.".to_string()));
+  assert_eq!(registers.read_reg(0), 0);
+}
+
+#[test]
+fn limits_steps() {
+  let (result, _) = run("@a:\ngoto b\n@b:\ngoto a", &[]);
+  assert_eq!(
+    result,
+    Err("The program did not finish within 100000 steps.".to_string())
+  );
+}
+
+#[test]
+fn rejects_jump_to_negative_address() {
+  let (result, _) = run("add r_pc, 0, -3", &[]);
+  assert_eq!(
+    result,
+    Err("Jump to invalid address -3 at instruction 0.".to_string())
+  );
+}
+
+fn vector(
+  name: &str,
+  inputs: &[(&str, i32)],
+  expected: &[(&str, i32)],
+) -> TerrainGenProgramTest {
+  let registers = |values: &[(&str, i32)]| -> BTreeMap<String, i32> {
+    values.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+  };
+  TerrainGenProgramTest {
+    name: name.to_string(),
+    program: TerrainGenProgramKind::Init,
+    inputs: registers(inputs),
+    neighbour: Vec::new(),
+    expected: registers(expected),
+  }
+}
+
+/**
+ * Validate the example rules, whose init program is `add r0, r1, r2`,
+ * with `init_program` and `tests` in its place, returning the failures.
+ */
+fn failed_tests(init_program: &str, tests: Vec<TerrainGenProgramTest>)
+  -> Vec<TerrainGenProgramTestValidation>
+{
+  let mut input = TerrainGenRules::new_example().to_input();
+  input.stage.init_program = init_program.to_string();
+  input.stage.tests = tests;
+  match input.to_validated_cached(&mut TerrainGenStageCache::default()) {
+    Ok(_) => Vec::new(),
+    Err(validation) => validation.stage.map_or(Vec::new(), |stage| stage.tests),
+  }
+}
+
+#[test]
+fn validation_reports_each_failing_vector() {
+  let failures = failed_tests("add r0, r1, r2", vec![
+    vector("Passes", &[("r1", 2), ("r2", 3)], &[("r0", 5)]),
+    vector("WrongSum", &[("r1", 2), ("r2", 3)], &[("r0", 6), ("r1", 2)]),
+    vector("TwoWrong", &[("r1", 1)], &[("r0", 0), ("r2", 7)]),
+  ]);
+  assert_eq!(failures.len(), 2);
+
+  assert_eq!(failures[0].name, "WrongSum");
+  assert_eq!(failures[0].index, 1);
+  assert_eq!(failures[0].errors.len(), 1);
+  let error = &failures[0].errors[0];
+  assert_eq!(error.code, DiagnosticCode::TestFailed);
+  assert_eq!(error.text, "r0: expected 6, got 5.");
+  assert_eq!(error.subpath, "/expected/r0");
+  assert_eq!(error.params["test"], "WrongSum");
+
+  assert_eq!(failures[1].name, "TwoWrong");
+  assert_eq!(failures[1].index, 2);
+  let texts: Vec<&str> =
+    failures[1].errors.iter().map(|error| error.text.as_str()).collect();
+  assert_eq!(texts, ["r0: expected 0, got 1.", "r2: expected 7, got 0."]);
+}
+
+#[test]
+fn validation_reports_interpreter_errors() {
+  let failures = failed_tests(
+    "@a:\ngoto b\n@b:\ngoto a",
+    vec![vector("Spins", &[], &[("r0", 0)])],
+  );
+  assert_eq!(failures.len(), 1);
+  assert_eq!(failures[0].errors.len(), 1);
+  let error = &failures[0].errors[0];
+  assert_eq!(error.code, DiagnosticCode::TestFailed);
+  assert_eq!(error.text, "The program did not finish within 100000 steps.");
+  assert_eq!(error.params["test"], "Spins");
+}
+
+#[test]
+fn validation_reports_unknown_registers() {
+  let failures = failed_tests("add r0, r1, r2", vec![
+    vector("BadNames", &[("x1", 1)], &[("r240", 0)]),
+  ]);
+  assert_eq!(failures.len(), 1);
+  let texts: Vec<&str> =
+    failures[0].errors.iter().map(|error| error.text.as_str()).collect();
+  assert_eq!(texts, [
+    "Unknown input register 'x1'; expected r0 to r239.",
+    "Unknown expected register 'r240'; expected r0 to r239.",
+  ]);
+  assert_eq!(failures[0].errors[0].subpath, "/inputs/x1");
+}
@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use crate::{
+  data::ruleset::{
+    TerrainGenParamRules,
+    TerrainGenProgramKind,
+    TerrainGenProgramTest,
+    TerrainGenRules,
+    TerrainGenStageCache,
+  },
+  shady_vm::{
+    shasm_program_parser,
+    ShadyInterpreter,
+    ShasmSymbols,
+    SHADY_REG_COUNT,
+  },
+};
+
+/**
+ * The example rules with a second parameter, `Roughness`, after the
+ * example's `SeaLevel`.
+ */
+fn rules_with_params() -> TerrainGenRules {
+  let mut rules = TerrainGenRules::new_example();
+  rules.params.push(TerrainGenParamRules {
+    name: "Roughness".to_string(),
+    description: String::new(),
+    default: 5,
+    min: 0,
+    max: 10,
+  });
+  rules
+}
+
+fn values(entries: &[(&str, i32)]) -> BTreeMap<String, i32> {
+  entries.iter().map(|(name, value)| (name.to_string(), *value)).collect()
+}
+
+#[test]
+fn preloads_params_into_register_block() {
+  let rules = rules_with_params();
+  let registers = TerrainGenParamRules::preload_registers(&rules.params);
+  let first = TerrainGenParamRules::FIRST_REG;
+  for reg in 0 .. SHADY_REG_COUNT {
+    let expected = match reg as u8 {
+      r if r == first => 200,
+      r if r == first + 1 => 5,
+      _ => 0,
+    };
+    assert_eq!(registers.read_reg(reg as u8), expected, "r{}", reg);
+  }
+}
+
+#[test]
+fn resolves_param_symbols_to_their_registers() {
+  let rules = rules_with_params();
+  let symbols = rules.symbols();
+  let first = TerrainGenParamRules::FIRST_REG as i64;
+  assert_eq!(symbols.resolve_symbol("param.SeaLevel", None), Ok(first));
+  assert_eq!(symbols.resolve_symbol("param.Roughness", None), Ok(first + 1));
+  assert_eq!(
+    symbols.resolve_symbol("param.Missing", None),
+    Err("No parameter 'Missing'".to_string())
+  );
+}
+
+#[test]
+fn programs_read_preloaded_params() {
+  let rules = rules_with_params();
+  let program = shasm_program_parser(
+    "add r0, r#param.SeaLevel, r#param.Roughness",
+    &rules.symbols(),
+  ).unwrap_or_else(|errors| panic!("Failed to assemble: {:?}", errors));
+  let mut registers = TerrainGenParamRules::preload_registers(&rules.params);
+  ShadyInterpreter::new(&program).run(&mut registers).unwrap();
+  assert_eq!(registers.read_reg(0), 205);
+}
+
+#[test]
+fn unknown_param_symbol_fails_to_assemble() {
+  let rules = rules_with_params();
+  let errors = shasm_program_parser(
+    "add r0, 0, 0\nadd r0, r#param.Missing, 0",
+    &rules.symbols(),
+  ).err().unwrap();
+  assert_eq!(errors.len(), 1);
+  // Line numbers count from zero.
+  assert_eq!(errors[0].line_no, 1);
+  assert_eq!(errors[0].message, "No parameter 'Missing'");
+}
+
+#[test]
+fn test_vectors_see_param_defaults() {
+  let mut input = rules_with_params().to_input();
+  input.stage.init_program =
+    "add r0, r#param.SeaLevel, r#param.Roughness".to_string();
+  input.stage.tests = vec![TerrainGenProgramTest {
+    name: "Defaults".to_string(),
+    program: TerrainGenProgramKind::Init,
+    inputs: BTreeMap::new(),
+    neighbour: Vec::new(),
+    expected: values(&[("r0", 205)]),
+  }];
+  let mut cache = TerrainGenStageCache::default();
+  assert!(input.to_validated_cached(&mut cache).is_ok());
+}
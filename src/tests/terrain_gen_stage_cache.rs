@@ -0,0 +1,63 @@
+use serde_json::Value;
+use crate::data::ruleset::{
+  TerrainGenInput,
+  TerrainGenRules,
+  TerrainGenStageCache,
+  TerrainGenValidation,
+};
+
+fn result_json(result: Result<TerrainGenRules, TerrainGenValidation>) -> Value {
+  match result {
+    Ok(rules) => serde_json::json!({ "ok": rules }),
+    Err(validation) => serde_json::json!({ "err": validation }),
+  }
+}
+
+/**
+ * Validate each edit in turn with one cache, and check that it gives what
+ * validating from scratch does.
+ */
+fn check_edits(edits: &[fn(&mut TerrainGenInput)]) {
+  let mut input = TerrainGenRules::new_example().to_input();
+  let mut cache = TerrainGenStageCache::default();
+  result_json(input.to_validated_cached(&mut cache));
+  for (index, edit) in edits.iter().enumerate() {
+    edit(&mut input);
+    assert_eq!(
+      result_json(input.to_validated_cached(&mut cache)),
+      result_json(
+        input.to_validated_cached(&mut TerrainGenStageCache::default())
+      ),
+      "edit {} validated differently with the cache", index
+    );
+  }
+}
+
+#[test]
+fn cached_validation_follows_program_edits() {
+  check_edits(&[
+    |input| input.stage.pairwise_program = "not an instruction".to_string(),
+    |input| input.stage.init_program = "add r0, r1, 5".to_string(),
+    |input| input.stage.pairwise_program = "add r0, r1, r2".to_string(),
+    |input| input.stage.init_program = "add r0, r1, r2".to_string(),
+  ]);
+}
+
+#[test]
+fn cached_validation_follows_test_edits() {
+  check_edits(&[
+    |input| { input.stage.tests[0].expected.insert("r0".to_string(), 99); },
+    |input| input.stage.tests.push(input.stage.tests[0].clone()),
+    |input| { input.stage.tests.remove(0); },
+  ]);
+}
+
+#[test]
+fn cached_validation_follows_format_and_param_edits() {
+  check_edits(&[
+    |input| input.stage.init_program = "add r0, r1, #param.SeaLevel".to_string(),
+    |input| input.params.clear(),
+    |input| input.params.push(TerrainGenRules::new_example().params[0].to_input()),
+    |input| input.stage.format.word_formats[0].components[0].bits = "0".to_string(),
+  ]);
+}
@@ -0,0 +1,123 @@
+use crate::{
+  data::{
+    map::{
+      CellComponentSelector,
+      CellData,
+      WorldDescriptor,
+      WorldDims,
+      CELL_DATA_NUM_WORDS,
+    },
+    ruleset::{
+      FormatComponentRules,
+      FormatComponentSelector,
+      FormatComponentType,
+      FormatRules,
+      FormatWordRules,
+      Ruleset,
+    },
+    GenerationCellDatumId,
+    VecMap,
+  },
+  data_store::DataStore,
+  game::mode::ViewWorldMode,
+};
+use super::TempDataRoot;
+
+/** A format unlike the example ruleset's, so that it must be the one read. */
+fn saved_format() -> FormatRules {
+  let component = |name: &str, offset, bits, kind| FormatComponentRules {
+    name: name.to_string(),
+    offset,
+    bits,
+    kind,
+  };
+  FormatRules {
+    word_formats: vec![
+      FormatWordRules {
+        name: "Climate".to_string(),
+        components: vec![
+          component("Rain", 0, 6, FormatComponentType::Unsigned),
+        ],
+      },
+      FormatWordRules {
+        name: "Terrain".to_string(),
+        components: vec![
+          component("Height", 4, 12, FormatComponentType::Signed),
+          component("Rock", 16, 3, FormatComponentType::Unsigned),
+        ],
+      },
+    ],
+  }
+}
+
+/** Save a world with `saved_format`, and read back the format it stored. */
+fn stored_format(root: &TempDataRoot) -> FormatRules {
+  let data_store = DataStore::new(root).unwrap();
+  let mut worlds = data_store.worlds().unwrap();
+  let descriptor = WorldDescriptor {
+    name: "Saved".to_string(),
+    description: String::new(),
+    seed: "seed".to_string(),
+    dims: WorldDims::new(4, 4),
+    ruleset_name: "Example Ruleset".to_string(),
+    heightmap: None,
+  };
+  let cells = VecMap::new(
+    descriptor.dims,
+    CellData::new([0; CELL_DATA_NUM_WORDS]),
+  );
+  worlds.write(&descriptor, &saved_format(), &cells).unwrap();
+
+  let (entry, _reader) = data_store.worlds().unwrap().open("Saved").unwrap();
+  entry.format
+}
+
+fn selector(word: &str, component: &str) -> GenerationCellDatumId {
+  GenerationCellDatumId::Selector(CellComponentSelector {
+    word: word.to_string(),
+    component: component.to_string(),
+  })
+}
+
+fn resolve(format: &FormatRules, datum_id: &GenerationCellDatumId)
+  -> Result<FormatComponentSelector, Vec<String>>
+{
+  ViewWorldMode::selector_for_datum_id(format, datum_id)
+}
+
+#[test]
+fn randgen_datum_is_not_available_for_saved_worlds() {
+  let root = TempDataRoot::new("view_world_randgen");
+  let format = stored_format(&root);
+  assert_eq!(
+    resolve(&format, &GenerationCellDatumId::RandGen {}).err().unwrap(),
+    vec!["RandGen datum id is not available for saved worlds"]
+  );
+}
+
+#[test]
+fn selectors_resolve_against_the_stored_format() {
+  let root = TempDataRoot::new("view_world_selectors");
+  let format = stored_format(&root);
+
+  let height = resolve(&format, &selector("Terrain", "Height")).unwrap();
+  assert_eq!(
+    (height.word, height.offset, height.count, height.signed),
+    (1, 4, 12, true)
+  );
+  let rock = resolve(&format, &selector("Terrain", "Rock")).unwrap();
+  assert_eq!(
+    (rock.word, rock.offset, rock.count, rock.signed),
+    (1, 16, 3, false)
+  );
+  let rain = resolve(&format, &selector("Climate", "Rain")).unwrap();
+  assert_eq!((rain.word, rain.offset, rain.count), (0, 0, 6));
+
+  // The ruleset's current format does not matter once the world is saved.
+  let example = Ruleset::new_example().terrain_gen.stage.format;
+  assert!(resolve(&example, &selector("ExampleWord", "Component1")).is_ok());
+  let errors = resolve(&format, &selector("ExampleWord", "Component1"))
+    .err().unwrap();
+  assert_eq!(errors[0], "Invalid selector");
+  assert!(resolve(&format, &selector("Terrain", "Rain")).is_err());
+}
@@ -0,0 +1,198 @@
+use std::{ fs, io::{ self, Cursor } };
+use crate::{
+  data::{
+    map::{
+      CellCoord,
+      CellData,
+      WorldDescriptor,
+      WorldDims,
+      CELL_DATA_NUM_WORDS,
+    },
+    ruleset::Ruleset,
+    world_file::{ WorldFile, WorldFileReader },
+    VecMap,
+  },
+  data_store::DataStore,
+};
+use super::TempDataRoot;
+
+const FORMAT_HASH: [u8; 32] = [7; 32];
+
+/**
+ * Cell data with a mix of what the codec has to handle: long runs of
+ * equal deltas, small smooth deltas, and deltas that wrap around.
+ */
+fn sample_cells(dims: WorldDims) -> VecMap<CellData> {
+  let mut cells = VecMap::new(dims, CellData::new([0; CELL_DATA_NUM_WORDS]));
+  for row in 0 .. dims.rows {
+    for col in 0 .. dims.columns {
+      let (c, r) = (col as u32, row as u32);
+      let mut words = [0_u32; CELL_DATA_NUM_WORDS];
+      words[0] = 5;
+      words[1] = c + r * 3;
+      words[2] = if (c + r) % 2 == 0 { 0 } else { u32::MAX };
+      words[3] = c.wrapping_mul(2_654_435_761) ^ r;
+      words[4] = (c / 10) * 1000 + r;
+      words[CELL_DATA_NUM_WORDS - 1] = 0x8000_0000 | (r << 16) | c;
+      cells.set(CellCoord::new(col, row), CellData::new(words));
+    }
+  }
+  cells
+}
+
+fn write_world(cells: &VecMap<CellData>) -> Vec<u8> {
+  let mut bytes = Vec::new();
+  WorldFile::write(&mut bytes, 1234, FORMAT_HASH, cells).unwrap();
+  bytes
+}
+
+fn open(bytes: Vec<u8>) -> io::Result<WorldFileReader<Cursor<Vec<u8>>>> {
+  WorldFileReader::open(Cursor::new(bytes))
+}
+
+fn assert_region_eq(
+  region: &VecMap<CellData>,
+  cells: &VecMap<CellData>,
+  top_left: CellCoord,
+) {
+  let dims = region.dims();
+  for row in 0 .. dims.rows {
+    for col in 0 .. dims.columns {
+      let source =
+        CellCoord::new(top_left.col + col, top_left.row + row);
+      assert_eq!(
+        region.get_ref(CellCoord::new(col, row)).words,
+        cells.get_ref(source).words,
+        "cell {:?} of region at {:?}", (col, row), top_left
+      );
+    }
+  }
+}
+
+#[test]
+fn round_trips_whole_chunks() {
+  let cells = sample_cells(WorldDims::new(128, 64));
+  let mut reader = open(write_world(&cells)).unwrap();
+  assert_eq!(reader.header().seed, 1234);
+  assert_eq!(reader.header().format_hash, FORMAT_HASH);
+  assert_eq!(reader.header().num_chunks(), 2);
+  let decoded = reader.read_all().unwrap();
+  assert_eq!(decoded.dims(), cells.dims());
+  assert_region_eq(&decoded, &cells, CellCoord::zero());
+}
+
+#[test]
+fn round_trips_partial_edge_chunks() {
+  // Neither dimension is a multiple of the 64x64 chunk size, so the
+  // chunks along the right and bottom edges are smaller.
+  let cells = sample_cells(WorldDims::new(100, 70));
+  let mut reader = open(write_world(&cells)).unwrap();
+  assert_eq!(reader.header().num_chunks(), 4);
+  let decoded = reader.read_all().unwrap();
+  assert_eq!(decoded.dims(), cells.dims());
+  assert_region_eq(&decoded, &cells, CellCoord::zero());
+}
+
+#[test]
+fn round_trips_a_world_smaller_than_a_chunk() {
+  let cells = sample_cells(WorldDims::new(3, 2));
+  let decoded = open(write_world(&cells)).unwrap().read_all().unwrap();
+  assert_region_eq(&decoded, &cells, CellCoord::zero());
+}
+
+#[test]
+fn reads_region_across_chunk_boundaries() {
+  let cells = sample_cells(WorldDims::new(150, 130));
+  let mut reader = open(write_world(&cells)).unwrap();
+
+  // Spans four chunks around (64, 64).
+  let top_left = CellCoord::new(60, 58);
+  let region = reader.read_region(top_left, WorldDims::new(10, 12)).unwrap();
+  assert_eq!(region.dims(), WorldDims::new(10, 12));
+  assert_region_eq(&region, &cells, top_left);
+
+  // Spans a full chunk column into the partial edge chunks.
+  let top_left = CellCoord::new(63, 100);
+  let region = reader.read_region(top_left, WorldDims::new(87, 30)).unwrap();
+  assert_region_eq(&region, &cells, top_left);
+}
+
+#[test]
+fn rejects_region_out_of_bounds() {
+  let cells = sample_cells(WorldDims::new(100, 70));
+  let mut reader = open(write_world(&cells)).unwrap();
+  let err = reader
+    .read_region(CellCoord::new(95, 0), WorldDims::new(10, 10))
+    .err().unwrap();
+  assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn rejects_truncated_file() {
+  let cells = sample_cells(WorldDims::new(100, 70));
+  let bytes = write_world(&cells);
+
+  // Cut inside the chunk payloads: the header and table still read.
+  let mut reader = open(bytes[.. bytes.len() - 10].to_vec()).unwrap();
+  let err = reader.read_all().err().unwrap();
+  assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+  // Cut inside the header.
+  let err = open(bytes[.. 20].to_vec()).err().unwrap();
+  assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn rejects_checksum_mismatch() {
+  let cells = sample_cells(WorldDims::new(100, 70));
+  let mut bytes = write_world(&cells);
+  // The last byte belongs to the last chunk's payload.
+  *bytes.last_mut().unwrap() ^= 0x01;
+
+  let mut reader = open(bytes).unwrap();
+  let last_chunk = reader.header().num_chunks() - 1;
+  let err = reader.read_chunk(last_chunk).unwrap_err();
+  assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+  assert_eq!(
+    err.to_string(),
+    format!("Checksum mismatch in world file chunk {}", last_chunk)
+  );
+
+  // The other chunks still read.
+  assert!(reader.read_chunk(0).is_ok());
+  assert!(reader.read_all().is_err());
+}
+
+#[test]
+fn world_store_replaces_worlds_atomically() {
+  let root = TempDataRoot::new("world_store_atomic");
+  let data_store = DataStore::new(&root).unwrap();
+  let mut worlds = data_store.worlds().unwrap();
+  let format = Ruleset::new_example().terrain_gen.stage.format;
+  let mut descriptor = WorldDescriptor {
+    name: "Valley".to_string(),
+    description: String::new(),
+    seed: "seed".to_string(),
+    dims: WorldDims::new(70, 3),
+    ruleset_name: "Example Ruleset".to_string(),
+    heightmap: None,
+  };
+  worlds.write(&descriptor, &format, &sample_cells(descriptor.dims))
+    .unwrap();
+
+  // Writing again under the same name replaces the file in place.
+  descriptor.dims = WorldDims::new(3, 70);
+  let cells = sample_cells(descriptor.dims);
+  worlds.write(&descriptor, &format, &cells).unwrap();
+
+  let worlds = data_store.worlds().unwrap();
+  let (entry, mut reader) = worlds.open("Valley").unwrap();
+  assert_eq!(entry.descriptor.dims, WorldDims::new(3, 70));
+  assert_region_eq(&reader.read_all().unwrap(), &cells, CellCoord::zero());
+
+  let mut filenames = fs::read_dir(root.join("worlds")).unwrap()
+    .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+    .collect::<Vec<_>>();
+  filenames.sort();
+  assert_eq!(filenames, vec![entry.filename, "worlds.json".to_string()]);
+}
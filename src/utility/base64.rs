@@ -0,0 +1,41 @@
+/**
+ * Decode standard (RFC 4648) base64.  Padding is optional and ASCII
+ * whitespace is ignored, so line-wrapped input is accepted.
+ */
+pub(crate) fn decode_base64(input: &str) -> Result<Vec<u8>, String> {
+  let mut out = Vec::with_capacity(input.len() * 3 / 4);
+  let mut acc: u32 = 0;
+  let mut acc_bits = 0;
+  let mut seen_padding = false;
+  for (i, ch) in input.bytes().enumerate() {
+    if ch.is_ascii_whitespace() {
+      continue;
+    }
+    if ch == b'=' {
+      seen_padding = true;
+      continue;
+    }
+    if seen_padding {
+      return Err(format!("Unexpected base64 data after padding at {}", i));
+    }
+    let value = match ch {
+      b'A' ..= b'Z' => ch - b'A',
+      b'a' ..= b'z' => ch - b'a' + 26,
+      b'0' ..= b'9' => ch - b'0' + 52,
+      b'+' => 62,
+      b'/' => 63,
+      _ => return Err(format!("Invalid base64 character at {}", i)),
+    };
+    acc = (acc << 6) | value as u32;
+    acc_bits += 6;
+    if acc_bits >= 8 {
+      acc_bits -= 8;
+      out.push((acc >> acc_bits) as u8);
+      acc &= (1 << acc_bits) - 1;
+    }
+  }
+  if acc_bits >= 6 {
+    return Err("Truncated base64 data".to_string());
+  }
+  Ok(out)
+}
@@ -6,7 +6,7 @@ use std::{
 
 pub(crate) fn append_to_file(path: &Path, data: &[u8]) -> io::Result<()> {
     let mut file = fs::OpenOptions::new()
-        .write(true)
+        .create(true)
         .append(true)
         .open(path)?;
 
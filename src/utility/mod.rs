@@ -1,2 +1,7 @@
-
+mod base64;
 mod file;
+
+pub(crate) use self::{
+  base64::decode_base64,
+  file::append_to_file,
+};